@@ -0,0 +1,75 @@
+use crate::json_store::JsonStore;
+use crate::models::{Action, ActionAuditEntry};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Global setting holding the user-defined actions as a JSON array
+pub const ACTIONS_KEY: &str = "actions";
+
+/// Maximum number of audit entries kept on disk
+const MAX_AUDIT_ENTRIES: usize = 1000;
+
+/// Load all registered actions
+pub fn load_actions(store: &JsonStore) -> Result<Vec<Action>, String> {
+    match store.get_setting(ACTIONS_KEY)? {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| format!("Failed to parse actions: {}", e))
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Replace all registered actions
+pub fn save_actions(store: &JsonStore, actions: &[Action]) -> Result<(), String> {
+    let json = serde_json::to_string(actions)
+        .map_err(|e| format!("Failed to serialize actions: {}", e))?;
+    store.set_setting(ACTIONS_KEY, &json)
+}
+
+/// Audit log of executed actions: ~/.devora/action_audit.jsonl (machine-local)
+fn audit_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("action_audit.jsonl")
+}
+
+/// Append an entry to the audit log, trimming it to the newest entries
+pub fn record_audit(config_dir: &Path, entry: &ActionAuditEntry) -> Result<(), String> {
+    let path = audit_path(config_dir);
+    let line =
+        serde_json::to_string(entry).map_err(|e| format!("Failed to serialize audit: {}", e))?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open audit log: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write audit log: {}", e))?;
+    drop(file);
+
+    let entries = read_audit(config_dir, usize::MAX);
+    if entries.len() > MAX_AUDIT_ENTRIES {
+        let kept = entries[..MAX_AUDIT_ENTRIES]
+            .iter()
+            .rev()
+            .filter_map(|e| serde_json::to_string(e).ok())
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(&path, kept + "\n").map_err(|e| format!("Failed to trim audit log: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Read the newest `limit` audit entries, newest first
+pub fn read_audit(config_dir: &Path, limit: usize) -> Vec<ActionAuditEntry> {
+    let Ok(content) = fs::read_to_string(audit_path(config_dir)) else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
+}
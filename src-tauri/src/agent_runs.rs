@@ -0,0 +1,314 @@
+use crate::hosts::{self, HostStatusCache};
+use crate::json_store::JsonStore;
+use crate::models::{CommandShell, RunningAgent};
+use crate::processes;
+use crate::shell;
+use crate::wsl;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::oneshot;
+
+/// Global setting: minutes a headless agent run may take before it's stopped
+pub const AGENT_RUN_TIMEOUT_KEY: &str = "agentRunTimeoutMinutes";
+
+/// Timeout of a headless agent run when the setting isn't set
+pub const DEFAULT_TIMEOUT_MINUTES: u64 = 60;
+
+/// A log is cut back to its newest half once it grows past this
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+
+/// A headless agent run: the command line to run and where to run it
+pub struct AgentRun {
+    /// Agent name for the log and the notification
+    pub agent: String,
+    /// The agent's command line, without env, as it goes into the log
+    pub command: String,
+    pub path: String,
+    pub host: Option<String>,
+    pub wsl_distro: Option<String>,
+    pub project_id: Option<String>,
+    /// Set on a local run's process, or inline for remote and WSL runs. It never
+    /// reaches the log.
+    pub env: HashMap<String, String>,
+}
+
+/// How a run ended
+enum Outcome {
+    Exited(ExitStatus),
+    Cancelled,
+    TimedOut,
+}
+
+/// Managed state: headless agent runs still going, keyed by run ID, so they can be
+/// listed and cancelled. Clones share the state.
+#[derive(Default, Clone)]
+pub struct AgentRuns {
+    running: Arc<Mutex<HashMap<String, (RunningAgent, oneshot::Sender<()>)>>>,
+}
+
+impl AgentRuns {
+    /// Start `run` in the background, stopping it once `timeout` passes. Its output
+    /// goes to the project's run log as it comes, and a notification says when
+    /// it's done.
+    pub fn start(&self, app: AppHandle, config_dir: PathBuf, run: AgentRun, timeout: Duration) {
+        let info = RunningAgent {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent: run.agent.clone(),
+            path: run.path.clone(),
+            host: run.host.clone(),
+            wsl_distro: run.wsl_distro.clone(),
+            project_id: run.project_id.clone(),
+            started_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        self.running
+            .lock()
+            .unwrap()
+            .insert(info.id.clone(), (info.clone(), cancel_tx));
+
+        let runs = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let log = log_path(&config_dir, run.project_id.as_deref());
+            let header = format!(
+                "=== {} {} in {}{}\n$ {}\n",
+                info.started_at,
+                run.agent,
+                run.host
+                    .as_deref()
+                    .map(|h| format!("{}:", h))
+                    .unwrap_or_default(),
+                run.path,
+                run.command
+            );
+            if let Err(e) = append_log(&log, &header) {
+                log::warn!("{}", e);
+            }
+
+            let result = execute(&app, &run, &log, cancel_rx, timeout).await;
+            runs.running.lock().unwrap().remove(&info.id);
+
+            let (summary, title) = match &result {
+                Ok(Outcome::Exited(status)) => {
+                    let code = status
+                        .code()
+                        .map_or("signal".to_string(), |c| c.to_string());
+                    let title = if status.success() {
+                        "Agent run finished"
+                    } else {
+                        "Agent run failed"
+                    };
+                    (format!("exit code {}", code), title)
+                }
+                Ok(Outcome::Cancelled) => ("cancelled".to_string(), "Agent run cancelled"),
+                Ok(Outcome::TimedOut) => (
+                    format!("timed out after {} minutes", timeout.as_secs() / 60),
+                    "Agent run timed out",
+                ),
+                Err(e) => (e.clone(), "Agent run failed"),
+            };
+            // Output that didn't end its last line gets a break before the footer
+            let open_line = fs::read(&log)
+                .map(|content| content.last().is_some_and(|&b| b != b'\n'))
+                .unwrap_or(false);
+            let footer = format!("{}=== {}\n\n", if open_line { "\n" } else { "" }, summary);
+            if let Err(e) = append_log(&log, &footer) {
+                log::warn!("{}", e);
+            }
+
+            if let Err(e) = app
+                .notification()
+                .builder()
+                .title(title)
+                .body(format!("{} in {}", run.agent, run.path))
+                .show()
+            {
+                log::warn!("Failed to show agent run notification: {}", e);
+            }
+        });
+    }
+
+    /// Runs still going, oldest first
+    pub fn list(&self) -> Vec<RunningAgent> {
+        let mut running: Vec<RunningAgent> = self
+            .running
+            .lock()
+            .unwrap()
+            .values()
+            .map(|(info, _)| info.clone())
+            .collect();
+        running.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        running
+    }
+
+    /// Stop a run; its log and notification say it was cancelled
+    pub fn cancel(&self, id: &str) -> Result<(), String> {
+        let (_, cancel) = self
+            .running
+            .lock()
+            .unwrap()
+            .remove(id)
+            .ok_or("Agent run not found (it may have finished)")?;
+        // The receiver is gone only if the run finished in the meantime
+        let _ = cancel.send(());
+        Ok(())
+    }
+}
+
+/// Headless runs' output: ~/.devora/agent_runs/<project id>.log (machine-local),
+/// with runs outside any project in global.log
+fn log_path(config_dir: &Path, project_id: Option<&str>) -> PathBuf {
+    let name = project_id
+        .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        .unwrap_or("global");
+    config_dir.join("agent_runs").join(format!("{}.log", name))
+}
+
+fn open_log(path: &Path) -> Result<File, String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create agent run log directory: {}", e))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open agent run log: {}", e))
+}
+
+fn append_log(path: &Path, text: &str) -> Result<(), String> {
+    let mut file = open_log(path)?;
+    file.write_all(text.as_bytes())
+        .map_err(|e| format!("Failed to write agent run log: {}", e))?;
+    drop(file);
+
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if size > MAX_LOG_BYTES {
+        let content = fs::read(path).map_err(|e| format!("Failed to read agent run log: {}", e))?;
+        let kept = &content[content.len() - (MAX_LOG_BYTES / 2) as usize..];
+        // Start at a line boundary so the log doesn't open mid-line
+        let start = kept.iter().position(|&b| b == b'\n').map_or(0, |i| i + 1);
+        fs::write(path, &kept[start..])
+            .map_err(|e| format!("Failed to trim agent run log: {}", e))?;
+    }
+    Ok(())
+}
+
+/// The end of a project's run log, at most `max_bytes` of it
+pub fn read_log(
+    config_dir: &Path,
+    project_id: Option<&str>,
+    max_bytes: usize,
+) -> Result<String, String> {
+    let content = match fs::read(log_path(config_dir, project_id)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(e) => return Err(format!("Failed to read agent run log: {}", e)),
+    };
+    let tail = &content[content.len().saturating_sub(max_bytes)..];
+    Ok(String::from_utf8_lossy(tail).into_owned())
+}
+
+/// Run the agent with its output appended to `log`, until it exits, `cancel` fires
+/// or `timeout` passes. Remote and WSL runs are also bounded by the `timeout`
+/// utility on their side, which stops them where killing the local ssh or wsl.exe
+/// wouldn't.
+async fn execute(
+    app: &AppHandle,
+    run: &AgentRun,
+    log: &Path,
+    cancel: oneshot::Receiver<()>,
+    timeout: Duration,
+) -> Result<Outcome, String> {
+    let inline = || {
+        let command = format!(
+            "{}{}",
+            shell::env_prefix(&run.env, CommandShell::Sh),
+            run.command
+        );
+        processes::remote_with_timeout(&command, timeout.as_secs())
+    };
+    let local = run.host.is_none() && run.wsl_distro.is_none();
+    let mut cmd = if let Some(host) = run.host.as_deref() {
+        let store = app.state::<JsonStore>();
+        let jump = hosts::jump_chain(&store, host);
+        let mut cmd = tokio::process::Command::new("ssh");
+        cmd.args(hosts::jump_args(jump.as_deref()));
+        // Path is left unquoted so that `~` expands on the remote side
+        cmd.arg(host)
+            .arg(format!("cd {} && {}", run.path, inline()));
+        cmd
+    } else if let Some(distro) = run.wsl_distro.as_deref() {
+        wsl::tokio_command(distro, Some(&run.path), None, &inline())?
+    } else {
+        let mut cmd = CommandShell::platform_default().tokio_command(&run.command);
+        cmd.current_dir(&run.path).envs(&run.env);
+        // In its own process group, so stopping it stops the agent's children too
+        #[cfg(unix)]
+        cmd.process_group(0);
+        cmd
+    };
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let stdout = open_log(log)?;
+    let stderr = stdout
+        .try_clone()
+        .map_err(|e| format!("Failed to open agent run log: {}", e))?;
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(stdout)
+        .stderr(stderr)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+
+    // A remote timeout gets a moment to stop the agent itself
+    let limit = if local {
+        timeout
+    } else {
+        timeout + Duration::from_secs(processes::REMOTE_TIMEOUT_GRACE_SECS)
+    };
+    let outcome = tokio::select! {
+        status = child.wait() => {
+            Outcome::Exited(status.map_err(|e| format!("Failed to wait for command: {}", e))?)
+        }
+        Ok(()) = cancel => Outcome::Cancelled,
+        _ = tokio::time::sleep(limit) => Outcome::TimedOut,
+    };
+    if !matches!(outcome, Outcome::Exited(_)) {
+        if local {
+            if let Some(pid) = child.id() {
+                if let Err(e) = processes::kill_local(pid) {
+                    log::debug!("{}", e);
+                }
+            }
+        }
+        if let Err(e) = child.kill().await {
+            log::debug!("Failed to stop agent run: {}", e);
+        }
+    }
+
+    let Outcome::Exited(status) = &outcome else {
+        return Ok(outcome);
+    };
+    if let Some(host) = run.host.as_deref() {
+        app.state::<HostStatusCache>()
+            .record_ssh_exit(host, status.code());
+    }
+    // The remote `timeout` exits with its own code when it stops the agent
+    if !local && status.code() == Some(processes::TIMEOUT_EXIT_CODE) {
+        return Ok(Outcome::TimedOut);
+    }
+    Ok(outcome)
+}
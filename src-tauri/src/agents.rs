@@ -0,0 +1,173 @@
+use crate::json_store::JsonStore;
+use crate::models::{AgentSession, CodingAgentType, CustomAgent};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Global setting holding the user-defined coding agents as a JSON array
+pub const CUSTOM_AGENTS_KEY: &str = "customAgents";
+
+/// Maximum number of launched sessions kept on disk
+const MAX_SESSIONS: usize = 500;
+
+pub fn load_custom(store: &JsonStore) -> Result<Vec<CustomAgent>, String> {
+    match store.get_setting(CUSTOM_AGENTS_KEY)? {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| format!("Failed to parse custom agents: {}", e))
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Replace the custom agents. IDs must be unique and every agent needs a command.
+pub fn save_custom(store: &JsonStore, agents: &[CustomAgent]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for agent in agents {
+        let id = agent.id.trim();
+        if id.is_empty() {
+            return Err("Custom agent IDs can't be empty".to_string());
+        }
+        if !ids.insert(id) {
+            return Err(format!("Duplicate custom agent \"{}\"", id));
+        }
+        if agent.command.trim().is_empty() {
+            return Err(format!("Custom agent \"{}\" has no command", agent.name));
+        }
+        let env = agent.env.as_deref().filter(|e| !e.trim().is_empty());
+        if env.is_some_and(|env| serde_json::from_str::<HashMap<String, String>>(env).is_err()) {
+            return Err(format!(
+                "Environment of custom agent \"{}\" must be a JSON object of strings",
+                agent.name
+            ));
+        }
+    }
+
+    let json = serde_json::to_string(agents)
+        .map_err(|e| format!("Failed to serialize custom agents: {}", e))?;
+    store.set_setting(CUSTOM_AGENTS_KEY, &json)
+}
+
+pub fn find_custom(store: &JsonStore, id: &str) -> Result<CustomAgent, String> {
+    load_custom(store)?
+        .into_iter()
+        .find(|agent| agent.id == id)
+        .ok_or_else(|| format!("Custom agent \"{}\" not found", id))
+}
+
+/// Executable of a built-in agent; None for `Custom`, whose command comes from
+/// the registry
+pub fn builtin_command(agent: &CodingAgentType) -> Option<&'static str> {
+    Some(match agent {
+        CodingAgentType::ClaudeCode => "claude",
+        CodingAgentType::Opencode => "opencode",
+        CodingAgentType::GeminiCli => "gemini",
+        CodingAgentType::Codex => "codex",
+        CodingAgentType::Aider => "aider",
+        CodingAgentType::Amp => "amp",
+        CodingAgentType::CursorAgent => "cursor-agent",
+        CodingAgentType::Custom => return None,
+    })
+}
+
+/// Command line for an agent: `args` replace an `{args}` placeholder in the
+/// command template, or are appended to it
+pub fn command_line(template: &str, args: Option<&str>) -> String {
+    let args = args.map(str::trim).unwrap_or_default();
+    if template.contains("{args}") {
+        return template.replace("{args}", args).trim().to_string();
+    }
+    if args.is_empty() {
+        template.trim().to_string()
+    } else {
+        format!("{} {}", template.trim(), args)
+    }
+}
+
+/// Arguments that make an agent pick up an earlier session in its working dir:
+/// the most recent one, or with `pick`, one chosen from the agent's list
+pub fn resume_args(agent: &CodingAgentType, pick: bool) -> Option<&'static str> {
+    match (agent, pick) {
+        (CodingAgentType::ClaudeCode, false) => Some("--continue"),
+        (CodingAgentType::ClaudeCode, true) => Some("--resume"),
+        // `resume` is a subcommand, so it has to come before other arguments
+        (CodingAgentType::Codex, false) => Some("resume --last"),
+        (CodingAgentType::Codex, true) => Some("resume"),
+        (CodingAgentType::Opencode, _) => Some("--continue"),
+        _ => None,
+    }
+}
+
+/// Arguments that make an agent run the prompt that follows them without asking
+/// anything and exit; None for agents without a non-interactive mode
+pub fn headless_args(agent: &CodingAgentType) -> Option<&'static str> {
+    match agent {
+        CodingAgentType::ClaudeCode | CodingAgentType::GeminiCli | CodingAgentType::CursorAgent => {
+            Some("-p")
+        }
+        // Subcommands, so they have to come before other arguments
+        CodingAgentType::Codex => Some("exec"),
+        CodingAgentType::Opencode => Some("run"),
+        CodingAgentType::Aider => Some("--yes-always --message"),
+        CodingAgentType::Amp => Some("-x"),
+        CodingAgentType::Custom => None,
+    }
+}
+
+/// Arguments that start an interactive session with the prompt that follows them
+/// as the first message; None for agents that can't take one
+pub fn prompt_args(agent: &CodingAgentType) -> Option<&'static str> {
+    match agent {
+        CodingAgentType::ClaudeCode | CodingAgentType::Codex | CodingAgentType::CursorAgent => {
+            Some("")
+        }
+        CodingAgentType::GeminiCli => Some("-i"),
+        CodingAgentType::Opencode => Some("--prompt"),
+        _ => None,
+    }
+}
+
+/// Launched agent sessions: ~/.devora/agent_sessions.jsonl (machine-local)
+fn sessions_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("agent_sessions.jsonl")
+}
+
+/// Append a launched session, trimming the log to the newest sessions
+pub fn record_session(config_dir: &Path, session: &AgentSession) -> Result<(), String> {
+    let path = sessions_path(config_dir);
+    let line = serde_json::to_string(session)
+        .map_err(|e| format!("Failed to serialize agent session: {}", e))?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open agent session log: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write agent session log: {}", e))?;
+    drop(file);
+
+    let sessions = read_sessions(config_dir);
+    if sessions.len() > MAX_SESSIONS {
+        let kept = sessions[..MAX_SESSIONS]
+            .iter()
+            .rev()
+            .filter_map(|s| serde_json::to_string(s).ok())
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(&path, kept + "\n")
+            .map_err(|e| format!("Failed to trim agent session log: {}", e))?;
+    }
+    Ok(())
+}
+
+/// All recorded sessions, newest first
+pub fn read_sessions(config_dir: &Path) -> Vec<AgentSession> {
+    let Ok(content) = fs::read_to_string(sessions_path(config_dir)) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
@@ -0,0 +1,75 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Clipboard tools tried in order; the text is written to their stdin
+fn clipboard_commands() -> Vec<(&'static str, Vec<&'static str>)> {
+    #[cfg(windows)]
+    {
+        vec![(
+            "powershell",
+            vec![
+                "-NoProfile",
+                "-Command",
+                "[Console]::InputEncoding = [Text.Encoding]::UTF8; Set-Clipboard -Value ([Console]::In.ReadToEnd())",
+            ],
+        )]
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        vec![("pbcopy", vec![])]
+    }
+
+    #[cfg(all(not(windows), not(target_os = "macos")))]
+    {
+        let mut commands = Vec::new();
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.push(("wl-copy", vec![]));
+        }
+        commands.push(("xclip", vec!["-selection", "clipboard"]));
+        commands.push(("xsel", vec!["--clipboard", "--input"]));
+        commands
+    }
+}
+
+/// Put text on the system clipboard
+pub fn copy_text(text: &str) -> Result<(), String> {
+    let mut last_error = String::from("No clipboard tool found");
+
+    for (program, args) in clipboard_commands() {
+        let mut cmd = Command::new(program);
+        cmd.args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                last_error = format!("Failed to run {}: {}", program, e);
+                continue;
+            }
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("Failed to write to clipboard: {}", e))?;
+        }
+        let status = child
+            .wait()
+            .map_err(|e| format!("Failed to write to clipboard: {}", e))?;
+        if status.success() {
+            return Ok(());
+        }
+        last_error = format!("{} exited with {}", program, status);
+    }
+
+    Err(format!("Failed to copy to clipboard: {}", last_error))
+}
@@ -0,0 +1,142 @@
+use crate::models::{CodeTodo, WorkingDir};
+use std::fs;
+use std::path::Path;
+
+/// Comment tags picked up by the scan
+const TAGS: &[&str] = &["TODO", "FIXME", "HACK"];
+
+/// Text before a tag must contain one of these for the line to count as a comment
+const COMMENT_MARKERS: &[&str] = &["//", "#", "/*", "*", "--", "<!--", ";"];
+
+/// Dependency and build output directories (hidden directories are skipped too)
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "vendor",
+    "dist",
+    "build",
+    "__pycache__",
+    "venv",
+];
+
+/// Larger files are generated or data, not source worth scanning
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// The scan stops after this many matches so a huge tree can't stall the UI
+const MAX_RESULTS: usize = 2000;
+
+/// Find TODO/FIXME/HACK comments in the local working dirs. Remote dirs are skipped.
+pub fn scan(working_dirs: &[WorkingDir]) -> Vec<CodeTodo> {
+    let mut found = Vec::new();
+    for dir in working_dirs.iter().filter(|d| d.host.is_none()) {
+        let root = Path::new(&dir.path);
+        if root.is_dir() {
+            walk(root, root, &dir.name, &mut found);
+        }
+    }
+    found
+}
+
+fn walk(root: &Path, dir: &Path, working_dir: &str, out: &mut Vec<CodeTodo>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.flatten().collect();
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        if out.len() >= MAX_RESULTS {
+            return;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        // file_type doesn't follow symlinks, so links are never walked into
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                walk(root, &path, working_dir, out);
+            }
+        } else if file_type.is_file() {
+            scan_file(root, &path, working_dir, out);
+        }
+    }
+}
+
+fn scan_file(root: &Path, path: &Path, working_dir: &str, out: &mut Vec<CodeTodo>) {
+    if fs::metadata(path).map_or(true, |m| m.len() > MAX_FILE_BYTES) {
+        return;
+    }
+    let Ok(bytes) = fs::read(path) else {
+        return;
+    };
+    // Same binary check as git: a NUL byte near the start
+    if bytes.iter().take(8000).any(|&b| b == 0) {
+        return;
+    }
+    let Ok(content) = String::from_utf8(bytes) else {
+        return;
+    };
+
+    let relative = path
+        .strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    for (index, line) in content.lines().enumerate() {
+        if let Some((tag, text)) = find_tag(line) {
+            out.push(CodeTodo {
+                working_dir: working_dir.to_string(),
+                path: relative.clone(),
+                line: index + 1,
+                tag: tag.to_string(),
+                text,
+            });
+            if out.len() >= MAX_RESULTS {
+                return;
+            }
+        }
+    }
+}
+
+/// The tag of a comment line and the text after it, e.g. `// TODO(me): fix` -> ("TODO", "fix")
+fn find_tag(line: &str) -> Option<(&'static str, String)> {
+    for tag in TAGS {
+        let mut search = 0;
+        while let Some(start) = line[search..].find(tag).map(|i| search + i) {
+            search = start + tag.len();
+            let before = &line[..start];
+            let after = &line[search..];
+            // Whole word only, e.g. not `TODOS` or `my_TODO`
+            if before
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+                || after
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_alphanumeric() || c == '_')
+            {
+                continue;
+            }
+            if !COMMENT_MARKERS.iter().any(|m| before.contains(m)) {
+                continue;
+            }
+
+            // Drop an owner/issue suffix like `(alice)` and the separator
+            let mut text = after;
+            if text.starts_with('(') {
+                text = text.find(')').map_or(text, |end| &text[end + 1..]);
+            }
+            let text = text
+                .trim_start_matches([':', '-', ' ', '\t'])
+                .trim_end()
+                .trim_end_matches("*/")
+                .trim_end_matches("-->")
+                .trim();
+            return Some((tag, text.to_string()));
+        }
+    }
+    None
+}
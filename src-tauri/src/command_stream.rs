@@ -0,0 +1,252 @@
+use crate::hosts;
+use crate::models::{CommandOutputChunk, CommandRunFinished, CommandShell, OutputStream};
+use crate::processes;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+use tokio::sync::oneshot;
+
+/// Event emitted with a `CommandOutputChunk` for each piece of streamed output
+pub const COMMAND_OUTPUT_EVENT: &str = "command://output";
+
+/// Event emitted with a `CommandRunFinished` when a streamed command ends
+pub const COMMAND_FINISHED_EVENT: &str = "command://finished";
+
+/// Managed state: commands started by `run_command_stream` that are still running,
+/// keyed by run ID, so they can be cancelled. Clones share the state.
+#[derive(Default, Clone)]
+pub struct CommandRuns {
+    cancels: Arc<Mutex<HashMap<String, oneshot::Sender<()>>>>,
+}
+
+impl CommandRuns {
+    /// Spawn `command` in `shell` (locally, or over SSH on `host`) and stream its
+    /// output as events tagged with `run_id`. `on_finish` runs before the finished
+    /// event.
+    #[allow(clippy::too_many_arguments)]
+    pub fn start(
+        &self,
+        app: &AppHandle,
+        run_id: String,
+        command: &str,
+        cwd: Option<&str>,
+        host: Option<&str>,
+        jump: Option<&str>,
+        shell: Option<CommandShell>,
+        on_finish: impl FnOnce(&CommandRunFinished) + Send + 'static,
+    ) -> Result<(), String> {
+        let mut child = stream_command(command, cwd, host, jump, shell)
+            .spawn()
+            .map_err(|e| format!("Failed to start command: {}", e))?;
+
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        // A remote command reports its process group on stderr, for cancelling
+        let (pgid_tx, mut pgid_rx) = oneshot::channel();
+        self.cancels
+            .lock()
+            .unwrap()
+            .insert(run_id.clone(), cancel_tx);
+
+        let stdout_task = tauri::async_runtime::spawn(pump(
+            app.clone(),
+            run_id.clone(),
+            OutputStream::Stdout,
+            child.stdout.take(),
+            None,
+        ));
+        let stderr_task = tauri::async_runtime::spawn(pump(
+            app.clone(),
+            run_id.clone(),
+            OutputStream::Stderr,
+            child.stderr.take(),
+            host.is_some().then_some(pgid_tx),
+        ));
+
+        let runs = self.clone();
+        let app = app.clone();
+        let remote = host.map(|h| (h.to_string(), jump.map(str::to_string)));
+        tauri::async_runtime::spawn(async move {
+            let (exit_code, cancelled) = tokio::select! {
+                status = child.wait() => (status.ok().and_then(|s| s.code()), false),
+                Ok(()) = cancel_rx => {
+                    let pgid = pgid_rx.try_recv().ok();
+                    stop(&mut child, remote.as_ref(), pgid).await;
+                    (None, true)
+                }
+            };
+            let _ = stdout_task.await;
+            let _ = stderr_task.await;
+
+            runs.cancels.lock().unwrap().remove(&run_id);
+            let finished = CommandRunFinished {
+                run_id,
+                exit_code,
+                cancelled,
+            };
+            on_finish(&finished);
+            let _ = app.emit(COMMAND_FINISHED_EVENT, finished);
+        });
+        Ok(())
+    }
+
+    /// Stop a running streamed command; its finished event reports `cancelled`
+    pub fn cancel(&self, run_id: &str) -> Result<(), String> {
+        let cancel = self
+            .cancels
+            .lock()
+            .unwrap()
+            .remove(run_id)
+            .ok_or("Command not found (it may have finished)")?;
+        // The receiver is gone only if the command finished in the meantime
+        let _ = cancel.send(());
+        Ok(())
+    }
+}
+
+/// Build the process: `shell` (else the platform shell) locally in its own process
+/// group (so cancelling stops its children too), or `ssh host` (through the `jump`
+/// hosts) running the command remotely in a process group it reports, in `shell`
+/// if set
+fn stream_command(
+    command: &str,
+    cwd: Option<&str>,
+    host: Option<&str>,
+    jump: Option<&str>,
+    shell: Option<CommandShell>,
+) -> Command {
+    let mut cmd = match host {
+        Some(host) => {
+            let command = match shell {
+                Some(shell) => shell.remote_line(command),
+                None => command.to_string(),
+            };
+            let remote = match cwd {
+                Some(dir) => format!("cd {} && {}", dir, command),
+                None => command,
+            };
+            let mut cmd = Command::new("ssh");
+            cmd.args(hosts::jump_args(jump))
+                .args([host, &processes::remote_group_line(&remote)]);
+            cmd
+        }
+        None => {
+            let mut cmd = shell
+                .unwrap_or_else(CommandShell::platform_default)
+                .tokio_command(command);
+            #[cfg(unix)]
+            cmd.process_group(0);
+            cmd.current_dir(cwd.unwrap_or("."));
+            cmd
+        }
+    };
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    cmd
+}
+
+/// Kill a cancelled command. Local commands are stopped with their process group
+/// (or tree). A remote one's process group (`pgid`, once it has reported it) is
+/// stopped over a second SSH session, since closing the first leaves processes
+/// that ignore SIGHUP running; then the session is closed.
+async fn stop(child: &mut Child, remote: Option<&(String, Option<String>)>, pgid: Option<u32>) {
+    match (remote, pgid) {
+        (None, _) => {
+            if let Some(pid) = child.id() {
+                if let Err(e) = processes::kill_local(pid) {
+                    log::debug!("{}", e);
+                }
+            }
+        }
+        (Some((host, jump)), Some(pgid)) => {
+            let mut cmd = Command::new("ssh");
+            cmd.args(hosts::jump_args(jump.as_deref()))
+                .args([host.as_str(), &processes::remote_kill_line(pgid)])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null());
+            #[cfg(windows)]
+            {
+                const CREATE_NO_WINDOW: u32 = 0x08000000;
+                cmd.creation_flags(CREATE_NO_WINDOW);
+            }
+            if let Err(e) = cmd.status().await {
+                log::debug!("Failed to stop remote command: {}", e);
+            }
+        }
+        (Some(_), None) => {}
+    }
+    if let Err(e) = child.kill().await {
+        log::debug!("Failed to kill cancelled command: {}", e);
+    }
+}
+
+/// Emit everything read from `reader` as output chunks. A multi-byte character
+/// split across reads is held back until it is complete. With `pgid` set, a first
+/// line reporting the remote process group is taken out and sent there.
+async fn pump(
+    app: AppHandle,
+    run_id: String,
+    stream: OutputStream,
+    reader: Option<impl AsyncRead + Unpin>,
+    mut pgid: Option<oneshot::Sender<u32>>,
+) {
+    let Some(mut reader) = reader else {
+        return;
+    };
+    let emit = |data: &[u8]| {
+        let _ = app.emit(
+            COMMAND_OUTPUT_EVENT,
+            CommandOutputChunk {
+                run_id: run_id.clone(),
+                stream: stream.clone(),
+                data: String::from_utf8_lossy(data).to_string(),
+            },
+        );
+    };
+
+    let mut buffer = [0u8; 8192];
+    let mut pending: Vec<u8> = Vec::new();
+    loop {
+        let read = match reader.read(&mut buffer).await {
+            Ok(0) | Err(_) => break,
+            Ok(read) => read,
+        };
+        pending.extend_from_slice(&buffer[..read]);
+        if let Some(tx) = pgid.take() {
+            let Some(end) = pending.iter().position(|&b| b == b'\n') else {
+                pgid = Some(tx);
+                continue;
+            };
+            let line = String::from_utf8_lossy(&pending[..end]).trim().to_string();
+            if let Some(id) = line.strip_prefix(processes::REMOTE_PGID_MARKER) {
+                if let Ok(id) = id.parse() {
+                    let _ = tx.send(id);
+                }
+                pending.drain(..=end);
+            }
+        }
+        let complete = match std::str::from_utf8(&pending) {
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            _ => pending.len(),
+        };
+        if complete > 0 {
+            emit(&pending[..complete]);
+            pending.drain(..complete);
+        }
+    }
+    if !pending.is_empty() {
+        emit(&pending);
+    }
+}
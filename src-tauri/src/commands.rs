@@ -1,13 +1,60 @@
 #![allow(non_snake_case)]
 
+use crate::actions;
+use crate::agent_runs::{self, AgentRun, AgentRuns};
+use crate::agents;
+use crate::clipboard;
+use crate::code_todos;
+use crate::command_stream::CommandRuns;
+use crate::custom_fields;
+use crate::database;
+use crate::dir_listing;
+use crate::discovery;
+use crate::docker;
+use crate::encoding;
+use crate::exports::{self, ReplaceImportGuard};
+use crate::file_preview;
+use crate::file_search;
+use crate::file_watch::FileCardWatcher;
+use crate::git::{self, GitProvider};
+use crate::github;
+use crate::handoff;
+use crate::hosts::{self, HostStatusCache};
+use crate::http_request;
+use crate::janitor::{self, JanitorState};
+use crate::jetbrains;
 use crate::json_store::JsonStore;
+use crate::keyring;
+use crate::line_index::LineIndexCache;
+use crate::maintenance::{self, MaintenanceState};
+use crate::markdown;
+use crate::migration::{self, MigrationReport, MigrationState};
 use crate::models::*;
+use crate::policy;
+use crate::ports;
+use crate::processes::{self, ProcessRegistry};
+use crate::prompts;
+use crate::schedules::{self, ScheduleState};
+use crate::scripts;
+use crate::services::{self, ServiceManager};
 use crate::settings::SettingsFile;
-use std::collections::HashMap;
+use crate::sftp;
+use crate::shell;
+use crate::ssh_config;
+use crate::sync;
+use crate::templates;
+use crate::terminal;
+use crate::tmux;
+use crate::todos;
+use crate::url_metadata;
+use crate::usage::UsageFile;
+use crate::wsl;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 use std::process::Command;
-use tauri::{AppHandle, Manager, State, WebviewUrl, WebviewWindowBuilder};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewUrl, WebviewWindowBuilder};
 
 // Reload store from disk (for Ctrl+R refresh)
 #[tauri::command]
@@ -15,21 +62,145 @@ pub fn reload_store(store: State<JsonStore>) -> Result<(), String> {
     store.reload()
 }
 
+// Re-read a single project from disk (cheaper than reload_store after an external edit)
+#[tauri::command]
+pub fn reload_project(id: String, store: State<JsonStore>) -> Result<Option<Project>, String> {
+    store.reload_project(&id)
+}
+
+// Wait for queued background saves to reach disk (e.g. before closing a window)
+#[tauri::command]
+pub fn flush_pending_saves(store: State<JsonStore>) -> Result<(), String> {
+    store.flush()
+}
+
 // Check if data files have been modified externally (e.g., by OneDrive sync)
 #[tauri::command]
 pub fn check_external_changes(store: State<JsonStore>) -> bool {
     store.has_external_changes()
 }
 
+// Outcome of the startup SQLite migration (also pushed via migration-* events)
+#[tauri::command]
+pub fn get_migration_report(state: State<MigrationState>) -> MigrationReport {
+    state.0.lock().unwrap().clone()
+}
+
+// Report of the latest temp file cleanup (None until the first pass finishes)
+#[tauri::command]
+pub fn get_cleanup_report(state: State<JanitorState>) -> Option<CleanupReport> {
+    state.0.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn run_cleanup_now(app: AppHandle) -> CleanupReport {
+    janitor::run_once(&app)
+}
+
+#[tauri::command]
+pub fn get_maintenance_status(
+    state: State<MaintenanceState>,
+    store: State<JsonStore>,
+) -> MaintenanceStatus {
+    state.status(store.get_setting_or(
+        maintenance::MAINTENANCE_AGGRESSIVENESS_KEY,
+        MaintenanceAggressiveness::default(),
+    ))
+}
+
+// Run every maintenance task immediately, ignoring idle/power state
+#[tauri::command]
+pub async fn run_maintenance_now(app: AppHandle) -> Result<MaintenanceReport, String> {
+    tokio::task::spawn_blocking(move || maintenance::run_all(&app))
+        .await
+        .map_err(|e| format!("Failed to run maintenance: {}", e))
+}
+
+// Detect cloud-sync conflict copies of metadata.json (without changing anything)
+#[tauri::command]
+pub fn check_metadata_conflicts(store: State<JsonStore>) -> Result<ConflictReport, String> {
+    store.merge_metadata_conflicts(false)
+}
+
+// Merge conflict copies into metadata.json and move them to backups/
+#[tauri::command]
+pub fn resolve_metadata_conflicts(store: State<JsonStore>) -> Result<ConflictReport, String> {
+    store.merge_metadata_conflicts(true)
+}
+
 // Projects
 #[tauri::command]
-pub fn get_projects(store: State<JsonStore>) -> Result<Vec<Project>, String> {
-    store.get_all_projects()
+pub fn get_projects(
+    sort: Option<ProjectSort>,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+) -> Result<Vec<Project>, String> {
+    let mut projects = store.get_all_projects()?;
+    usage.fill_opened(&mut projects);
+    sort.unwrap_or_default().apply(&mut projects);
+    Ok(projects)
+}
+
+// Most recently opened projects on this machine, newest first (never-opened excluded)
+#[tauri::command]
+pub fn get_recent_projects(
+    limit: Option<usize>,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+) -> Result<Vec<Project>, String> {
+    let mut projects = store.get_all_projects()?;
+    usage.fill_opened(&mut projects);
+    ProjectSort::RecentlyOpened.apply(&mut projects);
+    projects.retain(|p| p.last_opened_at.is_some());
+    projects.truncate(limit.unwrap_or(10));
+    Ok(projects)
+}
+
+// Record that a window for the project was opened (machine-local, doesn't touch updated_at)
+#[tauri::command]
+pub fn touch_project_opened(
+    id: String,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+) -> Result<Project, String> {
+    let mut project = store
+        .get_project_by_id(&id)?
+        .ok_or_else(|| format!("Project {} not found", id))?;
+    usage.record_project_opened(&id)?;
+    project.items = None;
+    usage.fill_opened(std::slice::from_mut(&mut project));
+    Ok(project)
+}
+
+#[tauri::command]
+pub fn get_project(
+    id: String,
+    itemSort: Option<ItemSort>,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+) -> Result<Option<Project>, String> {
+    let mut project = store.get_project_by_id(&id)?;
+    if let Some(items) = project.as_mut().and_then(|p| p.items.as_mut()) {
+        itemSort
+            .unwrap_or_default()
+            .apply(items, &usage.item_launches());
+    }
+    Ok(project)
 }
 
+// Items launched most often on this machine, from one project or across all projects
 #[tauri::command]
-pub fn get_project(id: String, store: State<JsonStore>) -> Result<Option<Project>, String> {
-    store.get_project_by_id(&id)
+pub fn get_most_used_items(
+    projectId: Option<String>,
+    limit: Option<usize>,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+) -> Result<Vec<Item>, String> {
+    store.get_most_used_items(
+        projectId.as_deref(),
+        &usage.item_launches(),
+        limit.unwrap_or(10),
+    )
 }
 
 #[tauri::command]
@@ -39,22 +210,153 @@ pub fn create_project(
     metadata: Option<ProjectMetadata>,
     store: State<JsonStore>,
 ) -> Result<Project, String> {
-    store.create_project(
-        &name,
-        &description.unwrap_or_default(),
-        metadata.unwrap_or_default(),
-    )
+    let mut metadata = metadata.unwrap_or_default();
+    check_custom_fields(&store, None, &mut metadata)?;
+    store.create_project(&name, &description.unwrap_or_default(), metadata)
+}
+
+#[tauri::command]
+pub fn duplicate_project(
+    id: String,
+    newName: String,
+    store: State<JsonStore>,
+) -> Result<Project, String> {
+    store.duplicate_project(&id, &newName)
 }
 
+// Adding a working dir enriches the project's links in the background (unless the
+// autoEnrichMetadata setting is "false"), emitting `project-enriched` when done
 #[tauri::command]
 pub fn update_project(
     id: String,
     name: Option<String>,
     description: Option<String>,
     metadata: Option<ProjectMetadata>,
+    app: AppHandle,
     store: State<JsonStore>,
 ) -> Result<Option<Project>, String> {
-    store.update_project(&id, name.as_deref(), description.as_deref(), metadata)
+    let metadata = match metadata {
+        Some(mut metadata) => {
+            check_custom_fields(&store, Some(&id), &mut metadata)?;
+            Some(metadata)
+        }
+        None => None,
+    };
+
+    let dir_key = |dir: &WorkingDir| (dir.path.clone(), dir.host.clone());
+    let previous_dirs: HashSet<_> = store
+        .get_project_by_id(&id)?
+        .and_then(|p| p.metadata.working_dirs)
+        .unwrap_or_default()
+        .iter()
+        .map(dir_key)
+        .collect();
+    let adds_dir = metadata
+        .as_ref()
+        .and_then(|m| m.working_dirs.as_ref())
+        .is_some_and(|dirs| {
+            dirs.iter()
+                .any(|dir| !previous_dirs.contains(&dir_key(dir)))
+        });
+
+    let updated = store.update_project(&id, name.as_deref(), description.as_deref(), metadata)?;
+    if adds_dir && store.get_setting(AUTO_ENRICH_METADATA_KEY)?.as_deref() != Some("false") {
+        tauri::async_runtime::spawn(async move {
+            let store = app.state::<JsonStore>();
+            match enrich_metadata(&store, &app.state::<HostStatusCache>(), &id).await {
+                Ok(Some(project)) => {
+                    let _ = app.emit(PROJECT_ENRICHED_EVENT, project);
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to enrich project {}: {}", id, e),
+            }
+        });
+    }
+    Ok(updated)
+}
+
+// Validate custom field values against the schema in settings (empty values are dropped)
+fn check_custom_fields(
+    store: &JsonStore,
+    project_id: Option<&str>,
+    metadata: &mut ProjectMetadata,
+) -> Result<(), String> {
+    let Some(fields) = metadata.custom_fields.as_mut() else {
+        return Ok(());
+    };
+    let previous = match project_id {
+        Some(id) => store
+            .get_project_by_id(id)?
+            .and_then(|p| p.metadata.custom_fields),
+        None => None,
+    };
+    custom_fields::validate(
+        &custom_fields::load_schema(store)?,
+        fields,
+        previous.as_ref(),
+    )?;
+    if fields.is_empty() {
+        metadata.custom_fields = None;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_custom_field_schema(
+    store: State<JsonStore>,
+) -> Result<Vec<CustomFieldDefinition>, String> {
+    custom_fields::load_schema(&store)
+}
+
+#[tauri::command]
+pub fn save_custom_field_schema(
+    schema: Vec<CustomFieldDefinition>,
+    store: State<JsonStore>,
+) -> Result<(), String> {
+    custom_fields::save_schema(&store, &schema)
+}
+
+// Allow/deny lists of executables and destructive patterns that need confirming,
+// enforced by run_command, open_coding_agent and the custom IDE commands
+#[tauri::command]
+pub fn get_command_policy(store: State<JsonStore>) -> Result<CommandPolicy, String> {
+    policy::load(&store)
+}
+
+#[tauri::command]
+pub fn save_command_policy(policy: CommandPolicy, store: State<JsonStore>) -> Result<(), String> {
+    policy::save(&store, &policy)
+}
+
+// Projects trusted on this machine skip the policy's allowlist and confirmations.
+// Trust is kept in settings.json, so it never travels with synced or imported data.
+#[tauri::command]
+pub fn get_trusted_projects(settings_file: State<SettingsFile>) -> Vec<String> {
+    settings_file.trusted_projects()
+}
+
+#[tauri::command]
+pub fn set_project_trusted(
+    projectId: String,
+    trusted: bool,
+    settings_file: State<SettingsFile>,
+) -> Result<(), String> {
+    settings_file.set_trusted(&projectId, trusted)
+}
+
+// Shell for commands that don't pick one (None: the platform shell locally, the
+// login shell over SSH). Kept in settings.json, as it depends on the machine.
+#[tauri::command]
+pub fn get_default_shell(settings_file: State<SettingsFile>) -> Option<CommandShell> {
+    settings_file.default_shell()
+}
+
+#[tauri::command]
+pub fn set_default_shell(
+    shell: Option<CommandShell>,
+    settings_file: State<SettingsFile>,
+) -> Result<(), String> {
+    settings_file.set_default_shell(shell)
 }
 
 #[tauri::command]
@@ -62,6 +364,123 @@ pub fn delete_project(id: String, store: State<JsonStore>) -> Result<bool, Strin
     store.delete_project(&id)
 }
 
+// Find git repositories (and optionally package.json / Cargo.toml roots) under a folder
+#[tauri::command]
+pub async fn scan_directory_for_projects(
+    root: String,
+    includeManifests: Option<bool>,
+    maxDepth: Option<usize>,
+    store: State<'_, JsonStore>,
+) -> Result<Vec<ProjectCandidate>, String> {
+    let root_path = std::path::PathBuf::from(&root);
+    if !root_path.is_dir() {
+        return Err(format!("{} is not a directory", root));
+    }
+
+    let include_manifests = includeManifests.unwrap_or(false);
+    let max_depth = maxDepth.unwrap_or(discovery::DEFAULT_SCAN_DEPTH);
+    let mut candidates = tokio::task::spawn_blocking(move || {
+        discovery::find_project_roots(&root_path, include_manifests, max_depth)
+    })
+    .await
+    .map_err(|e| format!("Failed to scan directory: {}", e))?;
+
+    let known_paths = store
+        .get_all_projects()?
+        .into_iter()
+        .flat_map(|p| p.metadata.working_dirs.unwrap_or_default())
+        .filter(|dir| dir.host.is_none())
+        .map(|dir| dir.path)
+        .collect();
+    discovery::annotate_candidates(&mut candidates, &known_paths).await;
+
+    Ok(candidates)
+}
+
+// Create one project per scanned candidate
+#[tauri::command]
+pub fn import_project_candidates(
+    candidates: Vec<ProjectCandidate>,
+    store: State<JsonStore>,
+) -> Result<Vec<Project>, String> {
+    candidates
+        .into_iter()
+        .map(|candidate| store.create_project(&candidate.name, "", candidate.metadata))
+        .collect()
+}
+
+// Setting key that disables automatic metadata enrichment when set to "false"
+const AUTO_ENRICH_METADATA_KEY: &str = "autoEnrichMetadata";
+
+// Emitted with the project after enriching it for a newly added working dir
+const PROJECT_ENRICHED_EVENT: &str = "project-enriched";
+
+// Fill in missing project links from the git remotes of its working directories
+#[tauri::command]
+pub async fn enrich_project_metadata(
+    projectId: String,
+    store: State<'_, JsonStore>,
+    cache: State<'_, HostStatusCache>,
+) -> Result<Option<Project>, String> {
+    enrich_metadata(&store, &cache, &projectId).await
+}
+
+async fn enrich_metadata(
+    store: &JsonStore,
+    cache: &HostStatusCache,
+    project_id: &str,
+) -> Result<Option<Project>, String> {
+    let project = match store.get_project_by_id(project_id)? {
+        Some(p) => p,
+        None => return Ok(None),
+    };
+
+    let mut metadata = project.metadata.clone();
+    let working_dirs = metadata.working_dirs.clone().unwrap_or_default();
+    let mut changed = false;
+
+    for dir in &working_dirs {
+        if let Some(host) = dir.host.as_deref() {
+            if ensure_host_online(host, cache, store).is_err() {
+                continue;
+            }
+        }
+        // Skip directories that are not git repos or have no origin remote
+        let Ok(remote) = git::get_remote_url(&dir.path, dir.host.as_deref()).await else {
+            continue;
+        };
+        let Some(web_url) = git::remote_to_web_url(&remote) else {
+            continue;
+        };
+
+        match GitProvider::from_url(&web_url) {
+            Some(GitProvider::GitHub)
+                if metadata.github_url.as_deref().is_none_or(str::is_empty) =>
+            {
+                metadata.github_url = Some(web_url);
+                changed = true;
+            }
+            Some(GitProvider::GitHub) | None => {}
+            Some(provider) => {
+                let links = metadata.other_links.get_or_insert_with(Vec::new);
+                if !links.iter().any(|l| l.url == web_url) {
+                    links.push(OtherLink {
+                        label: provider.label().to_string(),
+                        url: web_url,
+                    });
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    if !changed {
+        return Ok(Some(project));
+    }
+
+    store.update_project(project_id, None, None, Some(metadata))
+}
+
 // Items
 #[tauri::command]
 pub fn create_item(
@@ -77,6 +496,13 @@ pub fn create_item(
     commandMode: Option<CommandMode>,
     commandCwd: Option<String>,
     commandHost: Option<String>,
+    databaseClient: Option<DatabaseClient>,
+    language: Option<String>,
+    tmuxSession: Option<String>,
+    ports: Option<Vec<u16>>,
+    commandShell: Option<CommandShell>,
+    wslDistro: Option<String>,
+    customAgentId: Option<String>,
     store: State<JsonStore>,
 ) -> Result<Item, String> {
     store.create_item(
@@ -92,6 +518,13 @@ pub fn create_item(
         commandMode,
         commandCwd.as_deref(),
         commandHost.as_deref(),
+        databaseClient,
+        language.as_deref(),
+        tmuxSession.as_deref(),
+        ports,
+        commandShell,
+        wslDistro.as_deref(),
+        customAgentId.as_deref(),
     )
 }
 
@@ -108,6 +541,13 @@ pub fn update_item(
     commandMode: Option<Option<CommandMode>>,
     commandCwd: Option<Option<String>>,
     commandHost: Option<Option<String>>,
+    databaseClient: Option<Option<DatabaseClient>>,
+    language: Option<Option<String>>,
+    tmuxSession: Option<Option<String>>,
+    ports: Option<Option<Vec<u16>>>,
+    commandShell: Option<Option<CommandShell>>,
+    wslDistro: Option<Option<String>>,
+    customAgentId: Option<Option<String>>,
     order: Option<i32>,
     store: State<JsonStore>,
 ) -> Result<Option<Item>, String> {
@@ -123,15 +563,51 @@ pub fn update_item(
         commandMode,
         commandCwd.as_ref().map(|o| o.as_deref()),
         commandHost.as_ref().map(|o| o.as_deref()),
+        databaseClient,
+        language.as_ref().map(|o| o.as_deref()),
+        tmuxSession.as_ref().map(|o| o.as_deref()),
+        ports,
+        commandShell,
+        wslDistro.as_ref().map(|o| o.as_deref()),
+        customAgentId.as_ref().map(|o| o.as_deref()),
         order,
     )
 }
 
 #[tauri::command]
 pub fn delete_item(id: String, store: State<JsonStore>) -> Result<bool, String> {
+    if store
+        .find_item(&id)
+        .is_some_and(|item| item.item_type == ItemType::Database)
+    {
+        // Best-effort; an orphaned keyring entry is harmless
+        let _ = keyring::delete_secret(&database::secret_account(&id));
+    }
     store.delete_item(&id)
 }
 
+#[tauri::command]
+pub fn delete_items(ids: Vec<String>, store: State<JsonStore>) -> Result<usize, String> {
+    for id in &ids {
+        if store
+            .find_item(id)
+            .is_some_and(|item| item.item_type == ItemType::Database)
+        {
+            let _ = keyring::delete_secret(&database::secret_account(id));
+        }
+    }
+    store.delete_items(&ids)
+}
+
+#[tauri::command]
+pub fn update_items_common_fields(
+    ids: Vec<String>,
+    patch: ItemPatch,
+    store: State<JsonStore>,
+) -> Result<Vec<Item>, String> {
+    store.update_items(&ids, &patch)
+}
+
 #[tauri::command]
 pub fn reorder_items(
     projectId: String,
@@ -165,6 +641,64 @@ pub fn create_file_card(
     )
 }
 
+// Sticky note on the canvas, positioned and z-ordered like file cards
+#[tauri::command]
+pub fn create_note_card(
+    projectId: String,
+    title: Option<String>,
+    content: Option<String>,
+    positionX: Option<f64>,
+    positionY: Option<f64>,
+    store: State<JsonStore>,
+) -> Result<FileCard, String> {
+    store.create_card(
+        &projectId,
+        FileCard {
+            kind: CardKind::Note,
+            filename: title.unwrap_or_default(),
+            content: Some(content.unwrap_or_default()),
+            position_x: positionX.unwrap_or(100.0),
+            position_y: positionY.unwrap_or(100.0),
+            ..Default::default()
+        },
+    )
+}
+
+// A link card's URL, trimmed; only http(s) URLs are opened from the canvas
+fn link_url(url: &str) -> Result<String, String> {
+    let url = url.trim().to_string();
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("Not an http(s) URL: {}", url));
+    }
+    Ok(url)
+}
+
+// Link card on the canvas; the title defaults to the URL
+#[tauri::command]
+pub fn create_link_card(
+    projectId: String,
+    url: String,
+    title: Option<String>,
+    positionX: Option<f64>,
+    positionY: Option<f64>,
+    store: State<JsonStore>,
+) -> Result<FileCard, String> {
+    let url = link_url(&url)?;
+    store.create_card(
+        &projectId,
+        FileCard {
+            kind: CardKind::Link,
+            filename: title
+                .filter(|t| !t.trim().is_empty())
+                .unwrap_or(url.clone()),
+            url: Some(url),
+            position_x: positionX.unwrap_or(100.0),
+            position_y: positionY.unwrap_or(100.0),
+            ..Default::default()
+        },
+    )
+}
+
 #[tauri::command]
 pub fn update_file_card(
     id: String,
@@ -175,8 +709,12 @@ pub fn update_file_card(
     isExpanded: Option<bool>,
     isMinimized: Option<bool>,
     zIndex: Option<i32>,
+    maxSize: Option<Option<u64>>,
+    content: Option<String>,
+    url: Option<String>,
     store: State<JsonStore>,
 ) -> Result<Option<FileCard>, String> {
+    let url = url.as_deref().map(link_url).transpose()?;
     store.update_file_card(
         &id,
         filename.as_deref(),
@@ -186,14 +724,79 @@ pub fn update_file_card(
         isExpanded,
         isMinimized,
         zIndex,
+        maxSize,
+        content.as_deref(),
+        url.as_deref(),
     )
 }
 
 #[tauri::command]
-pub fn delete_file_card(id: String, store: State<JsonStore>) -> Result<bool, String> {
+pub fn delete_file_card(
+    id: String,
+    store: State<JsonStore>,
+    watcher: State<FileCardWatcher>,
+) -> Result<bool, String> {
+    watcher.unwatch(&id);
     store.delete_file_card(&id)
 }
 
+// Emit `file-card://changed` with the card ID whenever the card's file changes
+// on disk, until the card is unwatched (closed) or deleted
+#[tauri::command]
+pub fn watch_file_card(
+    app: AppHandle,
+    cardId: String,
+    store: State<JsonStore>,
+    watcher: State<FileCardWatcher>,
+) -> Result<(), String> {
+    let card = store.find_file_card(&cardId).ok_or("File card not found")?;
+    if card.kind != CardKind::File {
+        return Err("Only file cards can be watched".to_string());
+    }
+    watcher.watch(&app, &card.id, Path::new(&card.file_path))
+}
+
+#[tauri::command]
+pub fn unwatch_file_card(cardId: String, watcher: State<FileCardWatcher>) {
+    watcher.unwatch(&cardId);
+}
+
+// Canvas layouts: named snapshots of card positions and expanded/minimized states
+#[tauri::command]
+pub fn save_canvas_layout(
+    projectId: String,
+    name: String,
+    store: State<JsonStore>,
+) -> Result<CanvasLayout, String> {
+    store.save_canvas_layout(&projectId, &name)
+}
+
+#[tauri::command]
+pub fn apply_canvas_layout(
+    projectId: String,
+    name: String,
+    store: State<JsonStore>,
+) -> Result<Vec<FileCard>, String> {
+    store.apply_canvas_layout(&projectId, &name)
+}
+
+#[tauri::command]
+pub fn get_canvas_layouts(
+    projectId: String,
+    store: State<JsonStore>,
+) -> Result<Vec<CanvasLayout>, String> {
+    store.get_canvas_layouts(&projectId)
+}
+
+#[tauri::command]
+pub fn delete_canvas_layout(
+    projectId: String,
+    name: String,
+    store: State<JsonStore>,
+) -> Result<bool, String> {
+    store.delete_canvas_layout(&projectId, &name)
+}
+
 // Settings
 #[tauri::command]
 pub fn get_all_settings(store: State<JsonStore>) -> Result<HashMap<String, String>, String> {
@@ -215,60 +818,288 @@ pub fn delete_setting(key: String, store: State<JsonStore>) -> Result<(), String
     store.delete_setting(&key)
 }
 
-// Export/Import
+// Drafts (per-window unsaved UI state)
 #[tauri::command]
-pub fn export_data(
-    projectIds: Option<Vec<String>>,
+pub fn save_draft(
+    window: tauri::Window,
+    key: String,
+    content: String,
     store: State<JsonStore>,
-) -> Result<ExportData, String> {
-    store.export_all_data(projectIds)
+) -> Result<Draft, String> {
+    store.save_draft(window.label(), &key, &content)
 }
 
 #[tauri::command]
-pub fn export_data_to_file(
-    filePath: String,
-    projectIds: Option<Vec<String>>,
+pub fn load_draft(
+    window: tauri::Window,
+    key: String,
     store: State<JsonStore>,
-) -> Result<usize, String> {
-    let data = store.export_all_data(projectIds)?;
-    let json = serde_json::to_string_pretty(&data)
-        .map_err(|e| format!("Failed to serialize data: {}", e))?;
-    let count = data.projects.len();
-    fs::write(&filePath, &json).map_err(|e| format!("Failed to write file: {}", e))?;
-    Ok(count)
+) -> Result<Option<Draft>, String> {
+    store.load_draft(window.label(), &key)
 }
 
 #[tauri::command]
-pub fn import_data(
-    data: ImportData,
-    mode: Option<String>,
+pub fn delete_draft(
+    window: tauri::Window,
+    key: String,
     store: State<JsonStore>,
-) -> Result<ImportResult, String> {
-    store.import_data(data, &mode.unwrap_or_else(|| "merge".to_string()))
+) -> Result<bool, String> {
+    store.delete_draft(window.label(), &key)
 }
 
-// System operations
 #[tauri::command]
-pub fn open_ide(ideType: IdeType, path: String) -> Result<(), String> {
-    let cmd = match ideType {
-        // JetBrains IDEs
-        IdeType::Idea => "idea",
-        IdeType::Pycharm => "pycharm",
-        IdeType::Webstorm => "webstorm",
-        IdeType::Phpstorm => "phpstorm",
-        IdeType::Rubymine => "rubymine",
-        IdeType::Clion => "clion",
-        IdeType::Goland => "goland",
-        IdeType::Rider => "rider",
-        IdeType::Datagrip => "datagrip",
-        IdeType::Rustrover => "rustrover",
-        IdeType::Aqua => "aqua",
-        // Other IDEs
-        IdeType::Cursor => "cursor",
+pub fn list_drafts(store: State<JsonStore>) -> Result<Vec<Draft>, String> {
+    store.list_drafts()
+}
+
+// Git-backed data directory
+#[tauri::command]
+pub fn set_store_git_backed(enabled: bool, store: State<JsonStore>) -> Result<(), String> {
+    store.set_git_backed(enabled)
+}
+
+#[tauri::command]
+pub fn get_store_history(
+    limit: Option<usize>,
+    store: State<JsonStore>,
+) -> Result<Vec<StoreCommit>, String> {
+    store.get_store_history(limit.unwrap_or(50))
+}
+
+#[tauri::command]
+pub fn restore_store_commit(hash: String, store: State<JsonStore>) -> Result<(), String> {
+    store.restore_store_commit(&hash)
+}
+
+// Remote sync (WebDAV / S3)
+#[tauri::command]
+pub fn get_sync_settings(settings_file: State<SettingsFile>) -> Option<SyncConfig> {
+    settings_file.get_sync_config()
+}
+
+#[tauri::command]
+pub fn set_sync_settings(
+    config: Option<SyncConfig>,
+    settings_file: State<SettingsFile>,
+) -> Result<(), String> {
+    settings_file.set_sync_config(config)
+}
+
+#[tauri::command]
+pub fn get_sync_status(store: State<JsonStore>, settings_file: State<SettingsFile>) -> SyncStatus {
+    sync::get_status(
+        settings_file.get_sync_config().as_ref(),
+        &store.data_path(),
+        settings_file.config_dir(),
+    )
+}
+
+#[tauri::command]
+pub async fn sync_now(
+    store: State<'_, JsonStore>,
+    settings_file: State<'_, SettingsFile>,
+) -> Result<SyncReport, String> {
+    let config = settings_file
+        .get_sync_config()
+        .ok_or("Remote sync is not configured")?;
+    store.flush()?;
+    let report = sync::sync_now(&config, &store.data_path(), settings_file.config_dir()).await?;
+
+    // Pick up anything that was pulled
+    if !report.pulled.is_empty() {
+        store.reload()?;
+    }
+
+    Ok(report)
+}
+
+// Export/Import
+#[tauri::command]
+pub fn export_data(
+    projectIds: Option<Vec<String>>,
+    store: State<JsonStore>,
+) -> Result<ExportData, String> {
+    store.export_all_data(projectIds)
+}
+
+#[tauri::command]
+pub fn export_data_to_file(
+    filePath: String,
+    projectIds: Option<Vec<String>>,
+    store: State<JsonStore>,
+) -> Result<usize, String> {
+    let data = store.export_all_data(projectIds)?;
+    let json = serde_json::to_string_pretty(&data)
+        .map_err(|e| format!("Failed to serialize data: {}", e))?;
+    let count = data.projects.len();
+    fs::write(&filePath, &json).map_err(|e| format!("Failed to write file: {}", e))?;
+    Ok(count)
+}
+
+// Export only projects changed since the last export to the same destination.
+// The first export to a destination is a full export.
+#[tauri::command]
+pub fn export_incremental(
+    filePath: String,
+    destination: Option<String>,
+    store: State<JsonStore>,
+    settings_file: State<SettingsFile>,
+) -> Result<ExportData, String> {
+    let destination = destination.unwrap_or_else(|| filePath.clone());
+    let config_dir = settings_file.config_dir();
+    let previous = exports::last_export(config_dir, &destination);
+
+    let hashes = store.project_hashes();
+    let changed: Vec<String> = hashes
+        .iter()
+        .filter(|(id, hash)| {
+            previous
+                .as_ref()
+                .and_then(|mark| mark.project_hashes.get(*id))
+                != Some(*hash)
+        })
+        .map(|(id, _)| id.clone())
+        .collect();
+
+    let mut data = store.export_all_data(Some(changed))?;
+    if let Some(mark) = &previous {
+        data.since = Some(mark.exported_at.clone());
+        data.deleted_project_ids = Some(
+            mark.project_hashes
+                .keys()
+                .filter(|id| !hashes.contains_key(*id))
+                .cloned()
+                .collect(),
+        );
+    }
+
+    let json = serde_json::to_string_pretty(&data)
+        .map_err(|e| format!("Failed to serialize data: {}", e))?;
+    fs::write(&filePath, &json).map_err(|e| format!("Failed to write file: {}", e))?;
+
+    // Only a written export moves the baseline forward
+    exports::record_export(
+        config_dir,
+        &destination,
+        ExportMark {
+            exported_at: data.exported_at.clone(),
+            project_hashes: hashes,
+        },
+    )?;
+    Ok(data)
+}
+
+// Apply an incremental export: upsert its projects and remove deleted ones.
+// Upserted projects overwrite local changes to them (todo history, time entries and
+// canvas layouts are kept). Deletions are counted in `projectsDeleted`, and a
+// snapshot taken before them is returned as `snapshotId` (see restore_snapshot).
+#[tauri::command]
+pub fn import_incremental(
+    data: ImportData,
+    store: State<JsonStore>,
+) -> Result<ImportResult, String> {
+    store.apply_delta(data)
+}
+
+// Bundle a project export with its environment manifest into one file for onboarding
+#[tauri::command]
+pub fn export_handoff(
+    projectId: String,
+    filePath: String,
+    store: State<JsonStore>,
+) -> Result<HandoffPackage, String> {
+    let project = store
+        .get_project_by_id(&projectId)?
+        .ok_or_else(|| format!("Project {} not found", projectId))?;
+    // The package is meant to be handed on, so env values stay behind
+    let mut export = store.export_all_data(Some(vec![projectId]))?;
+    handoff::redact_env(&mut export);
+    let package = HandoffPackage {
+        environment: handoff::build_manifest(&store, &project),
+        export,
+    };
+    let json = serde_json::to_string_pretty(&package)
+        .map_err(|e| format!("Failed to serialize handoff package: {}", e))?;
+    fs::write(&filePath, &json).map_err(|e| format!("Failed to write file: {}", e))?;
+    Ok(package)
+}
+
+// Replace imports delete everything first: the caller must fetch a confirmation
+// token here, show what will be deleted, and pass the token back to import_data
+#[tauri::command]
+pub fn prepare_replace_import(
+    store: State<JsonStore>,
+    guard: State<ReplaceImportGuard>,
+) -> Result<ReplaceImportConfirmation, String> {
+    Ok(ReplaceImportConfirmation {
+        token: guard.issue(),
+        projects_to_delete: store.get_all_projects()?.len(),
+    })
+}
+
+#[tauri::command]
+pub fn import_data(
+    data: ImportData,
+    mode: Option<String>,
+    confirmationToken: Option<String>,
+    store: State<JsonStore>,
+    guard: State<ReplaceImportGuard>,
+) -> Result<ImportResult, String> {
+    let mode = mode.unwrap_or_else(|| "merge".to_string());
+    if mode == "replace" {
+        guard.consume(confirmationToken.as_deref())?;
+    }
+    store.import_data(data, &mode)
+}
+
+// Data snapshots (taken before replace imports and by scheduled maintenance)
+#[tauri::command]
+pub fn list_snapshots(store: State<JsonStore>) -> Vec<String> {
+    store.list_snapshots()
+}
+
+#[tauri::command]
+pub fn restore_snapshot(snapshotId: String, store: State<JsonStore>) -> Result<(), String> {
+    store.restore_snapshot(&snapshotId)
+}
+
+// System operations
+#[tauri::command]
+pub fn open_ide(
+    ideType: IdeType,
+    path: String,
+    itemId: Option<String>,
+    usage: State<UsageFile>,
+) -> Result<(), String> {
+    let cmd = match ideType {
+        // JetBrains IDEs
+        IdeType::Idea => "idea",
+        IdeType::Pycharm => "pycharm",
+        IdeType::Webstorm => "webstorm",
+        IdeType::Phpstorm => "phpstorm",
+        IdeType::Rubymine => "rubymine",
+        IdeType::Clion => "clion",
+        IdeType::Goland => "goland",
+        IdeType::Rider => "rider",
+        IdeType::Datagrip => "datagrip",
+        IdeType::Rustrover => "rustrover",
+        IdeType::Aqua => "aqua",
+        // Other IDEs
+        IdeType::Cursor => "cursor",
         IdeType::Vscode => "code",
         IdeType::Zed => "zed",
         IdeType::Antigravity => "antigravity",
     };
+    // JetBrains launchers installed by Toolbox often aren't on PATH
+    let is_jetbrains = !matches!(
+        ideType,
+        IdeType::Cursor | IdeType::Vscode | IdeType::Zed | IdeType::Antigravity
+    );
+    let launcher = if is_jetbrains {
+        jetbrains::launcher(cmd)
+    } else {
+        cmd.to_string()
+    };
 
     #[cfg(windows)]
     {
@@ -276,9 +1107,10 @@ pub fn open_ide(ideType: IdeType, path: String) -> Result<(), String> {
         const CREATE_NO_WINDOW: u32 = 0x08000000;
         const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
 
-        // Use cmd /c to run .cmd files, hide console and detach from parent
+        // Use cmd /c to run .cmd files, hide console and detach from parent. With /s
+        // cmd keeps the quotes around a launcher path with spaces.
         Command::new("cmd")
-            .args(["/c", cmd, &path])
+            .raw_arg(format!("/s /c \"\"{}\" \"{}\"\"", launcher, path))
             .creation_flags(CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP)
             .spawn()
             .map_err(|e| format!("Failed to open IDE: {}", e))?;
@@ -286,19 +1118,43 @@ pub fn open_ide(ideType: IdeType, path: String) -> Result<(), String> {
 
     #[cfg(not(windows))]
     {
-        Command::new(cmd)
+        Command::new(&launcher)
             .arg(&path)
             .spawn()
-            .map_err(|e| format!("Failed to open IDE: {}", e))?;
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound if is_jetbrains => format!(
+                    "Failed to open IDE: `{}` is neither on PATH nor installed with JetBrains Toolbox",
+                    cmd
+                ),
+                _ => format!("Failed to open IDE: {}", e),
+            })?;
     }
 
+    record_launch(&usage, itemId.as_deref());
     Ok(())
 }
 
 #[tauri::command]
-pub fn open_custom_ide(command: String, path: String) -> Result<(), String> {
-    // Replace {path} placeholder - no auto-quoting, user controls quoting in template
-    let full_command = command.replace("{path}", &path);
+pub fn open_custom_ide(
+    command: String,
+    path: String,
+    itemId: Option<String>,
+    confirmed: Option<bool>,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+    settings_file: State<SettingsFile>,
+) -> Result<(), String> {
+    let project_id = policy::item_project(&store, itemId.as_deref());
+    policy::check(
+        &store,
+        &settings_file,
+        project_id.as_deref(),
+        &command,
+        confirmed.unwrap_or(false),
+    )?;
+
+    // {path} is quoted for the template (quotes written around it are respected)
+    let values = [("path", path.as_str())];
 
     #[cfg(windows)]
     {
@@ -307,7 +1163,7 @@ pub fn open_custom_ide(command: String, path: String) -> Result<(), String> {
         const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
 
         Command::new("cmd")
-            .raw_arg(format!("/c {}", full_command))
+            .raw_arg(format!("/c {}", templates::render_cmd(&command, &values)))
             .creation_flags(CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP)
             .spawn()
             .map_err(|e| format!("Failed to open custom IDE: {}", e))?;
@@ -315,21 +1171,50 @@ pub fn open_custom_ide(command: String, path: String) -> Result<(), String> {
 
     #[cfg(not(windows))]
     {
-        Command::new("sh")
-            .args(["-c", &full_command])
+        templates::render(&command, &values)
+            .command()
             .spawn()
             .map_err(|e| format!("Failed to open custom IDE: {}", e))?;
     }
 
+    record_launch(&usage, itemId.as_deref());
     Ok(())
 }
 
+// Open `path` on `host` in a remote IDE. For Gateway, `ide` is the JetBrains IDE
+// (IntelliJ IDEA by default) it deploys when no backend is running on the host yet;
+// otherwise it joins the running one.
 #[tauri::command]
-pub fn open_remote_ide(
+pub async fn open_remote_ide(
     remoteIdeType: RemoteIdeType,
     host: String,
     path: String,
+    ide: Option<IdeType>,
+    itemId: Option<String>,
+    app: AppHandle,
+    store: State<'_, JsonStore>,
+    usage: State<'_, UsageFile>,
 ) -> Result<(), String> {
+    // Gateway is opened through its URL handler
+    if remoteIdeType == RemoteIdeType::Gateway {
+        use tauri_plugin_opener::OpenerExt;
+
+        let ide = ide.unwrap_or(IdeType::Idea);
+        let product = jetbrains::product_code(&ide)
+            .ok_or_else(|| format!("{} can't be deployed by JetBrains Gateway", ide))?;
+        let jump = hosts::jump_chain(&store, &host);
+        let product = if jetbrains::backend_running(&host, jump.as_deref()).await {
+            None
+        } else {
+            Some(product)
+        };
+        app.opener()
+            .open_url(jetbrains::gateway_url(&host, &path, product), None::<&str>)
+            .map_err(|e| format!("Failed to open JetBrains Gateway: {}", e))?;
+        record_launch(&usage, itemId.as_deref());
+        return Ok(());
+    }
+
     // Zed uses a different URI format: zed ssh://host/path
     if remoteIdeType == RemoteIdeType::Zed {
         let ssh_uri = format!("ssh://{}{}", host, path);
@@ -355,14 +1240,17 @@ pub fn open_remote_ide(
                 .map_err(|e| format!("Failed to open Zed remote: {}", e))?;
         }
 
+        record_launch(&usage, itemId.as_deref());
         return Ok(());
     }
 
-    // VS Code / Cursor use vscode-remote URI format
+    // VS Code and its forks use vscode-remote URI format
     let cmd = match remoteIdeType {
         RemoteIdeType::Cursor => "cursor",
         RemoteIdeType::Vscode => "code",
-        RemoteIdeType::Zed => unreachable!(), // Handled above
+        RemoteIdeType::Windsurf => "windsurf",
+        RemoteIdeType::Vscodium => "codium",
+        RemoteIdeType::Zed | RemoteIdeType::Gateway => unreachable!(), // Handled above
     };
 
     let folder_uri = format!("vscode-remote://ssh-remote+{}{}", host, path);
@@ -389,13 +1277,41 @@ pub fn open_remote_ide(
             .map_err(|e| format!("Failed to open remote IDE: {}", e))?;
     }
 
+    record_launch(&usage, itemId.as_deref());
     Ok(())
 }
 
 #[tauri::command]
-pub fn open_custom_remote_ide(command: String, host: String, path: String) -> Result<(), String> {
-    // Replace {host} and {path} placeholders - no auto-quoting, user controls quoting in template
-    let full_command = command.replace("{host}", &host).replace("{path}", &path);
+pub fn open_custom_remote_ide(
+    command: String,
+    host: String,
+    path: String,
+    itemId: Option<String>,
+    confirmed: Option<bool>,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+    settings_file: State<SettingsFile>,
+) -> Result<(), String> {
+    let project_id = policy::item_project(&store, itemId.as_deref());
+    policy::check(
+        &store,
+        &settings_file,
+        project_id.as_deref(),
+        &command,
+        confirmed.unwrap_or(false),
+    )?;
+
+    // {host}, {path}, {user} (from the SSH config, else the local user) and {jump}
+    // (the host's jump chain, empty if none) are quoted for the template (quotes
+    // written around them are respected)
+    let jump = hosts::jump_chain(&store, &host).unwrap_or_default();
+    let (_, user, _) = ssh_config::connection(&host);
+    let values = [
+        ("host", host.as_str()),
+        ("path", path.as_str()),
+        ("user", user.as_str()),
+        ("jump", jump.as_str()),
+    ];
 
     #[cfg(windows)]
     {
@@ -405,7 +1321,7 @@ pub fn open_custom_remote_ide(command: String, host: String, path: String) -> Re
 
         // For terminal apps like nvim, user should include 'start cmd /k' in their command template
         Command::new("cmd")
-            .raw_arg(format!("/c {}", full_command))
+            .raw_arg(format!("/c {}", templates::render_cmd(&command, &values)))
             .creation_flags(CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP)
             .spawn()
             .map_err(|e| format!("Failed to open custom remote IDE: {}", e))?;
@@ -413,31 +1329,130 @@ pub fn open_custom_remote_ide(command: String, host: String, path: String) -> Re
 
     #[cfg(not(windows))]
     {
-        Command::new("sh")
-            .args(["-c", &full_command])
+        templates::render(&command, &values)
+            .command()
             .spawn()
             .map_err(|e| format!("Failed to open custom remote IDE: {}", e))?;
     }
 
+    record_launch(&usage, itemId.as_deref());
     Ok(())
 }
 
-// Helper function to merge environment variables
-// Agent env overrides global env for same keys
-fn merge_env_vars(global_env: Option<&str>, agent_env: Option<&str>) -> HashMap<String, String> {
-    let mut result = HashMap::new();
+// Open a remote folder through a URI template such as
+// `windsurf://vscode-remote/ssh-remote+{host}{path}`, for editors with a URL handler
+// but no built-in entry. {host}, {path} and {user} are percent-encoded.
+#[tauri::command]
+pub fn open_remote_uri(
+    uriTemplate: String,
+    host: String,
+    path: String,
+    itemId: Option<String>,
+    app: AppHandle,
+    usage: State<UsageFile>,
+) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
+
+    let (_, user, _) = ssh_config::connection(&host);
+    let uri = templates::fill_uri(
+        &uriTemplate,
+        &[
+            ("host", host.as_str()),
+            ("path", path.as_str()),
+            ("user", user.as_str()),
+        ],
+    );
+    app.opener()
+        .open_url(uri, None::<&str>)
+        .map_err(|e| format!("Failed to open remote URI: {}", e))?;
+
+    record_launch(&usage, itemId.as_deref());
+    Ok(())
+}
 
-    // Parse global env vars first
-    if let Some(json) = global_env {
-        if !json.is_empty() {
-            if let Ok(vars) = serde_json::from_str::<HashMap<String, String>>(json) {
-                result.extend(vars);
-            }
+// Open a folder inside a WSL distro in VS Code or a fork of it (their WSL remote).
+// The path may be a Windows path, which is translated to the distro's form.
+#[tauri::command]
+pub fn open_wsl_ide(
+    remoteIdeType: RemoteIdeType,
+    distro: String,
+    path: String,
+    itemId: Option<String>,
+    usage: State<UsageFile>,
+) -> Result<(), String> {
+    wsl::check_available()?;
+    let cmd = match remoteIdeType {
+        RemoteIdeType::Cursor => "cursor",
+        RemoteIdeType::Vscode => "code",
+        RemoteIdeType::Windsurf => "windsurf",
+        RemoteIdeType::Vscodium => "codium",
+        RemoteIdeType::Zed => return Err("Zed can't open folders in WSL".to_string()),
+        RemoteIdeType::Gateway => {
+            return Err("JetBrains Gateway can't open folders in WSL".to_string());
         }
+    };
+    let path = wsl::linux_path(&distro, &path)?;
+    let remote = format!("wsl+{}", distro);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+
+        // Use cmd /c to run .cmd files, hide console and detach from parent
+        Command::new("cmd")
+            .args(["/c", cmd, "--remote", &remote, &path])
+            .creation_flags(CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP)
+            .spawn()
+            .map_err(|e| format!("Failed to open WSL IDE: {}", e))?;
     }
 
-    // Parse agent env vars (overrides global)
-    if let Some(json) = agent_env {
+    #[cfg(not(windows))]
+    let _ = (cmd, remote, path);
+
+    record_launch(&usage, itemId.as_deref());
+    Ok(())
+}
+
+// Installed WSL distros with their state and version
+#[tauri::command]
+pub async fn list_wsl_distros() -> Result<Vec<WslDistro>, String> {
+    tokio::task::spawn_blocking(wsl::list_distros)
+        .await
+        .map_err(|e| format!("Failed to list WSL distros: {}", e))?
+}
+
+// Translate a path between Windows and a WSL distro (Windows to Linux by default)
+#[tauri::command]
+pub async fn translate_wsl_path(
+    distro: String,
+    path: String,
+    toWindows: Option<bool>,
+) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        wsl::translate_path(&distro, &path, toWindows.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Failed to translate path: {}", e))?
+}
+
+// Helper function to merge environment variables
+// Agent env overrides global env for same keys
+fn merge_env_vars(
+    global_env: Option<&str>,
+    custom_agent_env: Option<&str>,
+    host_env: Option<&str>,
+    agent_env: Option<&str>,
+) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+
+    // Global env vars first, then a custom agent's defaults, then host overrides, then
+    // agent env vars (most specific wins)
+    for json in [global_env, custom_agent_env, host_env, agent_env]
+        .into_iter()
+        .flatten()
+    {
         if !json.is_empty() {
             if let Ok(vars) = serde_json::from_str::<HashMap<String, String>>(json) {
                 result.extend(vars);
@@ -448,7 +1463,18 @@ fn merge_env_vars(global_env: Option<&str>, agent_env: Option<&str>) -> HashMap<
     result
 }
 
+// `env 'VAR=value' ` prefix that puts env in front of a command for a POSIX shell.
+// A remote login shell may well be fish, which runs it the same way.
+fn posix_env_prefix(env_vars: &HashMap<String, String>) -> String {
+    shell::env_prefix(env_vars, CommandShell::Sh)
+}
+
+// Open a coding agent in a terminal at `path`: locally, on `host` over SSH (path is
+// then on the host), or inside a WSL distro. With `prompt` the session starts with
+// it as the first message. `resumeArgs` go in front of the args for this launch
+// only; the session is recorded with the args alone.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn open_coding_agent(
     codingAgentType: CodingAgentType,
     path: String,
@@ -456,22 +1482,229 @@ pub fn open_coding_agent(
     args: Option<String>,
     globalEnv: Option<String>,
     agentEnv: Option<String>,
+    host: Option<String>,
+    itemId: Option<String>,
+    confirmed: Option<bool>,
+    wslDistro: Option<String>,
+    customAgentId: Option<String>,
+    prompt: Option<String>,
+    headlessPrompt: Option<String>,
+    resumeArgs: Option<String>,
+    app: AppHandle,
+    cache: State<HostStatusCache>,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+    settings_file: State<SettingsFile>,
 ) -> Result<(), String> {
-    let base_cmd = match codingAgentType {
-        CodingAgentType::ClaudeCode => "claude",
-        CodingAgentType::Opencode => "opencode",
-        CodingAgentType::GeminiCli => "gemini",
-        CodingAgentType::Codex => "codex",
+    // A custom agent brings its command template and default args and env
+    let custom_agent = match codingAgentType {
+        CodingAgentType::Custom => {
+            let id = customAgentId
+                .as_deref()
+                .filter(|id| !id.is_empty())
+                .ok_or("No custom agent selected")?;
+            Some(agents::find_custom(&store, id)?)
+        }
+        _ => None,
+    };
+
+    // Per-host overrides: the host's terminal replaces the default terminal, its args
+    // apply when the item has none, and its env sits between global and item env
+    let host_override = host
+        .as_deref()
+        .and_then(|h| hosts::agent_override_for(&store, h));
+    let terminalType = host_override
+        .as_ref()
+        .and_then(|o| o.terminal_type.clone())
+        .or(terminalType);
+    let args = args
+        .filter(|a| !a.trim().is_empty())
+        .or_else(|| {
+            host_override
+                .as_ref()
+                .and_then(|o| o.coding_agent_args.clone())
+        })
+        .or_else(|| custom_agent.as_ref().and_then(|a| a.args.clone()));
+    // Kept without the resume flags, so resuming a resumed session doesn't repeat them
+    let session_args = args.clone();
+    let args = match (resumeArgs.filter(|r| !r.trim().is_empty()), args) {
+        (Some(resume), Some(args)) => Some(format!("{} {}", resume, args)),
+        (resume, args) => resume.or(args),
+    };
+    let host_env = host_override.and_then(|o| o.coding_agent_env);
+
+    let template = match &custom_agent {
+        Some(agent) => agent.command.as_str(),
+        None => agents::builtin_command(&codingAgentType).ok_or("No custom agent selected")?,
     };
 
     // Build full command with args
-    let agent_cmd = match &args {
-        Some(a) if !a.trim().is_empty() => format!("{} {}", base_cmd, a.trim()),
-        _ => base_cmd.to_string(),
+    let agent_cmd = agents::command_line(template, args.as_deref());
+    let project_id = policy::item_project(&store, itemId.as_deref());
+    policy::check(
+        &store,
+        &settings_file,
+        project_id.as_deref(),
+        &agent_cmd,
+        confirmed.unwrap_or(false),
+    )?;
+
+    // A prompt to start with follows the agent's prompt flags, quoted for the shell
+    // that runs the line
+    let prompt = prompt.filter(|p| !p.trim().is_empty());
+    let prompt_flags = match &prompt {
+        Some(_) => Some(agents::prompt_args(&codingAgentType).ok_or_else(|| {
+            format!(
+                "{} can't start with a prompt, run it headless",
+                codingAgentType
+            )
+        })?),
+        None => None,
+    };
+    let agent_line = |shell: CommandShell| match (&prompt, prompt_flags) {
+        (Some(prompt), Some(flags)) => {
+            let args = format!(
+                "{} {} {}",
+                args.as_deref().unwrap_or_default(),
+                flags,
+                shell::quote(prompt, shell)
+            );
+            agents::command_line(template, Some(args.trim()))
+        }
+        _ => agent_cmd.clone(),
+    };
+
+    let session = AgentSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        agent: codingAgentType.clone(),
+        custom_agent_id: custom_agent.as_ref().map(|a| a.id.clone()),
+        project_id: project_id.clone(),
+        item_id: itemId.clone(),
+        path: path.clone(),
+        host: host.clone().filter(|h| !h.is_empty()),
+        wsl_distro: wslDistro.clone().filter(|d| !d.is_empty()),
+        args: session_args,
+        terminal: terminalType.clone(),
+        started_at: chrono::Utc::now().to_rfc3339(),
     };
 
     // Merge environment variables
-    let env_vars = merge_env_vars(globalEnv.as_deref(), agentEnv.as_deref());
+    let env_vars = merge_env_vars(
+        globalEnv.as_deref(),
+        custom_agent.as_ref().and_then(|a| a.env.as_deref()),
+        host_env.as_deref(),
+        agentEnv.as_deref(),
+    );
+
+    // With a prompt the agent runs it headless in the background instead of in a
+    // terminal, its output going to the project's agent run log
+    if let Some(prompt) = headlessPrompt.filter(|p| !p.trim().is_empty()) {
+        let flags = agents::headless_args(&codingAgentType)
+            .ok_or_else(|| format!("{} can't run headless", codingAgentType))?;
+        let host = host.filter(|h| !h.is_empty());
+        let wsl_distro = wslDistro.filter(|d| !d.is_empty());
+        if host.is_some() && wsl_distro.is_some() {
+            return Err("A coding agent can't run both over SSH and in WSL".to_string());
+        }
+        if let Some(host) = host.as_deref() {
+            ensure_host_online(host, &cache, &store)?;
+        }
+        if wsl_distro.is_some() {
+            wsl::check_available()?;
+        }
+
+        // Remote and WSL runs go through a POSIX shell; a local run runs in the
+        // platform shell (cmd on Windows)
+        let prompt_shell = if host.is_some() || wsl_distro.is_some() {
+            CommandShell::Sh
+        } else {
+            CommandShell::platform_default()
+        };
+        let headless = format!(
+            "{} {} {}",
+            flags,
+            shell::quote(&prompt, prompt_shell),
+            args.as_deref().unwrap_or_default()
+        );
+        let timeout = store.get_setting_or(
+            agent_runs::AGENT_RUN_TIMEOUT_KEY,
+            agent_runs::DEFAULT_TIMEOUT_MINUTES,
+        );
+
+        app.state::<AgentRuns>().start(
+            app.clone(),
+            settings_file.config_dir().to_path_buf(),
+            AgentRun {
+                agent: codingAgentType.to_string(),
+                command: agents::command_line(template, Some(&headless)),
+                path,
+                host,
+                wsl_distro,
+                project_id,
+                env: env_vars,
+            },
+            std::time::Duration::from_secs(timeout.max(1) * 60),
+        );
+        record_launch(&usage, itemId.as_deref());
+        return Ok(());
+    }
+
+    // On a remote host the agent runs in an interactive `ssh -t` session that stays
+    // open in the remote shell once it exits; env goes inline for the remote shell
+    if let Some(host) = host.as_deref().filter(|h| !h.is_empty()) {
+        if wslDistro.as_deref().is_some_and(|d| !d.is_empty()) {
+            return Err("A coding agent can't run both over SSH and in WSL".to_string());
+        }
+        ensure_host_online(host, &cache, &store)?;
+        // Path is left unquoted so that `~` expands on the remote side
+        let remote = format!(
+            "cd {} && {}{} ; exec $SHELL -l",
+            path,
+            posix_env_prefix(&env_vars),
+            agent_line(CommandShell::Sh)
+        );
+        let jump = hosts::jump_chain(&store, host);
+        let line = terminal::ssh_command(host, jump.as_deref(), Some(&remote));
+        terminal::open(&store, terminalType, None, &line, &[])?;
+        record_agent_launch(&usage, &settings_file, itemId.as_deref(), &session);
+        return Ok(());
+    }
+
+    // In a WSL distro the agent runs under its Linux shell, so env goes inline in
+    // POSIX form whichever Windows terminal hosts it
+    if let Some(distro) = wslDistro.as_deref().filter(|d| !d.is_empty()) {
+        wsl::check_available()?;
+        let command = format!(
+            "{}{}",
+            posix_env_prefix(&env_vars),
+            agent_line(CommandShell::Sh)
+        );
+        let line = wsl::terminal_line(distro, Some(&path), &command);
+        terminal::open(&store, terminalType, None, &line, &[])?;
+        record_agent_launch(&usage, &settings_file, itemId.as_deref(), &session);
+        return Ok(());
+    }
+
+    // Shell that runs the agent's line: cmd on Windows (the PowerShell, Git Bash and
+    // Nushell terminals get their own line below), sh elsewhere, except that
+    // Terminal.app and iTerm type it into the user's login shell (which may be fish)
+    let login_shell = cfg!(target_os = "macos")
+        && !matches!(
+            terminalType,
+            Some(
+                TerminalType::Kitty
+                    | TerminalType::Alacritty
+                    | TerminalType::Ghostty
+                    | TerminalType::Wezterm
+            )
+        );
+    let line_shell = if cfg!(windows) {
+        CommandShell::Cmd
+    } else if login_shell {
+        shell::user_shell()
+    } else {
+        CommandShell::Sh
+    };
 
     // Build environment variable prefix for shell commands
     let env_prefix = if env_vars.is_empty() {
@@ -489,15 +1722,20 @@ pub fn open_coding_agent(
         }
         #[cfg(not(windows))]
         {
-            // For Unix shells: VAR=value VAR2=value2
-            env_vars
-                .iter()
-                .map(|(k, v)| format!("{}='{}'", k, v.replace("'", "'\\''")))
-                .collect::<Vec<_>>()
-                .join(" ")
-                + " "
+            // For Unix shells: env VAR=value VAR2=value2, quoted for the line's shell
+            shell::env_prefix(&env_vars, line_shell)
         }
     };
+    let full_cmd = format!("{}{}", env_prefix, agent_line(line_shell));
+
+    // A custom default terminal gets the agent with its env inline
+    if terminalType.is_none() {
+        if let Some(custom) = terminal::default_custom(&store)? {
+            terminal::open_custom(&custom, Some(&path), &full_cmd, &[])?;
+            record_agent_launch(&usage, &settings_file, itemId.as_deref(), &session);
+            return Ok(());
+        }
+    }
 
     #[cfg(windows)]
     {
@@ -505,16 +1743,19 @@ pub fn open_coding_agent(
         const CREATE_NO_WINDOW: u32 = 0x08000000;
 
         let terminal = terminalType.unwrap_or(TerminalType::Cmd);
-
-        // Build the full command with env prefix
-        let full_cmd = format!("{}{}", env_prefix, agent_cmd);
+        // Window title for `start`, which can't hold a quote
+        let title = custom_agent
+            .as_ref()
+            .map_or(codingAgentType.to_string(), |a| a.name.replace('"', ""));
+        // Shells that take the command as one argument get it quoted for cmd
+        let arg = |command: &str| shell::quote(command, CommandShell::Cmd);
 
         match terminal {
             TerminalType::Cmd => {
                 Command::new("cmd")
                     .raw_arg(format!(
                         "/c start \"{}\" /d \"{}\" cmd /k {}",
-                        agent_cmd, path, full_cmd
+                        title, path, full_cmd
                     ))
                     .creation_flags(CREATE_NO_WINDOW)
                     .spawn()
@@ -532,11 +1773,13 @@ pub fn open_coding_agent(
                         .join("; ")
                         + "; "
                 };
-                let ps_cmd = format!("{}{}", ps_env_prefix, agent_cmd);
+                let ps_cmd = format!("{}{}", ps_env_prefix, agent_line(CommandShell::Pwsh));
                 Command::new("cmd")
                     .raw_arg(format!(
-                        "/c start \"{}\" /d \"{}\" powershell -NoExit -Command \"{}\"",
-                        agent_cmd, path, ps_cmd
+                        "/c start \"{}\" /d \"{}\" powershell -NoExit -Command {}",
+                        title,
+                        path,
+                        arg(&ps_cmd)
                     ))
                     .creation_flags(CREATE_NO_WINDOW)
                     .spawn()
@@ -554,19 +1797,26 @@ pub fn open_coding_agent(
                         .join("; ")
                         + "; "
                 };
-                let ps_cmd = format!("{}{}", ps_env_prefix, agent_cmd);
+                let ps_cmd = format!("{}{}", ps_env_prefix, agent_line(CommandShell::Pwsh));
                 Command::new("cmd")
                     .raw_arg(format!(
-                        "/c start \"{}\" /d \"{}\" pwsh -NoExit -Command \"{}\"",
-                        agent_cmd, path, ps_cmd
+                        "/c start \"{}\" /d \"{}\" pwsh -NoExit -Command {}",
+                        title,
+                        path,
+                        arg(&ps_cmd)
                     ))
                     .creation_flags(CREATE_NO_WINDOW)
                     .spawn()
                     .map_err(|e| format!("Failed to open coding agent: {}", e))?;
             }
             TerminalType::WindowsTerminal => {
+                let wt = terminal::load_wt_options(&store)?;
                 Command::new("cmd")
-                    .raw_arg(format!("/c wt -d \"{}\" cmd /k {}", path, full_cmd))
+                    .raw_arg(format!(
+                        "/c wt {} cmd /k {}",
+                        terminal::wt_args(&wt, Some(&path)),
+                        terminal::wt_escape(&full_cmd)
+                    ))
                     .creation_flags(CREATE_NO_WINDOW)
                     .spawn()
                     .map_err(|e| format!("Failed to open coding agent: {}", e))?;
@@ -583,9 +1833,14 @@ pub fn open_coding_agent(
                         .join(" && ")
                         + " && "
                 };
-                let bash_cmd = format!("{}{}", bash_env_prefix, agent_cmd);
+                let bash_cmd = format!("{}{}", bash_env_prefix, agent_line(CommandShell::Bash));
                 Command::new("cmd")
-                    .raw_arg(format!("/c start \"{}\" /d \"{}\" \"C:\\Program Files\\Git\\bin\\bash.exe\" -c \"{} ; exec bash\"", agent_cmd, path, bash_cmd))
+                    .raw_arg(format!(
+                        "/c start \"{}\" /d \"{}\" \"C:\\Program Files\\Git\\bin\\bash.exe\" -c {}",
+                        title,
+                        path,
+                        arg(&format!("{} ; exec bash", bash_cmd))
+                    ))
                     .creation_flags(CREATE_NO_WINDOW)
                     .spawn()
                     .map_err(|e| format!("Failed to open coding agent: {}", e))?;
@@ -602,22 +1857,31 @@ pub fn open_coding_agent(
                         .join("; ")
                         + "; "
                 };
-                let nu_cmd = format!("{}{}", nu_env_prefix, agent_cmd);
+                let nu_cmd = format!("{}{}", nu_env_prefix, agent_line(CommandShell::Nushell));
                 Command::new("cmd")
                     .raw_arg(format!(
-                        "/c start \"{}\" /d \"{}\" nu -e \"{}\"",
-                        agent_cmd, path, nu_cmd
+                        "/c start \"{}\" /d \"{}\" nu -e {}",
+                        title,
+                        path,
+                        arg(&nu_cmd)
                     ))
                     .creation_flags(CREATE_NO_WINDOW)
                     .spawn()
                     .map_err(|e| format!("Failed to open coding agent: {}", e))?;
             }
+            other @ TerminalType::Wezterm => {
+                terminal::open(&store, Some(other), Some(&path), &full_cmd, &[])?;
+            }
+            TerminalType::Warp => {
+                let env: Vec<(String, String)> = env_vars.into_iter().collect();
+                terminal::open_warp(Some(&path), &agent_line(CommandShell::Cmd), &env)?;
+            }
             _ => {
                 // Fallback to cmd for unsupported terminals on Windows
                 Command::new("cmd")
                     .raw_arg(format!(
                         "/c start \"{}\" /d \"{}\" cmd /k {}",
-                        agent_cmd, path, full_cmd
+                        title, path, full_cmd
                     ))
                     .creation_flags(CREATE_NO_WINDOW)
                     .spawn()
@@ -629,9 +1893,12 @@ pub fn open_coding_agent(
     #[cfg(target_os = "macos")]
     {
         let terminal = terminalType.unwrap_or(TerminalType::MacTerminal);
-
-        // Build the full command with env prefix for Unix
-        let full_cmd = format!("{}{}", env_prefix, agent_cmd);
+        // Terminal.app and iTerm get the line inside an AppleScript string
+        let script_cmd = terminal::applescript_escape(&format!(
+            "cd {} && {}",
+            git::shell_quote(&path),
+            full_cmd
+        ));
 
         match terminal {
             TerminalType::ITerm2 => {
@@ -639,8 +1906,8 @@ pub fn open_coding_agent(
                     .args([
                         "-e",
                         &format!(
-                            "tell application \"iTerm\" to create window with default profile command \"cd '{}' && {}\"",
-                            path, full_cmd
+                            "tell application \"iTerm\" to create window with default profile command \"{}\"",
+                            script_cmd
                         ),
                     ])
                     .spawn()
@@ -674,14 +1941,21 @@ pub fn open_coding_agent(
                     .spawn()
                     .map_err(|e| format!("Failed to open coding agent: {}", e))?;
             }
+            TerminalType::Warp => {
+                let env: Vec<(String, String)> = env_vars.clone().into_iter().collect();
+                terminal::open_warp(Some(&path), &agent_line(CommandShell::Sh), &env)?;
+            }
+            other @ (TerminalType::Ghostty | TerminalType::Wezterm) => {
+                terminal::open(&store, Some(other), Some(&path), &full_cmd, &[])?;
+            }
             _ => {
                 // Default to Terminal.app
                 Command::new("osascript")
                     .args([
                         "-e",
                         &format!(
-                            "tell application \"Terminal\" to do script \"cd '{}' && {}\"",
-                            path, full_cmd
+                            "tell application \"Terminal\" to do script \"{}\"",
+                            script_cmd
                         ),
                     ])
                     .spawn()
@@ -693,10 +1967,11 @@ pub fn open_coding_agent(
     #[cfg(all(not(windows), not(target_os = "macos")))]
     {
         let terminal = terminalType.unwrap_or(TerminalType::GnomeTerminal);
-
-        // Build the full command with env prefix for Unix
-        let full_cmd = format!("{}{}", env_prefix, agent_cmd);
-        let shell_cmd = format!("cd '{}' && {} ; exec $SHELL", path, full_cmd);
+        let shell_cmd = format!(
+            "cd {} && {} ; exec $SHELL",
+            git::shell_quote(&path),
+            full_cmd
+        );
 
         match terminal {
             TerminalType::GnomeTerminal => {
@@ -748,6 +2023,16 @@ pub fn open_coding_agent(
                     .spawn()
                     .map_err(|e| format!("Failed to open coding agent: {}", e))?;
             }
+            TerminalType::Warp => {
+                let env: Vec<(String, String)> = env_vars.clone().into_iter().collect();
+                terminal::open_warp(Some(&path), &agent_line(CommandShell::Sh), &env)?;
+            }
+            other @ (TerminalType::Ghostty
+            | TerminalType::Wezterm
+            | TerminalType::Tilix
+            | TerminalType::Foot) => {
+                terminal::open(&store, Some(other), Some(&path), &full_cmd, &[])?;
+            }
             _ => {
                 // Fallback: try common terminals
                 let terminals = [
@@ -781,40 +2066,618 @@ pub fn open_coding_agent(
         }
     }
 
+    record_agent_launch(&usage, &settings_file, itemId.as_deref(), &session);
     Ok(())
 }
 
+// Host aliases from ~/.ssh/config and the files it includes (wildcard patterns skipped)
 #[tauri::command]
 pub fn get_ssh_hosts() -> Result<Vec<String>, String> {
-    let home = dirs::home_dir().ok_or("Could not find home directory")?;
-    let ssh_config_path = home.join(".ssh").join("config");
-
-    if !ssh_config_path.exists() {
-        return Ok(vec![]);
-    }
+    Ok(ssh_config::load_hosts()?
+        .into_iter()
+        .map(|entry| entry.host)
+        .collect())
+}
 
-    let content = fs::read_to_string(&ssh_config_path)
-        .map_err(|e| format!("Failed to read SSH config: {}", e))?;
+// Like get_ssh_hosts, with each host's HostName, User, Port, IdentityFile and ProxyJump
+#[tauri::command]
+pub fn get_ssh_host_details() -> Result<Vec<SshHostEntry>, String> {
+    ssh_config::load_hosts()
+}
 
-    let mut hosts = vec![];
-    for line in content.lines() {
-        let line = line.trim();
-        if line.to_lowercase().starts_with("host ") {
-            let host = line[5..].trim();
-            // Skip patterns with wildcards
-            if !host.contains('*') && !host.contains('?') {
-                hosts.push(host.to_string());
-            }
-        }
-    }
+// Fail fast when a host is known to be offline (or offline mode is on)
+fn ensure_host_online(
+    host: &str,
+    cache: &HostStatusCache,
+    store: &JsonStore,
+) -> Result<(), String> {
+    cache.ensure_online(host, store.get_setting_or(hosts::OFFLINE_MODE_KEY, false))
+}
 
-    Ok(hosts)
+#[tauri::command]
+pub fn get_host_statuses(cache: State<HostStatusCache>) -> Vec<HostStatus> {
+    cache.all()
 }
 
+// Coding agent sessions launched from the app, newest first, optionally only those
+// of one project
 #[tauri::command]
-pub async fn list_remote_dir(host: String, path: Option<String>) -> Result<DirListing, String> {
+pub fn list_agent_sessions(
+    projectId: Option<String>,
+    limit: Option<usize>,
+    settings_file: State<SettingsFile>,
+) -> Vec<AgentSession> {
+    agents::read_sessions(settings_file.config_dir())
+        .into_iter()
+        .filter(|session| projectId.is_none() || session.project_id == projectId)
+        .take(limit.unwrap_or(100))
+        .collect()
+}
+
+// The end of the output of headless agent runs in a project (or outside any
+// project), at most `maxBytes` of it
+#[tauri::command]
+pub fn get_agent_run_log(
+    projectId: Option<String>,
+    maxBytes: Option<usize>,
+    settings_file: State<SettingsFile>,
+) -> Result<String, String> {
+    agent_runs::read_log(
+        settings_file.config_dir(),
+        projectId.as_deref(),
+        maxBytes.unwrap_or(64 * 1024),
+    )
+}
+
+// Headless agent runs still going, oldest first
+#[tauri::command]
+pub fn list_agent_runs(runs: State<AgentRuns>) -> Vec<RunningAgent> {
+    runs.list()
+}
+
+// Stop a headless agent run by its ID from list_agent_runs
+#[tauri::command]
+pub fn cancel_agent_run(runId: String, runs: State<AgentRuns>) -> Result<(), String> {
+    runs.cancel(&runId)
+}
+
+// Prompt for a coding agent from a project's todos (the given checklist lines, or
+// all open ones) and notes
+#[tauri::command]
+pub fn compose_agent_prompt(
+    projectId: String,
+    todoLines: Option<Vec<usize>>,
+    noteIds: Option<Vec<String>>,
+    store: State<JsonStore>,
+) -> Result<String, String> {
+    let todos: Vec<ChecklistItem> = markdown::checklist(&store.get_project_todos(&projectId)?)
+        .into_iter()
+        .filter(|todo| match &todoLines {
+            Some(lines) => lines.contains(&todo.line),
+            None => !todo.completed,
+        })
+        .collect();
+    let notes = noteIds
+        .unwrap_or_default()
+        .iter()
+        .map(|id| {
+            store
+                .find_item(id)
+                .filter(|item| item.item_type == ItemType::Note && item.project_id == projectId)
+                .ok_or_else(|| format!("Note {} not found in the project", id))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(prompts::compose(&todos, &notes))
+}
+
+// Where an agent runs in a project: the project's item for it (if any), the path
+// and the host of that path
+fn agent_location(
+    project: Project,
+    agent: &CodingAgentType,
+    custom_agent_id: Option<&str>,
+) -> Result<(Option<Item>, String, Option<String>), String> {
+    let item = project.items.unwrap_or_default().into_iter().find(|item| {
+        item.item_type == ItemType::CodingAgent
+            && item.coding_agent_type.as_ref() == Some(agent)
+            && (*agent != CodingAgentType::Custom
+                || item.custom_agent_id.as_deref() == custom_agent_id)
+    });
+
+    let working_dirs = project.metadata.working_dirs.unwrap_or_default();
+    let (path, host) = match &item {
+        Some(item) => {
+            let host = working_dirs
+                .iter()
+                .find(|dir| dir.path == item.content)
+                .and_then(|dir| dir.host.clone());
+            (item.content.clone(), host)
+        }
+        None => {
+            let dir = working_dirs
+                .first()
+                .ok_or("Project has no working directory")?;
+            (dir.path.clone(), dir.host.clone())
+        }
+    };
+    Ok((item, path, host))
+}
+
+// Start a coding agent in a project with `prompt` as its first message, where the
+// project's item for that agent runs (with the item's args and env), else in the
+// project's first working dir. With `headless` the agent runs the prompt in the
+// background instead.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn launch_agent_with_prompt(
+    projectId: String,
+    agent: CodingAgentType,
+    prompt: String,
+    customAgentId: Option<String>,
+    headless: Option<bool>,
+    confirmed: Option<bool>,
+    app: AppHandle,
+    store: State<JsonStore>,
+) -> Result<(), String> {
+    if prompt.trim().is_empty() {
+        return Err("The prompt is empty".to_string());
+    }
+    let project = store
+        .get_project_by_id(&projectId)?
+        .ok_or("Project not found")?;
+    let (item, path, host) = agent_location(project, &agent, customAgentId.as_deref())?;
+    let wsl_distro = item.as_ref().and_then(|i| i.wsl_distro.clone());
+
+    let (prompt, headless_prompt) = if headless.unwrap_or(false) {
+        (None, Some(prompt))
+    } else {
+        (Some(prompt), None)
+    };
+
+    let terminal = store
+        .get_setting("defaultTerminal")?
+        .and_then(|t| t.parse::<TerminalType>().ok());
+    open_coding_agent(
+        agent,
+        path,
+        terminal,
+        item.as_ref().and_then(|i| i.coding_agent_args.clone()),
+        store.get_setting("codingAgentGlobalEnv")?,
+        item.as_ref().and_then(|i| i.coding_agent_env.clone()),
+        host,
+        item.as_ref().map(|i| i.id.clone()),
+        confirmed,
+        wsl_distro,
+        customAgentId.or_else(|| item.and_then(|i| i.custom_agent_id)),
+        prompt,
+        headless_prompt,
+        None,
+        app.clone(),
+        app.state::<HostStatusCache>(),
+        store,
+        app.state::<UsageFile>(),
+        app.state::<SettingsFile>(),
+    )
+}
+
+// Prompt template library
+#[tauri::command]
+pub fn get_prompt_templates(store: State<JsonStore>) -> Result<Vec<PromptTemplate>, String> {
+    prompts::load_templates(&store)
+}
+
+// Create or replace a prompt template (matched by id; a new one without an id gets one)
+#[tauri::command]
+pub fn save_prompt_template(
+    mut template: PromptTemplate,
+    store: State<JsonStore>,
+) -> Result<PromptTemplate, String> {
+    if template.id.is_empty() {
+        template.id = uuid::Uuid::new_v4().to_string();
+    }
+    let mut all = prompts::load_templates(&store)?;
+    match all.iter_mut().find(|t| t.id == template.id) {
+        Some(existing) => *existing = template.clone(),
+        None => all.push(template.clone()),
+    }
+    prompts::save_templates(&store, &all)?;
+    Ok(template)
+}
+
+#[tauri::command]
+pub fn delete_prompt_template(id: String, store: State<JsonStore>) -> Result<bool, String> {
+    let mut all = prompts::load_templates(&store)?;
+    let original_len = all.len();
+    all.retain(|t| t.id != id);
+    if all.len() == original_len {
+        return Ok(false);
+    }
+    prompts::save_templates(&store, &all)?;
+    Ok(true)
+}
+
+// Start a coding agent in a project with a prompt template rendered for it. The
+// agent defaults to the template's.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn launch_agent_with_template(
+    projectId: String,
+    templateId: String,
+    agent: Option<CodingAgentType>,
+    customAgentId: Option<String>,
+    headless: Option<bool>,
+    confirmed: Option<bool>,
+    app: AppHandle,
+    store: State<JsonStore>,
+) -> Result<(), String> {
+    let template = prompts::load_templates(&store)?
+        .into_iter()
+        .find(|t| t.id == templateId)
+        .ok_or_else(|| format!("Prompt template {} not found", templateId))?;
+    let agent = agent
+        .or_else(|| template.agent.clone())
+        .ok_or("No coding agent selected")?;
+    let project = store
+        .get_project_by_id(&projectId)?
+        .ok_or("Project not found")?;
+    let name = project.name.clone();
+    let (_, path, _) = agent_location(project, &agent, customAgentId.as_deref())?;
+    let open_todos: Vec<ChecklistItem> = markdown::checklist(&store.get_project_todos(&projectId)?)
+        .into_iter()
+        .filter(|todo| !todo.completed)
+        .collect();
+
+    let prompt = prompts::render(&template, &name, &open_todos, &path);
+    launch_agent_with_prompt(
+        projectId,
+        agent,
+        prompt,
+        customAgentId,
+        headless,
+        confirmed,
+        app,
+        store,
+    )
+}
+
+// Launch the agent of an earlier session again in the same place and terminal,
+// continuing its most recent conversation there (or with `pick`, letting the agent
+// list the conversations to resume)
+#[tauri::command]
+pub fn resume_agent_session(
+    sessionId: String,
+    pick: Option<bool>,
+    app: AppHandle,
+    cache: State<HostStatusCache>,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+    settings_file: State<SettingsFile>,
+) -> Result<(), String> {
+    let session = agents::read_sessions(settings_file.config_dir())
+        .into_iter()
+        .find(|session| session.id == sessionId)
+        .ok_or_else(|| format!("Agent session {} not found", sessionId))?;
+    let resume = agents::resume_args(&session.agent, pick.unwrap_or(false))
+        .ok_or_else(|| format!("{} can't resume sessions", session.agent))?;
+    // The item's env applies if it still exists; env isn't kept in the log
+    let agent_env = session
+        .item_id
+        .as_deref()
+        .and_then(|id| store.find_item(id))
+        .and_then(|item| item.coding_agent_env);
+
+    open_coding_agent(
+        session.agent,
+        session.path,
+        session.terminal,
+        session.args,
+        store.get_setting("codingAgentGlobalEnv")?,
+        agent_env,
+        session.host,
+        session.item_id,
+        None,
+        session.wsl_distro,
+        session.custom_agent_id,
+        None,
+        None,
+        Some(resume.to_string()),
+        app,
+        cache,
+        store,
+        usage,
+        settings_file,
+    )
+}
+
+// User-defined coding agents (name, command template, default args and env)
+#[tauri::command]
+pub fn get_custom_agents(store: State<JsonStore>) -> Result<Vec<CustomAgent>, String> {
+    agents::load_custom(&store)
+}
+
+// Replace the custom agent registry
+#[tauri::command]
+pub fn save_custom_agents(agents: Vec<CustomAgent>, store: State<JsonStore>) -> Result<(), String> {
+    agents::save_custom(&store, &agents)
+}
+
+// User-defined terminals (command templates with {cwd} and {cmd})
+#[tauri::command]
+pub fn get_custom_terminals(store: State<JsonStore>) -> Result<Vec<CustomTerminal>, String> {
+    terminal::load_custom(&store)
+}
+
+// Replace the custom terminal registry
+#[tauri::command]
+pub fn save_custom_terminals(
+    terminals: Vec<CustomTerminal>,
+    store: State<JsonStore>,
+) -> Result<(), String> {
+    terminal::save_custom(&store, &terminals)
+}
+
+#[tauri::command]
+pub fn get_host_agent_overrides(store: State<JsonStore>) -> Result<Vec<HostAgentOverride>, String> {
+    hosts::load_agent_overrides(&store)
+}
+
+// Create or replace the terminal/agent override for a host
+#[tauri::command]
+pub fn save_host_agent_override(
+    hostOverride: HostAgentOverride,
+    store: State<JsonStore>,
+) -> Result<Vec<HostAgentOverride>, String> {
+    let mut overrides = hosts::load_agent_overrides(&store)?;
+    match overrides.iter_mut().find(|o| o.host == hostOverride.host) {
+        Some(existing) => *existing = hostOverride,
+        None => overrides.push(hostOverride),
+    }
+    hosts::save_agent_overrides(&store, &overrides)?;
+    Ok(overrides)
+}
+
+#[tauri::command]
+pub fn delete_host_agent_override(
+    host: String,
+    store: State<JsonStore>,
+) -> Result<Vec<HostAgentOverride>, String> {
+    let mut overrides = hosts::load_agent_overrides(&store)?;
+    overrides.retain(|o| o.host != host);
+    hosts::save_agent_overrides(&store, &overrides)?;
+    Ok(overrides)
+}
+
+// Jump host chains (ssh -J) used to reach hosts in remote operations, by host
+#[tauri::command]
+pub fn get_host_jump_chains(store: State<JsonStore>) -> Result<HashMap<String, String>, String> {
+    hosts::load_jump_chains(&store)
+}
+
+// Set the jump host chain of a host; an empty or missing chain removes it. The
+// chains are also written to an ssh config that ~/.ssh/config includes, so
+// editors opening the host for a remote IDE go through them too.
+#[tauri::command]
+pub fn set_host_jump_chain(
+    host: String,
+    chain: Option<String>,
+    store: State<JsonStore>,
+    settings_file: State<SettingsFile>,
+) -> Result<HashMap<String, String>, String> {
+    hosts::save_jump_chain(&store, &host, chain.as_deref())?;
+    hosts::write_ssh_config(&store, settings_file.config_dir())?;
+    hosts::load_jump_chains(&store)
+}
+
+// Re-probe a host and update its cached reachability (ignores offline mode)
+#[tauri::command]
+pub async fn refresh_host_status(
+    host: String,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<HostStatus, String> {
+    let jump = hosts::jump_chain(&store, &host);
+    let status = match hosts::probe_host(&host, jump.as_deref()).await {
+        Ok(()) => cache.record(&host, true, None),
+        Err(e) => cache.record(&host, false, Some(e)),
+    };
+    Ok(status)
+}
+
+// Browse a remote directory over SFTP (the remote home by default)
+#[tauri::command]
+pub async fn sftp_list(
+    host: String,
+    path: Option<String>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<SftpListing, String> {
+    ensure_host_online(&host, &cache, &store)?;
+    sftp::list(&host, path.as_deref(), &cache).await
+}
+
+// Copy a remote file (or with `recursive`, a directory) to this machine
+#[tauri::command]
+pub async fn sftp_download(
+    host: String,
+    remotePath: String,
+    localPath: String,
+    recursive: Option<bool>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<(), String> {
+    ensure_host_online(&host, &cache, &store)?;
+    sftp::download(
+        &host,
+        &remotePath,
+        &localPath,
+        recursive.unwrap_or(false),
+        &cache,
+    )
+    .await
+}
+
+// Copy a local file (or with `recursive`, a directory) to the host
+#[tauri::command]
+pub async fn sftp_upload(
+    host: String,
+    localPath: String,
+    remotePath: String,
+    recursive: Option<bool>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<(), String> {
+    ensure_host_online(&host, &cache, &store)?;
+    sftp::upload(
+        &host,
+        &localPath,
+        &remotePath,
+        recursive.unwrap_or(false),
+        &cache,
+    )
+    .await
+}
+
+// tmux sessions on a host, so long-running agent and build sessions can be reattached
+#[tauri::command]
+pub async fn list_tmux_sessions(
+    host: String,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<Vec<TmuxSession>, String> {
+    ensure_host_online(&host, &cache, &store)?;
+    let jump = hosts::jump_chain(&store, &host);
+    tmux::list(&host, jump.as_deref(), &cache).await
+}
+
+// Open a plain terminal in a working dir, locally or on `host` over SSH (path is
+// then on the host)
+#[tauri::command]
+pub fn open_terminal(
+    path: String,
+    host: Option<String>,
+    terminalType: Option<TerminalType>,
+    cache: State<HostStatusCache>,
+    store: State<JsonStore>,
+) -> Result<(), String> {
+    match host.as_deref().filter(|h| !h.is_empty()) {
+        Some(host) => {
+            ensure_host_online(host, &cache, &store)?;
+            // Path is left unquoted so that `~` expands on the remote side
+            let remote = format!("cd {} && exec $SHELL -l", path);
+            let jump = hosts::jump_chain(&store, host);
+            let line = terminal::ssh_command(host, jump.as_deref(), Some(&remote));
+            terminal::open(&store, terminalType, None, &line, &[])
+        }
+        None => {
+            if !Path::new(&path).is_dir() {
+                return Err(format!("{} is not a directory", path));
+            }
+            terminal::open(&store, terminalType, Some(&path), "", &[])
+        }
+    }
+}
+
+// Show a local path in the system file manager: a directory is opened, a file is
+// selected in its folder
+#[tauri::command]
+pub fn reveal_in_file_manager(path: String, app: AppHandle) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
+
+    let target = Path::new(&path);
+    if !target.exists() {
+        return Err(format!("{} does not exist", path));
+    }
+    let result = if target.is_dir() {
+        app.opener().open_path(&path, None::<&str>)
+    } else {
+        app.opener().reveal_item_in_dir(target)
+    };
+    result.map_err(|e| format!("Failed to open file manager: {}", e))
+}
+
+// Open a terminal attached to an existing tmux session on a host
+#[tauri::command]
+pub fn attach_tmux_session(
+    host: String,
+    name: String,
+    terminalType: Option<TerminalType>,
+    cache: State<HostStatusCache>,
+    store: State<JsonStore>,
+) -> Result<(), String> {
+    ensure_host_online(&host, &cache, &store)?;
+    tmux::validate_name(&name)?;
+    let jump = hosts::jump_chain(&store, &host);
+    let ssh_cmd = terminal::ssh_command(&host, jump.as_deref(), Some(&tmux::attach_line(&name)));
+
+    terminal::open(&store, terminalType, None, &ssh_cmd, &[])
+}
+
+// Start a detached tmux session on a host, optionally in cwd and running command;
+// the command goes through the command policy like run_command
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_tmux_session(
+    host: String,
+    name: String,
+    cwd: Option<String>,
+    command: Option<String>,
+    confirmed: Option<bool>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+    settings_file: State<'_, SettingsFile>,
+) -> Result<(), String> {
+    if let Some(command) = command.as_deref() {
+        policy::check(
+            &store,
+            &settings_file,
+            None,
+            command,
+            confirmed.unwrap_or(false),
+        )?;
+    }
+    ensure_host_online(&host, &cache, &store)?;
+    let jump = hosts::jump_chain(&store, &host);
+    tmux::create(
+        &host,
+        jump.as_deref(),
+        &name,
+        cwd.as_deref(),
+        command.as_deref(),
+        &cache,
+    )
+    .await
+}
+
+// Check a host in stages (TCP connection with the server banner and latency, then
+// a non-interactive login) and explain a failure, updating the host's status
+#[tauri::command]
+pub async fn test_ssh_connection(
+    host: String,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<SshConnectionTest, String> {
+    let jump = hosts::jump_chain(&store, &host);
+    let result = hosts::test_connection(&host, jump.as_deref()).await;
+    cache.record(&host, result.reachable, result.error.clone());
+    Ok(result)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn list_remote_dir(
+    host: String,
+    path: Option<String>,
+    showHidden: Option<bool>,
+    sortBy: Option<DirSort>,
+    descending: Option<bool>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<DirListing, String> {
+    ensure_host_online(&host, &cache, &store)?;
+
     let target_path = path.unwrap_or_else(|| "~".to_string());
-    let cmd = format!("cd {} && pwd && ls -1F", target_path);
+    let cmd = dir_listing::remote_script(&target_path, showHidden.unwrap_or(false));
+    let jump = hosts::jump_chain(&store, &host);
 
     // On Unix, use ControlMaster to reuse authenticated connection
     // On Windows, ControlMaster is not supported (no Unix domain sockets)
@@ -827,6 +2690,7 @@ pub async fn list_remote_dir(host: String, path: Option<String>) -> Result<DirLi
         let socket_path_str = socket_path.to_string_lossy().to_string();
 
         tokio::process::Command::new("ssh")
+            .args(hosts::jump_args(jump.as_deref()))
             .args([
                 "-o",
                 "ControlMaster=auto",
@@ -847,6 +2711,7 @@ pub async fn list_remote_dir(host: String, path: Option<String>) -> Result<DirLi
         const CREATE_NO_WINDOW: u32 = 0x08000000;
 
         tokio::process::Command::new("ssh")
+            .args(hosts::jump_args(jump.as_deref()))
             .args([&host, &cmd])
             .creation_flags(CREATE_NO_WINDOW)
             .output()
@@ -854,6 +2719,8 @@ pub async fn list_remote_dir(host: String, path: Option<String>) -> Result<DirLi
             .map_err(|e| format!("Failed to execute SSH command: {}", e))?
     };
 
+    cache.record_ssh_output(&host, &output);
+
     if !output.status.success() {
         return Err(format!(
             "SSH command failed: {}",
@@ -866,18 +2733,12 @@ pub async fn list_remote_dir(host: String, path: Option<String>) -> Result<DirLi
 
     let current_path = lines.next().unwrap_or("~").to_string();
 
-    let entries: Vec<DirEntry> = lines
-        .filter(|line| !line.is_empty() && !line.starts_with('.'))
-        .map(|line| {
-            let is_dir = line.ends_with('/');
-            let name = if is_dir {
-                line.trim_end_matches('/').to_string()
-            } else {
-                line.trim_end_matches('@').trim_end_matches('*').to_string()
-            };
-            DirEntry { name, is_dir }
-        })
-        .collect();
+    let mut entries = dir_listing::parse_remote(lines);
+    dir_listing::sort_entries(
+        &mut entries,
+        sortBy.unwrap_or_default(),
+        descending.unwrap_or(false),
+    );
 
     Ok(DirListing {
         current_path,
@@ -885,16 +2746,173 @@ pub async fn list_remote_dir(host: String, path: Option<String>) -> Result<DirLi
     })
 }
 
+// Local counterpart of list_remote_dir, for the same folder picker
+#[tauri::command]
+pub fn list_local_dir(
+    path: Option<String>,
+    showHidden: Option<bool>,
+    sortBy: Option<DirSort>,
+    descending: Option<bool>,
+) -> Result<DirListing, String> {
+    let mut listing = dir_listing::list_local(path.as_deref(), showHidden.unwrap_or(false))?;
+    dir_listing::sort_entries(
+        &mut listing.entries,
+        sortBy.unwrap_or_default(),
+        descending.unwrap_or(false),
+    );
+    Ok(listing)
+}
+
+// Count a launch toward the item's usage stats; failures are only logged
+fn record_launch(usage: &UsageFile, item_id: Option<&str>) {
+    if let Some(id) = item_id {
+        if let Err(e) = usage.record_item_launch(id) {
+            log::warn!("Failed to record launch of item {}: {}", id, e);
+        }
+    }
+}
+
+// Count the launch of an agent's item and add it to the agent session log
+fn record_agent_launch(
+    usage: &UsageFile,
+    settings_file: &SettingsFile,
+    item_id: Option<&str>,
+    session: &AgentSession,
+) {
+    record_launch(usage, item_id);
+    if let Err(e) = agents::record_session(settings_file.config_dir(), session) {
+        log::warn!("Failed to record agent session: {}", e);
+    }
+}
+
+// The project's default cwd and host for a command that sets neither. They apply
+// as a pair, since a cwd belongs to the machine it was given for: a local cwd must
+// not run on the default host, nor a remote one locally.
+fn with_project_defaults(
+    store: &JsonStore,
+    project_id: Option<&str>,
+    cwd: Option<String>,
+    host: Option<String>,
+) -> Result<(Option<String>, Option<String>), String> {
+    if cwd.is_some() || host.is_some() {
+        return Ok((cwd, host));
+    }
+    let metadata = match project_id {
+        Some(id) => store.get_project_by_id(id)?.map(|p| p.metadata),
+        None => None,
+    }
+    .unwrap_or_default();
+    Ok((metadata.default_cwd, metadata.default_host))
+}
+
+// Run a command locally or over SSH. In output mode, `timeoutSecs` kills it (with
+// its child processes) once the time is up and the result is flagged `timed_out`.
+// `shell` (else the default shell setting) picks the shell that runs it, and in
+// output mode `stdin` is written to the command's input (e.g. for `psql -f -`).
+// Commands matching the policy's destructive patterns need `confirmed`. On Windows,
+// `wslDistro` runs the command inside that WSL distro instead of over SSH, in
+// `shell` or else a login `sh` (the default shell setting doesn't apply there).
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn run_command(
     command: String,
     mode: CommandMode,
     cwd: Option<String>,
     host: Option<String>,
+    projectId: Option<String>,
+    itemId: Option<String>,
+    timeoutSecs: Option<u64>,
+    shell: Option<CommandShell>,
+    stdin: Option<String>,
+    confirmed: Option<bool>,
+    wslDistro: Option<String>,
+    app: AppHandle,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+    usage: State<'_, UsageFile>,
+    processes: State<'_, ProcessRegistry>,
 ) -> Result<CommandResult, String> {
-    let is_background = matches!(mode, CommandMode::Background);
+    policy::check(
+        &store,
+        &app.state::<SettingsFile>(),
+        projectId.as_deref(),
+        &command,
+        confirmed.unwrap_or(false),
+    )?;
+    let wslDistro = wslDistro.filter(|d| !d.is_empty());
+    let (cwd, host) = match &wslDistro {
+        // The project's default host doesn't apply inside WSL
+        Some(_) if host.is_some() => {
+            return Err("A command can't run both over SSH and in WSL".to_string());
+        }
+        Some(_) => (cwd, None),
+        None => with_project_defaults(&store, projectId.as_deref(), cwd, host)?,
+    };
+    // The default shell is for this machine, and usually isn't in the distro
+    let shell = match &wslDistro {
+        Some(_) => shell,
+        None => shell::resolve(&app.state::<SettingsFile>(), shell),
+    };
+    let result = match mode {
+        CommandMode::Background => {
+            if stdin.is_some() {
+                return Err("Input can only be passed to commands run in output mode".to_string());
+            }
+            let process = RunningProcess {
+                id: uuid::Uuid::new_v4().to_string(),
+                pid: 0,
+                command,
+                project_id: projectId,
+                item_id: itemId.clone(),
+                jump_hosts: host.as_deref().and_then(|h| hosts::jump_chain(&store, h)),
+                wsl_distro: wslDistro,
+                host,
+                cwd,
+                started_at: chrono::Utc::now().to_rfc3339(),
+            };
+            start_background_command(process, shell, &app, &processes, &cache, &store).await?
+        }
+        CommandMode::Output => {
+            execute_command(
+                command,
+                cwd,
+                host,
+                wslDistro,
+                shell,
+                stdin,
+                timeoutSecs,
+                &cache,
+                &store,
+            )
+            .await?
+        }
+    };
+    if result.exit_code == 0 {
+        record_launch(&usage, itemId.as_deref());
+    }
+    Ok(result)
+}
 
+#[allow(clippy::too_many_arguments)]
+async fn execute_command(
+    command: String,
+    cwd: Option<String>,
+    host: Option<String>,
+    wsl_distro: Option<String>,
+    shell: Option<CommandShell>,
+    stdin: Option<String>,
+    timeout_secs: Option<u64>,
+    cache: &HostStatusCache,
+    store: &JsonStore,
+) -> Result<CommandResult, String> {
     if let Some(remote_host) = host {
+        ensure_host_online(&remote_host, cache, store)?;
+        let jump = hosts::jump_chain(store, &remote_host);
+        let command = match shell {
+            Some(shell) => shell.remote_line(&command),
+            None => command,
+        };
+
         // Remote command via SSH (async to avoid blocking UI)
         let ssh_cmd = if let Some(dir) = cwd {
             format!("cd {} && {}", dir, command)
@@ -902,80 +2920,1119 @@ pub async fn run_command(
             command.clone()
         };
 
-        let full_cmd = if is_background {
-            format!("nohup {} > /dev/null 2>&1 &", ssh_cmd)
-        } else {
-            ssh_cmd
-        };
+        if let Some(secs) = timeout_secs {
+            let mut cmd = tokio::process::Command::new("ssh");
+            cmd.args(hosts::jump_args(jump.as_deref())).args([
+                &remote_host,
+                &processes::remote_with_timeout(&ssh_cmd, secs),
+            ]);
+            #[cfg(windows)]
+            {
+                const CREATE_NO_WINDOW: u32 = 0x08000000;
+                cmd.creation_flags(CREATE_NO_WINDOW);
+            }
+            // The remote `timeout` stops the command; this only catches a hung connection
+            let limit = std::time::Duration::from_secs(secs + processes::REMOTE_TIMEOUT_GRACE_SECS);
+            let (output, client_timed_out) =
+                processes::output_with_timeout(cmd, stdin, limit, false).await?;
+            if !client_timed_out {
+                cache.record_ssh_output(&remote_host, &output);
+            }
+            let exit_code = output.status.code().unwrap_or(-1);
+            return Ok(CommandResult {
+                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                exit_code,
+                process_id: None,
+                timed_out: client_timed_out || exit_code == processes::TIMEOUT_EXIT_CODE,
+            });
+        }
 
+        let mut cmd = tokio::process::Command::new("ssh");
+        cmd.args(hosts::jump_args(jump.as_deref()))
+            .args([&remote_host, &ssh_cmd]);
         #[cfg(windows)]
-        let output = {
+        {
             const CREATE_NO_WINDOW: u32 = 0x08000000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+        // ssh forwards its stdin to the remote command
+        let output = processes::output_with_input(cmd, stdin).await?;
 
-            tokio::process::Command::new("ssh")
-                .args([&remote_host, &full_cmd])
-                .creation_flags(CREATE_NO_WINDOW)
-                .output()
-                .await
-                .map_err(|e| format!("Failed to execute SSH command: {}", e))?
+        cache.record_ssh_output(&remote_host, &output);
+
+        Ok(CommandResult {
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: output.status.code().unwrap_or(-1),
+            process_id: None,
+            timed_out: false,
+        })
+    } else if let Some(distro) = wsl_distro {
+        let cmd = wsl::tokio_command(&distro, cwd.as_deref(), shell, &command)?;
+        // Stopping wsl.exe ends the command inside the distro
+        let (output, timed_out) = match timeout_secs {
+            Some(secs) => {
+                let limit = std::time::Duration::from_secs(secs);
+                processes::output_with_timeout(cmd, stdin, limit, true).await?
+            }
+            None => (processes::output_with_input(cmd, stdin).await?, false),
         };
 
-        #[cfg(not(windows))]
-        let output = tokio::process::Command::new("ssh")
-            .args([&remote_host, &full_cmd])
-            .output()
-            .await
-            .map_err(|e| format!("Failed to execute SSH command: {}", e))?;
+        Ok(CommandResult {
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: output.status.code().unwrap_or(-1),
+            process_id: None,
+            timed_out,
+        })
+    } else if let Some(secs) = timeout_secs {
+        let shell = shell.unwrap_or_else(CommandShell::platform_default);
+        let mut cmd = shell.tokio_command(&command);
+        cmd.current_dir(cwd.unwrap_or_else(|| ".".to_string()));
+        let (output, timed_out) =
+            processes::output_with_timeout(cmd, stdin, std::time::Duration::from_secs(secs), true)
+                .await?;
 
         Ok(CommandResult {
             stdout: String::from_utf8_lossy(&output.stdout).to_string(),
             stderr: String::from_utf8_lossy(&output.stderr).to_string(),
             exit_code: output.status.code().unwrap_or(-1),
+            process_id: None,
+            timed_out,
         })
     } else {
-        // Local command (keep sync for simplicity, local commands are fast)
-        if is_background {
-            if cfg!(windows) {
-                Command::new("cmd")
-                    .args(["/C", "start", "/B", &command])
-                    .current_dir(cwd.unwrap_or_else(|| ".".to_string()))
-                    .spawn()
-                    .map_err(|e| format!("Failed to spawn background command: {}", e))?;
-            } else {
-                Command::new("sh")
-                    .args(["-c", &format!("nohup {} > /dev/null 2>&1 &", command)])
-                    .current_dir(cwd.unwrap_or_else(|| ".".to_string()))
-                    .spawn()
-                    .map_err(|e| format!("Failed to spawn background command: {}", e))?;
-            }
-
-            Ok(CommandResult {
-                stdout: String::new(),
-                stderr: String::new(),
-                exit_code: 0,
-            })
-        } else {
-            let output = if cfg!(windows) {
-                Command::new("cmd")
-                    .args(["/C", &command])
-                    .current_dir(cwd.unwrap_or_else(|| ".".to_string()))
-                    .output()
-                    .map_err(|e| format!("Failed to execute command: {}", e))?
-            } else {
-                Command::new("sh")
-                    .args(["-c", &command])
-                    .current_dir(cwd.unwrap_or_else(|| ".".to_string()))
-                    .output()
-                    .map_err(|e| format!("Failed to execute command: {}", e))?
-            };
+        let shell = shell.unwrap_or_else(CommandShell::platform_default);
+        let mut cmd = shell.tokio_command(&command);
+        cmd.current_dir(cwd.unwrap_or_else(|| ".".to_string()));
+        let output = processes::output_with_input(cmd, stdin).await?;
 
-            Ok(CommandResult {
-                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-                exit_code: output.status.code().unwrap_or(-1),
-            })
+        Ok(CommandResult {
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: output.status.code().unwrap_or(-1),
+            process_id: None,
+            timed_out: false,
+        })
+    }
+}
+
+/// Start `process.command` in the background and track it in the process registry.
+/// The PID of the returned process is filled in here.
+async fn start_background_command(
+    mut process: RunningProcess,
+    shell: Option<CommandShell>,
+    app: &AppHandle,
+    processes: &ProcessRegistry,
+    cache: &HostStatusCache,
+    store: &JsonStore,
+) -> Result<CommandResult, String> {
+    let id = process.id.clone();
+
+    if let Some(remote_host) = process.host.clone() {
+        ensure_host_online(&remote_host, cache, store)?;
+
+        let command = match shell {
+            Some(shell) => shell.remote_line(&process.command),
+            None => process.command.clone(),
+        };
+        let script = match &process.cwd {
+            Some(dir) => format!("cd {} && {}", dir, command),
+            None => command,
+        };
+        // The remote shell prints the PID of the detached command
+        let full_cmd = processes::remote_detached_line(&script);
+
+        #[cfg(windows)]
+        let output = {
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+            tokio::process::Command::new("ssh")
+                .args(hosts::jump_args(process.jump_hosts.as_deref()))
+                .args([&remote_host, &full_cmd])
+                .creation_flags(CREATE_NO_WINDOW)
+                .output()
+                .await
+                .map_err(|e| format!("Failed to execute SSH command: {}", e))?
+        };
+
+        #[cfg(not(windows))]
+        let output = tokio::process::Command::new("ssh")
+            .args(hosts::jump_args(process.jump_hosts.as_deref()))
+            .args([&remote_host, &full_cmd])
+            .output()
+            .await
+            .map_err(|e| format!("Failed to execute SSH command: {}", e))?;
+
+        cache.record_ssh_output(&remote_host, &output);
+
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let exit_code = output.status.code().unwrap_or(-1);
+        let pid = stdout.lines().last().and_then(|l| l.trim().parse().ok());
+        let process_id = match pid {
+            Some(pid) if exit_code == 0 => {
+                process.pid = pid;
+                processes.track_remote(process);
+                Some(id)
+            }
+            _ => None,
+        };
+        Ok(CommandResult {
+            stdout,
+            stderr,
+            exit_code,
+            process_id,
+            timed_out: false,
+        })
+    } else if let Some(distro) = process.wsl_distro.clone() {
+        let cmd = wsl::std_command(&distro, process.cwd.as_deref(), shell, &process.command)?;
+        let child = processes::spawn_detached(cmd, None)?;
+        process.pid = child.id();
+        processes.track_local(app, child, process);
+
+        Ok(CommandResult {
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_code: 0,
+            process_id: Some(id),
+            timed_out: false,
+        })
+    } else {
+        let cwd = process.cwd.clone().unwrap_or_else(|| ".".to_string());
+        let shell = shell.unwrap_or_else(CommandShell::platform_default);
+        let child = processes::spawn_background(shell, &process.command, &cwd, None)?;
+        process.pid = child.id();
+        processes.track_local(app, child, process);
+
+        Ok(CommandResult {
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_code: 0,
+            process_id: Some(id),
+            timed_out: false,
+        })
+    }
+}
+
+// List background processes started with run_command that are still running
+#[tauri::command]
+pub async fn list_running_processes(
+    app: AppHandle,
+    processes: State<'_, ProcessRegistry>,
+) -> Result<Vec<RunningProcess>, String> {
+    let processes = processes.inner().clone();
+    tokio::task::spawn_blocking(move || processes.list(&app))
+        .await
+        .map_err(|e| format!("Failed to list processes: {}", e))
+}
+
+// Stop a background process (with its children) by its ID from list_running_processes
+#[tauri::command]
+pub async fn kill_process(
+    app: AppHandle,
+    id: String,
+    processes: State<'_, ProcessRegistry>,
+) -> Result<(), String> {
+    let processes = processes.inner().clone();
+    tokio::task::spawn_blocking(move || processes.kill(&app, &id))
+        .await
+        .map_err(|e| format!("Failed to stop process: {}", e))?
+}
+
+// Whether a local TCP port is in use, and by which processes
+#[tauri::command]
+pub async fn check_port(port: u16) -> Result<PortStatus, String> {
+    tokio::task::spawn_blocking(move || ports::check(port))
+        .await
+        .map_err(|e| format!("Failed to check port: {}", e))
+}
+
+// Stop whatever is listening on a local TCP port, returning the processes stopped
+#[tauri::command]
+pub async fn kill_process_on_port(port: u16) -> Result<Vec<PortProcess>, String> {
+    tokio::task::spawn_blocking(move || ports::kill(port))
+        .await
+        .map_err(|e| format!("Failed to free port: {}", e))?
+}
+
+// Check the ports a Command item declares, so "port 3000 already in use" can be
+// shown before launching it. Items running on a remote host aren't checked.
+#[tauri::command]
+pub async fn check_item_ports(
+    itemId: String,
+    store: State<'_, JsonStore>,
+) -> Result<Vec<PortStatus>, String> {
+    let item = store
+        .find_item(&itemId)
+        .ok_or_else(|| format!("Item {} not found", itemId))?;
+    let (_, host) = with_project_defaults(
+        &store,
+        Some(&item.project_id),
+        item.command_cwd.clone(),
+        item.command_host.clone(),
+    )?;
+    if host.is_some() {
+        return Ok(Vec::new());
+    }
+    let declared = item.ports.unwrap_or_default();
+    tokio::task::spawn_blocking(move || declared.into_iter().map(ports::check).collect())
+        .await
+        .map_err(|e| format!("Failed to check ports: {}", e))
+}
+
+// Start a service defined in the project's metadata. State changes (starting,
+// running, unhealthy, crashed, stopped) are emitted as `service://status` events.
+// Its command goes through the command policy like run_command.
+#[tauri::command]
+pub fn start_service(
+    projectId: String,
+    serviceId: String,
+    confirmed: Option<bool>,
+    app: AppHandle,
+    store: State<JsonStore>,
+    manager: State<ServiceManager>,
+) -> Result<ServiceStatus, String> {
+    let service = services::find_service(&app, &projectId, &serviceId)
+        .ok_or_else(|| format!("Service {} not found", serviceId))?;
+    policy::check(
+        &store,
+        &app.state::<SettingsFile>(),
+        Some(&projectId),
+        &service.command,
+        confirmed.unwrap_or(false),
+    )?;
+    let (cwd, _) = with_project_defaults(&store, Some(&projectId), service.cwd.clone(), None)?;
+    let shell = shell::resolve(&app.state::<SettingsFile>(), None)
+        .unwrap_or_else(CommandShell::platform_default);
+    manager.start(&app, &projectId, &service, cwd.as_deref(), shell)
+}
+
+#[tauri::command]
+pub fn stop_service(serviceId: String, manager: State<ServiceManager>) -> Result<(), String> {
+    manager.stop(&serviceId)
+}
+
+// Stop the service (if running), wait for it to exit and start it again
+#[tauri::command]
+pub async fn restart_service(
+    projectId: String,
+    serviceId: String,
+    confirmed: Option<bool>,
+    app: AppHandle,
+    store: State<'_, JsonStore>,
+    manager: State<'_, ServiceManager>,
+) -> Result<ServiceStatus, String> {
+    if manager.stop(&serviceId).is_ok() {
+        manager.wait_stopped(&serviceId).await?;
+    }
+    start_service(projectId, serviceId, confirmed, app, store, manager)
+}
+
+// Status of each service defined in the project's metadata
+#[tauri::command]
+pub fn get_service_status(
+    projectId: String,
+    store: State<JsonStore>,
+    manager: State<ServiceManager>,
+) -> Result<Vec<ServiceStatus>, String> {
+    let project = store
+        .get_project_by_id(&projectId)?
+        .ok_or("Project not found")?;
+    Ok(project
+        .metadata
+        .services
+        .unwrap_or_default()
+        .iter()
+        .map(|service| manager.status(&projectId, service))
+        .collect())
+}
+
+// Start a command and stream its output as `command://output` events, followed by
+// `command://finished`. Returns the run ID the events carry (for cancel_command).
+// `shell` picks the shell as in run_command.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn run_command_stream(
+    command: String,
+    cwd: Option<String>,
+    host: Option<String>,
+    projectId: Option<String>,
+    itemId: Option<String>,
+    shell: Option<CommandShell>,
+    confirmed: Option<bool>,
+    app: AppHandle,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+    runs: State<'_, CommandRuns>,
+) -> Result<String, String> {
+    policy::check(
+        &store,
+        &app.state::<SettingsFile>(),
+        projectId.as_deref(),
+        &command,
+        confirmed.unwrap_or(false),
+    )?;
+    let (cwd, host) = with_project_defaults(&store, projectId.as_deref(), cwd, host)?;
+    if let Some(host) = host.as_deref() {
+        ensure_host_online(host, &cache, &store)?;
+    }
+
+    let jump = host.as_deref().and_then(|h| hosts::jump_chain(&store, h));
+    let shell = shell::resolve(&app.state::<SettingsFile>(), shell);
+
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let finish_app = app.clone();
+    let finish_host = host.clone();
+    runs.start(
+        &app,
+        run_id.clone(),
+        &command,
+        cwd.as_deref(),
+        host.as_deref(),
+        jump.as_deref(),
+        shell,
+        move |finished| {
+            if finished.cancelled {
+                return;
+            }
+            if let Some(host) = finish_host.as_deref() {
+                finish_app
+                    .state::<HostStatusCache>()
+                    .record_ssh_exit(host, finished.exit_code);
+            }
+            if finished.exit_code == Some(0) {
+                record_launch(&finish_app.state::<UsageFile>(), itemId.as_deref());
+            }
+        },
+    )?;
+    Ok(run_id)
+}
+
+// Stop a command started with run_command_stream
+#[tauri::command]
+pub fn cancel_command(runId: String, runs: State<CommandRuns>) -> Result<(), String> {
+    runs.cancel(&runId)
+}
+
+// Run a script item's steps in order (locally, or over SSH on its command_host),
+// streaming output per step and stopping at the first failure. All steps go
+// through the command policy before the first one starts.
+#[tauri::command]
+pub async fn run_script_item(
+    itemId: String,
+    onEvent: Channel<ScriptEvent>,
+    confirmed: Option<bool>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+    usage: State<'_, UsageFile>,
+    settings_file: State<'_, SettingsFile>,
+) -> Result<ScriptRunResult, String> {
+    let item = store
+        .find_item(&itemId)
+        .ok_or_else(|| format!("Item {} not found", itemId))?;
+    if item.item_type != ItemType::Script {
+        return Err("Item is not a script".to_string());
+    }
+    let steps = scripts::parse_steps(&item.content);
+    policy::check(
+        &store,
+        &settings_file,
+        Some(&item.project_id),
+        &steps.join("\n"),
+        confirmed.unwrap_or(false),
+    )?;
+    let (cwd, host) = with_project_defaults(
+        &store,
+        Some(&item.project_id),
+        item.command_cwd.clone(),
+        item.command_host.clone(),
+    )?;
+    if let Some(host) = host.as_deref() {
+        ensure_host_online(host, &cache, &store)?;
+    }
+
+    let shell = shell::resolve(&settings_file, item.command_shell);
+    let result = scripts::run_script(&steps, cwd.as_deref(), host.as_deref(), shell, |event| {
+        let _ = onEvent.send(event);
+    })
+    .await;
+    if result.success {
+        record_launch(&usage, Some(&itemId));
+    }
+    Ok(result)
+}
+
+// Command items of a project by ID, checked up front so a typo doesn't surface
+// halfway through a multi-item run
+fn project_command_items(
+    store: &JsonStore,
+    project_id: &str,
+    item_ids: &[String],
+) -> Result<Vec<Item>, String> {
+    let mut items = Vec::with_capacity(item_ids.len());
+    for id in item_ids {
+        let item = store
+            .find_item(id)
+            .filter(|item| item.project_id == project_id)
+            .ok_or_else(|| format!("Item {} not found in project", id))?;
+        if item.item_type != ItemType::Command {
+            return Err(format!("Item \"{}\" is not a command", item.title));
+        }
+        items.push(item);
+    }
+    Ok(items)
+}
+
+// Check the commands of a multi-item run against the policy before the first one
+// starts, so one that needs confirming doesn't end the run halfway. `confirmed`
+// then covers every item.
+fn check_items(app: &AppHandle, items: &[Item], confirmed: bool) -> Result<(), String> {
+    let store = app.state::<JsonStore>();
+    let settings_file = app.state::<SettingsFile>();
+    for item in items {
+        policy::check(
+            &store,
+            &settings_file,
+            Some(&item.project_id),
+            &item.content,
+            confirmed,
+        )
+        .map_err(|e| format!("{} (item \"{}\")", e, item.title))?;
+    }
+    Ok(())
+}
+
+// Run one Command item in `mode`, with its own cwd and host
+async fn run_command_item(
+    app: AppHandle,
+    item: Item,
+    mode: CommandMode,
+    confirmed: bool,
+) -> ItemRunResult {
+    let outcome = run_command(
+        item.content,
+        mode,
+        item.command_cwd,
+        item.command_host,
+        Some(item.project_id),
+        Some(item.id.clone()),
+        None,
+        item.command_shell,
+        None,
+        Some(confirmed),
+        item.wsl_distro,
+        app.clone(),
+        app.state::<HostStatusCache>(),
+        app.state::<JsonStore>(),
+        app.state::<UsageFile>(),
+        app.state::<ProcessRegistry>(),
+    )
+    .await;
+    match outcome {
+        Ok(result) => ItemRunResult {
+            item_id: item.id,
+            title: item.title,
+            success: result.exit_code == 0,
+            result: Some(result),
+            error: None,
+        },
+        Err(e) => ItemRunResult {
+            item_id: item.id,
+            title: item.title,
+            success: false,
+            result: None,
+            error: Some(e),
+        },
+    }
+}
+
+// Run Command items of a project one after another, e.g. build, migrate, seed,
+// start. Each step runs in output mode whatever the item's own mode, so the next
+// one starts only after it has exited and its exit code counts. With
+// `stopOnError` the first failing step ends the run. Commands that need
+// confirming are reported before anything runs; `confirmed` confirms them all.
+#[tauri::command]
+pub async fn run_item_sequence(
+    projectId: String,
+    itemIds: Vec<String>,
+    stopOnError: Option<bool>,
+    confirmed: Option<bool>,
+    app: AppHandle,
+    store: State<'_, JsonStore>,
+) -> Result<SequenceRunResult, String> {
+    let items = project_command_items(&store, &projectId, &itemIds)?;
+    let confirmed = confirmed.unwrap_or(false);
+    check_items(&app, &items, confirmed)?;
+
+    let stop_on_error = stopOnError.unwrap_or(true);
+    let mut result = SequenceRunResult {
+        total_steps: items.len(),
+        steps: Vec::new(),
+        success: true,
+    };
+    for item in items {
+        let step = run_command_item(app.clone(), item, CommandMode::Output, confirmed).await;
+        let failed = !step.success;
+        result.steps.push(step);
+        if failed {
+            result.success = false;
+            if stop_on_error {
+                break;
+            }
+        }
+    }
+    Ok(result)
+}
+
+// Run Command items of a project at the same time (e.g. frontend, backend and
+// worker), each in its own mode, returning each one's result in the order given. Confirmation works as
+// in run_item_sequence.
+#[tauri::command]
+pub async fn run_commands_parallel(
+    projectId: String,
+    itemIds: Vec<String>,
+    confirmed: Option<bool>,
+    app: AppHandle,
+    store: State<'_, JsonStore>,
+) -> Result<ParallelRunResult, String> {
+    let items = project_command_items(&store, &projectId, &itemIds)?;
+    let confirmed = confirmed.unwrap_or(false);
+    check_items(&app, &items, confirmed)?;
+
+    let handles: Vec<_> = items
+        .into_iter()
+        .map(|item| {
+            let fallback = (item.id.clone(), item.title.clone());
+            let mode = item.command_mode.unwrap_or(CommandMode::Background);
+            let handle =
+                tauri::async_runtime::spawn(run_command_item(app.clone(), item, mode, confirmed));
+            (fallback, handle)
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for ((item_id, title), handle) in handles {
+        results.push(handle.await.unwrap_or_else(|e| ItemRunResult {
+            item_id,
+            title,
+            success: false,
+            result: None,
+            error: Some(format!("Command task failed: {}", e)),
+        }));
+    }
+    Ok(ParallelRunResult {
+        success: results.iter().all(|r| r.success),
+        results,
+    })
+}
+
+// Run a Command item on a cron-like schedule while the app is open (e.g. "0 2 * * *"
+// or "@hourly"); an empty or missing schedule removes it. The command goes through
+// the command policy now, as nobody is there to confirm it when it runs; a
+// confirmation holds until the command changes.
+#[tauri::command]
+pub fn set_item_schedule(
+    itemId: String,
+    schedule: Option<String>,
+    confirmed: Option<bool>,
+    store: State<JsonStore>,
+    settings_file: State<SettingsFile>,
+    schedules: State<ScheduleState>,
+) -> Result<Option<ItemSchedule>, String> {
+    let item = store
+        .find_item(&itemId)
+        .ok_or_else(|| format!("Item {} not found", itemId))?;
+    if item.item_type != ItemType::Command {
+        return Err("Only command items can be scheduled".to_string());
+    }
+    let confirmed = confirmed.unwrap_or(false);
+    if schedule.as_deref().is_some_and(|s| !s.trim().is_empty()) {
+        policy::check(
+            &store,
+            &settings_file,
+            Some(&item.project_id),
+            &item.content,
+            confirmed,
+        )?;
+    }
+    schedules.set(
+        &itemId,
+        &item.project_id,
+        schedule.as_deref(),
+        confirmed.then_some(item.content),
+    )
+}
+
+// Scheduled Command items with their next run time and last outcome, soonest first
+#[tauri::command]
+pub fn list_scheduled_runs(app: AppHandle) -> Vec<ScheduledRun> {
+    schedules::list(&app)
+}
+
+// Send an HTTP request item (request line, headers and body stored in its content)
+#[tauri::command]
+pub async fn run_http_request(
+    itemId: String,
+    store: State<'_, JsonStore>,
+    usage: State<'_, UsageFile>,
+) -> Result<HttpResponse, String> {
+    let item = store
+        .find_item(&itemId)
+        .ok_or_else(|| format!("Item {} not found", itemId))?;
+    if item.item_type != ItemType::HttpRequest {
+        return Err("Item is not an HTTP request".to_string());
+    }
+    let request = http_request::parse_request(&item.content)?;
+    let response = http_request::send(&request).await?;
+    record_launch(&usage, Some(&itemId));
+    Ok(response)
+}
+
+// Title, description and favicon for a URL card, cached under the data dir.
+// `refresh` skips the cache.
+#[tauri::command]
+pub async fn fetch_url_metadata(
+    url: String,
+    refresh: Option<bool>,
+    store: State<'_, JsonStore>,
+) -> Result<UrlMetadata, String> {
+    let data_path = store.data_path();
+    if !refresh.unwrap_or(false) {
+        if let Some(metadata) = url_metadata::cached(&data_path, &url) {
+            return Ok(metadata);
         }
     }
+    let metadata = url_metadata::fetch(&url).await?;
+    // A failed cache write only costs a refetch next time
+    if let Err(e) = url_metadata::store_cached(&data_path, &metadata) {
+        log::warn!("{}", e);
+    }
+    Ok(metadata)
+}
+
+// Stars, open issues/PRs and CI status of the project's github_url repository.
+// Results are cached for a few minutes under the data dir; `refresh` skips the cache.
+#[tauri::command]
+pub async fn get_github_status(
+    projectId: String,
+    refresh: Option<bool>,
+    store: State<'_, JsonStore>,
+) -> Result<Option<GithubRepoStatus>, String> {
+    let project = store
+        .get_project_by_id(&projectId)?
+        .ok_or("Project not found")?;
+    let Some((owner, repo)) = project
+        .metadata
+        .github_url
+        .as_deref()
+        .and_then(github::parse_repo)
+    else {
+        return Ok(None);
+    };
+
+    let data_path = store.data_path();
+    if !refresh.unwrap_or(false) {
+        if let Some(status) = github::cached(&data_path, &owner, &repo) {
+            return Ok(Some(status));
+        }
+    }
+    let status = github::fetch_status(&owner, &repo).await?;
+    if let Err(e) = github::store_cached(&data_path, &status) {
+        log::warn!("{}", e);
+    }
+    Ok(Some(status))
+}
+
+// GitHub API token, kept in the OS keyring. An empty token removes it.
+#[tauri::command]
+pub fn set_github_token(token: String) -> Result<(), String> {
+    let token = token.trim();
+    if token.is_empty() {
+        keyring::delete_secret(github::TOKEN_ACCOUNT)
+    } else {
+        keyring::set_secret(github::TOKEN_ACCOUNT, token)
+    }
+}
+
+#[tauri::command]
+pub fn has_github_token() -> Result<bool, String> {
+    Ok(keyring::get_secret(github::TOKEN_ACCOUNT)?.is_some())
+}
+
+// Docker items: compose file or container, run in the item's cwd (or the project's
+// first working dir) and on its command_host if set
+async fn run_docker_item(
+    item_id: &str,
+    action: docker::DockerAction,
+    cache: &HostStatusCache,
+    store: &JsonStore,
+) -> Result<CommandResult, String> {
+    let item = store
+        .find_item(item_id)
+        .ok_or_else(|| format!("Item {} not found", item_id))?;
+    if item.item_type != ItemType::Docker {
+        return Err("Item is not a Docker item".to_string());
+    }
+
+    // Without an item or project default, run where the project's first working dir is
+    let (cwd, host) = match with_project_defaults(
+        store,
+        Some(&item.project_id),
+        item.command_cwd.clone(),
+        item.command_host.clone(),
+    )? {
+        (None, None) => store
+            .get_project_by_id(&item.project_id)?
+            .and_then(|p| p.metadata.working_dirs?.into_iter().next())
+            .map_or((None, None), |dir| (Some(dir.path), dir.host)),
+        pair => pair,
+    };
+    if let Some(host) = host.as_deref() {
+        ensure_host_online(host, cache, store)?;
+    }
+
+    let target = docker::DockerTarget::from_content(&item.content)?;
+    docker::run_docker(&target.args(action), cwd.as_deref(), host.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn docker_compose_up(
+    itemId: String,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+    usage: State<'_, UsageFile>,
+) -> Result<CommandResult, String> {
+    let result = run_docker_item(&itemId, docker::DockerAction::Up, &cache, &store).await?;
+    if result.exit_code == 0 {
+        record_launch(&usage, Some(&itemId));
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn docker_compose_down(
+    itemId: String,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<CommandResult, String> {
+    run_docker_item(&itemId, docker::DockerAction::Down, &cache, &store).await
+}
+
+#[tauri::command]
+pub async fn docker_compose_ps(
+    itemId: String,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<Vec<ContainerStatus>, String> {
+    let result = run_docker_item(&itemId, docker::DockerAction::Ps, &cache, &store).await?;
+    if result.exit_code != 0 {
+        return Err(format!("docker ps failed: {}", result.stderr.trim()));
+    }
+    Ok(docker::parse_ps(&result.stdout))
+}
+
+#[tauri::command]
+pub async fn docker_compose_logs(
+    itemId: String,
+    tail: Option<u32>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<CommandResult, String> {
+    let action = docker::DockerAction::Logs(tail.unwrap_or(200));
+    run_docker_item(&itemId, action, &cache, &store).await
+}
+
+// Database items: content is a connection string template whose `{password}`
+// placeholder is filled from the OS keyring, never stored in the project data
+fn open_database_item(item: &Item, store: &JsonStore) -> Result<(), String> {
+    if item.item_type != ItemType::Database {
+        return Err("Item is not a database item".to_string());
+    }
+    let secret = keyring::get_secret(&database::secret_account(&item.id))?;
+    database::open_client(
+        item.database_client.unwrap_or(DatabaseClient::Psql),
+        &item.title,
+        &item.content,
+        secret.as_deref(),
+        store,
+    )
+}
+
+#[tauri::command]
+pub fn open_database_client(
+    itemId: String,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+) -> Result<(), String> {
+    let item = store
+        .find_item(&itemId)
+        .ok_or_else(|| format!("Item {} not found", itemId))?;
+    open_database_item(&item, &store)?;
+    record_launch(&usage, Some(&itemId));
+    Ok(())
+}
+
+// Store a database item's password in the OS keyring (empty or null removes it)
+#[tauri::command]
+pub fn set_database_secret(itemId: String, secret: Option<String>) -> Result<(), String> {
+    let account = database::secret_account(&itemId);
+    match secret.filter(|s| !s.is_empty()) {
+        Some(secret) => keyring::set_secret(&account, &secret),
+        None => keyring::delete_secret(&account),
+    }
+}
+
+#[tauri::command]
+pub fn has_database_secret(itemId: String) -> Result<bool, String> {
+    Ok(keyring::get_secret(&database::secret_account(&itemId))?.is_some())
+}
+
+#[tauri::command]
+pub fn copy_snippet_to_clipboard(
+    itemId: String,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+) -> Result<(), String> {
+    let item = store
+        .find_item(&itemId)
+        .ok_or_else(|| format!("Item {} not found", itemId))?;
+    if item.item_type != ItemType::Snippet {
+        return Err("Item is not a snippet".to_string());
+    }
+    clipboard::copy_text(&item.content)?;
+    record_launch(&usage, Some(&itemId));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn copy_to_clipboard(text: String) -> Result<(), String> {
+    clipboard::copy_text(&text)
+}
+
+// Copy the `ssh -t` command that opens a shell on `host` (through its jump hosts),
+// in `path` when given. Returns the copied command.
+#[tauri::command]
+pub fn copy_ssh_command(
+    host: String,
+    path: Option<String>,
+    store: State<JsonStore>,
+) -> Result<String, String> {
+    // Path is left unquoted so that `~` expands on the remote side
+    let remote = path
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("cd {} && exec $SHELL -l", p));
+    let jump = hosts::jump_chain(&store, &host);
+    let line = terminal::ssh_command(&host, jump.as_deref(), remote.as_deref());
+    clipboard::copy_text(&line)?;
+    Ok(line)
+}
+
+// Copy the shell command that does what an item does (command, SSH session, coding
+// agent or snippet items). Returns the copied command.
+#[tauri::command]
+pub fn copy_item_command(itemId: String, store: State<JsonStore>) -> Result<String, String> {
+    let item = store
+        .find_item(&itemId)
+        .ok_or_else(|| format!("Item {} not found", itemId))?;
+    let line = item_shell_command(&store, item)?;
+    clipboard::copy_text(&line)?;
+    Ok(line)
+}
+
+// `command`, run in `cwd` on `host` over SSH, in a WSL distro or locally
+fn located_command(
+    store: &JsonStore,
+    command: &str,
+    cwd: Option<&str>,
+    host: Option<&str>,
+    wsl_distro: Option<&str>,
+) -> String {
+    let cwd = cwd.filter(|d| !d.is_empty());
+    if let Some(host) = host.filter(|h| !h.is_empty()) {
+        // Path is left unquoted so that `~` expands on the remote side
+        let remote = match cwd {
+            Some(dir) => format!("cd {} && {}", dir, command),
+            None => command.to_string(),
+        };
+        let jump = hosts::jump_chain(store, host);
+        return terminal::ssh_command(host, jump.as_deref(), Some(&remote));
+    }
+    if let Some(distro) = wsl_distro.filter(|d| !d.is_empty()) {
+        let cd = cwd
+            .map(|dir| format!(" --cd \"{}\"", dir))
+            .unwrap_or_default();
+        return format!("wsl -d {}{} -- {}", distro, cd, command);
+    }
+    match cwd {
+        Some(dir) if cfg!(windows) => format!("cd /d \"{}\" && {}", dir, command),
+        Some(dir) => format!("cd {} && {}", git::shell_quote(dir), command),
+        None => command.to_string(),
+    }
+}
+
+fn item_shell_command(store: &JsonStore, item: Item) -> Result<String, String> {
+    match item.item_type {
+        ItemType::Snippet => Ok(item.content),
+        ItemType::SshSession => ssh_item_command(&item, store).map(|(_, line)| line),
+        ItemType::Command => {
+            let (cwd, host) = with_project_defaults(
+                store,
+                Some(&item.project_id),
+                item.command_cwd,
+                item.command_host,
+            )?;
+            Ok(located_command(
+                store,
+                &item.content,
+                cwd.as_deref(),
+                host.as_deref(),
+                item.wsl_distro.as_deref(),
+            ))
+        }
+        // Env is left out so that API keys don't end up on the clipboard
+        ItemType::CodingAgent => {
+            let agent = item
+                .coding_agent_type
+                .ok_or("Coding agent item has no agent type")?;
+            let template = match &agent {
+                CodingAgentType::Custom => {
+                    let id = item
+                        .custom_agent_id
+                        .as_deref()
+                        .ok_or("No custom agent selected")?;
+                    agents::find_custom(store, id)?.command
+                }
+                agent => agents::builtin_command(agent)
+                    .unwrap_or_default()
+                    .to_string(),
+            };
+            let agent_cmd = agents::command_line(&template, item.coding_agent_args.as_deref());
+            let host = store.get_project_by_id(&item.project_id)?.and_then(|p| {
+                p.metadata
+                    .working_dirs?
+                    .into_iter()
+                    .find(|dir| dir.path == item.content)?
+                    .host
+            });
+            Ok(located_command(
+                store,
+                &agent_cmd,
+                Some(&item.content),
+                host.as_deref(),
+                item.wsl_distro.as_deref(),
+            ))
+        }
+        _ => Err("Item has no shell command".to_string()),
+    }
+}
+
+// Full-text search over items of every project, optionally limited to one item type
+#[tauri::command]
+pub fn search_items(
+    query: String,
+    itemType: Option<ItemType>,
+    store: State<JsonStore>,
+) -> Result<Vec<ItemSearchResult>, String> {
+    store.search_items_with_projects(&query, itemType)
+}
+
+// Full-text search over snippets in all projects, optionally limited to one language
+#[tauri::command]
+pub fn search_snippets(
+    query: String,
+    language: Option<String>,
+    store: State<JsonStore>,
+) -> Result<Vec<Item>, String> {
+    let mut snippets = store.search_items(&query, Some(ItemType::Snippet))?;
+    if let Some(language) = language.filter(|l| !l.is_empty()) {
+        snippets.retain(|item| {
+            item.language
+                .as_deref()
+                .is_some_and(|l| l.eq_ignore_ascii_case(&language))
+        });
+    }
+    Ok(snippets)
+}
+
+// SSH session items: an interactive `ssh -t` to command_host, starting in
+// command_cwd and attaching to (or creating) the tmux session if one is named
+fn open_ssh_item(item: &Item, cache: &HostStatusCache, store: &JsonStore) -> Result<(), String> {
+    let (host, ssh_cmd) = ssh_item_command(item, store)?;
+    ensure_host_online(&host, cache, store)?;
+    terminal::open(store, None, None, &ssh_cmd, &[])
+}
+
+// Host and `ssh -t` command line of an SSH session item
+fn ssh_item_command(item: &Item, store: &JsonStore) -> Result<(String, String), String> {
+    if item.item_type != ItemType::SshSession {
+        return Err("Item is not an SSH session".to_string());
+    }
+    let (cwd, host) = with_project_defaults(
+        store,
+        Some(&item.project_id),
+        item.command_cwd.clone(),
+        item.command_host.clone(),
+    )?;
+    let host = host
+        .as_deref()
+        .filter(|h| !h.is_empty())
+        .ok_or("SSH session item has no host")?;
+
+    // Path is left unquoted so that `~` expands on the remote side
+    let mut remote = Vec::new();
+    if let Some(dir) = cwd.as_deref().filter(|d| !d.is_empty()) {
+        remote.push(format!("cd {}", dir));
+    }
+    match item.tmux_session.as_deref().filter(|s| !s.is_empty()) {
+        Some(session) => remote.push(format!("tmux new -A -s {}", git::shell_quote(session))),
+        None if !remote.is_empty() => remote.push("exec $SHELL -l".to_string()),
+        None => {}
+    }
+
+    let jump = hosts::jump_chain(store, host);
+    let ssh_cmd = terminal::ssh_command(host, jump.as_deref(), Some(&remote.join(" && ")));
+    Ok((host.to_string(), ssh_cmd))
+}
+
+#[tauri::command]
+pub fn open_ssh_session(
+    itemId: String,
+    cache: State<HostStatusCache>,
+    store: State<JsonStore>,
+    usage: State<UsageFile>,
+) -> Result<(), String> {
+    let item = store
+        .find_item(&itemId)
+        .ok_or_else(|| format!("Item {} not found", itemId))?;
+    open_ssh_item(&item, &cache, &store)?;
+    record_launch(&usage, Some(&itemId));
+    Ok(())
+}
+
+// File size limit settings (values in bytes)
+const FILE_ABSOLUTE_MAX_KEY: &str = "fileAbsoluteMaxSize";
+const FILE_DEFAULT_READ_SIZE_KEY: &str = "fileDefaultReadSize";
+const FILE_STREAM_CHUNK_SIZE_KEY: &str = "fileStreamChunkSize";
+const DEFAULT_ABSOLUTE_MAX: u64 = 500 * 1024 * 1024; // 500MB
+const DEFAULT_READ_SIZE: u64 = 10 * 1024 * 1024; // 10MB
+const DEFAULT_STREAM_CHUNK_SIZE: u64 = 1024 * 1024; // 1MB
+const MIN_STREAM_CHUNK_SIZE: u64 = 4; // Fits any one character
+
+// Resolve file size limits from settings
+fn file_limits(store: &JsonStore) -> FileLimits {
+    FileLimits {
+        absolute_max_size: store.get_setting_or(FILE_ABSOLUTE_MAX_KEY, DEFAULT_ABSOLUTE_MAX),
+        default_read_size: store.get_setting_or(FILE_DEFAULT_READ_SIZE_KEY, DEFAULT_READ_SIZE),
+        stream_chunk_size: store
+            .get_setting_or(FILE_STREAM_CHUNK_SIZE_KEY, DEFAULT_STREAM_CHUNK_SIZE)
+            .max(MIN_STREAM_CHUNK_SIZE),
+    }
+}
+
+#[tauri::command]
+pub fn get_file_limits(store: State<JsonStore>) -> FileLimits {
+    file_limits(&store)
 }
 
 // File reading for drag-drop
@@ -985,71 +4042,106 @@ pub async fn read_file_content(
     max_size: Option<u64>,
     offset: Option<u64>,
     length: Option<u64>,
+    card_id: Option<String>,
+    store: State<'_, JsonStore>,
 ) -> Result<ReadFileResult, String> {
     use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
+    let limits = file_limits(&store);
+    // Per-card override takes precedence over the global default
+    let card_max_size = card_id
+        .and_then(|id| store.find_file_card(&id))
+        .and_then(|card| card.max_size);
+
     let metadata = tokio::fs::metadata(&path)
         .await
         .map_err(|e| format!("Failed to read file metadata: {}", e))?;
 
     let file_size = metadata.len();
 
-    // Absolute max file size for safety (500MB)
-    const ABSOLUTE_MAX: u64 = 500 * 1024 * 1024;
-    if file_size > ABSOLUTE_MAX {
+    if file_size > limits.absolute_max_size {
         return Err(format!(
             "File too large ({} bytes). Max: {} bytes",
-            file_size, ABSOLUTE_MAX
+            file_size, limits.absolute_max_size
         ));
     }
 
-    let content = if let (Some(offset_val), Some(length_val)) = (offset, length) {
-        // Chunk reading mode for virtual scrolling
-        let mut file = tokio::fs::File::open(&path)
-            .await
-            .map_err(|e| format!("Failed to open file: {}", e))?;
-
-        // Seek to offset
-        file.seek(tokio::io::SeekFrom::Start(offset_val))
-            .await
-            .map_err(|e| format!("Failed to seek file: {}", e))?;
-
-        // Read chunk
-        let bytes_to_read = length_val.min(file_size.saturating_sub(offset_val));
-        let mut buffer = vec![0; bytes_to_read as usize];
-        let bytes_read = file
-            .read(&mut buffer)
-            .await
-            .map_err(|e| format!("Failed to read file: {}", e))?;
-
-        buffer.truncate(bytes_read);
-        String::from_utf8(buffer).map_err(|e| format!("Failed to decode file as UTF-8: {}", e))?
-    } else {
-        // Legacy mode: read entire file or first max_size bytes
-        let max_size = max_size.unwrap_or(10 * 1024 * 1024); // Default 10MB
-
-        if file_size <= max_size {
-            // File is small enough, read entire file
-            tokio::fs::read_to_string(&path)
-                .await
-                .map_err(|e| format!("Failed to read file: {}", e))?
-        } else {
-            // File is larger than max_size, read only first max_size bytes
+    // Non-UTF-8 text is transcoded to UTF-8
+    let (content, encoding_name, chunk) =
+        if let (Some(offset_val), Some(length_val)) = (offset, length) {
+            // Chunk reading mode for virtual scrolling
             let mut file = tokio::fs::File::open(&path)
                 .await
                 .map_err(|e| format!("Failed to open file: {}", e))?;
 
-            let mut buffer = vec![0; max_size as usize];
+            // Seek to offset
+            file.seek(tokio::io::SeekFrom::Start(offset_val))
+                .await
+                .map_err(|e| format!("Failed to seek file: {}", e))?;
+
+            // Read chunk
+            let bytes_to_read = length_val.min(file_size.saturating_sub(offset_val));
+            let mut buffer = vec![0; bytes_to_read as usize];
             let bytes_read = file
                 .read(&mut buffer)
                 .await
                 .map_err(|e| format!("Failed to read file: {}", e))?;
 
             buffer.truncate(bytes_read);
-            String::from_utf8(buffer)
-                .map_err(|e| format!("Failed to decode file as UTF-8: {}", e))?
-        }
-    };
+            // The encoding comes from the start of the file, which a chunk further in
+            // may not tell apart (or may cut at an odd UTF-16 offset)
+            let file_encoding = if offset_val == 0 {
+                encoding::detect(&buffer)?
+            } else {
+                file.seek(tokio::io::SeekFrom::Start(0))
+                    .await
+                    .map_err(|e| format!("Failed to seek file: {}", e))?;
+                let mut head = Vec::with_capacity(encoding::HEAD_BYTES);
+                (&mut file)
+                    .take(encoding::HEAD_BYTES as u64)
+                    .read_to_end(&mut head)
+                    .await
+                    .map_err(|e| format!("Failed to read file: {}", e))?;
+                encoding::detect(&head)?
+            };
+            // The requested range can cut a multi-byte character at either end
+            let (content, range) = encoding::decode_chunk(file_encoding, &buffer, offset_val);
+            let chunk = (
+                offset_val + range.start as u64,
+                offset_val + range.end as u64,
+            );
+            (content, file_encoding.name(), Some(chunk))
+        } else {
+            // Legacy mode: read entire file or first max_size bytes
+            let max_size = card_max_size
+                .or(max_size)
+                .unwrap_or(limits.default_read_size)
+                .min(limits.absolute_max_size);
+
+            if file_size <= max_size {
+                // File is small enough, read entire file
+                let bytes = tokio::fs::read(&path)
+                    .await
+                    .map_err(|e| format!("Failed to read file: {}", e))?;
+                let (content, encoding_name) = encoding::decode(&bytes)?;
+                (content, encoding_name, None)
+            } else {
+                // File is larger than max_size, read only first max_size bytes
+                let mut file = tokio::fs::File::open(&path)
+                    .await
+                    .map_err(|e| format!("Failed to open file: {}", e))?;
+
+                let mut buffer = vec![0; max_size as usize];
+                let bytes_read = file
+                    .read(&mut buffer)
+                    .await
+                    .map_err(|e| format!("Failed to read file: {}", e))?;
+
+                buffer.truncate(bytes_read);
+                let (content, encoding_name) = encoding::decode(&buffer)?;
+                (content, encoding_name, None)
+            }
+        };
 
     // Extract filename from path
     let filename = std::path::Path::new(&path)
@@ -1062,80 +4154,615 @@ pub async fn read_file_content(
         filename,
         content,
         file_size,
+        encoding: encoding_name.to_string(),
+        chunk_start: chunk.map(|(start, _)| start),
+        chunk_end: chunk.map(|(_, end)| end),
+        modified_at: modified_at(&metadata),
+    })
+}
+
+/// A file's modification time as RFC 3339, compared verbatim by write_file_content
+fn modified_at(metadata: &std::fs::Metadata) -> Option<String> {
+    metadata
+        .modified()
+        .ok()
+        .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+}
+
+// Save a file edited in a file card. With `expected_mtime` (the `modified_at` of the
+// read) the write is rejected if the file changed since; the previous content is
+// kept in `<path>.bak`. A symlink is written through, to the file it points to.
+#[tauri::command]
+pub async fn write_file_content(
+    path: String,
+    content: String,
+    expected_mtime: Option<String>,
+    store: State<'_, JsonStore>,
+) -> Result<WriteFileResult, String> {
+    let limits = file_limits(&store);
+    if content.len() as u64 > limits.absolute_max_size {
+        return Err(format!(
+            "Content too large ({} bytes). Max: {} bytes",
+            content.len(),
+            limits.absolute_max_size
+        ));
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let target = match fs::canonicalize(&path) {
+            Ok(resolved) => resolved,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => path.clone().into(),
+            Err(e) => return Err(format!("Failed to resolve {}: {}", path, e)),
+        };
+        let target = target.as_path();
+        let existing = match fs::metadata(target) {
+            Ok(metadata) => Some(metadata),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(format!("Failed to read file metadata: {}", e)),
+        };
+        if let Some(expected) = expected_mtime.as_deref() {
+            let current = existing.as_ref().and_then(modified_at);
+            if current.as_deref() != Some(expected) {
+                return Err("File changed on disk since it was read; reload it first".to_string());
+            }
+        }
+
+        let backup_path = match &existing {
+            Some(metadata) if metadata.is_dir() => {
+                return Err(format!("{} is a directory", path));
+            }
+            Some(_) => {
+                let backup = format!("{}.bak", path);
+                fs::copy(target, &backup).map_err(|e| format!("Failed to write backup: {}", e))?;
+                Some(backup)
+            }
+            None => None,
+        };
+
+        // A uniquely named temp file in the same directory, renamed over the target,
+        // so a failed write never leaves a half-written file and concurrent saves
+        // don't share a temp file. It is removed again if anything fails.
+        let dir = target
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let mut temp = tempfile::Builder::new()
+            .prefix(".devora-")
+            .suffix(".tmp")
+            .tempfile_in(dir)
+            .map_err(|e| format!("Failed to create temp file: {}", e))?;
+        std::io::Write::write_all(&mut temp, content.as_bytes())
+            .map_err(|e| format!("Failed to write temp file: {}", e))?;
+        if let Some(metadata) = &existing {
+            let _ = temp.as_file().set_permissions(metadata.permissions());
+        }
+        temp.persist(target)
+            .map_err(|e| format!("Failed to replace file: {}", e.error))?;
+
+        let metadata =
+            fs::metadata(target).map_err(|e| format!("Failed to read file metadata: {}", e))?;
+        Ok(WriteFileResult {
+            file_size: metadata.len(),
+            modified_at: modified_at(&metadata),
+            backup_path,
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to write file: {}", e))?
+}
+
+// Preview for any file: text (like read_file_content, but binary-safe), images
+// and PDFs as base64 with their MIME type, or a hex dump for other binaries
+#[tauri::command]
+pub async fn read_file_preview(
+    path: String,
+    max_size: Option<u64>,
+    card_id: Option<String>,
+    store: State<'_, JsonStore>,
+) -> Result<FilePreview, String> {
+    use tokio::io::AsyncReadExt;
+
+    let limits = file_limits(&store);
+    let text_limit = card_id
+        .and_then(|id| store.find_file_card(&id))
+        .and_then(|card| card.max_size)
+        .or(max_size)
+        .unwrap_or(limits.default_read_size)
+        .min(limits.absolute_max_size);
+
+    let file_size = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    // Text and hex previews need only the start of the file; images and PDFs are
+    // read whole, up to their own cap
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut bytes = Vec::new();
+    (&mut file)
+        .take(file_preview::head_len(text_limit as usize) as u64)
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    if file_size <= file_preview::MEDIA_MAX_BYTES
+        && file_preview::is_media(&bytes, Path::new(&path))
+    {
+        (&mut file)
+            .take(file_preview::MEDIA_MAX_BYTES)
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+    }
+    Ok(file_preview::preview(
+        Path::new(&path),
+        bytes,
+        file_size,
+        text_limit as usize,
+    ))
+}
+
+// Stream a file in chunks over a channel, for files too large to read at once.
+// Sends at most `maxChunks` chunks per call; the caller requests more from
+// `next_offset` once it has consumed them, which bounds memory on both sides.
+// Chunks are decoded in the encoding detected from the file's head; a character
+// cut off at the end of a chunk is carried into the next, and `next_offset` is
+// where the decoded text ends.
+#[tauri::command]
+pub async fn stream_file_chunks(
+    path: String,
+    offset: Option<u64>,
+    chunkSize: Option<u64>,
+    maxChunks: Option<u32>,
+    onChunk: Channel<FileChunk>,
+    store: State<'_, JsonStore>,
+) -> Result<FileStreamProgress, String> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let limits = file_limits(&store);
+    let chunk_size = chunkSize
+        .unwrap_or(limits.stream_chunk_size)
+        .clamp(MIN_STREAM_CHUNK_SIZE, limits.stream_chunk_size);
+    let max_chunks = maxChunks.unwrap_or(4).max(1);
+
+    let file_size = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+
+    let mut head = Vec::with_capacity(encoding::HEAD_BYTES);
+    (&mut file)
+        .take(encoding::HEAD_BYTES as u64)
+        .read_to_end(&mut head)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let file_encoding = encoding::detect(&head)?;
+
+    // `position` is where the decoded text ends; `buffer` holds the bytes from there
+    let mut position = offset.unwrap_or(0).min(file_size);
+    file.seek(tokio::io::SeekFrom::Start(position))
+        .await
+        .map_err(|e| format!("Failed to seek file: {}", e))?;
+    let mut buffer = Vec::with_capacity(chunk_size as usize);
+    let mut read_to = position;
+
+    for _ in 0..max_chunks {
+        if read_to >= file_size {
+            break;
+        }
+
+        let bytes_read = (&mut file)
+            .take(chunk_size)
+            .read_to_end(&mut buffer)
+            .await
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if bytes_read == 0 {
+            break;
+        }
+        read_to += bytes_read as u64;
+
+        let (mut content, mut range) = encoding::decode_chunk(file_encoding, &buffer, position);
+        if read_to >= file_size && range.end < buffer.len() {
+            // A character cut off by the end of the file won't be completed
+            let (rest, _) = file_encoding.decode_without_bom_handling(&buffer[range.end..]);
+            content.push_str(&rest);
+            range.end = buffer.len();
+        }
+        if !content.is_empty() {
+            onChunk
+                .send(FileChunk {
+                    offset: position + range.start as u64,
+                    content,
+                })
+                .map_err(|e| format!("Failed to send chunk: {}", e))?;
+        }
+        position += range.end as u64;
+        buffer.drain(..range.end);
+    }
+
+    Ok(FileStreamProgress {
+        next_offset: position,
+        file_size,
+        eof: position >= file_size,
     })
 }
 
-// Get file info for virtual scrolling
+// Get file info for virtual scrolling
+#[tauri::command]
+pub async fn get_file_info(
+    path: String,
+    store: State<'_, JsonStore>,
+    cache: State<'_, LineIndexCache>,
+) -> Result<FileInfo, String> {
+    let absolute_max = file_limits(&store).absolute_max_size;
+
+    let metadata = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?;
+
+    let file_size = metadata.len();
+
+    if file_size > absolute_max {
+        return Err(format!(
+            "File too large ({} bytes). Max: {} bytes",
+            file_size, absolute_max
+        ));
+    }
+
+    // Builds the line index read_file_lines uses next
+    let cache = cache.inner().clone();
+    let index_path = path.clone();
+    let line_count = tokio::task::spawn_blocking(move || cache.line_count(Path::new(&index_path)))
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))??;
+
+    // Extract filename from path
+    let filename = std::path::Path::new(&path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    Ok(FileInfo {
+        filename,
+        file_size,
+        line_count,
+    })
+}
+
+// Read specific lines from file for virtual scrolling
+// The first request for a file indexes its line offsets; later ones (until the
+// file changes) seek straight to the requested lines
+#[tauri::command]
+pub async fn read_file_lines(
+    path: String,
+    start_line: usize,
+    count: usize,
+    cache: State<'_, LineIndexCache>,
+) -> Result<FileLinesResult, String> {
+    let cache = cache.inner().clone();
+    tokio::task::spawn_blocking(move || cache.read_lines(Path::new(&path), start_line, count))
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?
+}
+
+// Find lines of a file matching a query (plain text, or a regex with `regex`)
+// without sending the whole file to the frontend
+#[tauri::command]
+pub async fn search_in_file(
+    path: String,
+    query: String,
+    regex: Option<bool>,
+    caseSensitive: Option<bool>,
+) -> Result<FileSearchResult, String> {
+    tokio::task::spawn_blocking(move || {
+        file_search::search(
+            Path::new(&path),
+            &query,
+            regex.unwrap_or(false),
+            caseSensitive.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to search file: {}", e))?
+}
+
+// Diff a file against the last commit, or against `base` (a commit, branch or
+// tag), for the file card change view
+#[tauri::command]
+pub async fn diff_file_against_head(
+    path: String,
+    host: Option<String>,
+    base: Option<String>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<FileDiffResult, String> {
+    if let Some(remote_host) = host.as_deref() {
+        ensure_host_online(remote_host, &cache, &store)?;
+    }
+    git::diff_file(&path, host.as_deref(), base.as_deref().unwrap_or("HEAD")).await
+}
+
+// Branch, ahead/behind, dirty file count and last commit of a working dir
+#[tauri::command]
+pub async fn get_git_status(
+    path: String,
+    host: Option<String>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<GitStatus, String> {
+    if let Some(remote_host) = host.as_deref() {
+        ensure_host_online(remote_host, &cache, &store)?;
+    }
+    git::status(&path, host.as_deref()).await
+}
+
+// Git worktrees of a working dir's repository
+#[tauri::command]
+pub async fn list_git_worktrees(
+    path: String,
+    host: Option<String>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<Vec<GitWorktree>, String> {
+    if let Some(remote_host) = host.as_deref() {
+        ensure_host_online(remote_host, &cache, &store)?;
+    }
+    git::list_worktrees(&path, host.as_deref()).await
+}
+
+// Add a worktree for a branch (next to the repo unless `worktreePath` is given).
+// With `projectId` the worktree is also added to the project's working dirs, so
+// parallel agent sessions can be opened in it.
+#[tauri::command]
+pub async fn create_git_worktree(
+    path: String,
+    branch: String,
+    worktreePath: Option<String>,
+    host: Option<String>,
+    projectId: Option<String>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<GitWorktree, String> {
+    if let Some(remote_host) = host.as_deref() {
+        ensure_host_online(remote_host, &cache, &store)?;
+    }
+    let worktree_path = worktreePath
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| git::default_worktree_path(&path, &branch));
+    let worktree = git::add_worktree(&path, host.as_deref(), &branch, &worktree_path).await?;
+
+    if let Some(project_id) = projectId {
+        let project = store
+            .get_project_by_id(&project_id)?
+            .ok_or("Project not found")?;
+        let mut metadata = project.metadata;
+        let dirs = metadata.working_dirs.get_or_insert_with(Vec::new);
+        if !dirs
+            .iter()
+            .any(|d| d.path == worktree.path && d.host == host)
+        {
+            dirs.push(WorkingDir {
+                name: worktree.branch.clone().unwrap_or(branch),
+                path: worktree.path.clone(),
+                host,
+            });
+            store.update_project(&project_id, None, None, Some(metadata))?;
+        }
+    }
+    Ok(worktree)
+}
+
+// Remove a worktree, and with `projectId` drop it from the project's working dirs
+#[tauri::command]
+pub async fn remove_git_worktree(
+    path: String,
+    worktreePath: String,
+    force: Option<bool>,
+    host: Option<String>,
+    projectId: Option<String>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<(), String> {
+    if let Some(remote_host) = host.as_deref() {
+        ensure_host_online(remote_host, &cache, &store)?;
+    }
+    git::remove_worktree(
+        &path,
+        host.as_deref(),
+        &worktreePath,
+        force.unwrap_or(false),
+    )
+    .await?;
+
+    if let Some(project_id) = projectId {
+        if let Some(project) = store.get_project_by_id(&project_id)? {
+            let mut metadata = project.metadata;
+            if let Some(dirs) = metadata.working_dirs.as_mut() {
+                let before = dirs.len();
+                dirs.retain(|d| !(d.path == worktreePath && d.host == host));
+                if dirs.len() != before {
+                    store.update_project(&project_id, None, None, Some(metadata))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Recent commits of a working dir, newest first, for the project history panel
 #[tauri::command]
-pub async fn get_file_info(path: String) -> Result<FileInfo, String> {
-    let metadata = tokio::fs::metadata(&path)
-        .await
-        .map_err(|e| format!("Failed to read file metadata: {}", e))?;
+pub async fn get_git_log(
+    path: String,
+    limit: Option<usize>,
+    host: Option<String>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<Vec<GitCommit>, String> {
+    if let Some(remote_host) = host.as_deref() {
+        ensure_host_online(remote_host, &cache, &store)?;
+    }
+    git::log_with_links(&path, host.as_deref(), limit.unwrap_or(20).clamp(1, 500)).await
+}
 
-    let file_size = metadata.len();
+// Git quick actions on a working dir (local, or over SSH with `host`)
+#[tauri::command]
+pub async fn git_fetch(
+    path: String,
+    host: Option<String>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<GitActionResult, String> {
+    if let Some(remote_host) = host.as_deref() {
+        ensure_host_online(remote_host, &cache, &store)?;
+    }
+    git::fetch(&path, host.as_deref()).await
+}
 
-    // Absolute max file size for safety (500MB)
-    const ABSOLUTE_MAX: u64 = 500 * 1024 * 1024;
-    if file_size > ABSOLUTE_MAX {
-        return Err(format!(
-            "File too large ({} bytes). Max: {} bytes",
-            file_size, ABSOLUTE_MAX
-        ));
+#[tauri::command]
+pub async fn git_pull(
+    path: String,
+    host: Option<String>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<GitActionResult, String> {
+    if let Some(remote_host) = host.as_deref() {
+        ensure_host_online(remote_host, &cache, &store)?;
     }
+    git::pull(&path, host.as_deref()).await
+}
 
-    // Read file and count lines
-    let content = tokio::fs::read_to_string(&path)
-        .await
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+#[tauri::command]
+pub async fn git_list_branches(
+    path: String,
+    host: Option<String>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<Vec<GitBranch>, String> {
+    if let Some(remote_host) = host.as_deref() {
+        ensure_host_online(remote_host, &cache, &store)?;
+    }
+    git::list_branches(&path, host.as_deref()).await
+}
 
-    let line_count = content.lines().count();
+#[tauri::command]
+pub async fn git_checkout_branch(
+    path: String,
+    branch: String,
+    host: Option<String>,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<GitActionResult, String> {
+    if let Some(remote_host) = host.as_deref() {
+        ensure_host_online(remote_host, &cache, &store)?;
+    }
+    git::checkout_branch(&path, host.as_deref(), &branch).await
+}
 
-    // Extract filename from path
-    let filename = std::path::Path::new(&path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+/// Git status of a working dir, or the error; `reachable` is the result of the
+/// host check (Ok for local dirs)
+async fn working_dir_git_status(
+    dir: WorkingDir,
+    reachable: Result<(), String>,
+) -> WorkingDirGitStatus {
+    let result = match reachable {
+        Ok(()) => git::status(&dir.path, dir.host.as_deref()).await,
+        Err(e) => Err(e),
+    };
+    let (status, error) = match result {
+        Ok(status) => (Some(status), None),
+        Err(e) => (None, Some(e)),
+    };
+    WorkingDirGitStatus {
+        name: dir.name,
+        path: dir.path,
+        host: dir.host,
+        status,
+        error,
+    }
+}
 
-    Ok(FileInfo {
-        filename,
-        file_size,
-        line_count,
-    })
+fn host_reachable(
+    dir: &WorkingDir,
+    cache: &HostStatusCache,
+    store: &JsonStore,
+) -> Result<(), String> {
+    match dir.host.as_deref() {
+        Some(host) => ensure_host_online(host, cache, store),
+        None => Ok(()),
+    }
 }
 
-// Read specific lines from file for virtual scrolling
-// Simple implementation: read entire file, then slice
-// Trade memory for speed - works well for files up to 500MB
+// Git status of every working dir of a project, for the dashboard
 #[tauri::command]
-pub async fn read_file_lines(
-    path: String,
-    start_line: usize,
-    count: usize,
-) -> Result<FileLinesResult, String> {
-    let content = tokio::fs::read_to_string(&path)
-        .await
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+pub async fn get_project_git_status(
+    projectId: String,
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<Vec<WorkingDirGitStatus>, String> {
+    let project = store
+        .get_project_by_id(&projectId)?
+        .ok_or("Project not found")?;
+
+    let mut statuses = Vec::new();
+    for dir in project.metadata.working_dirs.unwrap_or_default() {
+        let reachable = host_reachable(&dir, &cache, &store);
+        statuses.push(working_dir_git_status(dir, reachable).await);
+    }
+    Ok(statuses)
+}
 
-    let all_lines: Vec<&str> = content.lines().collect();
+// Most working dirs get_all_repo_statuses checks at once, so many projects don't
+// start a git or ssh process per dir all together
+const REPO_STATUS_CONCURRENCY: usize = 8;
 
-    if start_line >= all_lines.len() {
-        return Ok(FileLinesResult {
-            lines: vec![],
-            start_line,
-        });
+// Repos with uncommitted changes or unpushed commits across all projects ("did I
+// forget to push anywhere?"). Working dirs are checked concurrently, a few at a
+// time; dirs that aren't repos or whose host is offline are left out.
+#[tauri::command]
+pub async fn get_all_repo_statuses(
+    cache: State<'_, HostStatusCache>,
+    store: State<'_, JsonStore>,
+) -> Result<Vec<ProjectRepoStatus>, String> {
+    let permits = std::sync::Arc::new(tokio::sync::Semaphore::new(REPO_STATUS_CONCURRENCY));
+    let mut tasks = Vec::new();
+    for project in store.get_all_projects()? {
+        for dir in project.metadata.working_dirs.clone().unwrap_or_default() {
+            let reachable = host_reachable(&dir, &cache, &store);
+            let (project_id, project_name) = (project.id.clone(), project.name.clone());
+            let permits = permits.clone();
+            tasks.push(tauri::async_runtime::spawn(async move {
+                // The semaphore is never closed
+                let _permit = permits.acquire_owned().await;
+                ProjectRepoStatus {
+                    project_id,
+                    project_name,
+                    repo: working_dir_git_status(dir, reachable).await,
+                }
+            }));
+        }
     }
 
-    let end_line = (start_line + count).min(all_lines.len());
-    let result_lines: Vec<String> = all_lines[start_line..end_line]
-        .iter()
-        .map(|&s| s.to_string())
-        .collect();
-
-    Ok(FileLinesResult {
-        lines: result_lines,
-        start_line,
-    })
+    let mut statuses = Vec::new();
+    for task in tasks {
+        let Ok(status) = task.await else {
+            continue;
+        };
+        let needs_attention = status.repo.status.as_ref().is_some_and(|s| {
+            // A branch that was never pushed counts as unpushed
+            let unpublished = s.branch.is_some() && s.upstream.is_none() && s.last_commit.is_some();
+            s.dirty_files > 0 || s.ahead > 0 || unpublished
+        });
+        if needs_attention {
+            statuses.push(status);
+        }
+    }
+    Ok(statuses)
 }
 
 // Data Path Management (renamed from Database Path)
@@ -1162,6 +4789,48 @@ pub fn set_data_path(path: String, settings_file: State<SettingsFile>) -> Result
     settings_file.set_data_path(path_option)
 }
 
+// Switch the data directory without restarting: optionally copy the current data
+// over, re-initialize the store there and tell all windows to reload
+#[tauri::command]
+pub fn switch_data_path(
+    path: String,
+    copyExisting: Option<bool>,
+    app: AppHandle,
+    store: State<JsonStore>,
+    settings_file: State<SettingsFile>,
+    migration_state: State<MigrationState>,
+) -> Result<String, String> {
+    let config_dir = settings_file.config_dir().to_path_buf();
+    let new_path = if path.is_empty() {
+        config_dir.clone()
+    } else {
+        std::path::PathBuf::from(&path)
+    };
+
+    if new_path == store.data_path() {
+        return Ok(new_path.to_string_lossy().to_string());
+    }
+
+    if copyExisting.unwrap_or(false) {
+        store.copy_data_to(&new_path)?;
+    }
+
+    store.switch_data_path(new_path.clone())?;
+
+    let path_option = if path.is_empty() { None } else { Some(path) };
+    settings_file.set_data_path(path_option)?;
+
+    // An old SQLite database at the new location is migrated like on startup
+    if migration::needs_migration(&config_dir, &new_path) {
+        migration_state.mark_running();
+        migration::spawn_background_migration(app.clone(), config_dir, new_path.clone(), |_| {});
+    }
+
+    let new_path = new_path.to_string_lossy().to_string();
+    let _ = app.emit("data-path-changed", &new_path);
+    Ok(new_path)
+}
+
 #[tauri::command]
 pub fn check_data_exists(path: String) -> bool {
     let metadata_path = Path::new(&path).join("metadata.json");
@@ -1202,6 +4871,24 @@ pub fn get_project_todos(projectId: String, store: State<JsonStore>) -> Result<S
     store.get_project_todos(&projectId)
 }
 
+// Todos matching a priority filter, in the requested order. Items keep their line in
+// the full markdown, which is what edits go through (get/set_project_todos).
+#[tauri::command]
+pub fn get_filtered_todos(
+    projectId: String,
+    maxPriority: Option<u8>,
+    includeCompleted: Option<bool>,
+    sort: Option<TodoSort>,
+    store: State<JsonStore>,
+) -> Result<Vec<ChecklistItem>, String> {
+    Ok(todos::filter_checklist(
+        &store.get_project_todos(&projectId)?,
+        maxPriority,
+        includeCompleted.unwrap_or(true),
+        sort.unwrap_or_default(),
+    ))
+}
+
 #[tauri::command]
 pub fn set_project_todos(
     projectId: String,
@@ -1211,6 +4898,222 @@ pub fn set_project_todos(
     store.set_project_todos(&projectId, &content)
 }
 
+// Time tracking: one timer runs at a time, across all projects
+#[tauri::command]
+pub fn start_timer(
+    projectId: String,
+    todo: Option<String>,
+    store: State<JsonStore>,
+) -> Result<TimeEntry, String> {
+    store.start_timer(&projectId, todo.as_deref())
+}
+
+#[tauri::command]
+pub fn stop_timer(store: State<JsonStore>) -> Result<Option<TimeEntry>, String> {
+    store.stop_timer()
+}
+
+#[tauri::command]
+pub fn get_running_timer(store: State<JsonStore>) -> Result<Option<TimeEntry>, String> {
+    store.get_running_timer()
+}
+
+#[tauri::command]
+pub fn get_time_entries(
+    projectId: String,
+    store: State<JsonStore>,
+) -> Result<Vec<TimeEntry>, String> {
+    store.get_time_entries(&projectId)
+}
+
+// Kanban view of the todos: columns come from the `todoStatusColumns` setting
+#[tauri::command]
+pub fn get_todos_by_status(
+    projectId: String,
+    store: State<JsonStore>,
+) -> Result<Vec<TodoColumn>, String> {
+    let columns = todos::load_status_columns(&store)?;
+    Ok(todos::by_status(
+        &store.get_project_todos(&projectId)?,
+        &columns,
+    ))
+}
+
+#[tauri::command]
+pub fn set_todo_status(
+    projectId: String,
+    line: usize,
+    status: String,
+    store: State<JsonStore>,
+) -> Result<String, String> {
+    let columns = todos::load_status_columns(&store)?;
+    store.set_todo_status(&projectId, line, &status, &columns)
+}
+
+#[tauri::command]
+pub fn get_todo_status_columns(store: State<JsonStore>) -> Result<Vec<String>, String> {
+    todos::load_status_columns(&store)
+}
+
+#[tauri::command]
+pub fn set_todo_status_columns(
+    columns: Vec<String>,
+    store: State<JsonStore>,
+) -> Result<(), String> {
+    todos::save_status_columns(&store, &columns)
+}
+
+// Todo activity over the last `days` days (default 30), per day and per week
+#[tauri::command]
+pub fn get_todo_stats(
+    projectId: String,
+    days: Option<u32>,
+    store: State<JsonStore>,
+) -> Result<TodoStats, String> {
+    store.get_todo_stats(&projectId, days.unwrap_or(30))
+}
+
+// Open todos across all projects that are overdue or due within `withinDays` (default 7)
+#[tauri::command]
+pub fn get_upcoming_todos(
+    withinDays: Option<i64>,
+    store: State<JsonStore>,
+) -> Result<Vec<ProjectTodo>, String> {
+    store.get_todos_across_projects(&TodoFilter {
+        due_within_days: Some(withinDays.unwrap_or(7)),
+        ..Default::default()
+    })
+}
+
+// Open todos from all projects, for the "Today" planning view and badge counts
+#[tauri::command]
+pub fn get_todos_across_projects(
+    filter: Option<TodoFilter>,
+    store: State<JsonStore>,
+) -> Result<Vec<ProjectTodo>, String> {
+    store.get_todos_across_projects(&filter.unwrap_or_default())
+}
+
+// Markdown notes: sanitized HTML for the preview, and checklist promotion into todos
+#[tauri::command]
+pub fn render_markdown(content: String) -> String {
+    markdown::render(&content)
+}
+
+#[tauri::command]
+pub fn get_note_checklist(
+    itemId: String,
+    store: State<JsonStore>,
+) -> Result<Vec<ChecklistItem>, String> {
+    let item = store.find_item(&itemId).ok_or("Item not found")?;
+    if item.item_type != ItemType::Note {
+        return Err("Item is not a note".to_string());
+    }
+    Ok(markdown::checklist(&item.content))
+}
+
+// Copy a note's checklist (or the given lines of it) into the project's todos
+#[tauri::command]
+pub fn promote_note_checklist(
+    itemId: String,
+    lines: Option<Vec<usize>>,
+    store: State<JsonStore>,
+) -> Result<String, String> {
+    let item = store.find_item(&itemId).ok_or("Item not found")?;
+    if item.item_type != ItemType::Note {
+        return Err("Item is not a note".to_string());
+    }
+    let mut entries = markdown::checklist(&item.content);
+    if let Some(lines) = lines {
+        entries.retain(|entry| lines.contains(&entry.line));
+    }
+    let entries: Vec<(String, bool)> = entries
+        .into_iter()
+        .map(|entry| (entry.text, entry.completed))
+        .collect();
+    store.append_project_todos(&item.project_id, &entries)
+}
+
+// TODO/FIXME/HACK comments in the project's local working dirs
+#[tauri::command]
+pub async fn scan_code_todos(
+    projectId: String,
+    store: State<'_, JsonStore>,
+) -> Result<Vec<CodeTodo>, String> {
+    let project = store
+        .get_project_by_id(&projectId)?
+        .ok_or("Project not found")?;
+    let working_dirs = project.metadata.working_dirs.unwrap_or_default();
+    tokio::task::spawn_blocking(move || code_todos::scan(&working_dirs))
+        .await
+        .map_err(|e| format!("Failed to scan for code todos: {}", e))
+}
+
+// Add selected code comments to the project's todos, pointing back at file and line
+#[tauri::command]
+pub fn import_code_todos(
+    projectId: String,
+    todos: Vec<CodeTodo>,
+    store: State<JsonStore>,
+) -> Result<String, String> {
+    let entries: Vec<(String, bool)> = todos
+        .iter()
+        .map(|todo| {
+            let text = if todo.text.is_empty() {
+                todo.tag.clone()
+            } else {
+                format!("{}: {}", todo.tag, todo.text)
+            };
+            (
+                format!(
+                    "{} ({}/{}:{})",
+                    text, todo.working_dir, todo.path, todo.line
+                ),
+                false,
+            )
+        })
+        .collect();
+    store.append_project_todos(&projectId, &entries)
+}
+
+#[tauri::command]
+pub fn get_todo_progress(
+    projectId: String,
+    store: State<JsonStore>,
+) -> Result<TodoProgress, String> {
+    store.get_todo_progress(&projectId)
+}
+
+// Projects nested via sub-project links, with todo progress rolled up from children
+#[tauri::command]
+pub fn get_project_tree(store: State<JsonStore>) -> Result<Vec<ProjectTreeNode>, String> {
+    store.get_project_tree()
+}
+
+#[tauri::command]
+pub fn link_projects(
+    projectId: String,
+    relatedIds: Vec<String>,
+    store: State<JsonStore>,
+) -> Result<Project, String> {
+    store.link_projects(&projectId, &relatedIds)
+}
+
+#[tauri::command]
+pub fn unlink_projects(
+    projectId: String,
+    relatedIds: Vec<String>,
+    store: State<JsonStore>,
+) -> Result<Project, String> {
+    store.unlink_projects(&projectId, &relatedIds)
+}
+
+// Nodes and edges (related-project and sub-project links) for the relationship view
+#[tauri::command]
+pub fn get_project_graph(store: State<JsonStore>) -> Result<ProjectGraph, String> {
+    store.get_project_graph()
+}
+
 // Window management
 #[tauri::command]
 pub async fn open_project_window(
@@ -1220,6 +5123,10 @@ pub async fn open_project_window(
 ) -> Result<(), String> {
     let window_label = format!("project-{}", projectId);
 
+    if let Err(e) = app.state::<UsageFile>().record_project_opened(&projectId) {
+        log::warn!("Failed to record project {} as opened: {}", projectId, e);
+    }
+
     // Check if window already exists
     if let Some(window) = app.get_webview_window(&window_label) {
         // Window exists, focus it
@@ -1241,3 +5148,316 @@ pub async fn open_project_window(
 
     Ok(())
 }
+
+// Actions registry
+#[tauri::command]
+pub fn get_actions(store: State<JsonStore>) -> Result<Vec<Action>, String> {
+    actions::load_actions(&store)
+}
+
+// Create or replace an action (matched by id)
+#[tauri::command]
+pub fn save_action(action: Action, store: State<JsonStore>) -> Result<Action, String> {
+    let mut all = actions::load_actions(&store)?;
+    match all.iter_mut().find(|a| a.id == action.id) {
+        Some(existing) => *existing = action.clone(),
+        None => all.push(action.clone()),
+    }
+    actions::save_actions(&store, &all)?;
+    Ok(action)
+}
+
+#[tauri::command]
+pub fn delete_action(id: String, store: State<JsonStore>) -> Result<bool, String> {
+    let mut all = actions::load_actions(&store)?;
+    let original_len = all.len();
+    all.retain(|a| a.id != id);
+    if all.len() == original_len {
+        return Ok(false);
+    }
+    actions::save_actions(&store, &all)?;
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn get_action_audit_log(
+    limit: Option<usize>,
+    settings_file: State<SettingsFile>,
+) -> Vec<ActionAuditEntry> {
+    actions::read_audit(settings_file.config_dir(), limit.unwrap_or(100))
+}
+
+// Single entry point for shortcuts, tray entries, deep links and the HTTP API.
+// `source` identifies the caller in the audit log.
+#[tauri::command]
+pub async fn execute_action(
+    actionId: String,
+    source: Option<String>,
+    confirmed: Option<bool>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let result = dispatch_action(&app, &actionId, confirmed.unwrap_or(false)).await;
+
+    let entry = ActionAuditEntry {
+        action_id: actionId,
+        source: source.unwrap_or_else(|| "unknown".to_string()),
+        success: result.is_ok(),
+        error: result.as_ref().err().cloned(),
+        executed_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let settings_file = app.state::<SettingsFile>();
+    if let Err(e) = actions::record_audit(settings_file.config_dir(), &entry) {
+        log::warn!("{}", e);
+    }
+
+    result
+}
+
+async fn dispatch_action(app: &AppHandle, action_id: &str, confirmed: bool) -> Result<(), String> {
+    let action = {
+        let store = app.state::<JsonStore>();
+        actions::load_actions(&store)?
+            .into_iter()
+            .find(|a| a.id == action_id)
+            .ok_or_else(|| format!("Action not found: {}", action_id))?
+    };
+
+    match action.operation {
+        ActionOperation::OpenProject { project_id } => {
+            let project = app
+                .state::<JsonStore>()
+                .get_project_by_id(&project_id)?
+                .ok_or_else(|| format!("Project not found: {}", project_id))?;
+            open_project_window(app.clone(), project.id, project.name).await
+        }
+        ActionOperation::RunItem { item_id } => {
+            let item = app
+                .state::<JsonStore>()
+                .find_item(&item_id)
+                .ok_or_else(|| format!("Item not found: {}", item_id))?;
+            launch_item(app, item, confirmed).await
+        }
+        ActionOperation::StartTimer { project_id } => app
+            .state::<JsonStore>()
+            .start_timer(&project_id, None)
+            .map(|_| ()),
+    }
+}
+
+// User-defined IDE from the customIdes / customRemoteIdes settings. Remote ones may
+// give a URI template instead of a command.
+#[derive(serde::Deserialize)]
+struct CustomIdeCommand {
+    id: String,
+    #[serde(default)]
+    command: String,
+    #[serde(default)]
+    uri: Option<String>,
+}
+
+fn find_custom_ide(store: &JsonStore, key: &str, id: &str) -> Result<CustomIdeCommand, String> {
+    let ides: Vec<CustomIdeCommand> = store
+        .get_setting(key)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    ides.into_iter()
+        .find(|ide| ide.id == id)
+        .ok_or_else(|| format!("Custom IDE \"{}\" not found", id))
+}
+
+// Launch an item the same way its section in the project view does. `confirmed`
+// confirms a command the policy asks about.
+async fn launch_item(app: &AppHandle, item: Item, confirmed: bool) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
+
+    let store = app.state::<JsonStore>();
+    let item_id = item.id.clone();
+
+    let launched = match item.item_type {
+        ItemType::Ide => {
+            let ide = item.ide_type.ok_or("IDE item has no IDE type")?;
+            match ide.parse::<IdeType>() {
+                Ok(ide_type) => open_ide(ide_type, item.content, None, app.state::<UsageFile>()),
+                Err(_) => open_custom_ide(
+                    find_custom_ide(&store, "customIdes", &ide)?.command,
+                    item.content,
+                    None,
+                    Some(confirmed),
+                    app.state::<JsonStore>(),
+                    app.state::<UsageFile>(),
+                    app.state::<SettingsFile>(),
+                ),
+            }
+        }
+        ItemType::RemoteIde => {
+            let ide = item
+                .remote_ide_type
+                .ok_or("Remote IDE item has no IDE type")?;
+            let (host, path) = item
+                .content
+                .split_once(':')
+                .ok_or("Remote IDE item has no host")?;
+            match ide.parse::<RemoteIdeType>() {
+                Ok(ide_type) => {
+                    open_remote_ide(
+                        ide_type,
+                        host.to_string(),
+                        path.to_string(),
+                        item.ide_type.as_deref().and_then(|i| i.parse().ok()),
+                        None,
+                        app.clone(),
+                        app.state::<JsonStore>(),
+                        app.state::<UsageFile>(),
+                    )
+                    .await
+                }
+                Err(_) => match find_custom_ide(&store, "customRemoteIdes", &ide)? {
+                    CustomIdeCommand { uri: Some(uri), .. } if !uri.is_empty() => open_remote_uri(
+                        uri,
+                        host.to_string(),
+                        path.to_string(),
+                        None,
+                        app.clone(),
+                        app.state::<UsageFile>(),
+                    ),
+                    custom => open_custom_remote_ide(
+                        custom.command,
+                        host.to_string(),
+                        path.to_string(),
+                        None,
+                        Some(confirmed),
+                        app.state::<JsonStore>(),
+                        app.state::<UsageFile>(),
+                        app.state::<SettingsFile>(),
+                    ),
+                },
+            }
+        }
+        ItemType::CodingAgent => {
+            let agent = item
+                .coding_agent_type
+                .ok_or("Coding agent item has no agent type")?;
+            let terminal = store
+                .get_setting("defaultTerminal")?
+                .and_then(|t| t.parse::<TerminalType>().ok());
+            // The agent runs on the host of its working dir, with any per-host override
+            let host = store.get_project_by_id(&item.project_id)?.and_then(|p| {
+                p.metadata
+                    .working_dirs?
+                    .into_iter()
+                    .find(|dir| dir.path == item.content)?
+                    .host
+            });
+            open_coding_agent(
+                agent,
+                item.content,
+                terminal,
+                item.coding_agent_args,
+                store.get_setting("codingAgentGlobalEnv")?,
+                item.coding_agent_env,
+                host,
+                None,
+                Some(confirmed),
+                item.wsl_distro,
+                item.custom_agent_id,
+                None,
+                None,
+                None,
+                app.clone(),
+                app.state::<HostStatusCache>(),
+                app.state::<JsonStore>(),
+                app.state::<UsageFile>(),
+                app.state::<SettingsFile>(),
+            )
+        }
+        ItemType::Command => {
+            let result = run_command(
+                item.content,
+                item.command_mode.unwrap_or(CommandMode::Background),
+                item.command_cwd,
+                item.command_host,
+                Some(item.project_id),
+                None,
+                None,
+                item.command_shell,
+                None,
+                Some(confirmed),
+                item.wsl_distro,
+                app.clone(),
+                app.state::<HostStatusCache>(),
+                app.state::<JsonStore>(),
+                app.state::<UsageFile>(),
+                app.state::<ProcessRegistry>(),
+            )
+            .await?;
+            if result.exit_code != 0 {
+                return Err(format!(
+                    "Command exited with code {}: {}",
+                    result.exit_code,
+                    result.stderr.trim()
+                ));
+            }
+            Ok(())
+        }
+        ItemType::Url => app
+            .opener()
+            .open_url(&item.content, None::<&str>)
+            .map_err(|e| format!("Failed to open URL: {}", e)),
+        ItemType::File => app
+            .opener()
+            .open_path(&item.content, None::<&str>)
+            .map_err(|e| format!("Failed to open file: {}", e)),
+        ItemType::Script => {
+            let steps = scripts::parse_steps(&item.content);
+            policy::check(
+                &store,
+                &app.state::<SettingsFile>(),
+                Some(&item.project_id),
+                &steps.join("\n"),
+                confirmed,
+            )?;
+            let (cwd, host) = with_project_defaults(
+                &store,
+                Some(&item.project_id),
+                item.command_cwd,
+                item.command_host,
+            )?;
+            if let Some(host) = host.as_deref() {
+                ensure_host_online(host, &app.state::<HostStatusCache>(), &store)?;
+            }
+            let shell = shell::resolve(&app.state::<SettingsFile>(), item.command_shell);
+            let result =
+                scripts::run_script(&steps, cwd.as_deref(), host.as_deref(), shell, |_| {}).await;
+            result.error.map_or(Ok(()), Err)
+        }
+        ItemType::HttpRequest => {
+            let response = http_request::send(&http_request::parse_request(&item.content)?).await?;
+            if response.status >= 400 {
+                return Err(format!("Request failed with HTTP {}", response.status));
+            }
+            Ok(())
+        }
+        ItemType::Docker => {
+            let result = run_docker_item(
+                &item.id,
+                docker::DockerAction::Up,
+                &app.state::<HostStatusCache>(),
+                &store,
+            )
+            .await?;
+            if result.exit_code != 0 {
+                return Err(format!("docker failed: {}", result.stderr.trim()));
+            }
+            Ok(())
+        }
+        ItemType::Database => open_database_item(&item, &store),
+        ItemType::Snippet => clipboard::copy_text(&item.content),
+        ItemType::SshSession => open_ssh_item(&item, &app.state::<HostStatusCache>(), &store),
+        ItemType::Note => Err("Note items can't be run".to_string()),
+    };
+
+    if launched.is_ok() {
+        record_launch(&app.state::<UsageFile>(), Some(&item_id));
+    }
+    launched
+}
@@ -0,0 +1,78 @@
+use crate::json_store::JsonStore;
+use crate::models::{CustomFieldDefinition, CustomFieldType};
+use std::collections::{HashMap, HashSet};
+
+/// Global setting holding the custom project field definitions as a JSON array
+pub const CUSTOM_FIELD_SCHEMA_KEY: &str = "customFieldSchema";
+
+pub fn load_schema(store: &JsonStore) -> Result<Vec<CustomFieldDefinition>, String> {
+    match store.get_setting(CUSTOM_FIELD_SCHEMA_KEY)? {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse custom field schema: {}", e)),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Replace the field definitions. Names must be unique and select fields need options.
+pub fn save_schema(store: &JsonStore, schema: &[CustomFieldDefinition]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for field in schema {
+        let name = field.name.trim();
+        if name.is_empty() {
+            return Err("Custom field names can't be empty".to_string());
+        }
+        if !names.insert(name) {
+            return Err(format!("Duplicate custom field \"{}\"", name));
+        }
+        if field.field_type == CustomFieldType::Select
+            && field.options.as_ref().is_none_or(|o| o.is_empty())
+        {
+            return Err(format!("Select field \"{}\" has no options", name));
+        }
+    }
+
+    let json = serde_json::to_string(schema)
+        .map_err(|e| format!("Failed to serialize custom field schema: {}", e))?;
+    store.set_setting(CUSTOM_FIELD_SCHEMA_KEY, &json)
+}
+
+fn check_value(field: &CustomFieldDefinition, value: &str) -> Result<(), String> {
+    let valid = match field.field_type {
+        CustomFieldType::Text => true,
+        CustomFieldType::Number => value.trim().parse::<f64>().is_ok(),
+        CustomFieldType::Boolean => value == "true" || value == "false",
+        CustomFieldType::Date => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        CustomFieldType::Select => field
+            .options
+            .as_ref()
+            .is_some_and(|options| options.iter().any(|o| o == value)),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid value \"{}\" for custom field \"{}\" ({:?})",
+            value, field.name, field.field_type
+        ))
+    }
+}
+
+/// Validate custom field values against the schema. Empty values are dropped.
+/// Fields missing from the schema are only accepted if their value is unchanged
+/// from `previous`, so removing a definition doesn't make old projects unsaveable.
+pub fn validate(
+    schema: &[CustomFieldDefinition],
+    fields: &mut HashMap<String, String>,
+    previous: Option<&HashMap<String, String>>,
+) -> Result<(), String> {
+    fields.retain(|_, value| !value.trim().is_empty());
+
+    for (name, value) in fields.iter() {
+        match schema.iter().find(|field| &field.name == name) {
+            Some(field) => check_value(field, value)?,
+            None if previous.and_then(|p| p.get(name)) == Some(value) => {}
+            None => return Err(format!("Unknown custom field \"{}\"", name)),
+        }
+    }
+    Ok(())
+}
@@ -203,6 +203,8 @@ impl Database {
                 metadata,
                 created_at: row.get(4)?,
                 updated_at: row.get(5)?,
+                last_opened_at: None,
+                todo_progress: None,
                 items: None,
             })
         })?;
@@ -226,6 +228,8 @@ impl Database {
                     metadata,
                     created_at: row.get(4)?,
                     updated_at: row.get(5)?,
+                    last_opened_at: None,
+                    todo_progress: None,
                     items: None,
                 })
             },
@@ -261,6 +265,7 @@ impl Database {
                         command_mode: command_mode_str.and_then(|s| s.parse().ok()),
                         command_cwd: row.get(11)?,
                         command_host: row.get(12)?,
+                        ..Default::default()
                     })
                 })?;
                 p.items = Some(items.filter_map(|r| r.ok()).collect());
@@ -294,6 +299,8 @@ impl Database {
             metadata,
             created_at: timestamp.clone(),
             updated_at: timestamp,
+            last_opened_at: None,
+            todo_progress: None,
             items: None,
         })
     }
@@ -407,6 +414,7 @@ impl Database {
             order,
             created_at: timestamp.clone(),
             updated_at: timestamp,
+            ..Default::default()
         })
     }
 
@@ -536,6 +544,7 @@ impl Database {
             order,
             created_at: existing.7,
             updated_at: timestamp,
+            ..Default::default()
         }))
     }
 
@@ -601,6 +610,7 @@ impl Database {
                 created_at: row.get(8)?,
                 updated_at: row.get(9)?,
                 is_minimized: row.get::<_, i32>(10).unwrap_or(0) == 1,
+                ..Default::default()
             })
         })?;
         rows.collect()
@@ -643,6 +653,7 @@ impl Database {
             z_index,
             created_at: timestamp.clone(),
             updated_at: timestamp,
+            ..Default::default()
         })
     }
 
@@ -673,6 +684,7 @@ impl Database {
                     created_at: row.get(8)?,
                     updated_at: row.get(9)?,
                     is_minimized: row.get::<_, i32>(10).unwrap_or(0) == 1,
+                    ..Default::default()
                 })
             })
             .ok();
@@ -708,6 +720,7 @@ impl Database {
             z_index,
             created_at: existing.created_at,
             updated_at: timestamp,
+            ..Default::default()
         }))
     }
 
@@ -784,6 +797,7 @@ impl Database {
                             metadata: row.get(3)?,
                             created_at: row.get(4)?,
                             updated_at: row.get(5)?,
+                            todos: None,
                         })
                     })?
                     .filter_map(|r| r.ok())
@@ -818,6 +832,7 @@ impl Database {
                             command_mode: command_mode_str.and_then(|s| s.parse().ok()),
                             command_cwd: row.get(11)?,
                             command_host: row.get(12)?,
+                            ..Default::default()
                         })
                     })?
                     .filter_map(|r| r.ok())
@@ -841,6 +856,7 @@ impl Database {
                             created_at: row.get(8)?,
                             updated_at: row.get(9)?,
                             is_minimized: row.get(10).unwrap_or(0),
+                            ..Default::default()
                         })
                     })?
                     .filter_map(|r| r.ok())
@@ -859,6 +875,7 @@ impl Database {
                         metadata: row.get(3)?,
                         created_at: row.get(4)?,
                         updated_at: row.get(5)?,
+                        todos: None,
                     })
                 })?
                 .filter_map(|r| r.ok())
@@ -892,6 +909,7 @@ impl Database {
                         command_mode: command_mode_str.and_then(|s| s.parse().ok()),
                         command_cwd: row.get(11)?,
                         command_host: row.get(12)?,
+                        ..Default::default()
                     })
                 })?
                 .filter_map(|r| r.ok())
@@ -913,6 +931,7 @@ impl Database {
                         created_at: row.get(8)?,
                         updated_at: row.get(9)?,
                         is_minimized: row.get(10).unwrap_or(0),
+                        ..Default::default()
                     })
                 })?
                 .filter_map(|r| r.ok())
@@ -927,6 +946,8 @@ impl Database {
             projects,
             items,
             file_cards: Some(file_cards),
+            since: None,
+            deleted_project_ids: None,
         })
     }
 
@@ -1059,6 +1080,8 @@ impl Database {
             items_imported,
             file_cards_imported,
             skipped,
+            snapshot_id: None,
+            projects_deleted: None,
         })
     }
 
@@ -0,0 +1,121 @@
+use crate::models::{DirEntry, DirListing, DirSort};
+use chrono::{DateTime, TimeZone, Utc};
+use std::fs;
+use std::path::PathBuf;
+
+/// Remote shell script that prints the resolved directory, then one
+/// `type|size|mtime|name` line per entry. GNU and BSD stat take different
+/// formats; `-L` follows symlinks so linked directories can be browsed.
+pub fn remote_script(path: &str, show_hidden: bool) -> String {
+    let globs = if show_hidden { "* .[!.]* ..?*" } else { "*" };
+    format!(
+        "cd {path} && pwd && {{ if stat -c %s . >/dev/null 2>&1; \
+         then stat -L -c '%F|%s|%Y|%n' -- {globs} 2>/dev/null; \
+         else stat -L -f '%HT|%z|%m|%N' -- {globs} 2>/dev/null; fi; true; }}"
+    )
+}
+
+/// Parse the lines printed by `remote_script` after the directory line. Globs
+/// that matched nothing fail in stat and print nothing.
+pub fn parse_remote(lines: std::str::Lines) -> Vec<DirEntry> {
+    lines
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(4, '|').collect();
+            let [kind, size, modified, name] = fields[..] else {
+                return None;
+            };
+            if name.is_empty() {
+                return None;
+            }
+            Some(DirEntry {
+                name: name.to_string(),
+                is_dir: kind.to_lowercase() == "directory",
+                size: size.parse().ok(),
+                modified: modified
+                    .parse()
+                    .ok()
+                    .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+                    .map(|t| t.to_rfc3339()),
+            })
+        })
+        .collect()
+}
+
+/// Entries of a local directory (the home directory by default; a leading `~` is
+/// expanded). Symlinks are listed as what they point to.
+pub fn list_local(path: Option<&str>, show_hidden: bool) -> Result<DirListing, String> {
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    let dir = match path.filter(|p| !p.is_empty()) {
+        None | Some("~") => home,
+        Some(p) => match p.strip_prefix("~/").or_else(|| p.strip_prefix("~\\")) {
+            Some(rest) => home.join(rest),
+            None => PathBuf::from(p),
+        },
+    };
+    let dir =
+        fs::canonicalize(&dir).map_err(|e| format!("Failed to open {}: {}", dir.display(), e))?;
+    let read = fs::read_dir(&dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+
+    let entries = read
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // A broken symlink is listed by its own metadata
+            let metadata = fs::metadata(entry.path())
+                .or_else(|_| entry.metadata())
+                .ok()?;
+            if !show_hidden && is_hidden(&name, &metadata) {
+                return None;
+            }
+            Some(DirEntry {
+                is_dir: metadata.is_dir(),
+                size: Some(metadata.len()),
+                modified: metadata
+                    .modified()
+                    .ok()
+                    .map(|t| DateTime::<Utc>::from(t).to_rfc3339()),
+                name,
+            })
+        })
+        .collect();
+
+    let current_path = dir.to_string_lossy();
+    Ok(DirListing {
+        // canonicalize gives verbatim paths (\\?\C:\...) on Windows
+        current_path: current_path
+            .strip_prefix(r"\\?\")
+            .unwrap_or(&current_path)
+            .to_string(),
+        entries,
+    })
+}
+
+/// Dotfiles, and on Windows files with the hidden attribute
+fn is_hidden(name: &str, metadata: &fs::Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0 {
+            return true;
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = metadata;
+    name.starts_with('.')
+}
+
+/// Directories first, then by `sort` (name for ties), optionally reversed
+pub fn sort_entries(entries: &mut [DirEntry], sort: DirSort, descending: bool) {
+    entries.sort_by(|a, b| {
+        let order = match sort {
+            DirSort::Name => std::cmp::Ordering::Equal,
+            DirSort::Size => a.size.cmp(&b.size),
+            // RFC 3339 timestamps in UTC sort chronologically as strings
+            DirSort::Modified => a.modified.cmp(&b.modified),
+        }
+        .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        let order = if descending { order.reverse() } else { order };
+        b.is_dir.cmp(&a.is_dir).then(order)
+    });
+}
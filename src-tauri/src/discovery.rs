@@ -0,0 +1,115 @@
+use crate::git::{self, GitProvider};
+use crate::models::{ProjectCandidate, ProjectMetadata, WorkingDir};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// How many directory levels below the scan root are searched by default
+pub const DEFAULT_SCAN_DEPTH: usize = 4;
+
+/// Manifest files that mark a project root when manifest detection is enabled
+const MANIFEST_MARKERS: &[&str] = &["package.json", "Cargo.toml"];
+
+/// Directories that never contain projects worth importing (dependencies, build output)
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "vendor",
+    "dist",
+    "build",
+    "__pycache__",
+    "venv",
+];
+
+/// Walk `root` looking for git repositories (and optionally manifest roots).
+/// A directory that is a project root is not descended into, so nested
+/// packages of a monorepo show up as one candidate.
+pub fn find_project_roots(
+    root: &Path,
+    include_manifests: bool,
+    max_depth: usize,
+) -> Vec<ProjectCandidate> {
+    let mut candidates = Vec::new();
+    walk(root, include_manifests, max_depth, &mut candidates);
+    candidates.sort_by(|a, b| a.path.cmp(&b.path));
+    candidates
+}
+
+fn walk(dir: &Path, include_manifests: bool, depth: usize, out: &mut Vec<ProjectCandidate>) {
+    // .git is a directory in a normal clone and a file in worktrees/submodules
+    let mut markers = Vec::new();
+    if dir.join(".git").exists() {
+        markers.push(".git".to_string());
+    }
+    if include_manifests {
+        for marker in MANIFEST_MARKERS {
+            if dir.join(marker).is_file() {
+                markers.push(marker.to_string());
+            }
+        }
+    }
+
+    if !markers.is_empty() {
+        let path = dir.to_string_lossy().to_string();
+        let name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        out.push(ProjectCandidate {
+            metadata: ProjectMetadata {
+                working_dirs: Some(vec![WorkingDir {
+                    name: name.clone(),
+                    path: path.clone(),
+                    host: None,
+                }]),
+                ..Default::default()
+            },
+            name,
+            path,
+            markers,
+            already_imported: false,
+        });
+        return;
+    }
+
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        // symlink_metadata so linked directories can't create loops
+        let Ok(meta) = fs::symlink_metadata(entry.path()) else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !meta.is_dir() || name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_str()) {
+            continue;
+        }
+        walk(&entry.path(), include_manifests, depth - 1, out);
+    }
+}
+
+/// Fill in GitHub URLs from origin remotes and flag candidates whose path is
+/// already a working dir of an existing project
+pub async fn annotate_candidates(
+    candidates: &mut [ProjectCandidate],
+    known_paths: &HashSet<String>,
+) {
+    for candidate in candidates.iter_mut() {
+        candidate.already_imported = known_paths.contains(&candidate.path);
+
+        if !candidate.markers.iter().any(|m| m == ".git") {
+            continue;
+        }
+        let Ok(remote) = git::get_remote_url(&candidate.path, None).await else {
+            continue;
+        };
+        if let Some(web_url) = git::remote_to_web_url(&remote) {
+            if GitProvider::from_url(&web_url) == Some(GitProvider::GitHub) {
+                candidate.metadata.github_url = Some(web_url);
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::git::shell_quote;
+use crate::models::{CommandResult, ContainerStatus};
+use tokio::process::Command;
+
+/// What a Docker item manages: a compose file (content ends in .yml/.yaml)
+/// or a single container by name
+pub enum DockerTarget {
+    Compose(String),
+    Container(String),
+}
+
+impl DockerTarget {
+    pub fn from_content(content: &str) -> Result<Self, String> {
+        let content = content.trim();
+        if content.is_empty() {
+            return Err("Docker item has no compose file or container name".to_string());
+        }
+        if content.ends_with(".yml") || content.ends_with(".yaml") {
+            Ok(Self::Compose(content.to_string()))
+        } else {
+            Ok(Self::Container(content.to_string()))
+        }
+    }
+
+    /// `docker` arguments for an action on this target
+    pub fn args(&self, action: DockerAction) -> Vec<String> {
+        let owned = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        match self {
+            Self::Compose(file) => {
+                let mut args = owned(&["compose", "-f", file]);
+                args.extend(match action {
+                    DockerAction::Up => owned(&["up", "-d"]),
+                    DockerAction::Down => owned(&["down"]),
+                    DockerAction::Ps => owned(&["ps", "-a", "--format", "json"]),
+                    DockerAction::Logs(tail) => {
+                        owned(&["logs", "--no-color", "--tail", &tail.to_string()])
+                    }
+                });
+                args
+            }
+            Self::Container(name) => match action {
+                DockerAction::Up => owned(&["start", name]),
+                DockerAction::Down => owned(&["stop", name]),
+                DockerAction::Ps => owned(&[
+                    "ps",
+                    "-a",
+                    "--filter",
+                    &format!("name=^{}$", name),
+                    "--format",
+                    "json",
+                ]),
+                DockerAction::Logs(tail) => owned(&["logs", "--tail", &tail.to_string(), name]),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum DockerAction {
+    Up,
+    Down,
+    Ps,
+    /// Number of trailing log lines
+    Logs(u32),
+}
+
+/// Run `docker` with `args` in `cwd`, on `host` via SSH if set
+pub async fn run_docker(
+    args: &[String],
+    cwd: Option<&str>,
+    host: Option<&str>,
+) -> Result<CommandResult, String> {
+    let mut cmd = match host {
+        Some(host) => {
+            let docker_cmd = format!(
+                "docker {}",
+                args.iter()
+                    .map(|a| shell_quote(a))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+            // Path is left unquoted so that `~` expands on the remote side
+            let remote = match cwd {
+                Some(dir) => format!("cd {} && {}", dir, docker_cmd),
+                None => docker_cmd,
+            };
+            let mut cmd = Command::new("ssh");
+            cmd.args([host, &remote]);
+            cmd
+        }
+        None => {
+            let mut cmd = Command::new("docker");
+            cmd.args(args);
+            if let Some(dir) = cwd {
+                cmd.current_dir(dir);
+            }
+            cmd
+        }
+    };
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute docker: {}", e))?;
+
+    Ok(CommandResult {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        exit_code: output.status.code().unwrap_or(-1),
+        process_id: None,
+        timed_out: false,
+    })
+}
+
+/// Parse `docker ps` / `docker compose ps` JSON output.
+/// Newer Docker prints one object per line, older compose versions a single array.
+pub fn parse_ps(output: &str) -> Vec<ContainerStatus> {
+    let trimmed = output.trim();
+    let values: Vec<serde_json::Value> = if trimmed.starts_with('[') {
+        serde_json::from_str(trimmed).unwrap_or_default()
+    } else {
+        trimmed
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    };
+
+    let field =
+        |value: &serde_json::Value, key: &str| value[key].as_str().unwrap_or_default().to_string();
+    values
+        .iter()
+        .map(|value| {
+            // compose reports "Name", plain `docker ps` reports "Names"
+            let name = match field(value, "Name") {
+                name if name.is_empty() => field(value, "Names"),
+                name => name,
+            };
+            ContainerStatus {
+                name,
+                service: value["Service"].as_str().map(String::from),
+                state: field(value, "State"),
+                status: field(value, "Status"),
+            }
+        })
+        .collect()
+}
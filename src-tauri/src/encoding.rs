@@ -0,0 +1,266 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
+use std::ops::Range;
+
+/// Bytes looked at for NULs when telling UTF-16 and binary data apart from text
+const SNIFF_BYTES: usize = 8000;
+
+/// Bytes from the start of a file that its encoding is detected from
+pub const HEAD_BYTES: usize = 64 * 1024;
+
+/// Length of `bytes` without a multi-byte UTF-8 character cut off at the end
+pub fn utf8_complete_len(bytes: &[u8]) -> usize {
+    // The last lead byte is at most 3 bytes from the end of a complete sequence
+    for back in 1..=bytes.len().min(4) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0b1100_0000 == 0b1000_0000 {
+            continue;
+        }
+        let width = match byte {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+        return if width > back {
+            bytes.len() - back
+        } else {
+            bytes.len()
+        };
+    }
+    bytes.len()
+}
+
+/// UTF-16 without a BOM: NULs in every other byte, the high bytes of ASCII characters
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(SNIFF_BYTES) & !1];
+    let units = sample.len() / 2;
+    if units < 2 {
+        return None;
+    }
+    let zeros_at = |parity: usize| {
+        sample
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    if odd * 10 >= units * 3 && even * 10 < units {
+        Some(UTF_16LE)
+    } else if even * 10 >= units * 3 && odd * 10 < units {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Encoding of text from its (first) bytes: a BOM, valid UTF-8, BOM-less UTF-16,
+/// or chardetng's guess (Latin-1, GBK, Shift_JIS, ...). Errors for binary data.
+pub fn detect(bytes: &[u8]) -> Result<&'static Encoding, String> {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return Ok(encoding);
+    }
+    // NUL is valid UTF-8, so this comes first: text has none except as UTF-16 high bytes
+    if bytes[..bytes.len().min(SNIFF_BYTES)].contains(&0) {
+        return sniff_utf16(bytes).ok_or_else(|| "File appears to be binary".to_string());
+    }
+    if std::str::from_utf8(&bytes[..utf8_complete_len(bytes)]).is_ok() {
+        return Ok(UTF_8);
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    Ok(detector.guess(None, true))
+}
+
+/// Length of `bytes` without a character cut off at the end
+fn complete_len(encoding: &'static Encoding, bytes: &[u8]) -> usize {
+    if encoding == UTF_8 {
+        utf8_complete_len(bytes)
+    } else if encoding == UTF_16LE || encoding == UTF_16BE {
+        let end = bytes.len() & !1;
+        if end < 2 {
+            return end;
+        }
+        let last = [bytes[end - 2], bytes[end - 1]];
+        let unit = if encoding == UTF_16LE {
+            u16::from_le_bytes(last)
+        } else {
+            u16::from_be_bytes(last)
+        };
+        // A high surrogate whose low half is cut off
+        if (0xd800..0xdc00).contains(&unit) {
+            end - 2
+        } else {
+            end
+        }
+    } else {
+        bytes.len()
+    }
+}
+
+/// Decode bytes with `encoding` to UTF-8, dropping a BOM and a character cut off
+/// at the end (as happens when only the start of a file is read)
+pub fn decode_with(encoding: &'static Encoding, bytes: &[u8]) -> String {
+    let end = complete_len(encoding, bytes);
+    let (text, _) = encoding.decode_with_bom_removal(&bytes[..end]);
+    text.into_owned()
+}
+
+/// Decode text in any detected encoding to UTF-8; returns the text and the
+/// encoding's name (e.g. "UTF-8", "windows-1252", "GBK", "UTF-16LE")
+pub fn decode(bytes: &[u8]) -> Result<(String, &'static str), String> {
+    let encoding = detect(bytes)?;
+    Ok((decode_with(encoding, bytes), encoding.name()))
+}
+
+/// Decode a chunk read at byte `offset` of a file in `encoding`, which is detected
+/// once from the file's head (a chunk alone can't tell, e.g. UTF-16 read from an
+/// odd offset looks like the other byte order). Characters cut off at either end
+/// are left out; returns the text and the range of `bytes` that was decoded, so the
+/// next chunk can start where this one ended.
+pub fn decode_chunk(
+    encoding: &'static Encoding,
+    bytes: &[u8],
+    offset: u64,
+) -> (String, Range<usize>) {
+    let start = if encoding == UTF_8 {
+        // Continuation bytes of a character that started before the chunk
+        bytes
+            .iter()
+            .take(3)
+            .take_while(|&&b| b & 0b1100_0000 == 0b1000_0000)
+            .count()
+    } else if encoding == UTF_16LE || encoding == UTF_16BE {
+        // Code units are aligned to the start of the file
+        (offset % 2) as usize
+    } else {
+        0
+    }
+    .min(bytes.len());
+    let end = start + complete_len(encoding, &bytes[start..]);
+    let text = if offset == 0 {
+        encoding.decode_with_bom_removal(&bytes[..end]).0
+    } else {
+        encoding.decode_without_bom_handling(&bytes[start..end]).0
+    };
+    (text.into_owned(), start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()
+    }
+
+    fn utf16be(text: &str) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| unit.to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn detects_bom() {
+        assert_eq!(detect(b"\xef\xbb\xbfhello").unwrap(), UTF_8);
+        assert_eq!(detect(b"\xff\xfeh\0i\0").unwrap(), UTF_16LE);
+        assert_eq!(detect(b"\xfe\xff\0h\0i").unwrap(), UTF_16BE);
+    }
+
+    #[test]
+    fn detects_utf8_with_cut_off_character() {
+        let bytes = "naïve café".as_bytes();
+        assert_eq!(detect(&bytes[..bytes.len() - 1]).unwrap(), UTF_8);
+    }
+
+    #[test]
+    fn sniffs_utf16_without_bom() {
+        assert_eq!(detect(&utf16le("hello world")).unwrap(), UTF_16LE);
+        assert_eq!(detect(&utf16be("hello world")).unwrap(), UTF_16BE);
+    }
+
+    #[test]
+    fn rejects_binary() {
+        let elf_header = [
+            0x7f, b'E', b'L', b'F', 2, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0x3e, 0,
+        ];
+        assert!(detect(&elf_header).is_err());
+    }
+
+    #[test]
+    fn guesses_legacy_encodings() {
+        let (text, name) = decode(b"caf\xe9 cr\xe8me br\xfbl\xe9e").unwrap();
+        assert_eq!(name, "windows-1252");
+        assert_eq!(text, "café crème brûlée");
+    }
+
+    #[test]
+    fn decode_drops_bom() {
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend(utf16le("hi"));
+        assert_eq!(decode(&bytes).unwrap(), ("hi".to_string(), "UTF-16LE"));
+    }
+
+    #[test]
+    fn utf16_chunks_at_odd_offsets_keep_the_byte_order() {
+        let text = "line one\nline two\nline three\n";
+        for bytes in [utf16le(text), utf16be(text)] {
+            let encoding = detect(&bytes).unwrap();
+            for offset in [1, 3, 7, 11] {
+                let chunk = &bytes[offset..offset + 20];
+                let (decoded, range) = decode_chunk(encoding, chunk, offset as u64);
+                assert_eq!(range, 1..19);
+                let first = (offset + 1) / 2;
+                let expected: String = text.chars().skip(first).take(9).collect();
+                assert_eq!(decoded, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn utf16_chunk_at_odd_offset_without_nuls_in_view() {
+        // A chunk of CJK text has no NULs to sniff at all; the head decides
+        let text = "一二三四五六七八九十";
+        let bytes = utf16le(&format!("ab{}", text));
+        let encoding = detect(&bytes[..4]).unwrap();
+        let (decoded, range) = decode_chunk(encoding, &bytes[5..], 5);
+        assert_eq!(range, 1..bytes.len() - 5);
+        assert_eq!(decoded, &text[3..]);
+    }
+
+    #[test]
+    fn utf8_chunk_skips_cut_off_characters() {
+        let text = "añb€c";
+        let bytes = text.as_bytes();
+        // Starts inside `ñ` and ends inside `€`
+        let (decoded, range) = decode_chunk(UTF_8, &bytes[2..6], 2);
+        assert_eq!(decoded, "b");
+        assert_eq!(range, 1..2);
+    }
+
+    #[test]
+    fn utf16_chunk_keeps_surrogate_pairs_whole() {
+        let bytes = utf16le("a😀b");
+        // Ends between the two halves of the emoji
+        let (decoded, range) = decode_chunk(UTF_16LE, &bytes[..4], 0);
+        assert_eq!(decoded, "a");
+        assert_eq!(range, 0..2);
+        let (decoded, range) = decode_chunk(UTF_16LE, &bytes[2..], 2);
+        assert_eq!(decoded, "😀b");
+        assert_eq!(range, 0..6);
+    }
+
+    #[test]
+    fn first_chunk_drops_bom() {
+        let mut bytes = vec![0xef, 0xbb, 0xbf];
+        bytes.extend(b"hello");
+        let (decoded, range) = decode_chunk(UTF_8, &bytes, 0);
+        assert_eq!(decoded, "hello");
+        assert_eq!(range, 0..bytes.len());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::models::ExportMark;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a replace-import confirmation token stays valid
+const CONFIRMATION_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Managed state holding the outstanding confirmation token for a replace import.
+/// Tokens are single-use so a stale UI can't replay a destructive import.
+pub struct ReplaceImportGuard(Mutex<Option<(String, Instant)>>);
+
+impl ReplaceImportGuard {
+    pub fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    /// Issue a new token, invalidating any previous one
+    pub fn issue(&self) -> String {
+        let token = uuid::Uuid::new_v4().to_string();
+        *self.0.lock().unwrap() = Some((token.clone(), Instant::now()));
+        token
+    }
+
+    /// Consume `token`, failing if it doesn't match the outstanding one or has expired
+    pub fn consume(&self, token: Option<&str>) -> Result<(), String> {
+        let issued = self.0.lock().unwrap().take();
+        match (issued, token) {
+            (Some((expected, at)), Some(token))
+                if expected == token && at.elapsed() < CONFIRMATION_TTL =>
+            {
+                Ok(())
+            }
+            _ => Err("Replace import requires a valid confirmation token".to_string()),
+        }
+    }
+}
+
+/// Last incremental export per destination: ~/.devora/export_state.json (machine-local)
+fn state_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("export_state.json")
+}
+
+/// Mark of the last successful export to `destination`, if any
+pub fn last_export(config_dir: &Path, destination: &str) -> Option<ExportMark> {
+    let content = fs::read_to_string(state_path(config_dir)).ok()?;
+    let mut marks: HashMap<String, ExportMark> = serde_json::from_str(&content).ok()?;
+    marks.remove(destination)
+}
+
+/// Remember a successful export to `destination`
+pub fn record_export(config_dir: &Path, destination: &str, mark: ExportMark) -> Result<(), String> {
+    let path = state_path(config_dir);
+    let mut marks: HashMap<String, ExportMark> = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    marks.insert(destination.to_string(), mark);
+
+    let json = serde_json::to_string_pretty(&marks)
+        .map_err(|e| format!("Failed to serialize export state: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write export state: {}", e))
+}
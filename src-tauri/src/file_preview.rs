@@ -0,0 +1,130 @@
+use crate::models::{FilePreview, FilePreviewKind};
+use base64::Engine;
+use std::path::Path;
+
+/// Bytes shown in the hex dump of a binary file
+const HEX_DUMP_BYTES: usize = 512;
+
+/// Bytes inspected for NULs when deciding whether a file is text (same as git)
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+/// Images and PDFs larger than this are previewed without their data
+pub const MEDIA_MAX_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Bytes from the start of a file that its preview is built from, unless it's an
+/// image or PDF (which are read whole)
+pub fn head_len(text_limit: usize) -> usize {
+    text_limit.max(BINARY_SNIFF_BYTES)
+}
+
+/// Whether the file is an image or PDF, from its first bytes
+pub fn is_media(head: &[u8], path: &Path) -> bool {
+    sniff_mime(head, path).is_some()
+}
+
+/// MIME type of an image or PDF, from its magic bytes (SVG by extension, as it's text)
+fn sniff_mime(bytes: &[u8], path: &Path) -> Option<&'static str> {
+    let signatures: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"\x00\x00\x01\x00", "image/x-icon"),
+        (b"%PDF-", "application/pdf"),
+    ];
+    if let Some((_, mime)) = signatures
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+    {
+        return Some(mime);
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    // "BM" alone is too common at the start of text; the header's reserved bytes are zero
+    if bytes.len() >= 14 && bytes.starts_with(b"BM") && bytes[6..10] == [0, 0, 0, 0] {
+        return Some("image/bmp");
+    }
+    let is_svg = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    is_svg.then_some("image/svg+xml")
+}
+
+/// `xxd`-style dump: offset, 16 hex bytes, printable ASCII
+fn hex_dump(bytes: &[u8]) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!("{:08x}  {:<47}  {}", row * 16, hex.join(" "), ascii)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Classify a file and build its preview: images and PDFs as base64, text up to
+/// `text_limit` bytes, anything else as a hex dump of its first bytes. `bytes` may
+/// be just the start of the file; images and PDFs then come without their data.
+pub fn preview(path: &Path, bytes: Vec<u8>, file_size: u64, text_limit: usize) -> FilePreview {
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    let mut preview = FilePreview {
+        filename,
+        file_size,
+        kind: FilePreviewKind::Binary,
+        mime_type: None,
+        content: None,
+        data_base64: None,
+        hex_dump: None,
+    };
+
+    if let Some(mime) = sniff_mime(&bytes, path) {
+        preview.kind = if mime == "application/pdf" {
+            FilePreviewKind::Pdf
+        } else {
+            FilePreviewKind::Image
+        };
+        preview.mime_type = Some(mime.to_string());
+        if bytes.len() as u64 == file_size {
+            preview.data_base64 = Some(base64::engine::general_purpose::STANDARD.encode(&bytes));
+        }
+        return preview;
+    }
+
+    let sniffed = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+    if !sniffed.contains(&0) {
+        let mut end = bytes.len().min(text_limit);
+        // A cut in the middle of a multi-byte character isn't a decoding error
+        let text = loop {
+            match std::str::from_utf8(&bytes[..end]) {
+                Ok(text) => break Some(text),
+                Err(e) if e.error_len().is_none() && end > 0 => end = e.valid_up_to(),
+                Err(_) => break None,
+            }
+        };
+        if let Some(text) = text {
+            preview.kind = FilePreviewKind::Text;
+            preview.mime_type = Some("text/plain".to_string());
+            preview.content = Some(text.to_string());
+            return preview;
+        }
+    }
+
+    preview.hex_dump = Some(hex_dump(&bytes[..bytes.len().min(HEX_DUMP_BYTES)]));
+    preview
+}
@@ -0,0 +1,113 @@
+use crate::encoding;
+use crate::models::{FileSearchMatch, FileSearchResult};
+use regex::{Regex, RegexBuilder};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// The search stops after this many matching lines
+const MAX_MATCHES: usize = 1000;
+
+/// Longer lines are cut to a window of this many characters around the match
+const MAX_SNIPPET_CHARS: usize = 200;
+
+/// Characters of context kept before the match in a cut line
+const SNIPPET_CONTEXT_CHARS: usize = 60;
+
+const READ_CHUNK_BYTES: usize = 256 * 1024;
+
+fn build_pattern(query: &str, regex: bool, case_sensitive: bool) -> Result<Regex, String> {
+    let pattern = if regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+/// The line, or a window of it around the byte offset `at` when it is long
+fn snippet(line: &str, at: usize) -> String {
+    if line.chars().count() <= MAX_SNIPPET_CHARS {
+        return line.to_string();
+    }
+    let match_char = line[..at.min(line.len())].chars().count();
+    let start = match_char.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let window: String = line.chars().skip(start).take(MAX_SNIPPET_CHARS).collect();
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    snippet.push_str(&window);
+    if start + MAX_SNIPPET_CHARS < line.chars().count() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// Lines of a file matching `query` (plain text, or a regex with `regex`). The file
+/// is decoded in chunks in its detected encoding, so only the matches are kept.
+pub fn search(
+    path: &Path,
+    query: &str,
+    regex: bool,
+    case_sensitive: bool,
+) -> Result<FileSearchResult, String> {
+    if query.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    let pattern = build_pattern(query, regex, case_sensitive)?;
+
+    let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut chunk = vec![0; READ_CHUNK_BYTES];
+    let mut read = file
+        .read(&mut chunk)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut decoder = encoding::detect(&chunk[..read])?.new_decoder_with_bom_removal();
+
+    let mut result = FileSearchResult {
+        matches: Vec::new(),
+        truncated: false,
+    };
+    let mut pending = String::new();
+    let mut line_number = 0;
+    loop {
+        let last = read == 0;
+        let max_len = decoder
+            .max_utf8_buffer_length(read)
+            .ok_or_else(|| "File chunk too large to decode".to_string())?;
+        pending.reserve(max_len);
+        let _ = decoder.decode_to_string(&chunk[..read], &mut pending, last);
+
+        // Complete lines are searched; the rest waits for the next chunk
+        let complete = if last {
+            pending.len()
+        } else {
+            pending.rfind('\n').map_or(0, |i| i + 1)
+        };
+        for line in pending[..complete].lines() {
+            if let Some(found) = pattern.find(line) {
+                result.matches.push(FileSearchMatch {
+                    line: line_number,
+                    column: line[..found.start()].chars().count(),
+                    snippet: snippet(line, found.start()),
+                });
+                if result.matches.len() >= MAX_MATCHES {
+                    result.truncated = true;
+                    return Ok(result);
+                }
+            }
+            line_number += 1;
+        }
+        pending.drain(..complete);
+
+        if last {
+            return Ok(result);
+        }
+        read = file
+            .read(&mut chunk)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+    }
+}
@@ -0,0 +1,153 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Event emitted with the card ID when the file behind an open card changes
+pub const FILE_CARD_CHANGED_EVENT: &str = "file-card://changed";
+
+/// Editors write a file in several steps; a card's change is emitted once its file
+/// has been quiet this long, so the event follows the last write of a burst
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+type WatchedFiles = Arc<Mutex<HashMap<PathBuf, HashSet<String>>>>;
+
+/// Card ID -> time of its latest change, while its event waits to be emitted
+type PendingChanges = Arc<Mutex<HashMap<String, Instant>>>;
+
+/// Managed state: watches the files of open file cards. Parent directories are
+/// watched rather than the files, so saves that replace the file are still seen.
+#[derive(Default)]
+pub struct FileCardWatcher {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    /// Watched file -> IDs of the open cards showing it
+    files: WatchedFiles,
+}
+
+impl FileCardWatcher {
+    fn create_watcher(app: AppHandle, files: WatchedFiles) -> Result<RecommendedWatcher, String> {
+        let pending = PendingChanges::default();
+        notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            let Ok(event) = result else {
+                return;
+            };
+            if !matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                return;
+            }
+
+            let card_ids: Vec<String> = {
+                let files = files.lock().unwrap();
+                event
+                    .paths
+                    .iter()
+                    .filter_map(|path| files.get(path))
+                    .flatten()
+                    .cloned()
+                    .collect()
+            };
+            let now = Instant::now();
+            for card_id in card_ids {
+                // A change already waiting is pushed back; otherwise start waiting
+                let waiting = pending.lock().unwrap().insert(card_id.clone(), now);
+                if waiting.is_none() {
+                    Self::emit_when_quiet(app.clone(), pending.clone(), card_id);
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to start file watcher: {}", e))
+    }
+
+    /// Emit the card's change event once no further change arrived for `DEBOUNCE`
+    fn emit_when_quiet(app: AppHandle, pending: PendingChanges, card_id: String) {
+        std::thread::spawn(move || {
+            loop {
+                let wait = {
+                    let mut pending = pending.lock().unwrap();
+                    let Some(last_change) = pending.get(&card_id) else {
+                        return;
+                    };
+                    let quiet_for = last_change.elapsed();
+                    if quiet_for >= DEBOUNCE {
+                        pending.remove(&card_id);
+                        break;
+                    }
+                    DEBOUNCE - quiet_for
+                };
+                std::thread::sleep(wait);
+            }
+            let _ = app.emit(FILE_CARD_CHANGED_EVENT, &card_id);
+        });
+    }
+
+    /// Start watching the file shown by a card
+    pub fn watch(&self, app: &AppHandle, card_id: &str, path: &Path) -> Result<(), String> {
+        let dir = path
+            .parent()
+            .filter(|dir| dir.is_dir())
+            .ok_or_else(|| format!("Can't watch {}: no parent directory", path.display()))?;
+
+        let mut watcher = self.watcher.lock().unwrap();
+        if watcher.is_none() {
+            *watcher = Some(Self::create_watcher(app.clone(), self.files.clone())?);
+        }
+        let watcher = watcher.as_mut().unwrap();
+
+        // A card shows one file; drop whatever it was watching before
+        self.unwatch_locked(watcher, card_id);
+
+        // The files lock is never held while calling into the watcher: its event
+        // thread takes the lock in the callback
+        let dir_watched = {
+            let mut files = self.files.lock().unwrap();
+            let watched = files.keys().any(|file| file.parent() == Some(dir));
+            files
+                .entry(path.to_path_buf())
+                .or_default()
+                .insert(card_id.to_string());
+            watched
+        };
+        if !dir_watched {
+            if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                self.files.lock().unwrap().remove(path);
+                return Err(format!("Failed to watch {}: {}", dir.display(), e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop watching for a card (closed or deleted)
+    pub fn unwatch(&self, card_id: &str) {
+        if let Some(watcher) = self.watcher.lock().unwrap().as_mut() {
+            self.unwatch_locked(watcher, card_id);
+        }
+    }
+
+    fn unwatch_locked(&self, watcher: &mut RecommendedWatcher, card_id: &str) {
+        let released_dirs: Vec<PathBuf> = {
+            let mut files = self.files.lock().unwrap();
+            let mut released = Vec::new();
+            files.retain(|file, cards| {
+                cards.remove(card_id);
+                if cards.is_empty() {
+                    released.push(file.clone());
+                }
+                !cards.is_empty()
+            });
+            released
+                .iter()
+                .filter_map(|file| file.parent())
+                .filter(|dir| !files.keys().any(|f| f.parent() == Some(*dir)))
+                .map(Path::to_path_buf)
+                .collect()
+        };
+
+        for dir in released_dirs {
+            let _ = watcher.unwatch(&dir);
+        }
+    }
+}
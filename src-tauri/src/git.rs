@@ -0,0 +1,632 @@
+use crate::models::{
+    DiffHunk, FileDiffResult, GitActionResult, GitBranch, GitCommit, GitStatus, GitWorktree,
+    StoreCommit,
+};
+use std::path::Path;
+use tokio::process::Command;
+
+/// Known git hosting providers that have a browsable web UI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GitProvider {
+    GitHub,
+    GitLab,
+    Bitbucket,
+}
+
+impl GitProvider {
+    /// Detect the provider from a web URL host
+    pub fn from_url(url: &str) -> Option<Self> {
+        let host = url
+            .trim_start_matches("https://")
+            .split('/')
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+
+        if host == "github.com" || host.ends_with(".github.com") {
+            Some(Self::GitHub)
+        } else if host == "gitlab.com" || host.starts_with("gitlab.") {
+            Some(Self::GitLab)
+        } else if host == "bitbucket.org" || host.starts_with("bitbucket.") {
+            Some(Self::Bitbucket)
+        } else {
+            None
+        }
+    }
+
+    /// Display label used for project links
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::GitHub => "GitHub",
+            Self::GitLab => "GitLab",
+            Self::Bitbucket => "Bitbucket",
+        }
+    }
+
+    /// Page of a commit, given the repository's web URL
+    pub fn commit_url(&self, web_url: &str, hash: &str) -> String {
+        let web_url = web_url.trim_end_matches('/');
+        match self {
+            Self::GitHub => format!("{}/commit/{}", web_url, hash),
+            Self::GitLab => format!("{}/-/commit/{}", web_url, hash),
+            Self::Bitbucket => format!("{}/commits/{}", web_url, hash),
+        }
+    }
+}
+
+/// Quote a string for use as a single POSIX shell word
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Run a git command inside `path` and return its stdout.
+/// If `host` is set, the command runs on the remote host via SSH.
+pub async fn run_git(path: &str, host: Option<&str>, args: &[&str]) -> Result<String, String> {
+    let output = if let Some(remote_host) = host {
+        // Path is left unquoted so that `~` expands on the remote side, as is a
+        // leading `~/` of an argument (e.g. the default path of a new worktree)
+        let git_args = args
+            .iter()
+            .map(|a| match a.strip_prefix("~/") {
+                Some(rest) => format!("~/{}", shell_quote(rest)),
+                None => shell_quote(a),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let remote_cmd = format!("cd {} && git {}", path, git_args);
+
+        #[cfg(windows)]
+        {
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+            Command::new("ssh")
+                .args([remote_host, &remote_cmd])
+                .creation_flags(CREATE_NO_WINDOW)
+                .output()
+                .await
+                .map_err(|e| format!("Failed to execute SSH command: {}", e))?
+        }
+
+        #[cfg(not(windows))]
+        {
+            Command::new("ssh")
+                .args([remote_host, &remote_cmd])
+                .output()
+                .await
+                .map_err(|e| format!("Failed to execute SSH command: {}", e))?
+        }
+    } else {
+        #[cfg(windows)]
+        {
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+            Command::new("git")
+                .arg("-C")
+                .arg(path)
+                .args(args)
+                .creation_flags(CREATE_NO_WINDOW)
+                .output()
+                .await
+                .map_err(|e| format!("Failed to execute git: {}", e))?
+        }
+
+        #[cfg(not(windows))]
+        {
+            Command::new("git")
+                .arg("-C")
+                .arg(path)
+                .args(args)
+                .output()
+                .await
+                .map_err(|e| format!("Failed to execute git: {}", e))?
+        }
+    };
+
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Get the URL of the `origin` remote for the repository at `path`
+pub async fn get_remote_url(path: &str, host: Option<&str>) -> Result<String, String> {
+    let url = run_git(path, host, &["remote", "get-url", "origin"]).await?;
+    Ok(url.trim().to_string())
+}
+
+/// Convert a git remote URL (SSH, scp-like or HTTPS) into a browsable HTTPS URL
+///
+/// - `git@github.com:user/repo.git` -> `https://github.com/user/repo`
+/// - `ssh://git@gitlab.com:22/group/repo.git` -> `https://gitlab.com/group/repo`
+/// - `https://user@bitbucket.org/team/repo.git` -> `https://bitbucket.org/team/repo`
+pub fn remote_to_web_url(remote: &str) -> Option<String> {
+    let remote = remote.trim();
+
+    let (host, path) = if let Some((_, rest)) = remote.split_once("://") {
+        let (authority, path) = rest.split_once('/')?;
+        let host = authority.rsplit('@').next()?;
+        let host = host.split(':').next()?;
+        (host, path)
+    } else {
+        // scp-like syntax: [user@]host:path
+        let (authority, path) = remote.split_once(':')?;
+        let host = authority.rsplit('@').next()?;
+        (host, path)
+    };
+
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+
+    Some(format!("https://{}/{}", host, path))
+}
+
+/// Split a file path into its parent directory and file name.
+/// Accepts both `/` and `\` separators so local Windows paths work too.
+pub fn split_file_path(path: &str) -> (&str, &str) {
+    match path.rfind(['/', '\\']) {
+        Some(0) => ("/", &path[1..]),
+        Some(idx) => (&path[..idx], &path[idx + 1..]),
+        None => (".", path),
+    }
+}
+
+/// Parse a hunk range like `12,5` or `12` into (start, count)
+fn parse_hunk_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// Parse unified diff output into hunks, ignoring file headers
+pub fn parse_unified_diff(diff: &str) -> Vec<DiffHunk> {
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+
+    for line in diff.lines() {
+        if let Some(rest) = line.strip_prefix("@@ ") {
+            // Format: @@ -old_start,old_lines +new_start,new_lines @@ section
+            let mut parts = rest.split_whitespace();
+            let old = parts.next().and_then(|p| p.strip_prefix('-'));
+            let new = parts.next().and_then(|p| p.strip_prefix('+'));
+            if let (Some((old_start, old_lines)), Some((new_start, new_lines))) = (
+                old.and_then(parse_hunk_range),
+                new.and_then(parse_hunk_range),
+            ) {
+                hunks.push(DiffHunk {
+                    old_start,
+                    old_lines,
+                    new_start,
+                    new_lines,
+                    header: line.to_string(),
+                    lines: Vec::new(),
+                });
+            }
+        } else if let Some(hunk) = hunks.last_mut() {
+            if line.starts_with([' ', '+', '-', '\\']) {
+                hunk.lines.push(line.to_string());
+            }
+        }
+    }
+
+    hunks
+}
+
+/// Unified diff of a single file against `base` (a commit, branch or tag) of the
+/// repo it lives in
+pub async fn diff_file(
+    path: &str,
+    host: Option<&str>,
+    base: &str,
+) -> Result<FileDiffResult, String> {
+    // git would take a base starting with `-` as an option
+    if base.is_empty() || base.starts_with('-') {
+        return Err(format!("Invalid revision \"{}\"", base));
+    }
+    let (dir, filename) = split_file_path(path);
+    let repo_root = run_git(dir, host, &["rev-parse", "--show-toplevel"])
+        .await
+        .map_err(|_| "File is not in a git repository".to_string())?;
+    // Untracked files have no diff against the base; they're reported as such
+    let tracked = run_git(dir, host, &["ls-files", "--error-unmatch", "--", filename])
+        .await
+        .is_ok();
+    let diff = if tracked {
+        run_git(dir, host, &["diff", "--no-color", base, "--", filename]).await?
+    } else {
+        String::new()
+    };
+
+    Ok(FileDiffResult {
+        path: path.to_string(),
+        base: base.to_string(),
+        repo_root: repo_root.trim().to_string(),
+        tracked,
+        hunks: parse_unified_diff(&diff),
+        diff,
+    })
+}
+
+// ==================== Working Dir Repositories ====================
+
+/// Commits of the repository at `path`, newest first
+pub async fn log(path: &str, host: Option<&str>, limit: usize) -> Result<Vec<GitCommit>, String> {
+    let limit_arg = format!("-n{}", limit);
+    let log = run_git(
+        path,
+        host,
+        &["log", &limit_arg, "--format=%H%x1f%an%x1f%aI%x1f%s"],
+    )
+    .await?;
+
+    Ok(log
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\x1f');
+            Some(GitCommit {
+                hash: parts.next()?.to_string(),
+                author: parts.next()?.to_string(),
+                date: parts.next()?.to_string(),
+                message: parts.next()?.to_string(),
+                url: None,
+            })
+        })
+        .collect())
+}
+
+/// Commits with links to their pages on the git host, if `origin` is on a known provider
+pub async fn log_with_links(
+    path: &str,
+    host: Option<&str>,
+    limit: usize,
+) -> Result<Vec<GitCommit>, String> {
+    let mut commits = log(path, host, limit).await?;
+    let web = get_remote_url(path, host)
+        .await
+        .ok()
+        .and_then(|remote| remote_to_web_url(&remote))
+        .and_then(|web_url| Some((GitProvider::from_url(&web_url)?, web_url)));
+    if let Some((provider, web_url)) = web {
+        for commit in &mut commits {
+            commit.url = Some(provider.commit_url(&web_url, &commit.hash));
+        }
+    }
+    Ok(commits)
+}
+
+/// Branch, upstream divergence, dirty file count and last commit of the repository at `path`
+pub async fn status(path: &str, host: Option<&str>) -> Result<GitStatus, String> {
+    let output = run_git(path, host, &["status", "--porcelain=v2", "--branch"]).await?;
+
+    let mut status = GitStatus::default();
+    for line in output.lines() {
+        if let Some(head) = line.strip_prefix("# branch.head ") {
+            status.branch = (head != "(detached)").then(|| head.to_string());
+        } else if let Some(upstream) = line.strip_prefix("# branch.upstream ") {
+            status.upstream = Some(upstream.to_string());
+        } else if let Some(counts) = line.strip_prefix("# branch.ab ") {
+            // Format: +<ahead> -<behind>
+            for count in counts.split_whitespace() {
+                if let Some(ahead) = count.strip_prefix('+') {
+                    status.ahead = ahead.parse().unwrap_or(0);
+                } else if let Some(behind) = count.strip_prefix('-') {
+                    status.behind = behind.parse().unwrap_or(0);
+                }
+            }
+        } else if !line.is_empty() && !line.starts_with('#') {
+            status.dirty_files += 1;
+        }
+    }
+
+    // Fails in a repository without commits
+    status.last_commit = log(path, host, 1)
+        .await
+        .ok()
+        .and_then(|commits| commits.into_iter().next());
+    Ok(status)
+}
+
+/// Fetch all remotes (pruning deleted branches)
+pub async fn fetch(path: &str, host: Option<&str>) -> Result<GitActionResult, String> {
+    let output = run_git(path, host, &["fetch", "--all", "--prune"]).await?;
+    Ok(GitActionResult {
+        output: output.trim().to_string(),
+        status: status(path, host).await?,
+    })
+}
+
+/// Pull the current branch. Only fast-forwards: a quick action never creates a
+/// merge commit, diverged branches are left for the user to resolve.
+pub async fn pull(path: &str, host: Option<&str>) -> Result<GitActionResult, String> {
+    let output = run_git(path, host, &["pull", "--ff-only"]).await?;
+    Ok(GitActionResult {
+        output: output.trim().to_string(),
+        status: status(path, host).await?,
+    })
+}
+
+/// Local branches, then remote-tracking ones
+pub async fn list_branches(path: &str, host: Option<&str>) -> Result<Vec<GitBranch>, String> {
+    let refs = run_git(
+        path,
+        host,
+        &[
+            "for-each-ref",
+            "--format=%(refname)%1f%(HEAD)%1f%(upstream:short)%1f%(committerdate:iso-strict)",
+            "refs/heads",
+            "refs/remotes",
+        ],
+    )
+    .await?;
+
+    Ok(refs
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\x1f');
+            let refname = parts.next()?;
+            let (name, is_remote) = match refname.strip_prefix("refs/heads/") {
+                Some(name) => (name, false),
+                None => (refname.strip_prefix("refs/remotes/")?, true),
+            };
+            // `origin/HEAD` only points at the remote's default branch
+            if is_remote && name.ends_with("/HEAD") {
+                return None;
+            }
+            Some(GitBranch {
+                name: name.to_string(),
+                is_current: parts.next()? == "*",
+                is_remote,
+                upstream: Some(parts.next()?)
+                    .filter(|u| !u.is_empty())
+                    .map(str::to_string),
+                last_commit_date: parts.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Switch to a branch. A remote branch name without a local branch (`feature` for
+/// `origin/feature`) creates a local tracking branch, as `git switch` does.
+pub async fn checkout_branch(
+    path: &str,
+    host: Option<&str>,
+    branch: &str,
+) -> Result<GitActionResult, String> {
+    let branch = branch.trim();
+    if branch.is_empty() || branch.starts_with('-') {
+        return Err(format!("Invalid branch name: {}", branch));
+    }
+    let output = run_git(path, host, &["switch", branch]).await?;
+    Ok(GitActionResult {
+        output: output.trim().to_string(),
+        status: status(path, host).await?,
+    })
+}
+
+/// Worktrees of the repository at `path`, the main one first
+pub async fn list_worktrees(path: &str, host: Option<&str>) -> Result<Vec<GitWorktree>, String> {
+    let output = run_git(path, host, &["worktree", "list", "--porcelain"]).await?;
+
+    // Blocks of `key value` lines separated by blank lines, each starting with `worktree`
+    let mut worktrees: Vec<GitWorktree> = Vec::new();
+    for line in output.lines() {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        if key == "worktree" {
+            worktrees.push(GitWorktree {
+                path: value.to_string(),
+                head: None,
+                branch: None,
+                is_main: worktrees.is_empty(),
+                locked: false,
+                prunable: false,
+            });
+            continue;
+        }
+        let Some(worktree) = worktrees.last_mut() else {
+            continue;
+        };
+        match key {
+            "HEAD" => worktree.head = Some(value.to_string()),
+            "branch" => worktree.branch = Some(value.trim_start_matches("refs/heads/").to_string()),
+            "locked" => worktree.locked = true,
+            "prunable" => worktree.prunable = true,
+            _ => {}
+        }
+    }
+    Ok(worktrees)
+}
+
+/// Default location of a new worktree: next to the repository, named after it and the branch
+pub fn default_worktree_path(repo_path: &str, branch: &str) -> String {
+    let repo_path = repo_path.trim_end_matches(['/', '\\']);
+    let (parent, repo_name) = split_file_path(repo_path);
+    let branch: String = branch
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let separator = if parent.ends_with(['/', '\\']) {
+        ""
+    } else {
+        "/"
+    };
+    format!("{}{}{}-{}", parent, separator, repo_name, branch)
+}
+
+/// Add a worktree for `branch` at `worktree_path`. An existing local branch, or a
+/// remote one git can track, is checked out; otherwise the branch is created from HEAD.
+/// Returns the new worktree.
+pub async fn add_worktree(
+    path: &str,
+    host: Option<&str>,
+    branch: &str,
+    worktree_path: &str,
+) -> Result<GitWorktree, String> {
+    let branch = branch.trim();
+    if branch.is_empty() || branch.starts_with('-') || worktree_path.starts_with('-') {
+        return Err(format!("Invalid branch name: {}", branch));
+    }
+
+    let branches = list_branches(path, host).await?;
+    let remote_suffix = format!("/{}", branch);
+    let exists = branches
+        .iter()
+        .any(|b| b.name == branch || (b.is_remote && b.name.ends_with(&remote_suffix)));
+    if exists {
+        run_git(path, host, &["worktree", "add", worktree_path, branch]).await?;
+    } else {
+        run_git(
+            path,
+            host,
+            &["worktree", "add", "-b", branch, worktree_path],
+        )
+        .await?;
+    }
+
+    list_worktrees(path, host)
+        .await?
+        .into_iter()
+        .find(|w| w.branch.as_deref() == Some(branch))
+        .ok_or_else(|| format!("Worktree for {} was not created", branch))
+}
+
+/// Remove a worktree. Without `force`, git refuses if it has uncommitted changes.
+pub async fn remove_worktree(
+    path: &str,
+    host: Option<&str>,
+    worktree_path: &str,
+    force: bool,
+) -> Result<(), String> {
+    let mut args = vec!["worktree", "remove"];
+    if force {
+        args.push("--force");
+    }
+    args.extend(["--", worktree_path]);
+    run_git(path, host, &args).await?;
+    Ok(())
+}
+
+// ==================== Store Versioning ====================
+
+/// Identity used for commits made by the app in the data directory
+const STORE_GIT_IDENTITY: [&str; 4] = [
+    "-c",
+    "user.name=Devora",
+    "-c",
+    "user.email=devora@localhost",
+];
+
+/// Run a git command synchronously in `dir` (used by the store, which is not async)
+pub fn run_git_sync(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let mut cmd = std::process::Command::new("git");
+    cmd.arg("-C").arg(dir).args(STORE_GIT_IDENTITY).args(args);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute git: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Initialize a git repository in the data directory if there isn't one yet
+pub fn init_store_repo(dir: &Path) -> Result<(), String> {
+    if dir.join(".git").exists() {
+        return Ok(());
+    }
+
+    run_git_sync(dir, &["init", "--quiet"])?;
+
+    // Temp files and local-only folders should never be committed
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        std::fs::write(&gitignore, "*.tmp\nbackups/\ndrafts/\n")
+            .map_err(|e| format!("Failed to write .gitignore: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Stage everything in the data directory and commit it.
+/// Returns Ok(false) if there was nothing to commit.
+pub fn commit_store(dir: &Path, message: &str) -> Result<bool, String> {
+    run_git_sync(dir, &["add", "-A"])?;
+
+    // `diff --cached --quiet` exits non-zero when there are staged changes
+    if run_git_sync(dir, &["diff", "--cached", "--quiet"]).is_ok() {
+        return Ok(false);
+    }
+
+    run_git_sync(dir, &["commit", "--quiet", "-m", message])?;
+    Ok(true)
+}
+
+/// List commits in the data directory, newest first
+pub fn store_history(dir: &Path, limit: usize) -> Result<Vec<StoreCommit>, String> {
+    let limit_arg = format!("-n{}", limit);
+    let log = run_git_sync(dir, &["log", &limit_arg, "--format=%H%x1f%s%x1f%cI"])?;
+
+    Ok(log
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\x1f');
+            Some(StoreCommit {
+                hash: parts.next()?.to_string(),
+                message: parts.next()?.to_string(),
+                committed_at: parts.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Restore the data directory to the state of `hash` and record it as a new commit
+pub fn restore_store(dir: &Path, hash: &str) -> Result<(), String> {
+    // Validate the revision before touching the working tree
+    let commit = format!("{}^{{commit}}", hash);
+    run_git_sync(dir, &["rev-parse", "--verify", "--quiet", &commit])
+        .map_err(|_| format!("Unknown commit: {}", hash))?;
+
+    run_git_sync(
+        dir,
+        &[
+            "restore",
+            "--source",
+            hash,
+            "--staged",
+            "--worktree",
+            "--",
+            ".",
+        ],
+    )?;
+
+    let short = &hash[..hash.len().min(8)];
+    commit_store(dir, &format!("Restore data to {}", short))?;
+    Ok(())
+}
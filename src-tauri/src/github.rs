@@ -0,0 +1,159 @@
+use crate::http_request::{self, HttpRequest};
+use crate::keyring;
+use crate::models::{GithubRepoStatus, HttpHeader};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Keyring account holding the GitHub API token
+pub const TOKEN_ACCOUNT: &str = "github:token";
+
+/// Cached statuses older than this are fetched again
+const CACHE_TTL_SECS: i64 = 10 * 60;
+
+const API_URL: &str = "https://api.github.com";
+
+/// Owner and repository name from a GitHub web URL (`https://github.com/owner/repo`)
+pub fn parse_repo(github_url: &str) -> Option<(String, String)> {
+    let rest = github_url
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("www.");
+    let path = rest.strip_prefix("github.com/")?;
+    let mut parts = path.split('/').filter(|p| !p.is_empty());
+    let owner = parts.next()?;
+    let repo = parts.next()?.trim_end_matches(".git");
+    Some((owner.to_string(), repo.to_string()))
+}
+
+fn cache_path(data_path: &Path) -> PathBuf {
+    data_path.join("cache").join("github_status.json")
+}
+
+fn load_cache(data_path: &Path) -> HashMap<String, GithubRepoStatus> {
+    fs::read_to_string(cache_path(data_path))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn cache_key(owner: &str, repo: &str) -> String {
+    format!("{}/{}", owner, repo).to_lowercase()
+}
+
+/// Cached status of a repository, unless it has expired
+pub fn cached(data_path: &Path, owner: &str, repo: &str) -> Option<GithubRepoStatus> {
+    let status = load_cache(data_path).remove(&cache_key(owner, repo))?;
+    let fetched_at = chrono::DateTime::parse_from_rfc3339(&status.fetched_at).ok()?;
+    let age = chrono::Utc::now().signed_duration_since(fetched_at);
+    (age.num_seconds() < CACHE_TTL_SECS).then_some(status)
+}
+
+pub fn store_cached(data_path: &Path, status: &GithubRepoStatus) -> Result<(), String> {
+    let mut cache = load_cache(data_path);
+    cache.insert(cache_key(&status.owner, &status.repo), status.clone());
+
+    let path = cache_path(data_path);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(&cache)
+        .map_err(|e| format!("Failed to serialize GitHub status cache: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write GitHub status cache: {}", e))
+}
+
+/// GET an API path. Without a token public repositories still work, at a lower rate limit.
+async fn api_get(path: &str, token: Option<&str>) -> Result<Value, String> {
+    let mut headers = vec![
+        HttpHeader {
+            name: "Accept".to_string(),
+            value: "application/vnd.github+json".to_string(),
+        },
+        HttpHeader {
+            name: "User-Agent".to_string(),
+            value: "Devora".to_string(),
+        },
+    ];
+    if let Some(token) = token {
+        headers.push(HttpHeader {
+            name: "Authorization".to_string(),
+            value: format!("Bearer {}", token),
+        });
+    }
+    let response = http_request::send(&HttpRequest {
+        method: "GET".to_string(),
+        url: format!("{}{}", API_URL, path),
+        headers,
+        body: None,
+    })
+    .await?;
+
+    let body: Value = serde_json::from_str(&response.body)
+        .map_err(|e| format!("Failed to parse GitHub response: {}", e))?;
+    if response.status != 200 {
+        let message = body["message"].as_str().unwrap_or("unknown error");
+        return Err(format!(
+            "GitHub API error ({}): {}",
+            response.status, message
+        ));
+    }
+    Ok(body)
+}
+
+/// Stars, open issue and PR counts and the latest CI run of the default branch
+pub async fn fetch_status(owner: &str, repo: &str) -> Result<GithubRepoStatus, String> {
+    let token = keyring::get_secret(TOKEN_ACCOUNT)?.filter(|t| !t.is_empty());
+    let token = token.as_deref();
+
+    let info = api_get(&format!("/repos/{}/{}", owner, repo), token).await?;
+    let default_branch = info["default_branch"]
+        .as_str()
+        .unwrap_or("main")
+        .to_string();
+    // GitHub counts open PRs as issues too
+    let open_issues_and_prs = info["open_issues_count"].as_u64().unwrap_or(0);
+
+    let prs = api_get(
+        &format!(
+            "/search/issues?q=repo:{}/{}+type:pr+state:open&per_page=1",
+            owner, repo
+        ),
+        token,
+    )
+    .await?;
+    let open_prs = prs["total_count"].as_u64().unwrap_or(0);
+
+    // Repositories without Actions just have no runs
+    let runs = api_get(
+        &format!(
+            "/repos/{}/{}/actions/runs?branch={}&per_page=1",
+            owner, repo, default_branch
+        ),
+        token,
+    )
+    .await
+    .ok();
+    let latest_run = runs.as_ref().and_then(|r| r["workflow_runs"].get(0));
+    // A finished run reports its conclusion (success, failure, ...), others their status
+    let ci_status = latest_run.and_then(|run| {
+        run["conclusion"]
+            .as_str()
+            .or_else(|| run["status"].as_str())
+            .map(str::to_string)
+    });
+    let ci_url = latest_run.and_then(|run| run["html_url"].as_str().map(str::to_string));
+
+    Ok(GithubRepoStatus {
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+        stars: info["stargazers_count"].as_u64().unwrap_or(0),
+        open_issues: open_issues_and_prs.saturating_sub(open_prs),
+        open_prs,
+        default_branch,
+        ci_status,
+        ci_url,
+        fetched_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
@@ -0,0 +1,228 @@
+use crate::json_store::JsonStore;
+use crate::models::{EnvironmentManifest, ExportData, ItemType, Project, ToolRequirement};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Files whose variable names (never values) are listed as required env vars
+const ENV_TEMPLATE_FILES: &[&str] = &[".env.example", ".env.sample", ".env.template"];
+
+/// Build the environment manifest for a project: tools detected in its local
+/// working dirs, SSH hosts it references and the env var names it needs.
+/// Only names are collected for env vars so secrets never end up in the package.
+pub fn build_manifest(store: &JsonStore, project: &Project) -> EnvironmentManifest {
+    let mut tools: BTreeMap<String, ToolRequirement> = BTreeMap::new();
+    let mut ssh_hosts = BTreeSet::new();
+    let mut env_vars = BTreeSet::new();
+
+    for dir in project.metadata.working_dirs.iter().flatten() {
+        match &dir.host {
+            // Remote dirs can't be inspected offline; the host is what a teammate needs
+            Some(host) => {
+                ssh_hosts.insert(host.clone());
+            }
+            None => {
+                let path = Path::new(&dir.path);
+                for tool in detect_tools(path) {
+                    merge_tool(&mut tools, tool);
+                }
+                env_vars.extend(env_template_names(path));
+            }
+        }
+    }
+
+    for item in project.items.iter().flatten() {
+        if let Some(host) = &item.command_host {
+            ssh_hosts.insert(host.clone());
+        }
+        if item.item_type == ItemType::RemoteIde {
+            if let Some((host, _)) = item.content.split_once(':') {
+                ssh_hosts.insert(host.to_string());
+            }
+        }
+        if let Some(env) = &item.coding_agent_env {
+            env_vars.extend(env_json_names(env));
+        }
+    }
+    if let Ok(Some(global_env)) = store.get_setting("codingAgentGlobalEnv") {
+        env_vars.extend(env_json_names(&global_env));
+    }
+
+    EnvironmentManifest {
+        tools: tools.into_values().collect(),
+        ssh_hosts: ssh_hosts.into_iter().collect(),
+        env_vars: env_vars.into_iter().collect(),
+    }
+}
+
+/// Blank out the values of the items' coding agent env in a package's export. The
+/// names stay, so the recipient sees what to fill in.
+pub fn redact_env(export: &mut ExportData) {
+    for item in &mut export.items {
+        if let Some(env) = item.coding_agent_env.as_mut() {
+            let blank: BTreeMap<String, String> = env_json_names(env)
+                .into_iter()
+                .map(|name| (name, String::new()))
+                .collect();
+            *env = serde_json::to_string(&blank).unwrap_or_default();
+        }
+    }
+}
+
+/// Keep one entry per tool, preferring the one that pins a version
+fn merge_tool(tools: &mut BTreeMap<String, ToolRequirement>, tool: ToolRequirement) {
+    match tools.get(&tool.name) {
+        Some(existing) if existing.version.is_some() || tool.version.is_none() => {}
+        _ => {
+            tools.insert(tool.name.clone(), tool);
+        }
+    }
+}
+
+fn tool(name: &str, version: Option<String>, source: &str) -> ToolRequirement {
+    ToolRequirement {
+        name: name.to_string(),
+        version: version
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty()),
+        source: source.to_string(),
+    }
+}
+
+/// Detect the toolchains a directory needs from its marker and version files
+fn detect_tools(dir: &Path) -> Vec<ToolRequirement> {
+    let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+    let mut tools = Vec::new();
+
+    // asdf / mise pin every tool in one file
+    if let Some(content) = read(".tool-versions") {
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            if let Some(name) = parts.next() {
+                tools.push(tool(name, parts.next().map(String::from), ".tool-versions"));
+            }
+        }
+    }
+
+    if let Some(manifest) = read("Cargo.toml") {
+        let pinned = read("rust-toolchain.toml")
+            .and_then(|toolchain| toml_string(&toolchain, "channel"))
+            .map(|v| (v, "rust-toolchain.toml"))
+            .or_else(|| read("rust-toolchain").map(|v| (v, "rust-toolchain")));
+        match pinned {
+            Some((version, source)) => tools.push(tool("rust", Some(version), source)),
+            None => tools.push(tool(
+                "rust",
+                toml_string(&manifest, "rust-version"),
+                "Cargo.toml",
+            )),
+        }
+    }
+
+    if let Some(package_json) = read("package.json") {
+        let package: serde_json::Value = serde_json::from_str(&package_json).unwrap_or_default();
+        let node_version = read(".nvmrc")
+            .map(|v| (v, ".nvmrc"))
+            .or_else(|| read(".node-version").map(|v| (v, ".node-version")))
+            .or_else(|| {
+                package["engines"]["node"]
+                    .as_str()
+                    .map(|v| (v.to_string(), "package.json"))
+            });
+        match node_version {
+            Some((version, source)) => tools.push(tool("node", Some(version), source)),
+            None => tools.push(tool("node", None, "package.json")),
+        }
+
+        // "packageManager": "pnpm@9.1.0" pins the manager; lockfiles only name it
+        if let Some((name, version)) = package["packageManager"]
+            .as_str()
+            .and_then(|pm| pm.split_once('@'))
+        {
+            tools.push(tool(name, Some(version.to_string()), "package.json"));
+        } else if dir.join("pnpm-lock.yaml").exists() {
+            tools.push(tool("pnpm", None, "pnpm-lock.yaml"));
+        } else if dir.join("yarn.lock").exists() {
+            tools.push(tool("yarn", None, "yarn.lock"));
+        } else if dir.join("bun.lockb").exists() || dir.join("bun.lock").exists() {
+            tools.push(tool("bun", None, "bun.lock"));
+        }
+    }
+
+    if dir.join("pyproject.toml").exists() || dir.join("requirements.txt").exists() {
+        let source = if dir.join("pyproject.toml").exists() {
+            "pyproject.toml"
+        } else {
+            "requirements.txt"
+        };
+        match read(".python-version") {
+            Some(version) => tools.push(tool("python", Some(version), ".python-version")),
+            None => tools.push(tool("python", None, source)),
+        }
+    }
+
+    if let Some(go_mod) = read("go.mod") {
+        let version = go_mod
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("go ").map(String::from));
+        tools.push(tool("go", version, "go.mod"));
+    }
+
+    for compose in [
+        "docker-compose.yml",
+        "docker-compose.yaml",
+        "compose.yml",
+        "compose.yaml",
+    ] {
+        if dir.join(compose).exists() {
+            tools.push(tool("docker", None, compose));
+            break;
+        }
+    }
+    if dir.join("Dockerfile").exists() {
+        tools.push(tool("docker", None, "Dockerfile"));
+    }
+
+    tools
+}
+
+/// Value of a top-level `key = "value"` line in a TOML file
+fn toml_string(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        (k.trim() == key).then(|| v.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Variable names declared in a directory's .env template files
+fn env_template_names(dir: &Path) -> Vec<String> {
+    ENV_TEMPLATE_FILES
+        .iter()
+        .filter_map(|name| fs::read_to_string(dir.join(name)).ok())
+        .flat_map(|content| {
+            content
+                .lines()
+                .filter_map(|line| {
+                    let line = line.trim();
+                    if line.starts_with('#') {
+                        return None;
+                    }
+                    let line = line.strip_prefix("export ").unwrap_or(line);
+                    let (name, _) = line.split_once('=')?;
+                    Some(name.trim().to_string())
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Variable names of a coding agent env JSON object (`{"NAME": "value"}`)
+fn env_json_names(json: &str) -> Vec<String> {
+    serde_json::from_str::<HashMap<String, String>>(json)
+        .map(|vars| vars.into_keys().collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,414 @@
+use crate::json_store::JsonStore;
+use crate::models::{HostAgentOverride, HostStatus, SshConnectionTest};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// Global setting that treats every SSH host as offline
+pub const OFFLINE_MODE_KEY: &str = "offlineMode";
+
+/// Global setting holding per-host terminal/agent overrides as a JSON array
+pub const HOST_AGENT_OVERRIDES_KEY: &str = "hostAgentOverrides";
+
+/// Global setting mapping SSH hosts to the jump hosts to reach them through, in
+/// ssh's `-J` format (e.g. "bastion" or "me@outer,inner:2222"). Hosts with a
+/// ProxyJump in ~/.ssh/config need no entry: ssh applies it itself. The chains
+/// are written to an ssh config of their own as well (see `write_ssh_config`).
+pub const HOST_JUMP_CHAINS_KEY: &str = "hostJumpChains";
+
+/// ssh config in the config dir holding the jump chains as ProxyJump entries
+const SSH_CONFIG_FILE: &str = "ssh_config";
+
+/// How long an unreachable host stays short-circuited before remote
+/// operations are allowed to try it again
+const OFFLINE_RECHECK_SECS: i64 = 300;
+
+/// Connection timeout for reachability probes
+const PROBE_TIMEOUT_SECS: u32 = 5;
+
+/// ssh exits with 255 when the connection itself failed (as opposed to the
+/// remote command failing)
+const SSH_CONNECTION_ERROR: i32 = 255;
+
+/// Last-known reachability of each SSH host, persisted to
+/// ~/.devora/host_status.json so "last seen" survives restarts
+pub struct HostStatusCache {
+    path: PathBuf,
+    statuses: Mutex<HashMap<String, HostStatus>>,
+}
+
+impl HostStatusCache {
+    pub fn new(config_dir: &Path) -> Self {
+        let path = config_dir.join("host_status.json");
+        let statuses = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            statuses: Mutex::new(statuses),
+        }
+    }
+
+    fn save(&self, statuses: &HashMap<String, HostStatus>) {
+        // The cache is best-effort; failing to persist it must not fail the operation
+        if let Ok(content) = serde_json::to_string_pretty(statuses) {
+            let _ = fs::write(&self.path, content);
+        }
+    }
+
+    /// All cached host statuses, sorted by host name
+    pub fn all(&self) -> Vec<HostStatus> {
+        let mut statuses: Vec<HostStatus> =
+            self.statuses.lock().unwrap().values().cloned().collect();
+        statuses.sort_by(|a, b| a.host.cmp(&b.host));
+        statuses
+    }
+
+    /// Record the outcome of a connection attempt to `host`
+    pub fn record(&self, host: &str, reachable: bool, error: Option<String>) -> HostStatus {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut statuses = self.statuses.lock().unwrap();
+
+        let last_seen = if reachable {
+            Some(now.clone())
+        } else {
+            statuses.get(host).and_then(|s| s.last_seen.clone())
+        };
+
+        let status = HostStatus {
+            host: host.to_string(),
+            reachable,
+            last_checked: now,
+            last_seen,
+            error,
+        };
+        statuses.insert(host.to_string(), status.clone());
+        self.save(&statuses);
+        status
+    }
+
+    /// Record the result of an SSH invocation based on its exit code
+    pub fn record_ssh_output(&self, host: &str, output: &std::process::Output) {
+        if output.status.code() == Some(SSH_CONNECTION_ERROR) {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            self.record(host, false, Some(stderr));
+        } else {
+            self.record(host, true, None);
+        }
+    }
+
+    /// Record the result of an SSH invocation whose output was streamed elsewhere
+    pub fn record_ssh_exit(&self, host: &str, exit_code: Option<i32>) {
+        if exit_code == Some(SSH_CONNECTION_ERROR) {
+            self.record(host, false, Some("SSH connection failed".to_string()));
+        } else {
+            self.record(host, true, None);
+        }
+    }
+
+    /// Fail fast if `host` is known to be offline, instead of waiting for an SSH timeout.
+    /// With `offline_mode` every host is treated as offline.
+    pub fn ensure_online(&self, host: &str, offline_mode: bool) -> Result<(), String> {
+        let statuses = self.statuses.lock().unwrap();
+        let status = statuses.get(host);
+
+        if !offline_mode {
+            let Some(status) = status.filter(|s| !s.reachable) else {
+                return Ok(());
+            };
+            let recently_checked = chrono::DateTime::parse_from_rfc3339(&status.last_checked)
+                .map(|checked| {
+                    chrono::Utc::now()
+                        .signed_duration_since(checked)
+                        .num_seconds()
+                        < OFFLINE_RECHECK_SECS
+                })
+                .unwrap_or(false);
+            if !recently_checked {
+                return Ok(());
+            }
+        }
+
+        let last_seen = status
+            .and_then(|s| s.last_seen.as_deref())
+            .unwrap_or("never");
+        Err(format!("Host {} offline (last seen {})", host, last_seen))
+    }
+}
+
+/// Probe `host` with a no-op SSH command that can't prompt for credentials
+pub async fn probe_host(host: &str, jump: Option<&str>) -> Result<(), String> {
+    let timeout = format!("ConnectTimeout={}", PROBE_TIMEOUT_SECS);
+    let mut cmd = Command::new("ssh");
+    cmd.args(jump_args(jump))
+        .args(["-o", "BatchMode=yes", "-o", &timeout, host, "true"]);
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute SSH command: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Connect to `host` in stages for test_ssh_connection: a plain TCP connection to
+/// the resolved address reads the server banner and measures latency, then a
+/// non-interactive login checks authentication
+pub async fn test_connection(host: &str, jump: Option<&str>) -> SshConnectionTest {
+    let mut result = SshConnectionTest {
+        host: host.to_string(),
+        reachable: false,
+        authenticated: false,
+        banner: None,
+        latency_ms: None,
+        login_ms: None,
+        error: None,
+        hint: None,
+    };
+
+    let config = resolved_config(host).await;
+    let proxied =
+        jump.is_some() || config.contains_key("proxyjump") || config.contains_key("proxycommand");
+    if !proxied {
+        let hostname = config
+            .get("hostname")
+            .cloned()
+            .unwrap_or_else(|| host.to_string());
+        let port = config
+            .get("port")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(22);
+        match tokio::task::spawn_blocking(move || read_banner(&hostname, port)).await {
+            Ok(Ok((banner, latency))) => {
+                result.reachable = true;
+                result.banner = Some(banner);
+                result.latency_ms = Some(latency.as_millis() as u64);
+            }
+            Ok(Err(e)) => {
+                result.hint = Some(hint_for(&e).to_string());
+                result.error = Some(e);
+                return result;
+            }
+            Err(e) => log::debug!("Banner check failed: {}", e),
+        }
+    }
+
+    let started = Instant::now();
+    match probe_host(host, jump).await {
+        Ok(()) => {
+            result.reachable = true;
+            result.authenticated = true;
+            result.login_ms = Some(started.elapsed().as_millis() as u64);
+        }
+        Err(e) => {
+            // Behind a proxy only the login attempt shows whether the server answered
+            result.reachable |= is_auth_error(&e);
+            result.hint = Some(hint_for(&e).to_string());
+            result.error = Some(e);
+        }
+    }
+    result
+}
+
+/// Effective settings for `host` from `ssh -G` (keys are lowercase)
+async fn resolved_config(host: &str) -> HashMap<String, String> {
+    let mut cmd = Command::new("ssh");
+    cmd.args(["-G", host]);
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let Ok(output) = cmd.output().await else {
+        return HashMap::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter(|(_, value)| *value != "none")
+        .map(|(key, value)| (key.to_lowercase(), value.to_string()))
+        .collect()
+}
+
+/// Connect to an SSH server and read its identification line, timing the connect
+fn read_banner(hostname: &str, port: u16) -> Result<(String, Duration), String> {
+    let timeout = Duration::from_secs(PROBE_TIMEOUT_SECS as u64);
+    let addr = (hostname, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Could not resolve hostname {}: {}", hostname, e))?
+        .next()
+        .ok_or_else(|| format!("Could not resolve hostname {}", hostname))?;
+
+    let started = Instant::now();
+    let stream = TcpStream::connect_timeout(&addr, timeout)
+        .map_err(|e| format!("Failed to connect to {} port {}: {}", hostname, port, e))?;
+    let latency = started.elapsed();
+
+    stream
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| format!("Failed to read SSH banner: {}", e))?;
+    // Servers may send other lines before the one starting with "SSH-"
+    let banner = BufReader::new(stream)
+        .lines()
+        .take(10)
+        .map_while(Result::ok)
+        .find(|line| line.starts_with("SSH-"))
+        .ok_or_else(|| format!("{} port {} did not answer as an SSH server", hostname, port))?;
+    Ok((banner.trim().to_string(), latency))
+}
+
+fn is_auth_error(error: &str) -> bool {
+    error.contains("Permission denied") || error.contains("Too many authentication failures")
+}
+
+/// Suggested fix for a failed connection, from ssh's error message
+fn hint_for(error: &str) -> &'static str {
+    let error_lower = error.to_lowercase();
+    if is_auth_error(error) {
+        "The server rejected the login. Add your key to ssh-agent or set IdentityFile for this host in ~/.ssh/config; password logins aren't supported."
+    } else if error_lower.contains("could not resolve") {
+        "The hostname could not be resolved. Check the HostName in ~/.ssh/config or your network."
+    } else if error_lower.contains("connection refused") {
+        "Nothing accepts SSH connections on that port. Check that sshd is running and the Port setting."
+    } else if error_lower.contains("timed out") || error_lower.contains("no route") {
+        "The host did not answer. Check that it is up and reachable (VPN, firewall)."
+    } else if error.contains("REMOTE HOST IDENTIFICATION HAS CHANGED") {
+        "The host key changed. If that is expected, remove the old key with ssh-keygen -R <host>."
+    } else if error.contains("Host key verification failed") {
+        "The host key is unknown. Connect once from a terminal to accept it."
+    } else {
+        "Run ssh -v with this host in a terminal for details."
+    }
+}
+
+/// Load all per-host terminal/agent overrides
+pub fn load_agent_overrides(store: &JsonStore) -> Result<Vec<HostAgentOverride>, String> {
+    match store.get_setting(HOST_AGENT_OVERRIDES_KEY)? {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse host agent overrides: {}", e)),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Replace all per-host terminal/agent overrides
+pub fn save_agent_overrides(
+    store: &JsonStore,
+    overrides: &[HostAgentOverride],
+) -> Result<(), String> {
+    let json = serde_json::to_string(overrides)
+        .map_err(|e| format!("Failed to serialize host agent overrides: {}", e))?;
+    store.set_setting(HOST_AGENT_OVERRIDES_KEY, &json)
+}
+
+/// Override configured for `host`, if any
+pub fn agent_override_for(store: &JsonStore, host: &str) -> Option<HostAgentOverride> {
+    load_agent_overrides(store)
+        .ok()?
+        .into_iter()
+        .find(|o| o.host == host)
+}
+
+/// Jump host chains by host
+pub fn load_jump_chains(store: &JsonStore) -> Result<HashMap<String, String>, String> {
+    match store.get_setting(HOST_JUMP_CHAINS_KEY)? {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse host jump chains: {}", e)),
+        _ => Ok(HashMap::new()),
+    }
+}
+
+/// Set (or with None, remove) the jump host chain of `host`
+pub fn save_jump_chain(store: &JsonStore, host: &str, chain: Option<&str>) -> Result<(), String> {
+    let mut chains = load_jump_chains(store)?;
+    match chain.map(str::trim).filter(|c| !c.is_empty()) {
+        Some(chain) => {
+            if chain.contains(char::is_whitespace) || chain.split(',').any(str::is_empty) {
+                return Err(format!(
+                    "Invalid jump host chain \"{}\": use comma-separated [user@]host[:port]",
+                    chain
+                ));
+            }
+            chains.insert(host.to_string(), chain.to_string());
+        }
+        None => {
+            chains.remove(host);
+        }
+    }
+    let json = serde_json::to_string(&chains)
+        .map_err(|e| format!("Failed to serialize host jump chains: {}", e))?;
+    store.set_setting(HOST_JUMP_CHAINS_KEY, &json)
+}
+
+/// Jump host chain configured in the app for `host`, if any
+pub fn jump_chain(store: &JsonStore, host: &str) -> Option<String> {
+    load_jump_chains(store).ok()?.remove(host)
+}
+
+/// Write the jump chains as ProxyJump entries to ~/.devora/ssh_config and include
+/// that file at the top of ~/.ssh/config (once, when there are chains). Every ssh
+/// connection to a host then takes its chain, including git, sftp and docker over
+/// SSH and the sessions editors open for remote IDEs.
+pub fn write_ssh_config(store: &JsonStore, config_dir: &Path) -> Result<(), String> {
+    let mut chains: Vec<_> = load_jump_chains(store)?.into_iter().collect();
+    chains.sort();
+    let mut content =
+        String::from("# Jump host chains set in Devora. Written by the app, edits are lost.\n");
+    for (host, chain) in &chains {
+        // ssh matches Host patterns against the host name, without the user
+        let name = host.rsplit('@').next().unwrap_or(host);
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            continue;
+        }
+        content.push_str(&format!("\nHost {}\n    ProxyJump {}\n", name, chain));
+    }
+    let path = config_dir.join(SSH_CONFIG_FILE);
+    fs::write(&path, content).map_err(|e| format!("Failed to write SSH config: {}", e))?;
+    if chains.is_empty() {
+        return Ok(());
+    }
+
+    let Some(ssh_dir) = dirs::home_dir().map(|home| home.join(".ssh")) else {
+        return Ok(());
+    };
+    let user_config = ssh_dir.join("config");
+    let include = format!("Include \"{}\"", path.to_string_lossy().replace('\\', "/"));
+    let existing = match fs::read_to_string(&user_config) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read ~/.ssh/config: {}", e)),
+    };
+    if existing.lines().any(|line| line.trim() == include) {
+        return Ok(());
+    }
+    fs::create_dir_all(&ssh_dir).map_err(|e| format!("Failed to create ~/.ssh: {}", e))?;
+    // At the top: a line after a Host block would only apply to that block's hosts
+    fs::write(&user_config, format!("{}\n\n{}", include, existing))
+        .map_err(|e| format!("Failed to update ~/.ssh/config: {}", e))
+}
+
+/// ssh arguments that route the connection through `chain`; put them before the host
+pub fn jump_args(chain: Option<&str>) -> Vec<&str> {
+    match chain {
+        Some(chain) => vec!["-J", chain],
+        None => Vec::new(),
+    }
+}
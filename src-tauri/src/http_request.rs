@@ -0,0 +1,178 @@
+use crate::models::{HttpHeader, HttpResponse};
+use std::process::Stdio;
+use std::time::Instant;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Requests taking longer than this are aborted
+const REQUEST_TIMEOUT_SECS: u32 = 30;
+
+/// HTTP request parsed from an item's content, written like an `.http` file:
+///
+/// ```text
+/// POST https://api.example.com/items
+/// Content-Type: application/json
+///
+/// {"name": "test"}
+/// ```
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<HttpHeader>,
+    pub body: Option<String>,
+}
+
+/// Parse request text: request line, header lines, blank line, body.
+/// A request line without a method is a GET.
+pub fn parse_request(content: &str) -> Result<HttpRequest, String> {
+    let mut lines = content
+        .lines()
+        .skip_while(|line| line.trim().is_empty() || line.trim_start().starts_with('#'));
+
+    let request_line = lines.next().ok_or("HTTP request has no request line")?;
+    let mut parts = request_line.split_whitespace();
+    let (method, url) = match (parts.next(), parts.next()) {
+        (Some(method), Some(url)) => (method.to_uppercase(), url.to_string()),
+        (Some(url), None) => ("GET".to_string(), url.to_string()),
+        _ => return Err("HTTP request has no URL".to_string()),
+    };
+
+    let mut headers = Vec::new();
+    for line in lines.by_ref() {
+        if line.trim().is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid header line: {}", line))?;
+        headers.push(HttpHeader {
+            name: name.trim().to_string(),
+            value: value.trim().to_string(),
+        });
+    }
+
+    let body = lines.collect::<Vec<_>>().join("\n");
+    Ok(HttpRequest {
+        method,
+        url,
+        headers,
+        body: (!body.trim().is_empty()).then_some(body),
+    })
+}
+
+/// Quote a value for a curl config file
+fn curl_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+/// Split curl `--include` output into status, headers and body.
+/// Interim `100 Continue` responses are skipped.
+fn parse_response(output: &[u8]) -> Result<(u16, Vec<HttpHeader>, String), String> {
+    let mut rest = output;
+    loop {
+        let split = rest
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or("Malformed HTTP response")?;
+        let head = String::from_utf8_lossy(&rest[..split]).to_string();
+        rest = &rest[split + 4..];
+
+        let mut head_lines = head.lines();
+        let status: u16 = head_lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|code| code.parse().ok())
+            .ok_or("Malformed HTTP status line")?;
+        if status == 100 {
+            continue;
+        }
+
+        let headers = head_lines
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some(HttpHeader {
+                    name: name.trim().to_string(),
+                    value: value.trim().to_string(),
+                })
+            })
+            .collect();
+        return Ok((status, headers, String::from_utf8_lossy(rest).to_string()));
+    }
+}
+
+/// Send the request with curl. The request is passed as a curl config on stdin
+/// so header values (tokens) never show up in the process list.
+pub async fn send(request: &HttpRequest) -> Result<HttpResponse, String> {
+    let mut config = format!(
+        "url = {}\nrequest = {}\n",
+        curl_quote(&request.url),
+        curl_quote(&request.method)
+    );
+    for header in &request.headers {
+        config.push_str(&format!(
+            "header = {}\n",
+            curl_quote(&format!("{}: {}", header.name, header.value))
+        ));
+    }
+    if let Some(body) = &request.body {
+        config.push_str(&format!("data-binary = {}\n", curl_quote(body)));
+    }
+
+    let mut cmd = Command::new("curl");
+    cmd.args([
+        "-s",
+        "-S",
+        "--include",
+        "--max-time",
+        &REQUEST_TIMEOUT_SECS.to_string(),
+        "-K",
+        "-",
+    ]);
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let started = Instant::now();
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(config.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to pass request to curl: {}", e))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Request to {} failed: {}",
+            request.url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let (status, headers, body) = parse_response(&output.stdout)?;
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
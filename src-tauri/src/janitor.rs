@@ -0,0 +1,151 @@
+use crate::json_store::JsonStore;
+use crate::models::CleanupReport;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+
+/// Temporaries younger than this may still belong to a write in progress
+const MIN_TEMP_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// How often the janitor runs after the startup pass
+const JANITOR_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Prefix of temp files the app writes to the system temp directory
+const SYSTEM_TEMP_PREFIX: &str = "devora-";
+
+/// Write `content` to a new file in the system temp directory, named so that the
+/// janitor sweeps it up, that only the user can read (0600 on Unix; the Windows
+/// temp directory is per-user already)
+pub fn write_private_temp(name: &str, content: &str) -> Result<PathBuf, String> {
+    let path = std::env::temp_dir().join(format!("{}{}", SYSTEM_TEMP_PREFIX, name));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&path)
+        .map_err(|e| format!("Failed to create temp file: {}", e))?;
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("Failed to write temp file: {}", e))?;
+    Ok(path)
+}
+
+/// Remove a temp file that another program reads once `delay` has passed
+pub fn remove_later(path: PathBuf, delay: Duration) {
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        if let Err(e) = fs::remove_file(&path) {
+            log::debug!("Failed to remove {}: {}", path.display(), e);
+        }
+    });
+}
+
+/// Managed state holding the report of the latest cleanup pass
+pub struct JanitorState(pub Mutex<Option<CleanupReport>>);
+
+/// Remove a file if it is a stale temporary, recording the outcome
+fn remove_if_stale(path: &Path, now: SystemTime, report: &mut CleanupReport) {
+    // symlink_metadata so links are never followed out of the directory
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return;
+    };
+    if !meta.is_file() {
+        return;
+    }
+    let age = meta
+        .modified()
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .unwrap_or_default();
+    if age < MIN_TEMP_AGE {
+        return;
+    }
+
+    match fs::remove_file(path) {
+        Ok(()) => {
+            report.removed.push(path.to_string_lossy().to_string());
+            report.bytes_freed += meta.len();
+        }
+        Err(e) => report
+            .errors
+            .push(format!("Failed to remove {}: {}", path.display(), e)),
+    }
+}
+
+/// Walk the data directory removing orphaned `*.tmp` files (skipping `.git`)
+fn clean_dir(dir: &Path, now: SystemTime, report: &mut CleanupReport) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if entry.file_name() != ".git" {
+                clean_dir(&path, now, report);
+            }
+        } else if path.extension().and_then(|e| e.to_str()) == Some("tmp") {
+            remove_if_stale(&path, now, report);
+        }
+    }
+}
+
+/// Remove stale temporaries from the data directory and the system temp directory
+pub fn clean_temp_files(data_path: &Path) -> CleanupReport {
+    let now = SystemTime::now();
+    let mut report = CleanupReport {
+        removed: Vec::new(),
+        bytes_freed: 0,
+        errors: Vec::new(),
+        ran_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    clean_dir(data_path, now, &mut report);
+
+    if let Ok(entries) = fs::read_dir(std::env::temp_dir()) {
+        for entry in entries.flatten() {
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(SYSTEM_TEMP_PREFIX)
+            {
+                remove_if_stale(&entry.path(), now, &mut report);
+            }
+        }
+    }
+
+    if !report.removed.is_empty() {
+        log::info!(
+            "Janitor removed {} temp files ({} bytes)",
+            report.removed.len(),
+            report.bytes_freed
+        );
+    }
+    report
+}
+
+/// Run a cleanup pass against the current data directory and remember its report
+pub fn run_once(app: &AppHandle) -> CleanupReport {
+    let data_path = app.state::<JsonStore>().data_path();
+    let report = clean_temp_files(&data_path);
+    *app.state::<JanitorState>().0.lock().unwrap() = Some(report.clone());
+    report
+}
+
+/// Clean up on startup and then periodically on a background thread
+pub fn spawn_janitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        loop {
+            run_once(&app);
+            std::thread::sleep(JANITOR_INTERVAL);
+        }
+    });
+}
@@ -0,0 +1,216 @@
+use crate::hosts;
+use crate::models::IdeType;
+use crate::processes;
+use crate::ssh_config;
+use crate::templates;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long to wait for a host to say whether an IDE backend is running there
+const BACKEND_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How deep to look under the Toolbox apps dir: `<app>/ch-0/<build>/bin/<launcher>`
+/// (macOS app bundles in a build dir are looked into without going deeper)
+const MAX_APPS_DEPTH: usize = 4;
+
+/// Where to run a JetBrains launcher such as `idea` from: the command itself when
+/// it's on PATH, else the Toolbox shell script or the newest IDE Toolbox installed,
+/// else the bare command so the spawn error names it.
+pub fn launcher(cmd: &str) -> String {
+    if on_path(cmd) {
+        return cmd.to_string();
+    }
+    toolbox_script(cmd)
+        .or_else(|| toolbox_install(cmd))
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|| cmd.to_string())
+}
+
+fn on_path(cmd: &str) -> bool {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    let names: Vec<String> = if cfg!(windows) {
+        ["cmd", "bat", "exe"]
+            .iter()
+            .map(|ext| format!("{}.{}", cmd, ext))
+            .collect()
+    } else {
+        vec![cmd.to_string()]
+    };
+    std::env::split_paths(&paths).any(|dir| names.iter().any(|name| dir.join(name).is_file()))
+}
+
+/// Toolbox's data directory
+fn toolbox_dir() -> Option<PathBuf> {
+    // ~/Library/Application Support on macOS, ~/.local/share on Linux
+    let base = if cfg!(windows) {
+        dirs::data_local_dir()
+    } else {
+        dirs::data_dir()
+    };
+    base.map(|d| d.join("JetBrains").join("Toolbox"))
+}
+
+/// The shell script Toolbox generates for `cmd`, in the scripts location set in
+/// Toolbox's settings (`shell_scripts.location`) or its default `scripts` dir
+fn toolbox_script(cmd: &str) -> Option<PathBuf> {
+    let toolbox = toolbox_dir()?;
+    let configured = fs::read_to_string(toolbox.join(".settings.json"))
+        .ok()
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+        .and_then(|settings| {
+            settings["shell_scripts"]["location"]
+                .as_str()
+                .map(PathBuf::from)
+        });
+    let dir = configured.unwrap_or_else(|| toolbox.join("scripts"));
+    let name = if cfg!(windows) {
+        format!("{}.cmd", cmd)
+    } else {
+        cmd.to_string()
+    };
+    Some(dir.join(name)).filter(|path| path.is_file())
+}
+
+/// The launcher of an IDE Toolbox installed, newest build first when several
+/// channels or versions are installed
+fn toolbox_install(cmd: &str) -> Option<PathBuf> {
+    let names: Vec<String> = if cfg!(windows) {
+        vec![format!("{}64.exe", cmd), format!("{}.exe", cmd)]
+    } else if cfg!(target_os = "macos") {
+        vec![cmd.to_string()]
+    } else {
+        vec![cmd.to_string(), format!("{}.sh", cmd)]
+    };
+
+    // Toolbox 1.x keeps IDEs under apps/<product>/ch-<n>/<build>; 2.x installs them
+    // under apps/<name> on Linux, ~/Applications on macOS and Programs on Windows.
+    // The latter hold other apps too, so only the IDE's own dir is searched there.
+    let mut roots: Vec<(PathBuf, usize)> = toolbox_dir()
+        .map(|d| (d.join("apps"), 0))
+        .into_iter()
+        .collect();
+    let shallow = MAX_APPS_DEPTH - 2;
+    if cfg!(windows) {
+        roots.extend(dirs::data_local_dir().map(|d| (d.join("Programs"), shallow)));
+    } else if cfg!(target_os = "macos") {
+        roots.extend(dirs::home_dir().map(|d| (d.join("Applications"), shallow)));
+    }
+
+    let mut found = Vec::new();
+    for (root, depth) in roots {
+        find_launchers(&root, &names, depth, &mut found);
+    }
+    found.into_iter().max_by_key(|launcher| build_of(launcher))
+}
+
+fn find_launchers(dir: &Path, names: &[String], depth: usize, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        // Launchers live in bin/, or Contents/MacOS/ in a macOS app bundle. A bundle
+        // is looked into directly, as under Toolbox 1.x's build dir its launcher is
+        // deeper than the walk goes.
+        let launcher_dir = if file_name.ends_with(".app") {
+            Some(path.join("Contents").join("MacOS"))
+        } else if file_name == "bin" || file_name == "MacOS" {
+            Some(path.clone())
+        } else {
+            None
+        };
+        if let Some(launcher_dir) = launcher_dir {
+            found.extend(
+                names
+                    .iter()
+                    .map(|name| launcher_dir.join(name))
+                    .filter(|p| p.is_file()),
+            );
+        } else if depth < MAX_APPS_DEPTH && !file_name.ends_with(".plugins") {
+            find_launchers(&path, names, depth + 1, found);
+        }
+    }
+}
+
+/// Numeric parts of the build directory a launcher is in (e.g. 241.14494.240),
+/// empty for layouts without one
+fn build_of(launcher: &Path) -> Vec<u32> {
+    launcher
+        .ancestors()
+        .filter_map(|dir| dir.file_name()?.to_str())
+        .find(|name| name.split('.').all(|part| part.parse::<u32>().is_ok()))
+        .map(|name| {
+            name.split('.')
+                .filter_map(|part| part.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Gateway's product code for a JetBrains IDE (None for other IDEs)
+pub fn product_code(ide: &IdeType) -> Option<&'static str> {
+    match ide {
+        IdeType::Idea => Some("IU"),
+        IdeType::Pycharm => Some("PY"),
+        IdeType::Webstorm => Some("WS"),
+        IdeType::Phpstorm => Some("PS"),
+        IdeType::Rubymine => Some("RM"),
+        IdeType::Clion => Some("CL"),
+        IdeType::Goland => Some("GO"),
+        IdeType::Rider => Some("RD"),
+        IdeType::Datagrip => Some("DB"),
+        IdeType::Rustrover => Some("RR"),
+        IdeType::Aqua => Some("QA"),
+        _ => None,
+    }
+}
+
+/// Whether a JetBrains IDE backend Gateway deployed is running on `host` (one
+/// started from Gateway's ~/.cache/JetBrains/RemoteDev/dist). A host that can't
+/// be asked counts as having none.
+pub async fn backend_running(host: &str, jump: Option<&str>) -> bool {
+    let mut cmd = tokio::process::Command::new("ssh");
+    cmd.args(hosts::jump_args(jump)).args([
+        "-o",
+        "BatchMode=yes",
+        host,
+        "pgrep -f RemoteDev/dist/ >/dev/null",
+    ]);
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    matches!(
+        processes::output_with_timeout(cmd, None, BACKEND_PROBE_TIMEOUT, false).await,
+        Ok((output, false)) if output.status.success()
+    )
+}
+
+/// `jetbrains-gateway://` link that makes Gateway connect to `host` over SSH and
+/// open `path` there. With `product` (a `product_code`) Gateway deploys that IDE
+/// and starts its backend; without, it joins a backend already running. Gateway
+/// doesn't read ~/.ssh/config, so an alias is resolved to its HostName, User and
+/// Port.
+pub fn gateway_url(host: &str, path: &str, product: Option<&str>) -> String {
+    let (hostname, user, port) = ssh_config::connection(host);
+    let deploy = match product {
+        Some(product) => format!("deploy=true&productCode={}", product),
+        None => "deploy=false".to_string(),
+    };
+    format!(
+        "jetbrains-gateway://connect#type=ssh&{}&host={}&port={}&user={}&projectPath={}",
+        deploy,
+        templates::percent_encode(&hostname, ""),
+        port,
+        templates::percent_encode(&user, ""),
+        templates::percent_encode(path, "")
+    )
+}
@@ -1,14 +1,39 @@
+use crate::git;
+use crate::markdown;
 use crate::models::*;
+use crate::save_worker::SaveWorker;
+use crate::schema;
+use crate::todos;
 use chrono::Utc;
 use log::info;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::hash::{DefaultHasher, Hasher};
 use std::io::Write;
-use std::path::PathBuf;
-use std::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use uuid::Uuid;
 
+/// Setting key for the item content size (bytes) above which content is stored as a blob
+const ITEM_BLOB_THRESHOLD_KEY: &str = "itemBlobThreshold";
+const DEFAULT_ITEM_BLOB_THRESHOLD: usize = 256 * 1024;
+
+/// Setting key for the project file size (bytes) above which a project is split into parts
+const PROJECT_SPLIT_THRESHOLD_KEY: &str = "projectSplitThreshold";
+const DEFAULT_PROJECT_SPLIT_THRESHOLD: usize = 1024 * 1024;
+
+/// Number of pre-import snapshots kept (older ones are pruned)
+const IMPORT_SNAPSHOTS_KEPT: usize = 10;
+
+/// Todo events kept per project for statistics (older ones are dropped)
+const MAX_TODO_EVENTS: usize = 5000;
+
+/// Setting key for git-backed mode (every save is committed to a repo in the data dir)
+const GIT_BACKED_STORE_KEY: &str = "gitBackedStore";
+
 /// Project info stored in metadata (id + name for quick access)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectInfo {
@@ -16,6 +41,15 @@ pub struct ProjectInfo {
     pub name: String,
 }
 
+/// Project list entry cached in cache/project_list.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedProject {
+    project: Project,
+    /// Newest modification time (ms since epoch) of the project's files when cached,
+    /// so entries for files changed elsewhere (e.g. by sync) are dropped on load
+    modified_ms: u64,
+}
+
 /// Metadata stored in metadata.json
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Metadata {
@@ -30,23 +64,13 @@ pub struct Metadata {
     pub global_settings: HashMap<String, String>,
 }
 
-/// Legacy project data format (for migration)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct LegacyProjectData {
-    pub id: String,
-    pub name: String,
-    pub description: String,
-    pub metadata: ProjectMetadata,
-    pub items: Vec<Item>,
-    pub todos: Vec<LegacyTodoItem>,
-    pub file_cards: Vec<FileCard>,
-    pub created_at: String,
-    pub updated_at: String,
-}
-
-/// Full project data stored in projects/{id}.json
+/// Full project data stored in projects/{id}.json, or split into
+/// projects/{id}/{project,items,file_cards}.json + todos.md for large projects
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectData {
+    /// Schema version of the project file (see `schema::CURRENT_VERSION`)
+    #[serde(default)]
+    pub version: u32,
     pub id: String,
     pub name: String,
     pub description: String,
@@ -54,11 +78,146 @@ pub struct ProjectData {
     pub items: Vec<Item>,
     #[serde(default)]
     pub todos: String,
+    /// History of todo additions/completions, for statistics (oldest first)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub todo_events: Vec<TodoEvent>,
+    /// Timer entries, oldest first (at most one running, across all projects)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time_entries: Vec<TimeEntry>,
     pub file_cards: Vec<FileCard>,
+    /// Saved arrangements of the file cards, by name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub canvas_layouts: Vec<CanvasLayout>,
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// Header of a split project: everything except items, todos and file cards
+/// (todo history, time entries and canvas layouts live here, as they change together
+/// with updated_at)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProjectHeader {
+    version: u32,
+    id: String,
+    name: String,
+    description: String,
+    metadata: ProjectMetadata,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    todo_events: Vec<TodoEvent>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    time_entries: Vec<TimeEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    canvas_layouts: Vec<CanvasLayout>,
+    created_at: String,
+    updated_at: String,
+}
+
+/// Parts of a project changed since it was last written
+#[derive(Debug, Default)]
+struct DirtyParts {
+    header: bool,
+    items: bool,
+    todos: bool,
+    file_cards: bool,
+    /// Items whose content changed (their blobs must be rewritten)
+    contents: HashSet<String>,
+}
+
+impl DirtyParts {
+    /// Everything changed (new project or no previous version known)
+    fn all(project: &ProjectData) -> Self {
+        Self {
+            header: true,
+            items: true,
+            todos: true,
+            file_cards: true,
+            contents: project.items.iter().map(|i| i.id.clone()).collect(),
+        }
+    }
+
+    /// Compare a project's part hashes with those of its last written version
+    fn between(written: Option<&PartHashes>, hashes: &PartHashes) -> Self {
+        let Some(prev) = written else {
+            return Self {
+                header: true,
+                items: true,
+                todos: true,
+                file_cards: true,
+                contents: hashes.contents.keys().cloned().collect(),
+            };
+        };
+        Self {
+            header: prev.header != hashes.header,
+            items: prev.items != hashes.items,
+            todos: prev.todos != hashes.todos,
+            file_cards: prev.file_cards != hashes.file_cards,
+            contents: hashes
+                .contents
+                .iter()
+                .filter(|(id, hash)| prev.contents.get(*id) != Some(*hash))
+                .map(|(id, _)| id.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Hashes of a project's parts, computed by the save worker and compared with
+/// those of the version last written to tell which parts need rewriting
+#[derive(Debug)]
+struct PartHashes {
+    header: u64,
+    items: u64,
+    todos: u64,
+    file_cards: u64,
+    /// Item ID -> hash of its content
+    contents: HashMap<String, u64>,
+}
+
+impl PartHashes {
+    fn of(project: &ProjectData) -> Self {
+        let header = (
+            project.version,
+            &project.name,
+            &project.description,
+            &project.metadata,
+            &project.todo_events,
+            &project.time_entries,
+            &project.canvas_layouts,
+            &project.created_at,
+            &project.updated_at,
+        );
+        Self {
+            header: hash_json(&header),
+            items: hash_json(&project.items),
+            todos: hash_json(&project.todos),
+            file_cards: hash_json(&project.file_cards),
+            contents: project
+                .items
+                .iter()
+                .map(|item| (item.id.clone(), hash_json(&item.content)))
+                .collect(),
+        }
+    }
+}
+
+/// Hash of a value's JSON form, streamed into the hasher without building it
+fn hash_json<T: Serialize + ?Sized>(value: &T) -> u64 {
+    struct HashWriter(DefaultHasher);
+    impl Write for HashWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut writer = HashWriter(DefaultHasher::new());
+    let _ = serde_json::to_writer(&mut writer, value);
+    writer.0.finish()
+}
+
 impl ProjectData {
     /// Convert to Project (without items for list views)
     pub fn to_project(&self) -> Project {
@@ -69,6 +228,8 @@ impl ProjectData {
             metadata: self.metadata.clone(),
             created_at: self.created_at.clone(),
             updated_at: self.updated_at.clone(),
+            last_opened_at: None,
+            todo_progress: None,
             items: None,
         }
     }
@@ -82,6 +243,8 @@ impl ProjectData {
             metadata: self.metadata.clone(),
             created_at: self.created_at.clone(),
             updated_at: self.updated_at.clone(),
+            last_opened_at: None,
+            todo_progress: None,
             items: Some(self.items.clone()),
         }
     }
@@ -89,11 +252,23 @@ impl ProjectData {
 
 /// JSON-based storage for projects and settings
 pub struct JsonStore {
-    data_path: PathBuf,
+    data_path: RwLock<PathBuf>,
     metadata: RwLock<Metadata>,
     projects_cache: RwLock<HashMap<String, ProjectData>>,
+    /// Item / file card ID -> owning project ID, filled as projects are loaded or saved
+    item_index: RwLock<HashMap<String, String>>,
+    /// Project id -> list entry with todo progress, kept machine-local in
+    /// cache/project_list.json so listing projects doesn't load every project file
+    project_list: RwLock<HashMap<String, Project>>,
     /// Track when we last loaded the metadata (for external change detection)
     last_metadata_mtime: RwLock<Option<std::time::SystemTime>>,
+    /// Writes project files and git commits off the command thread
+    writer: SaveWorker,
+    /// Part hashes per project as last loaded or written, owned by the writer
+    written_parts: Arc<Mutex<HashMap<String, PartHashes>>>,
+    /// Set while the SQLite migration fills the data directory; saves are refused
+    /// until then, as the migration would overwrite them
+    migrating: AtomicBool,
 }
 
 impl JsonStore {
@@ -110,7 +285,7 @@ impl JsonStore {
 
         // Load metadata
         let metadata_path = data_path.join("metadata.json");
-        let (metadata, mtime, needs_save) = if metadata_path.exists() {
+        let (metadata, mut mtime, needs_save) = if metadata_path.exists() {
             let content = fs::read_to_string(&metadata_path)
                 .map_err(|e| format!("Failed to read metadata.json: {}", e))?;
             let mtime = fs::metadata(&metadata_path)
@@ -140,10 +315,27 @@ impl JsonStore {
                 false
             };
 
-            (metadata, mtime, needs_save)
+            // Bring the schema version up to date (metadata has no structural migrations yet)
+            let needs_version_bump = metadata.version < schema::CURRENT_VERSION;
+            if needs_version_bump {
+                Self::write_backup(
+                    &data_path,
+                    &format!("metadata.v{}.json", metadata.version),
+                    &content,
+                )?;
+                metadata.version = schema::CURRENT_VERSION;
+            } else if metadata.version > schema::CURRENT_VERSION {
+                log::warn!(
+                    "metadata.json has schema version {} (newer than supported {})",
+                    metadata.version,
+                    schema::CURRENT_VERSION
+                );
+            }
+
+            (metadata, mtime, needs_save || needs_version_bump)
         } else {
             let metadata = Metadata {
-                version: 1,
+                version: schema::CURRENT_VERSION,
                 project_ids: Vec::new(),
                 projects: Vec::new(),
                 global_settings: HashMap::new(),
@@ -159,37 +351,66 @@ impl JsonStore {
         // Save migrated metadata if needed
         if needs_save {
             Self::write_json_atomic(&metadata_path, &metadata)?;
+            mtime = fs::metadata(&metadata_path)
+                .ok()
+                .and_then(|m| m.modified().ok());
             info!("Migration complete: {} projects", metadata.projects.len());
         }
 
         info!("JsonStore initialized at {:?}", data_path);
 
         Ok(Self {
-            data_path,
+            data_path: RwLock::new(data_path),
             metadata: RwLock::new(metadata),
             projects_cache: RwLock::new(HashMap::new()),
+            item_index: RwLock::new(HashMap::new()),
+            project_list: RwLock::new(Self::load_project_list(&data_path)),
             last_metadata_mtime: RwLock::new(mtime),
+            writer: SaveWorker::new(),
+            written_parts: Arc::new(Mutex::new(HashMap::new())),
+            migrating: AtomicBool::new(false),
         })
     }
 
+    /// Refuse (or with false, allow again) saves while a migration writes the data
+    /// directory
+    pub fn set_migrating(&self, migrating: bool) {
+        self.migrating.store(migrating, Ordering::SeqCst);
+    }
+
+    fn ensure_writable(&self) -> Result<(), String> {
+        if self.migrating.load(Ordering::SeqCst) {
+            return Err(
+                "Your data is still being migrated, try again once it has finished".to_string(),
+            );
+        }
+        Ok(())
+    }
+
     /// Get the data path
-    #[allow(dead_code)]
-    pub fn data_path(&self) -> &PathBuf {
-        &self.data_path
+    pub fn data_path(&self) -> PathBuf {
+        self.data_path.read().unwrap().clone()
     }
 
     /// Write JSON to file atomically (write to temp, then rename)
     fn write_json_atomic<T: Serialize>(path: &PathBuf, data: &T) -> Result<(), String> {
         let json = serde_json::to_string_pretty(data)
             .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        Self::write_string_atomic(path, &json)
+    }
 
+    /// Write text to file atomically (write to temp, then rename)
+    fn write_string_atomic(path: &PathBuf, json: &str) -> Result<(), String> {
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)
                 .map_err(|e| format!("Failed to create parent directory: {}", e))?;
         }
 
-        let temp_path = path.with_extension("json.tmp");
+        let temp_path = path.with_extension(format!(
+            "{}.tmp",
+            path.extension().and_then(|e| e.to_str()).unwrap_or("json")
+        ));
 
         // Write to temp file
         let mut file = fs::File::create(&temp_path)
@@ -206,39 +427,53 @@ impl JsonStore {
         Ok(())
     }
 
+    /// Write the original content of a file to `{data_path}/backups/{name}` before migrating it.
+    /// Existing backups are never overwritten so the oldest copy is preserved.
+    fn write_backup(data_path: &Path, name: &str, content: &str) -> Result<(), String> {
+        let backups_dir = data_path.join("backups");
+        fs::create_dir_all(&backups_dir)
+            .map_err(|e| format!("Failed to create backups directory: {}", e))?;
+
+        let backup_path = backups_dir.join(name);
+        if !backup_path.exists() {
+            fs::write(&backup_path, content)
+                .map_err(|e| format!("Failed to write backup {}: {}", name, e))?;
+        }
+        Ok(())
+    }
+
     /// Save metadata
     fn save_metadata(&self) -> Result<(), String> {
-        let metadata = self.metadata.read().unwrap();
-        let path = self.data_path.join("metadata.json");
-        Self::write_json_atomic(&path, &*metadata)
+        self.ensure_writable()?;
+        {
+            let metadata = self.metadata.read().unwrap();
+            let path = self.data_path().join("metadata.json");
+            Self::write_json_atomic(&path, &*metadata)?;
+        }
+        self.commit_if_git_backed("Update metadata");
+        Ok(())
     }
 
-    /// Get project file path
-    fn project_path(&self, id: &str) -> PathBuf {
-        self.data_path.join("projects").join(format!("{}.json", id))
+    /// Single-file layout path: projects/{id}.json
+    fn project_file(data_path: &Path, id: &str) -> PathBuf {
+        data_path.join("projects").join(format!("{}.json", id))
     }
 
-    /// Convert legacy Vec<LegacyTodoItem> to markdown string
-    fn convert_todos_to_markdown(todos: &[LegacyTodoItem]) -> String {
-        if todos.is_empty() {
-            return String::new();
-        }
-
-        let mut sorted_todos = todos.to_vec();
-        sorted_todos.sort_by_key(|t| t.order);
+    /// Split layout directory: projects/{id}/
+    fn project_dir(data_path: &Path, id: &str) -> PathBuf {
+        data_path.join("projects").join(id)
+    }
 
-        sorted_todos
-            .iter()
-            .map(|todo| {
-                let indent = "  ".repeat(todo.indent_level as usize);
-                let checkbox = if todo.completed { "[x]" } else { "[ ]" };
-                format!("{}- {} {}", indent, checkbox, todo.content)
-            })
-            .collect::<Vec<_>>()
-            .join("\n")
+    /// Check whether a project has files on disk (in either layout)
+    fn project_exists(&self, id: &str) -> bool {
+        let data_path = self.data_path();
+        Self::project_file(&data_path, id).exists()
+            || Self::project_dir(&data_path, id)
+                .join("project.json")
+                .exists()
     }
 
-    /// Load project from file (with automatic migration from legacy format)
+    /// Load project from file (with automatic schema migration)
     fn load_project(&self, id: &str) -> Result<ProjectData, String> {
         // Check cache first
         {
@@ -249,64 +484,392 @@ impl JsonStore {
         }
 
         // Load from file
-        let path = self.project_path(id);
-        let content = fs::read_to_string(&path)
-            .map_err(|e| format!("Failed to read project file: {}", e))?;
-
-        // Try to parse as new format first
-        let data: ProjectData = match serde_json::from_str(&content) {
-            Ok(data) => data,
-            Err(_) => {
-                // Try legacy format with Vec<LegacyTodoItem>
-                let legacy: LegacyProjectData = serde_json::from_str(&content)
-                    .map_err(|e| format!("Failed to parse project file: {}", e))?;
-
-                // Convert to new format
-                let todos_markdown = Self::convert_todos_to_markdown(&legacy.todos);
-
-                let migrated = ProjectData {
-                    id: legacy.id,
-                    name: legacy.name,
-                    description: legacy.description,
-                    metadata: legacy.metadata,
-                    items: legacy.items,
-                    todos: todos_markdown,
-                    file_cards: legacy.file_cards,
-                    created_at: legacy.created_at,
-                    updated_at: legacy.updated_at,
-                };
-
-                // Save migrated data
-                Self::write_json_atomic(&path, &migrated)?;
-                info!("Migrated project {} to new todos format", id);
-
-                migrated
-            }
-        };
+        let data_path = self.data_path();
+        let (content, mut value) = Self::read_project_value(&data_path, id)?;
+
+        // Upgrade older schema versions before deserializing
+        let from_version = schema::migrate_project(&mut value)?;
+        let mut data: ProjectData = serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse project file: {}", e))?;
+
+        Self::inline_blobs(&data_path, &mut data);
+
+        if from_version < schema::CURRENT_VERSION {
+            // Keep the original file around in case the migration lost something
+            Self::write_backup(
+                &data_path,
+                &format!("{}.v{}.json", id, from_version),
+                &content,
+            )?;
+            Self::write_project_files(
+                &data_path,
+                data.clone(),
+                &DirtyParts::all(&data),
+                self.get_setting_or(ITEM_BLOB_THRESHOLD_KEY, DEFAULT_ITEM_BLOB_THRESHOLD),
+                self.get_setting_or(PROJECT_SPLIT_THRESHOLD_KEY, DEFAULT_PROJECT_SPLIT_THRESHOLD),
+            )?;
+            info!(
+                "Migrated project {} from schema v{} to v{}",
+                id,
+                from_version,
+                schema::CURRENT_VERSION
+            );
+        }
 
         // Store in cache
+        self.index_project(&data);
         self.projects_cache
             .write()
             .unwrap()
             .insert(id.to_string(), data.clone());
+        self.seed_written_parts(&data);
 
         Ok(data)
     }
 
-    /// Save project to file
-    fn save_project(&self, project: &ProjectData) -> Result<(), String> {
-        let path = self.project_path(&project.id);
-        Self::write_json_atomic(&path, project)?;
+    /// Record a project as read from disk as its last written version, so its next
+    /// save rewrites only what changed. Hashed on the writer, after pending writes.
+    fn seed_written_parts(&self, project: &ProjectData) {
+        let written_parts = self.written_parts.clone();
+        let data = project.clone();
+        self.writer.submit(
+            None,
+            Box::new(move || {
+                let hashes = PartHashes::of(&data);
+                written_parts
+                    .lock()
+                    .unwrap()
+                    .insert(data.id.clone(), hashes);
+                Ok(())
+            }),
+        );
+    }
 
+    /// Save project: the cache is updated immediately, the file is written
+    /// by the background worker (see `flush` to wait for it)
+    fn save_project(&self, project: &ProjectData) -> Result<(), String> {
+        self.ensure_writable()?;
         // Update cache
+        self.index_project(project);
         self.projects_cache
             .write()
             .unwrap()
             .insert(project.id.clone(), project.clone());
 
+        let data_path = self.data_path();
+        let data = project.clone();
+        let written_parts = self.written_parts.clone();
+        let blob_threshold =
+            self.get_setting_or(ITEM_BLOB_THRESHOLD_KEY, DEFAULT_ITEM_BLOB_THRESHOLD);
+        let split_threshold =
+            self.get_setting_or(PROJECT_SPLIT_THRESHOLD_KEY, DEFAULT_PROJECT_SPLIT_THRESHOLD);
+        self.writer.submit(
+            Some(format!("project:{}", project.id)),
+            Box::new(move || {
+                // Only the parts that changed since the last write are rewritten; a
+                // failed write keeps the old hashes so the next save retries them
+                let hashes = PartHashes::of(&data);
+                let dirty =
+                    DirtyParts::between(written_parts.lock().unwrap().get(&data.id), &hashes);
+                let id = data.id.clone();
+                Self::write_project_files(
+                    &data_path,
+                    data,
+                    &dirty,
+                    blob_threshold,
+                    split_threshold,
+                )?;
+                written_parts.lock().unwrap().insert(id, hashes);
+                Ok(())
+            }),
+        );
+
+        self.commit_if_git_backed(&format!("Update project {}", project.name));
+
+        self.project_list
+            .write()
+            .unwrap()
+            .insert(project.id.clone(), Self::list_entry(project));
+        self.save_project_list();
+        Ok(())
+    }
+
+    /// Project without items, with its todo progress (as shown in project lists)
+    fn list_entry(project: &ProjectData) -> Project {
+        let mut entry = project.to_project();
+        entry.todo_progress = Some(todos::todo_progress(&project.todos));
+        entry
+    }
+
+    /// Machine-local project list cache (not synced, unlike metadata.json)
+    fn project_list_path(data_path: &Path) -> PathBuf {
+        data_path.join("cache").join("project_list.json")
+    }
+
+    /// Newest modification time (ms since epoch) of a project's files, in either layout
+    fn project_modified_ms(data_path: &Path, id: &str) -> Option<u64> {
+        let mut files = vec![Self::project_file(data_path, id)];
+        if let Ok(entries) = fs::read_dir(Self::project_dir(data_path, id)) {
+            files.extend(entries.flatten().map(|entry| entry.path()));
+        }
+        files
+            .iter()
+            .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+            .max()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|since| since.as_millis() as u64)
+    }
+
+    /// Load the cached list entries, dropping those whose project files changed since
+    fn load_project_list(data_path: &Path) -> HashMap<String, Project> {
+        let cached: HashMap<String, CachedProject> =
+            fs::read_to_string(Self::project_list_path(data_path))
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default();
+        cached
+            .into_iter()
+            .filter(|(id, entry)| {
+                Self::project_modified_ms(data_path, id) == Some(entry.modified_ms)
+            })
+            .map(|(id, entry)| (id, entry.project))
+            .collect()
+    }
+
+    /// Queue a write of the project list cache (after the project writes queued before it)
+    fn save_project_list(&self) {
+        let data_path = self.data_path();
+        let list = self.project_list.read().unwrap().clone();
+        self.writer.submit(
+            Some("cache:project_list".to_string()),
+            Box::new(move || {
+                let cached: HashMap<String, CachedProject> = list
+                    .into_iter()
+                    .filter_map(|(id, project)| {
+                        let modified_ms = Self::project_modified_ms(&data_path, &id)?;
+                        Some((
+                            id,
+                            CachedProject {
+                                project,
+                                modified_ms,
+                            },
+                        ))
+                    })
+                    .collect();
+                // Only a cache: a failed write shouldn't be reported as a failed save
+                if let Err(e) =
+                    Self::write_json_atomic(&Self::project_list_path(&data_path), &cached)
+                {
+                    log::warn!("Failed to write project list cache: {}", e);
+                }
+                Ok(())
+            }),
+        );
+    }
+
+    /// Read a project's raw JSON value from either layout.
+    /// Returns the original text too (used for migration backups).
+    fn read_project_value(
+        data_path: &Path,
+        id: &str,
+    ) -> Result<(String, serde_json::Value), String> {
+        let dir = Self::project_dir(data_path, id);
+        let header_path = dir.join("project.json");
+
+        if !header_path.exists() {
+            let content = fs::read_to_string(Self::project_file(data_path, id))
+                .map_err(|e| format!("Failed to read project file: {}", e))?;
+            let value = serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse project file: {}", e))?;
+            return Ok((content, value));
+        }
+
+        let read_json = |name: &str| -> Result<serde_json::Value, String> {
+            match fs::read_to_string(dir.join(name)) {
+                Ok(content) => serde_json::from_str(&content)
+                    .map_err(|e| format!("Failed to parse {}: {}", name, e)),
+                Err(_) => Ok(serde_json::Value::Array(Vec::new())),
+            }
+        };
+
+        let mut value = read_json("project.json")?;
+        let obj = value
+            .as_object_mut()
+            .ok_or("Project header is not a JSON object")?;
+        obj.insert("items".to_string(), read_json("items.json")?);
+        obj.insert("file_cards".to_string(), read_json("file_cards.json")?);
+        obj.insert(
+            "todos".to_string(),
+            serde_json::Value::String(fs::read_to_string(dir.join("todos.md")).unwrap_or_default()),
+        );
+
+        let content = serde_json::to_string_pretty(&value)
+            .map_err(|e| format!("Failed to serialize project: {}", e))?;
+        Ok((content, value))
+    }
+
+    /// Write a project to disk. Small projects use the single-file layout; once a
+    /// project outgrows `split_threshold` it moves to the split layout, where only
+    /// the parts marked dirty are rewritten.
+    fn write_project_files(
+        data_path: &Path,
+        project: ProjectData,
+        dirty: &DirtyParts,
+        blob_threshold: usize,
+        split_threshold: usize,
+    ) -> Result<(), String> {
+        let project = Self::externalize_blobs(data_path, project, blob_threshold, &dirty.contents)?;
+        let dir = Self::project_dir(data_path, &project.id);
+        let single_path = Self::project_file(data_path, &project.id);
+
+        if dir.join("project.json").exists() {
+            return Self::write_project_parts(&dir, &project, dirty);
+        }
+
+        let json = serde_json::to_string_pretty(&project)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        if json.len() <= split_threshold {
+            return Self::write_string_atomic(&single_path, &json);
+        }
+
+        info!("Splitting project {} into parts", project.id);
+        Self::write_project_parts(&dir, &project, &DirtyParts::all(&project))?;
+        if single_path.exists() {
+            fs::remove_file(&single_path)
+                .map_err(|e| format!("Failed to remove single-file project: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Write the dirty parts of a split project
+    fn write_project_parts(
+        dir: &Path,
+        project: &ProjectData,
+        dirty: &DirtyParts,
+    ) -> Result<(), String> {
+        if dirty.items {
+            Self::write_json_atomic(&dir.join("items.json"), &project.items)?;
+        }
+        if dirty.file_cards {
+            Self::write_json_atomic(&dir.join("file_cards.json"), &project.file_cards)?;
+        }
+        if dirty.todos {
+            Self::write_string_atomic(&dir.join("todos.md"), &project.todos)?;
+        }
+        // Header last: its presence marks the split layout as complete
+        if dirty.header || !dir.join("project.json").exists() {
+            let header = ProjectHeader {
+                version: project.version,
+                id: project.id.clone(),
+                name: project.name.clone(),
+                description: project.description.clone(),
+                metadata: project.metadata.clone(),
+                todo_events: project.todo_events.clone(),
+                time_entries: project.time_entries.clone(),
+                canvas_layouts: project.canvas_layouts.clone(),
+                created_at: project.created_at.clone(),
+                updated_at: project.updated_at.clone(),
+            };
+            Self::write_json_atomic(&dir.join("project.json"), &header)?;
+        }
         Ok(())
     }
 
+    /// Blob directory for a project's large item contents: blobs/{project_id}/
+    fn blob_dir(data_path: &Path, project_id: &str) -> PathBuf {
+        data_path.join("blobs").join(project_id)
+    }
+
+    /// Move item contents larger than `threshold` into blob files, returning the
+    /// on-disk form of the project. Blobs of items no longer stored that way are removed.
+    fn externalize_blobs(
+        data_path: &Path,
+        mut project: ProjectData,
+        threshold: usize,
+        changed: &HashSet<String>,
+    ) -> Result<ProjectData, String> {
+        let dir = Self::blob_dir(data_path, &project.id);
+        let mut kept = HashSet::new();
+
+        for item in &mut project.items {
+            if item.content.len() <= threshold {
+                continue;
+            }
+            let name = format!("{}.txt", item.id);
+            let blob_path = dir.join(&name);
+            if changed.contains(&item.id) || !blob_path.exists() {
+                fs::create_dir_all(&dir)
+                    .map_err(|e| format!("Failed to create blob directory: {}", e))?;
+                let temp_path = blob_path.with_extension("txt.tmp");
+                fs::write(&temp_path, &item.content)
+                    .map_err(|e| format!("Failed to write blob: {}", e))?;
+                fs::rename(&temp_path, &blob_path)
+                    .map_err(|e| format!("Failed to rename blob: {}", e))?;
+            }
+            item.content = String::new();
+            item.content_blob = Some(name.clone());
+            kept.insert(name);
+        }
+
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.ends_with(".txt") && !kept.contains(&name) {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+
+        Ok(project)
+    }
+
+    /// Read blob-backed item contents back into the items (in-memory form)
+    fn inline_blobs(data_path: &Path, project: &mut ProjectData) {
+        let dir = Self::blob_dir(data_path, &project.id);
+        for item in &mut project.items {
+            let Some(name) = item.content_blob.take() else {
+                continue;
+            };
+            match fs::read_to_string(dir.join(&name)) {
+                Ok(content) => item.content = content,
+                Err(e) => log::warn!("Missing content blob {} for item {}: {}", name, item.id, e),
+            }
+        }
+    }
+
+    /// Wait until all queued project writes are on disk.
+    /// Returns the last background write error, if any.
+    pub fn flush(&self) -> Result<(), String> {
+        self.writer.flush()
+    }
+
+    /// Point the IDs of a project's items and file cards at the project in the item index
+    fn index_project(&self, project: &ProjectData) {
+        let mut index = self.item_index.write().unwrap();
+        index.retain(|_, project_id| *project_id != project.id);
+        for id in project
+            .items
+            .iter()
+            .map(|i| &i.id)
+            .chain(project.file_cards.iter().map(|c| &c.id))
+        {
+            index.insert(id.clone(), project.id.clone());
+        }
+    }
+
+    /// Find the project owning an item or file card.
+    /// Falls back to loading every project (which fills the index) on a miss.
+    fn owning_project(&self, id: &str) -> Option<String> {
+        if let Some(project_id) = self.item_index.read().unwrap().get(id) {
+            return Some(project_id.clone());
+        }
+
+        for project_id in self.get_project_ids() {
+            let _ = self.load_project(&project_id);
+        }
+        self.item_index.read().unwrap().get(id).cloned()
+    }
+
     /// Helper to get all project IDs
     fn get_project_ids(&self) -> Vec<String> {
         let metadata = self.metadata.read().unwrap();
@@ -327,18 +890,39 @@ impl JsonStore {
 
     /// Get all projects (without items)
     pub fn get_all_projects(&self) -> Result<Vec<Project>, String> {
-        let metadata = self.metadata.read().unwrap();
-        let mut projects = Vec::new();
+        let ids: Vec<String> = {
+            let metadata = self.metadata.read().unwrap();
+            metadata.projects.iter().map(|p| p.id.clone()).collect()
+        };
 
-        for info in &metadata.projects {
-            match self.load_project(&info.id) {
-                Ok(data) => projects.push(data.to_project()),
+        let mut projects = Vec::new();
+        let mut uncached = false;
+        for id in ids {
+            let cached = self.project_list.read().unwrap().get(&id).cloned();
+            if let Some(project) = cached {
+                projects.push(project);
+                continue;
+            }
+            // Only projects missing from the list cache are loaded
+            match self.load_project(&id) {
+                Ok(data) => {
+                    let project = Self::list_entry(&data);
+                    self.project_list
+                        .write()
+                        .unwrap()
+                        .insert(id, project.clone());
+                    uncached = true;
+                    projects.push(project);
+                }
                 Err(e) => {
                     // Log error but continue - don't fail entire list for one bad project
-                    log::warn!("Failed to load project {}: {}", info.id, e);
+                    log::warn!("Failed to load project {}: {}", id, e);
                 }
             }
         }
+        if uncached {
+            self.save_project_list();
+        }
 
         // Sort by updated_at descending
         projects.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
@@ -360,6 +944,36 @@ impl JsonStore {
         }
     }
 
+    /// Re-read a single project file from disk, replacing its cached copy.
+    /// Returns None if the project no longer exists.
+    pub fn reload_project(&self, id: &str) -> Result<Option<Project>, String> {
+        self.flush()?;
+        self.projects_cache.write().unwrap().remove(id);
+        self.project_list.write().unwrap().remove(id);
+
+        if !self.project_exists(id) {
+            return Ok(None);
+        }
+        let data = self.load_project(id)?;
+
+        // Keep the name in metadata in sync with an externally renamed project
+        let renamed = {
+            let mut meta = self.metadata.write().unwrap();
+            match meta.projects.iter_mut().find(|p| p.id == id) {
+                Some(info) if info.name != data.name => {
+                    info.name = data.name.clone();
+                    true
+                }
+                _ => false,
+            }
+        };
+        if renamed {
+            self.save_metadata()?;
+        }
+
+        Ok(Some(data.to_project_with_items()))
+    }
+
     /// Create a new project
     pub fn create_project(
         &self,
@@ -371,12 +985,16 @@ impl JsonStore {
         let timestamp = Self::now();
 
         let project_data = ProjectData {
+            version: schema::CURRENT_VERSION,
             id: id.clone(),
             name: name.to_string(),
             description: description.to_string(),
             metadata,
             items: Vec::new(),
             todos: String::new(),
+            todo_events: Vec::new(),
+            time_entries: Vec::new(),
+            canvas_layouts: Vec::new(),
             file_cards: Vec::new(),
             created_at: timestamp.clone(),
             updated_at: timestamp,
@@ -398,6 +1016,66 @@ impl JsonStore {
         Ok(project_data.to_project())
     }
 
+    /// Deep-copy a project (items, todos, file cards, metadata) under a new name,
+    /// with fresh IDs and timestamps
+    pub fn duplicate_project(&self, id: &str, new_name: &str) -> Result<Project, String> {
+        let source = self.load_project(id)?;
+        let new_id = Self::new_id();
+        let timestamp = Self::now();
+
+        let items = source
+            .items
+            .into_iter()
+            .map(|item| Item {
+                id: Self::new_id(),
+                project_id: new_id.clone(),
+                content_blob: None,
+                created_at: timestamp.clone(),
+                updated_at: timestamp.clone(),
+                ..item
+            })
+            .collect();
+        let file_cards = source
+            .file_cards
+            .into_iter()
+            .map(|card| FileCard {
+                id: Self::new_id(),
+                project_id: new_id.clone(),
+                created_at: timestamp.clone(),
+                updated_at: timestamp.clone(),
+                ..card
+            })
+            .collect();
+
+        let project_data = ProjectData {
+            version: schema::CURRENT_VERSION,
+            id: new_id.clone(),
+            name: new_name.to_string(),
+            description: source.description,
+            metadata: source.metadata,
+            items,
+            todos: source.todos,
+            todo_events: Vec::new(),
+            time_entries: Vec::new(),
+            canvas_layouts: Vec::new(),
+            file_cards,
+            created_at: timestamp.clone(),
+            updated_at: timestamp,
+        };
+
+        self.save_project(&project_data)?;
+        {
+            let mut meta = self.metadata.write().unwrap();
+            meta.projects.push(ProjectInfo {
+                id: new_id,
+                name: new_name.to_string(),
+            });
+        }
+        self.save_metadata()?;
+
+        Ok(project_data.to_project())
+    }
+
     /// Update a project
     pub fn update_project(
         &self,
@@ -449,14 +1127,32 @@ impl JsonStore {
             }
         }
 
-        // Delete project file
-        let path = self.project_path(id);
+        // Delete project file (after any queued write that would recreate it)
+        self.flush()?;
+        let data_path = self.data_path();
+        let path = Self::project_file(&data_path, id);
         if path.exists() {
             fs::remove_file(&path).map_err(|e| format!("Failed to delete project file: {}", e))?;
         }
+        let project_dir = Self::project_dir(&data_path, id);
+        if project_dir.exists() {
+            fs::remove_dir_all(&project_dir)
+                .map_err(|e| format!("Failed to delete project directory: {}", e))?;
+        }
+        let blob_dir = Self::blob_dir(&data_path, id);
+        if blob_dir.exists() {
+            let _ = fs::remove_dir_all(&blob_dir);
+        }
 
         // Remove from cache
         self.projects_cache.write().unwrap().remove(id);
+        self.written_parts.lock().unwrap().remove(id);
+        self.project_list.write().unwrap().remove(id);
+        self.save_project_list();
+        self.item_index
+            .write()
+            .unwrap()
+            .retain(|_, project_id| project_id != id);
 
         // Update metadata
         {
@@ -485,6 +1181,13 @@ impl JsonStore {
         command_mode: Option<CommandMode>,
         command_cwd: Option<&str>,
         command_host: Option<&str>,
+        database_client: Option<DatabaseClient>,
+        language: Option<&str>,
+        tmux_session: Option<&str>,
+        ports: Option<Vec<u16>>,
+        command_shell: Option<CommandShell>,
+        wsl_distro: Option<&str>,
+        custom_agent_id: Option<&str>,
     ) -> Result<Item, String> {
         let mut project_data = self.load_project(project_id)?;
 
@@ -500,6 +1203,18 @@ impl JsonStore {
             .unwrap_or(-1)
             + 1;
 
+        // Command-like items given neither a cwd nor a host inherit the project's default pair
+        let (command_cwd, command_host) =
+            if item_type.runs_commands() && command_cwd.is_none() && command_host.is_none() {
+                let metadata = &project_data.metadata;
+                (metadata.default_cwd.clone(), metadata.default_host.clone())
+            } else {
+                (
+                    command_cwd.map(|s| s.to_string()),
+                    command_host.map(|s| s.to_string()),
+                )
+            };
+
         let item = Item {
             id,
             project_id: project_id.to_string(),
@@ -512,11 +1227,19 @@ impl JsonStore {
             coding_agent_args: coding_agent_args.map(|s| s.to_string()),
             coding_agent_env: coding_agent_env.map(|s| s.to_string()),
             command_mode,
-            command_cwd: command_cwd.map(|s| s.to_string()),
-            command_host: command_host.map(|s| s.to_string()),
+            command_cwd,
+            command_host,
+            database_client,
+            language: language.map(|s| s.to_string()),
+            tmux_session: tmux_session.map(|s| s.to_string()),
+            ports,
+            command_shell,
+            wsl_distro: wsl_distro.map(|s| s.to_string()),
+            custom_agent_id: custom_agent_id.map(|s| s.to_string()),
             order,
             created_at: timestamp.clone(),
             updated_at: timestamp.clone(),
+            ..Default::default()
         };
 
         project_data.items.push(item.clone());
@@ -541,84 +1264,253 @@ impl JsonStore {
         command_mode: Option<Option<CommandMode>>,
         command_cwd: Option<Option<&str>>,
         command_host: Option<Option<&str>>,
+        database_client: Option<Option<DatabaseClient>>,
+        language: Option<Option<&str>>,
+        tmux_session: Option<Option<&str>>,
+        ports: Option<Option<Vec<u16>>>,
+        command_shell: Option<Option<CommandShell>>,
+        wsl_distro: Option<Option<&str>>,
+        custom_agent_id: Option<Option<&str>>,
         order: Option<i32>,
     ) -> Result<Option<Item>, String> {
         // Find which project contains this item
-        let project_ids = self.get_project_ids();
-
-        for project_id in &project_ids {
-            let mut project_data = match self.load_project(project_id) {
-                Ok(data) => data,
-                Err(_) => continue,
-            };
-
-            if let Some(item) = project_data.items.iter_mut().find(|i| i.id == id) {
-                if let Some(t) = title {
-                    item.title = t.to_string();
-                }
-                if let Some(c) = content {
-                    item.content = c.to_string();
-                }
-                if let Some(it) = ide_type {
-                    item.ide_type = it;
-                }
-                if let Some(rit) = remote_ide_type {
-                    item.remote_ide_type = rit;
-                }
-                if let Some(cat) = coding_agent_type {
-                    item.coding_agent_type = cat;
-                }
-                if let Some(caa) = coding_agent_args {
-                    item.coding_agent_args = caa.map(|s| s.to_string());
-                }
-                if let Some(cae) = coding_agent_env {
-                    item.coding_agent_env = cae.map(|s| s.to_string());
-                }
-                if let Some(cm) = command_mode {
-                    item.command_mode = cm;
-                }
-                if let Some(cc) = command_cwd {
-                    item.command_cwd = cc.map(|s| s.to_string());
-                }
-                if let Some(ch) = command_host {
-                    item.command_host = ch.map(|s| s.to_string());
-                }
-                if let Some(o) = order {
-                    item.order = o;
-                }
-                item.updated_at = Self::now();
-                project_data.updated_at = item.updated_at.clone();
+        let Some(project_id) = self.owning_project(id) else {
+            return Ok(None);
+        };
+        let mut project_data = self.load_project(&project_id)?;
 
-                let updated_item = item.clone();
-                self.save_project(&project_data)?;
-                return Ok(Some(updated_item));
-            }
+        let Some(item) = project_data.items.iter_mut().find(|i| i.id == id) else {
+            return Ok(None);
+        };
+        if let Some(t) = title {
+            item.title = t.to_string();
+        }
+        if let Some(c) = content {
+            item.content = c.to_string();
+        }
+        if let Some(it) = ide_type {
+            item.ide_type = it;
+        }
+        if let Some(rit) = remote_ide_type {
+            item.remote_ide_type = rit;
+        }
+        if let Some(cat) = coding_agent_type {
+            item.coding_agent_type = cat;
+        }
+        if let Some(caa) = coding_agent_args {
+            item.coding_agent_args = caa.map(|s| s.to_string());
+        }
+        if let Some(cae) = coding_agent_env {
+            item.coding_agent_env = cae.map(|s| s.to_string());
+        }
+        if let Some(cm) = command_mode {
+            item.command_mode = cm;
         }
+        if let Some(cc) = command_cwd {
+            item.command_cwd = cc.map(|s| s.to_string());
+        }
+        if let Some(ch) = command_host {
+            item.command_host = ch.map(|s| s.to_string());
+        }
+        if let Some(dc) = database_client {
+            item.database_client = dc;
+        }
+        if let Some(l) = language {
+            item.language = l.map(|s| s.to_string());
+        }
+        if let Some(ts) = tmux_session {
+            item.tmux_session = ts.map(|s| s.to_string());
+        }
+        if let Some(p) = ports {
+            item.ports = p;
+        }
+        if let Some(cs) = command_shell {
+            item.command_shell = cs;
+        }
+        if let Some(wd) = wsl_distro {
+            item.wsl_distro = wd.map(|s| s.to_string());
+        }
+        if let Some(ca) = custom_agent_id {
+            item.custom_agent_id = ca.map(|s| s.to_string());
+        }
+        if let Some(o) = order {
+            item.order = o;
+        }
+        item.updated_at = Self::now();
+        project_data.updated_at = item.updated_at.clone();
 
-        Ok(None)
+        let updated_item = item.clone();
+        self.save_project(&project_data)?;
+        Ok(Some(updated_item))
     }
 
-    /// Delete an item
-    pub fn delete_item(&self, id: &str) -> Result<bool, String> {
-        let project_ids = self.get_project_ids();
+    /// Find an item by ID across all projects
+    pub fn find_item(&self, id: &str) -> Option<Item> {
+        let project_id = self.owning_project(id)?;
+        self.load_project(&project_id)
+            .ok()?
+            .items
+            .into_iter()
+            .find(|i| i.id == id)
+    }
 
-        for project_id in &project_ids {
-            let mut project_data = match self.load_project(project_id) {
-                Ok(data) => data,
-                Err(_) => continue,
-            };
+    /// Launched items ordered by launch count, from one project or all of them
+    pub fn get_most_used_items(
+        &self,
+        project_id: Option<&str>,
+        launches: &HashMap<String, ItemLaunches>,
+        limit: usize,
+    ) -> Result<Vec<Item>, String> {
+        let project_ids = match project_id {
+            Some(id) => vec![id.to_string()],
+            None => self.get_project_ids(),
+        };
+        let mut items = Vec::new();
+        for id in project_ids {
+            let project_data = self.load_project(&id)?;
+            items.extend(
+                project_data
+                    .items
+                    .into_iter()
+                    .filter(|item| launches.contains_key(&item.id)),
+            );
+        }
+        ItemSort::MostUsed.apply(&mut items, launches);
+        items.truncate(limit);
+        Ok(items)
+    }
+
+    /// Full-text search over items in every project: each whitespace-separated term
+    /// must appear (case-insensitively) in the title, content or snippet language
+    pub fn search_items(
+        &self,
+        query: &str,
+        item_type: Option<ItemType>,
+    ) -> Result<Vec<Item>, String> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut matches = Vec::new();
+        for id in self.get_project_ids() {
+            let project_data = self.load_project(&id)?;
+            matches.extend(project_data.items.into_iter().filter(|item| {
+                if item_type.as_ref().is_some_and(|t| *t != item.item_type) {
+                    return false;
+                }
+                let text = format!(
+                    "{}\n{}\n{}",
+                    item.title,
+                    item.content,
+                    item.language.as_deref().unwrap_or_default()
+                )
+                .to_lowercase();
+                terms.iter().all(|term| text.contains(term))
+            }));
+        }
+        Ok(matches)
+    }
+
+    /// `search_items` with each match paired with its project's name
+    pub fn search_items_with_projects(
+        &self,
+        query: &str,
+        item_type: Option<ItemType>,
+    ) -> Result<Vec<ItemSearchResult>, String> {
+        let items = self.search_items(query, item_type)?;
+        let metadata = self.metadata.read().unwrap();
+        let names: HashMap<&str, &str> = metadata
+            .projects
+            .iter()
+            .map(|p| (p.id.as_str(), p.name.as_str()))
+            .collect();
+
+        Ok(items
+            .into_iter()
+            .map(|item| ItemSearchResult {
+                project_name: names
+                    .get(item.project_id.as_str())
+                    .map(|name| name.to_string())
+                    .unwrap_or_default(),
+                item,
+            })
+            .collect())
+    }
+
+    /// Delete an item
+    pub fn delete_item(&self, id: &str) -> Result<bool, String> {
+        let Some(project_id) = self.owning_project(id) else {
+            return Ok(false);
+        };
+        let mut project_data = self.load_project(&project_id)?;
+
+        let original_len = project_data.items.len();
+        project_data.items.retain(|i| i.id != id);
+
+        if project_data.items.len() == original_len {
+            return Ok(false);
+        }
+
+        project_data.updated_at = Self::now();
+        self.save_project(&project_data)?;
+        Ok(true)
+    }
+
+    /// Group item IDs by owning project; unknown IDs are dropped
+    fn items_by_project(&self, ids: &[String]) -> HashMap<String, HashSet<String>> {
+        let mut grouped: HashMap<String, HashSet<String>> = HashMap::new();
+        for id in ids {
+            if let Some(project_id) = self.owning_project(id) {
+                grouped.entry(project_id).or_default().insert(id.clone());
+            }
+        }
+        grouped
+    }
 
+    /// Delete several items, loading and saving each affected project once.
+    /// Returns the number of items deleted.
+    pub fn delete_items(&self, ids: &[String]) -> Result<usize, String> {
+        let mut deleted = 0;
+        for (project_id, ids) in self.items_by_project(ids) {
+            let mut project_data = self.load_project(&project_id)?;
             let original_len = project_data.items.len();
-            project_data.items.retain(|i| i.id != id);
+            project_data.items.retain(|i| !ids.contains(&i.id));
 
-            if project_data.items.len() < original_len {
+            let removed = original_len - project_data.items.len();
+            if removed > 0 {
+                deleted += removed;
                 project_data.updated_at = Self::now();
                 self.save_project(&project_data)?;
-                return Ok(true);
             }
         }
+        Ok(deleted)
+    }
+
+    /// Apply the same field changes to several items, loading and saving each
+    /// affected project once. Returns the updated items.
+    pub fn update_items(&self, ids: &[String], patch: &ItemPatch) -> Result<Vec<Item>, String> {
+        let mut updated = Vec::new();
+        for (project_id, ids) in self.items_by_project(ids) {
+            let mut project_data = self.load_project(&project_id)?;
+            let timestamp = Self::now();
 
-        Ok(false)
+            let before = updated.len();
+            for item in project_data
+                .items
+                .iter_mut()
+                .filter(|i| ids.contains(&i.id))
+            {
+                patch.apply(item);
+                item.updated_at = timestamp.clone();
+                updated.push(item.clone());
+            }
+            if updated.len() > before {
+                project_data.updated_at = timestamp;
+                self.save_project(&project_data)?;
+            }
+        }
+        Ok(updated)
     }
 
     /// Reorder items within a project
@@ -659,6 +1551,21 @@ impl JsonStore {
         position_x: f64,
         position_y: f64,
     ) -> Result<FileCard, String> {
+        self.create_card(
+            project_id,
+            FileCard {
+                filename: filename.to_string(),
+                file_path: file_path.to_string(),
+                position_x,
+                position_y,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Add a card of any kind on top of the project's canvas. The ID, project,
+    /// z-index and timestamps of `card` are filled in here.
+    pub fn create_card(&self, project_id: &str, card: FileCard) -> Result<FileCard, String> {
         let mut project_data = self.load_project(project_id)?;
 
         let id = Self::new_id();
@@ -675,15 +1582,10 @@ impl JsonStore {
         let card = FileCard {
             id,
             project_id: project_id.to_string(),
-            filename: filename.to_string(),
-            file_path: file_path.to_string(),
-            position_x,
-            position_y,
-            is_expanded: false,
-            is_minimized: false,
             z_index,
             created_at: timestamp.clone(),
             updated_at: timestamp,
+            ..card
         };
 
         project_data.file_cards.push(card.clone());
@@ -703,68 +1605,175 @@ impl JsonStore {
         is_expanded: Option<bool>,
         is_minimized: Option<bool>,
         z_index: Option<i32>,
+        max_size: Option<Option<u64>>,
+        content: Option<&str>,
+        url: Option<&str>,
     ) -> Result<Option<FileCard>, String> {
-        let project_ids = self.get_project_ids();
-
-        for project_id in &project_ids {
-            let mut project_data = match self.load_project(project_id) {
-                Ok(data) => data,
-                Err(_) => continue,
-            };
-
-            if let Some(card) = project_data.file_cards.iter_mut().find(|c| c.id == id) {
-                if let Some(f) = filename {
-                    card.filename = f.to_string();
-                }
-                if let Some(fp) = file_path {
-                    card.file_path = fp.to_string();
-                }
-                if let Some(px) = position_x {
-                    card.position_x = px;
-                }
-                if let Some(py) = position_y {
-                    card.position_y = py;
-                }
-                if let Some(ie) = is_expanded {
-                    card.is_expanded = ie;
-                }
-                if let Some(im) = is_minimized {
-                    card.is_minimized = im;
-                }
-                if let Some(z) = z_index {
-                    card.z_index = z;
-                }
-                card.updated_at = Self::now();
+        let Some(project_id) = self.owning_project(id) else {
+            return Ok(None);
+        };
+        let mut project_data = self.load_project(&project_id)?;
 
-                let updated_card = card.clone();
-                self.save_project(&project_data)?;
-                return Ok(Some(updated_card));
-            }
+        let Some(card) = project_data.file_cards.iter_mut().find(|c| c.id == id) else {
+            return Ok(None);
+        };
+        if let Some(f) = filename {
+            card.filename = f.to_string();
+        }
+        if let Some(fp) = file_path {
+            card.file_path = fp.to_string();
+        }
+        if let Some(px) = position_x {
+            card.position_x = px;
+        }
+        if let Some(py) = position_y {
+            card.position_y = py;
         }
+        if let Some(ie) = is_expanded {
+            card.is_expanded = ie;
+        }
+        if let Some(im) = is_minimized {
+            card.is_minimized = im;
+        }
+        if let Some(z) = z_index {
+            card.z_index = z;
+        }
+        if let Some(ms) = max_size {
+            card.max_size = ms;
+        }
+        if let Some(c) = content {
+            card.content = Some(c.to_string());
+        }
+        if let Some(u) = url {
+            card.url = Some(u.to_string());
+        }
+        card.updated_at = Self::now();
 
-        Ok(None)
+        let updated_card = card.clone();
+        self.save_project(&project_data)?;
+        Ok(Some(updated_card))
+    }
+
+    /// Find a file card by ID across all projects
+    pub fn find_file_card(&self, id: &str) -> Option<FileCard> {
+        let project_id = self.owning_project(id)?;
+        self.load_project(&project_id)
+            .ok()?
+            .file_cards
+            .into_iter()
+            .find(|c| c.id == id)
     }
 
     /// Delete a file card
     pub fn delete_file_card(&self, id: &str) -> Result<bool, String> {
-        let project_ids = self.get_project_ids();
+        let Some(project_id) = self.owning_project(id) else {
+            return Ok(false);
+        };
+        let mut project_data = self.load_project(&project_id)?;
 
-        for project_id in &project_ids {
-            let mut project_data = match self.load_project(project_id) {
-                Ok(data) => data,
-                Err(_) => continue,
-            };
+        let original_len = project_data.file_cards.len();
+        project_data.file_cards.retain(|c| c.id != id);
+
+        if project_data.file_cards.len() == original_len {
+            return Ok(false);
+        }
 
-            let original_len = project_data.file_cards.len();
-            project_data.file_cards.retain(|c| c.id != id);
+        self.save_project(&project_data)?;
+        Ok(true)
+    }
 
-            if project_data.file_cards.len() < original_len {
-                self.save_project(&project_data)?;
-                return Ok(true);
-            }
+    // ==================== Canvas Layouts ====================
+
+    /// Snapshot the placement of all of a project's cards under `name`,
+    /// replacing a layout of the same name
+    pub fn save_canvas_layout(&self, project_id: &str, name: &str) -> Result<CanvasLayout, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Layout name is empty".to_string());
         }
+        let mut project_data = self.load_project(project_id)?;
+
+        let layout = CanvasLayout {
+            name: name.to_string(),
+            cards: project_data
+                .file_cards
+                .iter()
+                .map(|card| CardPlacement {
+                    card_id: card.id.clone(),
+                    position_x: card.position_x,
+                    position_y: card.position_y,
+                    is_expanded: card.is_expanded,
+                    is_minimized: card.is_minimized,
+                    z_index: card.z_index,
+                })
+                .collect(),
+            saved_at: Self::now(),
+        };
+        match project_data
+            .canvas_layouts
+            .iter_mut()
+            .find(|l| l.name == layout.name)
+        {
+            Some(existing) => *existing = layout.clone(),
+            None => project_data.canvas_layouts.push(layout.clone()),
+        }
+        self.save_project(&project_data)?;
+        Ok(layout)
+    }
+
+    /// Move the project's cards back to a saved layout. Cards added after it was
+    /// saved keep their current placement. Returns the cards, like get_file_cards.
+    pub fn apply_canvas_layout(
+        &self,
+        project_id: &str,
+        name: &str,
+    ) -> Result<Vec<FileCard>, String> {
+        let mut project_data = self.load_project(project_id)?;
+        let layout = project_data
+            .canvas_layouts
+            .iter()
+            .find(|l| l.name == name.trim())
+            .cloned()
+            .ok_or_else(|| format!("Canvas layout \"{}\" not found", name))?;
+
+        let timestamp = Self::now();
+        for placement in &layout.cards {
+            let Some(card) = project_data
+                .file_cards
+                .iter_mut()
+                .find(|c| c.id == placement.card_id)
+            else {
+                continue;
+            };
+            card.position_x = placement.position_x;
+            card.position_y = placement.position_y;
+            card.is_expanded = placement.is_expanded;
+            card.is_minimized = placement.is_minimized;
+            card.z_index = placement.z_index;
+            card.updated_at = timestamp.clone();
+        }
+        self.save_project(&project_data)?;
+
+        let mut cards = project_data.file_cards;
+        cards.sort_by_key(|c| c.z_index);
+        Ok(cards)
+    }
 
-        Ok(false)
+    pub fn get_canvas_layouts(&self, project_id: &str) -> Result<Vec<CanvasLayout>, String> {
+        Ok(self.load_project(project_id)?.canvas_layouts)
+    }
+
+    pub fn delete_canvas_layout(&self, project_id: &str, name: &str) -> Result<bool, String> {
+        let mut project_data = self.load_project(project_id)?;
+        let original_len = project_data.canvas_layouts.len();
+        project_data
+            .canvas_layouts
+            .retain(|l| l.name != name.trim());
+        if project_data.canvas_layouts.len() == original_len {
+            return Ok(false);
+        }
+        self.save_project(&project_data)?;
+        Ok(true)
     }
 
     // ==================== Settings CRUD ====================
@@ -781,6 +1790,16 @@ impl JsonStore {
         Ok(metadata.global_settings.get(key).cloned())
     }
 
+    /// Get a setting parsed as `T`, falling back to `default` if missing or invalid
+    pub fn get_setting_or<T: std::str::FromStr>(&self, key: &str, default: T) -> T {
+        let metadata = self.metadata.read().unwrap();
+        metadata
+            .global_settings
+            .get(key)
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(default)
+    }
+
     /// Set a setting
     pub fn set_setting(&self, key: &str, value: &str) -> Result<(), String> {
         {
@@ -812,11 +1831,398 @@ impl JsonStore {
     /// Set todos markdown for a project
     pub fn set_project_todos(&self, project_id: &str, content: &str) -> Result<(), String> {
         let mut project_data = self.load_project(project_id)?;
-        project_data.todos = content.to_string();
-        project_data.updated_at = Self::now();
+        Self::replace_todos(&mut project_data, content.to_string());
         self.save_project(&project_data)
     }
 
+    /// Set a project's todos markdown, recording the todo events of the change
+    fn replace_todos(project_data: &mut ProjectData, todos: String) {
+        let timestamp = Self::now();
+        let events = todos::diff_events(&project_data.todos, &todos, &timestamp);
+        project_data.todo_events.extend(events);
+        let excess = project_data
+            .todo_events
+            .len()
+            .saturating_sub(MAX_TODO_EVENTS);
+        project_data.todo_events.drain(..excess);
+        project_data.todos = todos;
+        project_data.updated_at = timestamp;
+    }
+
+    /// Append (text, completed) todos to a project's todos, skipping any whose text
+    /// is already a todo there. Returns the new todos markdown.
+    pub fn append_project_todos(
+        &self,
+        project_id: &str,
+        entries: &[(String, bool)],
+    ) -> Result<String, String> {
+        let mut project_data = self.load_project(project_id)?;
+        let existing: HashSet<String> = markdown::checklist(&project_data.todos)
+            .into_iter()
+            .map(|todo| todo.text)
+            .collect();
+
+        let mut todos = project_data.todos.clone();
+        for (text, completed) in entries.iter().filter(|(text, _)| !existing.contains(text)) {
+            if !todos.is_empty() && !todos.ends_with('\n') {
+                todos.push('\n');
+            }
+            let mark = if *completed { 'x' } else { ' ' };
+            todos.push_str(&format!("- [{}] {}\n", mark, text));
+        }
+
+        if todos != project_data.todos {
+            Self::replace_todos(&mut project_data, todos);
+            self.save_project(&project_data)?;
+        }
+        Ok(project_data.todos)
+    }
+
+    /// Move a todo between kanban columns. Returns the new todos markdown.
+    pub fn set_todo_status(
+        &self,
+        project_id: &str,
+        line: usize,
+        status: &str,
+        columns: &[String],
+    ) -> Result<String, String> {
+        let mut project_data = self.load_project(project_id)?;
+        let todos = todos::set_status(&project_data.todos, line, status, columns)?;
+        if todos != project_data.todos {
+            Self::replace_todos(&mut project_data, todos);
+            self.save_project(&project_data)?;
+        }
+        Ok(project_data.todos)
+    }
+
+    /// Added/completed todo counts per day and week over the last `days` days
+    pub fn get_todo_stats(&self, project_id: &str, days: u32) -> Result<TodoStats, String> {
+        let project_data = self.load_project(project_id)?;
+        Ok(todos::stats(
+            &project_data.todo_events,
+            days,
+            chrono::Local::now().date_naive(),
+        ))
+    }
+
+    /// Get todo completion counts for a project
+    pub fn get_todo_progress(&self, project_id: &str) -> Result<TodoProgress, String> {
+        let project_data = self.load_project(project_id)?;
+        Ok(todos::todo_progress(&project_data.todos))
+    }
+
+    /// Open todos of every project matching the filter: soonest due first
+    /// (undated last), then by priority
+    pub fn get_todos_across_projects(
+        &self,
+        filter: &TodoFilter,
+    ) -> Result<Vec<ProjectTodo>, String> {
+        let today = chrono::Local::now().date_naive();
+        let horizon = filter
+            .due_within_days
+            .map(|days| today + chrono::Duration::days(days.max(0)));
+        let projects: Vec<(String, String)> = {
+            let metadata = self.metadata.read().unwrap();
+            metadata
+                .projects
+                .iter()
+                .map(|p| (p.id.clone(), p.name.clone()))
+                .collect()
+        };
+
+        let mut open_todos = Vec::new();
+        for (project_id, project_name) in projects {
+            let project_data = self.load_project(&project_id)?;
+            for todo in markdown::checklist(&project_data.todos) {
+                if todo.completed {
+                    continue;
+                }
+                let due = todos::parse_due(&todo.text);
+                let priority = todos::parse_priority(&todo.text);
+                if horizon.is_some_and(|horizon| due.is_none_or(|due| due > horizon)) {
+                    continue;
+                }
+                if filter
+                    .max_priority
+                    .is_some_and(|max| priority.is_none_or(|p| p > max))
+                {
+                    continue;
+                }
+                open_todos.push(ProjectTodo {
+                    project_id: project_id.clone(),
+                    project_name: project_name.clone(),
+                    text: todos::strip_markers(&todo.text),
+                    due_date: due.map(|due| due.format("%Y-%m-%d").to_string()),
+                    priority,
+                    overdue: due.is_some_and(|due| due < today),
+                });
+            }
+        }
+        open_todos.sort_by(|a, b| {
+            a.due_date
+                .is_none()
+                .cmp(&b.due_date.is_none())
+                .then_with(|| a.due_date.cmp(&b.due_date))
+                .then_with(|| {
+                    a.priority
+                        .unwrap_or(u8::MAX)
+                        .cmp(&b.priority.unwrap_or(u8::MAX))
+                })
+                .then_with(|| a.project_name.cmp(&b.project_name))
+        });
+        Ok(open_todos)
+    }
+
+    // ==================== Time Tracking ====================
+
+    /// Start a timer on a project (and optionally one of its todos), stopping
+    /// whichever timer was running
+    pub fn start_timer(&self, project_id: &str, todo: Option<&str>) -> Result<TimeEntry, String> {
+        // Loaded after stopping, in case the running timer belonged to this project
+        self.stop_timer()?;
+        let mut project_data = self.load_project(project_id)?;
+
+        let entry = TimeEntry {
+            id: Self::new_id(),
+            project_id: project_id.to_string(),
+            todo: todo.map(|t| t.to_string()).filter(|t| !t.trim().is_empty()),
+            started_at: Self::now(),
+            ended_at: None,
+            duration_secs: None,
+        };
+        project_data.time_entries.push(entry.clone());
+        self.save_project(&project_data)?;
+        Ok(entry)
+    }
+
+    /// Stop the running timer, if any, returning its finished entry
+    pub fn stop_timer(&self) -> Result<Option<TimeEntry>, String> {
+        for project_id in self.get_project_ids() {
+            let mut project_data = self.load_project(&project_id)?;
+            let Some(entry) = project_data
+                .time_entries
+                .iter_mut()
+                .find(|e| e.ended_at.is_none())
+            else {
+                continue;
+            };
+
+            let ended = Utc::now();
+            entry.duration_secs = chrono::DateTime::parse_from_rfc3339(&entry.started_at)
+                .ok()
+                .map(|started| ended.signed_duration_since(started).num_seconds().max(0));
+            entry.ended_at = Some(ended.to_rfc3339());
+            let stopped = entry.clone();
+            self.save_project(&project_data)?;
+            return Ok(Some(stopped));
+        }
+        Ok(None)
+    }
+
+    /// The running timer, if any
+    pub fn get_running_timer(&self) -> Result<Option<TimeEntry>, String> {
+        for project_id in self.get_project_ids() {
+            let project_data = self.load_project(&project_id)?;
+            if let Some(entry) = project_data
+                .time_entries
+                .into_iter()
+                .find(|e| e.ended_at.is_none())
+            {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+
+    /// A project's time entries, newest first
+    pub fn get_time_entries(&self, project_id: &str) -> Result<Vec<TimeEntry>, String> {
+        let mut entries = self.load_project(project_id)?.time_entries;
+        entries.reverse();
+        Ok(entries)
+    }
+
+    // ==================== Project Tree ====================
+
+    /// Add related-project references from `id` to each of `related_ids`
+    pub fn link_projects(&self, id: &str, related_ids: &[String]) -> Result<Project, String> {
+        let mut project_data = self.load_project(id)?;
+        let related = project_data
+            .metadata
+            .related_project_ids
+            .get_or_insert_with(Vec::new);
+        for related_id in related_ids {
+            if related_id == id {
+                return Err("A project can't be linked to itself".to_string());
+            }
+            if !self.project_exists(related_id) {
+                return Err(format!("Project {} not found", related_id));
+            }
+            if !related.contains(related_id) {
+                related.push(related_id.clone());
+            }
+        }
+        project_data.updated_at = Self::now();
+        self.save_project(&project_data)?;
+        Ok(project_data.to_project())
+    }
+
+    /// Remove related-project references from `id`
+    pub fn unlink_projects(&self, id: &str, related_ids: &[String]) -> Result<Project, String> {
+        let mut project_data = self.load_project(id)?;
+        if let Some(related) = project_data.metadata.related_project_ids.as_mut() {
+            related.retain(|r| !related_ids.contains(r));
+            if related.is_empty() {
+                project_data.metadata.related_project_ids = None;
+            }
+        }
+        project_data.updated_at = Self::now();
+        self.save_project(&project_data)?;
+        Ok(project_data.to_project())
+    }
+
+    /// All projects as graph nodes, with related-project and sub-project links as
+    /// edges. Links to projects that no longer exist are left out.
+    pub fn get_project_graph(&self) -> Result<ProjectGraph, String> {
+        let mut projects = Vec::new();
+        for project_id in self.get_project_ids() {
+            match self.load_project(&project_id) {
+                Ok(data) => projects.push(data),
+                Err(e) => log::warn!("Failed to load project {}: {}", project_id, e),
+            }
+        }
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let ids: HashSet<&str> = projects.iter().map(|p| p.id.as_str()).collect();
+        let mut edges = Vec::new();
+        for project in &projects {
+            let related = project
+                .metadata
+                .related_project_ids
+                .iter()
+                .flatten()
+                .map(|target| (target, None));
+            let sub_projects = project
+                .metadata
+                .sub_projects
+                .iter()
+                .flatten()
+                .map(|link| (&link.project_id, Some(link.kind.clone())));
+            for (target, link_kind) in related.chain(sub_projects) {
+                if ids.contains(target.as_str()) {
+                    edges.push(ProjectGraphEdge {
+                        source: project.id.clone(),
+                        target: target.clone(),
+                        link_kind,
+                    });
+                }
+            }
+        }
+
+        let nodes = projects
+            .into_iter()
+            .map(|p| ProjectGraphNode {
+                id: p.id,
+                name: p.name,
+            })
+            .collect();
+        Ok(ProjectGraph { nodes, edges })
+    }
+
+    /// Get all projects as a tree following `sub_projects` links.
+    /// Projects that aren't anyone's sub-project are roots; each node's
+    /// `rollup_progress` includes the todos of all its descendants.
+    pub fn get_project_tree(&self) -> Result<Vec<ProjectTreeNode>, String> {
+        let mut projects: HashMap<String, ProjectData> = HashMap::new();
+        for project_id in self.get_project_ids() {
+            match self.load_project(&project_id) {
+                Ok(data) => {
+                    projects.insert(project_id, data);
+                }
+                Err(e) => log::warn!("Failed to load project {}: {}", project_id, e),
+            }
+        }
+
+        let child_ids: HashSet<&str> = projects
+            .values()
+            .flat_map(|p| p.metadata.sub_projects.iter().flatten())
+            .map(|link| link.project_id.as_str())
+            .collect();
+
+        let mut root_ids: Vec<&String> = projects
+            .keys()
+            .filter(|id| !child_ids.contains(id.as_str()))
+            .collect();
+        root_ids.sort_by_key(|id| &projects[*id].name);
+
+        let mut visited = HashSet::new();
+        let mut roots: Vec<ProjectTreeNode> = root_ids
+            .into_iter()
+            .map(|id| Self::build_tree_node(&projects, id, None, &mut Vec::new(), &mut visited))
+            .collect();
+
+        // Projects that only appear inside a link cycle would otherwise be unreachable
+        let mut orphans: Vec<&String> = projects
+            .keys()
+            .filter(|id| !visited.contains(id.as_str()))
+            .collect();
+        orphans.sort_by_key(|id| &projects[*id].name);
+        for id in orphans {
+            if !visited.contains(id.as_str()) {
+                roots.push(Self::build_tree_node(
+                    &projects,
+                    id,
+                    None,
+                    &mut Vec::new(),
+                    &mut visited,
+                ));
+            }
+        }
+
+        Ok(roots)
+    }
+
+    /// Recursively build a tree node, skipping links back to an ancestor
+    fn build_tree_node(
+        projects: &HashMap<String, ProjectData>,
+        id: &str,
+        link_kind: Option<ProjectLinkKind>,
+        ancestors: &mut Vec<String>,
+        visited: &mut HashSet<String>,
+    ) -> ProjectTreeNode {
+        let data = &projects[id];
+        visited.insert(id.to_string());
+        ancestors.push(id.to_string());
+
+        let mut children = Vec::new();
+        for link in data.metadata.sub_projects.iter().flatten() {
+            if !projects.contains_key(&link.project_id) || ancestors.contains(&link.project_id) {
+                continue;
+            }
+            children.push(Self::build_tree_node(
+                projects,
+                &link.project_id,
+                Some(link.kind.clone()),
+                ancestors,
+                visited,
+            ));
+        }
+
+        ancestors.pop();
+
+        let todo_progress = todos::todo_progress(&data.todos);
+        let rollup_progress = children.iter().fold(todo_progress.clone(), |acc, child| {
+            acc.merge(&child.rollup_progress)
+        });
+
+        ProjectTreeNode {
+            project: data.to_project(),
+            link_kind,
+            todo_progress,
+            rollup_progress,
+            children,
+        }
+    }
+
     // ==================== Export/Import ====================
 
     /// Export all data
@@ -840,6 +2246,7 @@ impl JsonStore {
                     metadata: metadata_json,
                     created_at: project_data.created_at.clone(),
                     updated_at: project_data.updated_at.clone(),
+                    todos: (!project_data.todos.is_empty()).then(|| project_data.todos.clone()),
                 });
 
                 items.extend(project_data.items);
@@ -849,13 +2256,17 @@ impl JsonStore {
                     file_cards.push(FileCardRow {
                         id: card.id,
                         project_id: card.project_id,
+                        kind: card.kind,
                         filename: card.filename,
                         file_path: card.file_path,
+                        content: card.content,
+                        url: card.url,
                         position_x: card.position_x,
                         position_y: card.position_y,
                         is_expanded: if card.is_expanded { 1 } else { 0 },
                         is_minimized: if card.is_minimized { 1 } else { 0 },
                         z_index: card.z_index,
+                        max_size: card.max_size,
                         created_at: card.created_at,
                         updated_at: card.updated_at,
                     });
@@ -869,6 +2280,8 @@ impl JsonStore {
             projects,
             items,
             file_cards: Some(file_cards),
+            since: None,
+            deleted_project_ids: None,
         })
     }
 
@@ -879,7 +2292,11 @@ impl JsonStore {
         let mut file_cards_imported = 0;
         let mut skipped = 0;
 
+        let mut snapshot_id = None;
         if mode == "replace" {
+            // Keep a restorable copy of everything that is about to be deleted
+            snapshot_id = Some(self.create_snapshot("import", IMPORT_SNAPSHOTS_KEPT)?);
+
             // Delete all existing projects
             let existing_ids = self.get_project_ids();
 
@@ -899,57 +2316,9 @@ impl JsonStore {
                 }
             }
 
-            let project_metadata: ProjectMetadata =
-                serde_json::from_str(&project_row.metadata).unwrap_or_default();
-
-            // Gather items for this project
-            let project_items: Vec<Item> = data
-                .items
-                .iter()
-                .filter(|i| i.project_id == project_row.id)
-                .cloned()
-                .collect();
-
-            items_imported += project_items.len() as i32;
-
-            // Gather file cards for this project
-            let project_file_cards: Vec<FileCard> = data
-                .file_cards
-                .as_ref()
-                .map(|cards| {
-                    cards
-                        .iter()
-                        .filter(|c| c.project_id == project_row.id)
-                        .map(|c| FileCard {
-                            id: c.id.clone(),
-                            project_id: c.project_id.clone(),
-                            filename: c.filename.clone(),
-                            file_path: c.file_path.clone(),
-                            position_x: c.position_x,
-                            position_y: c.position_y,
-                            is_expanded: c.is_expanded == 1,
-                            is_minimized: c.is_minimized == 1,
-                            z_index: c.z_index,
-                            created_at: c.created_at.clone(),
-                            updated_at: c.updated_at.clone(),
-                        })
-                        .collect()
-                })
-                .unwrap_or_default();
-
-            file_cards_imported += project_file_cards.len() as i32;
-
-            let project_data = ProjectData {
-                id: project_row.id.clone(),
-                name: project_row.name.clone(),
-                description: project_row.description.clone(),
-                metadata: project_metadata,
-                items: project_items,
-                todos: String::new(), // Import doesn't include todos currently
-                file_cards: project_file_cards,
-                created_at: project_row.created_at.clone(),
-                updated_at: project_row.updated_at.clone(),
-            };
+            let project_data = Self::project_from_import(&data, project_row);
+            items_imported += project_data.items.len() as i32;
+            file_cards_imported += project_data.file_cards.len() as i32;
 
             // Save project file
             self.save_project(&project_data)?;
@@ -973,12 +2342,603 @@ impl JsonStore {
             items_imported,
             file_cards_imported,
             skipped,
+            snapshot_id,
+            projects_deleted: None,
         })
     }
 
+    /// Build a project from an import row plus its items and file cards
+    fn project_from_import(data: &ImportData, project_row: &ProjectRow) -> ProjectData {
+        let project_metadata: ProjectMetadata =
+            serde_json::from_str(&project_row.metadata).unwrap_or_default();
+
+        // Gather items for this project
+        let project_items: Vec<Item> = data
+            .items
+            .iter()
+            .filter(|i| i.project_id == project_row.id)
+            .cloned()
+            .collect();
+
+        // Gather file cards for this project
+        let project_file_cards: Vec<FileCard> = data
+            .file_cards
+            .as_ref()
+            .map(|cards| {
+                cards
+                    .iter()
+                    .filter(|c| c.project_id == project_row.id)
+                    .map(|c| FileCard {
+                        id: c.id.clone(),
+                        project_id: c.project_id.clone(),
+                        kind: c.kind,
+                        filename: c.filename.clone(),
+                        file_path: c.file_path.clone(),
+                        content: c.content.clone(),
+                        url: c.url.clone(),
+                        position_x: c.position_x,
+                        position_y: c.position_y,
+                        is_expanded: c.is_expanded == 1,
+                        is_minimized: c.is_minimized == 1,
+                        z_index: c.z_index,
+                        max_size: c.max_size,
+                        created_at: c.created_at.clone(),
+                        updated_at: c.updated_at.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        ProjectData {
+            version: schema::CURRENT_VERSION,
+            id: project_row.id.clone(),
+            name: project_row.name.clone(),
+            description: project_row.description.clone(),
+            metadata: project_metadata,
+            items: project_items,
+            todos: project_row.todos.clone().unwrap_or_default(),
+            todo_events: Vec::new(),
+            time_entries: Vec::new(),
+            canvas_layouts: Vec::new(),
+            file_cards: project_file_cards,
+            created_at: project_row.created_at.clone(),
+            updated_at: project_row.updated_at.clone(),
+        }
+    }
+
+    /// Content hash of every project, used to find projects changed since an export
+    pub fn project_hashes(&self) -> HashMap<String, String> {
+        self.get_project_ids()
+            .into_iter()
+            .filter_map(|id| {
+                let data = self.load_project(&id).ok()?;
+                let json = serde_json::to_vec(&data).ok()?;
+                Some((id, format!("{:x}", Sha256::digest(&json))))
+            })
+            .collect()
+    }
+
+    /// Apply an incremental export: projects in the delta replace the local copies
+    /// (keeping local todos, which exports don't carry) and deleted projects are removed
+    pub fn apply_delta(&self, data: ImportData) -> Result<ImportResult, String> {
+        let mut projects_imported = 0;
+        let mut items_imported = 0;
+        let mut file_cards_imported = 0;
+
+        // Deleted projects are gone for good, so keep a restorable copy of them first
+        let existing_ids: HashSet<String> = self.get_project_ids().into_iter().collect();
+        let to_delete: Vec<&String> = data
+            .deleted_project_ids
+            .iter()
+            .flatten()
+            .filter(|id| existing_ids.contains(*id))
+            .collect();
+        let snapshot_id = if to_delete.is_empty() {
+            None
+        } else {
+            Some(self.create_snapshot("import", IMPORT_SNAPSHOTS_KEPT)?)
+        };
+
+        for project_row in &data.projects {
+            let mut project_data = Self::project_from_import(&data, project_row);
+            let existing = self.load_project(&project_row.id).ok();
+            if let Some(existing) = &existing {
+                // Exports from older versions carry no todos
+                if project_row.todos.is_none() {
+                    project_data.todos = existing.todos.clone();
+                }
+                project_data.todo_events = existing.todo_events.clone();
+                project_data.time_entries = existing.time_entries.clone();
+                project_data.canvas_layouts = existing.canvas_layouts.clone();
+            }
+            items_imported += project_data.items.len() as i32;
+            file_cards_imported += project_data.file_cards.len() as i32;
+
+            self.save_project(&project_data)?;
+            {
+                let mut meta = self.metadata.write().unwrap();
+                match meta.projects.iter_mut().find(|p| p.id == project_row.id) {
+                    Some(info) => info.name = project_row.name.clone(),
+                    None => meta.projects.push(ProjectInfo {
+                        id: project_row.id.clone(),
+                        name: project_row.name.clone(),
+                    }),
+                }
+            }
+            projects_imported += 1;
+        }
+        self.save_metadata()?;
+
+        for id in &to_delete {
+            self.delete_project(id)?;
+        }
+
+        Ok(ImportResult {
+            projects_imported,
+            items_imported,
+            file_cards_imported,
+            skipped: 0,
+            snapshot_id,
+            projects_deleted: Some(to_delete.len() as i32),
+        })
+    }
+
+    // ==================== Conflict Copies ====================
+
+    /// Find conflict copies of metadata.json created by cloud sync clients, e.g.
+    /// `metadata (1).json`, `metadata-DESKTOP-ABC.json` (OneDrive) or
+    /// `metadata (conflicted copy 2024-01-01).json` (Dropbox)
+    fn find_metadata_conflict_copies(&self) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(self.data_path()) else {
+            return Vec::new();
+        };
+
+        let mut copies: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|path| {
+                let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                    return false;
+                };
+                path.is_file()
+                    && name != "metadata.json"
+                    && name.starts_with("metadata")
+                    && name.ends_with(".json")
+            })
+            .collect();
+        copies.sort();
+        copies
+    }
+
+    /// Detect metadata.json conflict copies and merge them into the current metadata:
+    /// projects only listed in a copy are added (if their project file exists) and
+    /// settings are merged, with the newer file winning for keys present in both.
+    /// With `apply = false` this only reports what would change.
+    pub fn merge_metadata_conflicts(&self, apply: bool) -> Result<ConflictReport, String> {
+        let copies = self.find_metadata_conflict_copies();
+        let metadata_path = self.data_path().join("metadata.json");
+        let current_mtime = fs::metadata(&metadata_path)
+            .ok()
+            .and_then(|m| m.modified().ok());
+
+        let mut merged = self.metadata.read().unwrap().clone();
+        let mut conflicts = Vec::new();
+
+        for copy_path in &copies {
+            let file = copy_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let Some(copy) = fs::read_to_string(copy_path)
+                .ok()
+                .and_then(|c| serde_json::from_str::<Metadata>(&c).ok())
+            else {
+                log::warn!("Skipping unreadable metadata conflict copy {}", file);
+                continue;
+            };
+
+            let copy_mtime = fs::metadata(copy_path).ok().and_then(|m| m.modified().ok());
+            let is_newer = match (copy_mtime, current_mtime) {
+                (Some(copy), Some(current)) => copy > current,
+                (Some(_), None) => true,
+                _ => false,
+            };
+
+            let mut projects_added = Vec::new();
+            for info in copy.projects {
+                let known = merged.projects.iter().any(|p| p.id == info.id);
+                if !known && self.project_exists(&info.id) {
+                    projects_added.push(info.name.clone());
+                    merged.projects.push(info);
+                }
+            }
+
+            let mut settings_changed = Vec::new();
+            for (key, value) in copy.global_settings {
+                let take = match merged.global_settings.get(&key) {
+                    None => true,
+                    Some(existing) => is_newer && *existing != value,
+                };
+                if take {
+                    settings_changed.push(key.clone());
+                    merged.global_settings.insert(key, value);
+                }
+            }
+            settings_changed.sort();
+
+            conflicts.push(MetadataConflict {
+                file,
+                modified_at: copy_mtime.map(|t| chrono::DateTime::<Utc>::from(t).to_rfc3339()),
+                is_newer,
+                projects_added,
+                settings_changed,
+            });
+        }
+
+        if !apply || copies.is_empty() {
+            return Ok(ConflictReport {
+                conflicts,
+                resolved: false,
+            });
+        }
+
+        *self.metadata.write().unwrap() = merged;
+        self.save_metadata()?;
+
+        // Keep the copies around in backups/ rather than deleting them
+        let backups_dir = self.data_path().join("backups");
+        fs::create_dir_all(&backups_dir)
+            .map_err(|e| format!("Failed to create backups directory: {}", e))?;
+        let stamp = Utc::now().format("%Y%m%d%H%M%S");
+        for copy_path in &copies {
+            if let Some(name) = copy_path.file_name() {
+                let target = backups_dir.join(format!("{}.{}", stamp, name.to_string_lossy()));
+                fs::rename(copy_path, &target)
+                    .map_err(|e| format!("Failed to move conflict copy: {}", e))?;
+            }
+        }
+
+        // Our own write shouldn't be reported as an external change
+        let mtime = fs::metadata(&metadata_path)
+            .ok()
+            .and_then(|m| m.modified().ok());
+        *self.last_metadata_mtime.write().unwrap() = mtime;
+
+        Ok(ConflictReport {
+            conflicts,
+            resolved: true,
+        })
+    }
+
+    // ==================== Drafts ====================
+
+    /// Make a window label or draft key safe to use as a file name
+    fn sanitize_file_name(name: &str) -> String {
+        name.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    /// Get draft file path: drafts/{window}/{key}.json
+    fn draft_path(&self, window: &str, key: &str) -> PathBuf {
+        self.data_path()
+            .join("drafts")
+            .join(Self::sanitize_file_name(window))
+            .join(format!("{}.json", Self::sanitize_file_name(key)))
+    }
+
+    /// Save a draft for a window (overwrites any previous draft with the same key)
+    pub fn save_draft(&self, window: &str, key: &str, content: &str) -> Result<Draft, String> {
+        let draft = Draft {
+            window: window.to_string(),
+            key: key.to_string(),
+            content: content.to_string(),
+            updated_at: Self::now(),
+        };
+        Self::write_json_atomic(&self.draft_path(window, key), &draft)?;
+        Ok(draft)
+    }
+
+    /// Load a draft for a window
+    pub fn load_draft(&self, window: &str, key: &str) -> Result<Option<Draft>, String> {
+        let path = self.draft_path(window, key);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Failed to read draft: {}", e))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse draft: {}", e))
+    }
+
+    /// Delete a draft (after it was saved for real or discarded)
+    pub fn delete_draft(&self, window: &str, key: &str) -> Result<bool, String> {
+        let path = self.draft_path(window, key);
+        if !path.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(&path).map_err(|e| format!("Failed to delete draft: {}", e))?;
+
+        // Drop the window folder once its last draft is gone
+        if let Some(parent) = path.parent() {
+            let _ = fs::remove_dir(parent);
+        }
+        Ok(true)
+    }
+
+    /// List all drafts across windows (for recovery after a crash)
+    pub fn list_drafts(&self) -> Result<Vec<Draft>, String> {
+        let drafts_dir = self.data_path().join("drafts");
+        let Ok(window_dirs) = fs::read_dir(&drafts_dir) else {
+            return Ok(Vec::new());
+        };
+
+        let mut drafts = Vec::new();
+        for window_dir in window_dirs.flatten() {
+            let Ok(files) = fs::read_dir(window_dir.path()) else {
+                continue;
+            };
+            for file in files.flatten() {
+                let path = file.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                    continue;
+                }
+                match fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|c| serde_json::from_str::<Draft>(&c).ok())
+                {
+                    Some(draft) => drafts.push(draft),
+                    None => log::warn!("Skipping unreadable draft {:?}", path),
+                }
+            }
+        }
+
+        drafts.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        Ok(drafts)
+    }
+
+    // ==================== Git-backed Store ====================
+
+    /// Whether every save should be committed to a git repo in the data dir
+    pub fn is_git_backed(&self) -> bool {
+        self.get_setting_or(GIT_BACKED_STORE_KEY, false)
+    }
+
+    /// Commit the data directory after a save. Failures are logged, never
+    /// propagated: the JSON files are already safely on disk at this point.
+    fn commit_if_git_backed(&self, message: &str) {
+        if !self.is_git_backed() {
+            return;
+        }
+        // Runs on the save worker, after the writes queued before it
+        let dir = self.data_path();
+        let message = message.to_string();
+        self.writer.submit(
+            None,
+            Box::new(move || {
+                if let Err(e) = git::commit_store(&dir, &message) {
+                    log::warn!("Failed to commit data directory: {}", e);
+                }
+                Ok(())
+            }),
+        );
+    }
+
+    /// Enable or disable git-backed mode, initializing the repo when enabling
+    pub fn set_git_backed(&self, enabled: bool) -> Result<(), String> {
+        if enabled {
+            git::init_store_repo(&self.data_path())?;
+        }
+        self.set_setting(GIT_BACKED_STORE_KEY, if enabled { "true" } else { "false" })
+    }
+
+    /// Get commit history of the data directory
+    pub fn get_store_history(&self, limit: usize) -> Result<Vec<StoreCommit>, String> {
+        self.flush()?;
+        if !self.data_path().join(".git").exists() {
+            return Ok(Vec::new());
+        }
+        git::store_history(&self.data_path(), limit)
+    }
+
+    /// Restore all data files to a previous commit and reload
+    pub fn restore_store_commit(&self, hash: &str) -> Result<(), String> {
+        self.flush()?;
+        if !self.data_path().join(".git").exists() {
+            return Err("Data directory is not git-backed".to_string());
+        }
+        git::restore_store(&self.data_path(), hash)?;
+        self.reload()
+    }
+
+    // ==================== Maintenance ====================
+
+    /// Snapshot directory: backups/snapshots/
+    fn snapshots_dir(&self) -> PathBuf {
+        self.data_path().join("backups").join("snapshots")
+    }
+
+    /// Copy the current data into `backups/snapshots/{timestamp}-{kind}/`, keeping the
+    /// newest `keep` snapshots of that kind. Returns the snapshot ID (its directory name).
+    pub fn create_snapshot(&self, kind: &str, keep: usize) -> Result<String, String> {
+        let snapshots_dir = self.snapshots_dir();
+        let id = format!("{}-{}", Utc::now().format("%Y%m%dT%H%M%S%3f"), kind);
+        let dest = snapshots_dir.join(&id);
+        fs::create_dir_all(&dest)
+            .map_err(|e| format!("Failed to create snapshot directory: {}", e))?;
+        self.copy_data_to(&dest)?;
+
+        let suffix = format!("-{}", kind);
+        let mut snapshots: Vec<PathBuf> = fs::read_dir(&snapshots_dir)
+            .map_err(|e| format!("Failed to read snapshots directory: {}", e))?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_dir()
+                    && path
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy().ends_with(&suffix))
+            })
+            .collect();
+        // Timestamped names sort chronologically
+        snapshots.sort();
+        let excess = snapshots.len().saturating_sub(keep);
+        for old in &snapshots[..excess] {
+            fs::remove_dir_all(old)
+                .map_err(|e| format!("Failed to remove old snapshot {}: {}", old.display(), e))?;
+        }
+
+        Ok(id)
+    }
+
+    /// IDs of all complete snapshots, newest first
+    pub fn list_snapshots(&self) -> Vec<String> {
+        let mut ids: Vec<String> = fs::read_dir(self.snapshots_dir())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.path().join("metadata.json").exists())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        ids.sort_by(|a, b| b.cmp(a));
+        ids
+    }
+
+    /// Replace the current data with a snapshot and reload.
+    /// Projects created after the snapshot are removed.
+    pub fn restore_snapshot(&self, id: &str) -> Result<(), String> {
+        if id.is_empty() || id.starts_with('.') || id.contains(['/', '\\']) {
+            return Err(format!("Invalid snapshot ID: {}", id));
+        }
+        let source = self.snapshots_dir().join(id);
+        if !source.join("metadata.json").exists() {
+            return Err(format!("Snapshot {} not found", id));
+        }
+
+        self.flush()?;
+        let data_path = self.data_path();
+        for dir in ["projects", "blobs"] {
+            let target = data_path.join(dir);
+            if target.exists() {
+                fs::remove_dir_all(&target)
+                    .map_err(|e| format!("Failed to remove {}: {}", target.display(), e))?;
+            }
+            Self::copy_dir_all(&source.join(dir), &target)?;
+        }
+        fs::copy(
+            source.join("metadata.json"),
+            data_path.join("metadata.json"),
+        )
+        .map_err(|e| format!("Failed to restore metadata.json: {}", e))?;
+
+        info!("Restored snapshot {}", id);
+        self.reload()
+    }
+
+    /// Recursively copy a directory (a missing source copies nothing)
+    fn copy_dir_all(source: &Path, dest: &Path) -> Result<(), String> {
+        let Ok(entries) = fs::read_dir(source) else {
+            return Ok(());
+        };
+        fs::create_dir_all(dest)
+            .map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let target = dest.join(entry.file_name());
+            if path.is_dir() {
+                Self::copy_dir_all(&path, &target)?;
+            } else {
+                fs::copy(&path, &target)
+                    .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-read every project file from disk (bypassing the cache) and report problems:
+    /// listed projects that are missing or unparseable, and project files not in metadata.json
+    pub fn check_integrity(&self) -> Result<Vec<String>, String> {
+        self.flush()?;
+        let data_path = self.data_path();
+        let ids = self.get_project_ids();
+        let mut problems = Vec::new();
+
+        for id in &ids {
+            if !self.project_exists(id) {
+                problems.push(format!("Project {} has no project file", id));
+                continue;
+            }
+            let parsed = Self::read_project_value(&data_path, id).and_then(|(_, mut value)| {
+                schema::migrate_project(&mut value)?;
+                serde_json::from_value::<ProjectData>(value)
+                    .map_err(|e| format!("Failed to parse project file: {}", e))
+            });
+            match parsed {
+                Ok(data) if data.id != *id => {
+                    problems.push(format!("Project file {} contains project {}", id, data.id))
+                }
+                Ok(_) => {}
+                Err(e) => problems.push(format!("Project {}: {}", id, e)),
+            }
+        }
+
+        for entry in fs::read_dir(data_path.join("projects"))
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let id = if entry.path().is_dir() {
+                name
+            } else if let Some(id) = name.strip_suffix(".json") {
+                // Conflict copies are reported by the sync conflict flow, not here
+                if id.contains('.') {
+                    continue;
+                }
+                id.to_string()
+            } else {
+                continue;
+            };
+            if !ids.contains(&id) {
+                problems.push(format!(
+                    "Project file {} is not listed in metadata.json",
+                    id
+                ));
+            }
+        }
+
+        Ok(problems)
+    }
+
+    /// Rebuild the item/file card index from every project. Returns the number of indexed IDs.
+    pub fn rebuild_item_index(&self) -> usize {
+        self.item_index.write().unwrap().clear();
+        for id in self.get_project_ids() {
+            match self.load_project(&id) {
+                Ok(data) => self.index_project(&data),
+                Err(e) => log::warn!("Failed to index project {}: {}", id, e),
+            }
+        }
+        self.item_index.read().unwrap().len()
+    }
+
     /// Clear project cache (useful after external changes)
     pub fn clear_cache(&self) {
+        // Pending writes must land first or the next load would read stale files
+        if let Err(e) = self.flush() {
+            log::error!("{}", e);
+        }
         self.projects_cache.write().unwrap().clear();
+        self.written_parts.lock().unwrap().clear();
+        self.item_index.write().unwrap().clear();
+        self.project_list.write().unwrap().clear();
     }
 
     /// Reload all data from disk (metadata + clear cache)
@@ -989,9 +2949,95 @@ impl JsonStore {
         self.reload_metadata()
     }
 
+    /// Point the store at a different data directory and load everything from there
+    pub fn switch_data_path(&self, data_path: PathBuf) -> Result<(), String> {
+        // Build the new state first so a bad path leaves the current store untouched
+        let fresh = Self::new(data_path)?;
+        self.flush()?;
+
+        *self.metadata.write().unwrap() = fresh.metadata.into_inner().unwrap();
+        *self.projects_cache.write().unwrap() = fresh.projects_cache.into_inner().unwrap();
+        self.written_parts.lock().unwrap().clear();
+        *self.item_index.write().unwrap() = fresh.item_index.into_inner().unwrap();
+        *self.project_list.write().unwrap() = fresh.project_list.into_inner().unwrap();
+        *self.last_metadata_mtime.write().unwrap() =
+            fresh.last_metadata_mtime.into_inner().unwrap();
+        *self.data_path.write().unwrap() = fresh.data_path.into_inner().unwrap();
+        Ok(())
+    }
+
+    /// Copy metadata.json and all project files to another data directory.
+    /// Refuses to overwrite a directory that already contains data.
+    pub fn copy_data_to(&self, dest: &Path) -> Result<(), String> {
+        self.flush()?;
+        let source = self.data_path();
+        if dest.join("metadata.json").exists() {
+            return Err(format!("{} already contains Devora data", dest.display()));
+        }
+
+        let dest_projects = dest.join("projects");
+        fs::create_dir_all(&dest_projects)
+            .map_err(|e| format!("Failed to create projects directory: {}", e))?;
+
+        let entries = fs::read_dir(source.join("projects"))
+            .map_err(|e| format!("Failed to read projects directory: {}", e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                // Split project: projects/{id}/{project,items,file_cards}.json + todos.md
+                let dest_dir = dest_projects.join(entry.file_name());
+                fs::create_dir_all(&dest_dir)
+                    .map_err(|e| format!("Failed to create project directory: {}", e))?;
+                for part in fs::read_dir(&path).into_iter().flatten().flatten() {
+                    let ext = part
+                        .path()
+                        .extension()
+                        .and_then(|e| e.to_str())
+                        .map(String::from);
+                    if !matches!(ext.as_deref(), Some("json") | Some("md")) {
+                        continue;
+                    }
+                    fs::copy(part.path(), dest_dir.join(part.file_name()))
+                        .map_err(|e| format!("Failed to copy {}: {}", part.path().display(), e))?;
+                }
+                continue;
+            }
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            fs::copy(&path, dest_projects.join(entry.file_name()))
+                .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+        }
+
+        // Large item contents: blobs/{project_id}/{item_id}.txt
+        if let Ok(project_dirs) = fs::read_dir(source.join("blobs")) {
+            for project_dir in project_dirs.flatten() {
+                let dest_dir = dest.join("blobs").join(project_dir.file_name());
+                fs::create_dir_all(&dest_dir)
+                    .map_err(|e| format!("Failed to create blob directory: {}", e))?;
+                for blob in fs::read_dir(project_dir.path())
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                {
+                    if blob.path().extension().and_then(|e| e.to_str()) != Some("txt") {
+                        continue;
+                    }
+                    fs::copy(blob.path(), dest_dir.join(blob.file_name()))
+                        .map_err(|e| format!("Failed to copy {}: {}", blob.path().display(), e))?;
+                }
+            }
+        }
+
+        // Metadata last, so a partial copy is never mistaken for existing data
+        fs::copy(source.join("metadata.json"), dest.join("metadata.json"))
+            .map_err(|e| format!("Failed to copy metadata.json: {}", e))?;
+        Ok(())
+    }
+
     /// Reload metadata from disk
     pub fn reload_metadata(&self) -> Result<(), String> {
-        let path = self.data_path.join("metadata.json");
+        let path = self.data_path().join("metadata.json");
         if path.exists() {
             let content = fs::read_to_string(&path)
                 .map_err(|e| format!("Failed to read metadata.json: {}", e))?;
@@ -1020,7 +3066,7 @@ impl JsonStore {
     /// Check if data has been modified externally (e.g., by OneDrive sync)
     /// Returns true if files have changed since we last loaded them
     pub fn has_external_changes(&self) -> bool {
-        let path = self.data_path.join("metadata.json");
+        let path = self.data_path().join("metadata.json");
         let current_mtime = fs::metadata(&path).ok().and_then(|m| m.modified().ok());
         let last_mtime = *self.last_metadata_mtime.read().unwrap();
 
@@ -1036,3 +3082,80 @@ impl JsonStore {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_command(store: &JsonStore, project_id: &str, cwd: Option<&str>) -> Item {
+        store
+            .create_item(
+                project_id,
+                ItemType::Command,
+                "Build",
+                "make",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                cwd,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn items_inherit_default_cwd_and_host_as_a_pair() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = JsonStore::new(dir.path().to_path_buf()).unwrap();
+        let metadata = ProjectMetadata {
+            default_cwd: Some("/srv/app".to_string()),
+            default_host: Some("prod".to_string()),
+            ..Default::default()
+        };
+        let project = store.create_project("App", "", metadata).unwrap();
+
+        let item = create_command(&store, &project.id, None);
+        assert_eq!(item.command_cwd.as_deref(), Some("/srv/app"));
+        assert_eq!(item.command_host.as_deref(), Some("prod"));
+
+        // A local cwd must not be paired with the default remote host
+        let item = create_command(&store, &project.id, Some("/home/me/app"));
+        assert_eq!(item.command_cwd.as_deref(), Some("/home/me/app"));
+        assert_eq!(item.command_host, None);
+    }
+
+    #[test]
+    fn incremental_import_snapshots_before_deleting() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = JsonStore::new(dir.path().to_path_buf()).unwrap();
+        let project = store
+            .create_project("Old", "", ProjectMetadata::default())
+            .unwrap();
+
+        let result = store
+            .apply_delta(ImportData {
+                projects: Vec::new(),
+                items: Vec::new(),
+                file_cards: None,
+                deleted_project_ids: Some(vec![project.id.clone(), "unknown".to_string()]),
+            })
+            .unwrap();
+        assert_eq!(result.projects_deleted, Some(1));
+        assert!(store.get_project_by_id(&project.id).unwrap().is_none());
+
+        store
+            .restore_snapshot(&result.snapshot_id.unwrap())
+            .unwrap();
+        assert!(store.get_project_by_id(&project.id).unwrap().is_some());
+    }
+}
@@ -0,0 +1,182 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Service name secrets are filed under in the OS keyring
+const SERVICE: &str = "devora";
+
+/// Read a secret from the OS keyring (Keychain, Secret Service or the Windows
+/// Credential Locker). Returns None when no secret is stored for `account`.
+pub fn get_secret(account: &str) -> Result<Option<String>, String> {
+    #[cfg(target_os = "macos")]
+    let output = keyring_command(
+        "security",
+        &["find-generic-password", "-s", SERVICE, "-a", account, "-w"],
+    )
+    .output();
+
+    #[cfg(all(not(windows), not(target_os = "macos")))]
+    let output = keyring_command(
+        "secret-tool",
+        &["lookup", "service", SERVICE, "account", account],
+    )
+    .output();
+
+    #[cfg(windows)]
+    let output = keyring_command(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            &format!(
+                "{} try {{ $c = $vault.Retrieve('{}', '{}'); $c.RetrievePassword(); $c.Password }} catch {{ exit 1 }}",
+                PASSWORD_VAULT,
+                SERVICE,
+                account.replace('\'', "''")
+            ),
+        ],
+    )
+    .output();
+
+    let output = output.map_err(|e| format!("Failed to access keyring: {}", e))?;
+    // All three tools exit non-zero when nothing is stored
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let secret = String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches(['\r', '\n'])
+        .to_string();
+    Ok(Some(secret))
+}
+
+/// Store (or replace) a secret in the OS keyring. The secret is passed on stdin so
+/// it doesn't show up in the process list.
+pub fn set_secret(account: &str, secret: &str) -> Result<(), String> {
+    // `-w` at the end makes security prompt for the secret, then once more to
+    // confirm it; without a terminal both prompts read stdin
+    #[cfg(target_os = "macos")]
+    let (mut cmd, stdin) = (
+        keyring_command(
+            "security",
+            &[
+                "add-generic-password",
+                "-U",
+                "-s",
+                SERVICE,
+                "-a",
+                account,
+                "-w",
+            ],
+        ),
+        format!("{}\n{}\n", secret, secret),
+    );
+
+    #[cfg(all(not(windows), not(target_os = "macos")))]
+    let (mut cmd, stdin) = (
+        keyring_command(
+            "secret-tool",
+            &[
+                "store",
+                &format!("--label=Devora: {}", account),
+                "service",
+                SERVICE,
+                "account",
+                account,
+            ],
+        ),
+        format!("{}\n", secret),
+    );
+
+    #[cfg(windows)]
+    let (mut cmd, stdin) = (
+        keyring_command(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                &format!(
+                    "{} $secret = [Console]::In.ReadLine(); $vault.Add((New-Object Windows.Security.Credentials.PasswordCredential('{}', '{}', $secret)))",
+                    PASSWORD_VAULT,
+                    SERVICE,
+                    account.replace('\'', "''")
+                ),
+            ],
+        ),
+        format!("{}\n", secret),
+    );
+
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to access keyring: {}", e))?;
+    if let Some(mut pipe) = child.stdin.take() {
+        pipe.write_all(stdin.as_bytes())
+            .map_err(|e| format!("Failed to pass secret to keyring: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to access keyring: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to store secret: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Remove a secret from the OS keyring. Removing a missing secret is not an error.
+pub fn delete_secret(account: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let output = keyring_command(
+        "security",
+        &["delete-generic-password", "-s", SERVICE, "-a", account],
+    )
+    .output();
+
+    #[cfg(all(not(windows), not(target_os = "macos")))]
+    let output = keyring_command(
+        "secret-tool",
+        &["clear", "service", SERVICE, "account", account],
+    )
+    .output();
+
+    #[cfg(windows)]
+    let output = keyring_command(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            &format!(
+                "{} try {{ $vault.Remove($vault.Retrieve('{}', '{}')) }} catch {{ }}",
+                PASSWORD_VAULT,
+                SERVICE,
+                account.replace('\'', "''")
+            ),
+        ],
+    )
+    .output();
+
+    output
+        .map(|_| ())
+        .map_err(|e| format!("Failed to access keyring: {}", e))
+}
+
+/// PowerShell prelude loading the WinRT credential locker into `$vault`
+#[cfg(windows)]
+const PASSWORD_VAULT: &str = "[void][Windows.Security.Credentials.PasswordVault,Windows.Security.Credentials,ContentType=WindowsRuntime]; $vault = New-Object Windows.Security.Credentials.PasswordVault;";
+
+fn keyring_command(program: &str, args: &[&str]) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    cmd
+}
@@ -1,14 +1,76 @@
+mod actions;
+mod agent_runs;
+mod agents;
+mod clipboard;
+mod code_todos;
+mod command_stream;
 mod commands;
+mod custom_fields;
+mod database;
 mod db;
+mod dir_listing;
+mod discovery;
+mod docker;
+mod encoding;
+mod exports;
+mod file_preview;
+mod file_search;
+mod file_watch;
+mod git;
+mod github;
+mod handoff;
+mod hosts;
+mod http_request;
+mod janitor;
+mod jetbrains;
 mod json_store;
+mod keyring;
+mod line_index;
+mod maintenance;
+mod markdown;
 mod migration;
 mod models;
+mod policy;
+mod ports;
+mod processes;
+mod prompts;
+mod reminders;
+mod save_worker;
+mod schedules;
+mod schema;
+mod scripts;
+mod services;
 mod settings;
+mod sftp;
+mod shell;
+mod ssh_config;
+mod sync;
+mod templates;
+mod terminal;
+mod tmux;
+mod todos;
+mod url_metadata;
+mod usage;
+mod wsl;
 
+use agent_runs::AgentRuns;
+use command_stream::CommandRuns;
+use exports::ReplaceImportGuard;
+use file_watch::FileCardWatcher;
+use hosts::HostStatusCache;
+use janitor::JanitorState;
 use json_store::JsonStore;
+use line_index::LineIndexCache;
+use maintenance::MaintenanceState;
+use migration::MigrationState;
+use processes::ProcessRegistry;
+use reminders::ReminderState;
+use schedules::ScheduleState;
+use services::ServiceManager;
 use settings::SettingsFile;
 use std::fs;
 use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+use usage::UsageFile;
 
 /// Parse --project <name> from command line arguments
 fn parse_project_arg() -> Option<String> {
@@ -22,6 +84,42 @@ fn parse_project_arg() -> Option<String> {
     None
 }
 
+/// Write the host jump chains' ssh config (see `hosts::write_ssh_config`)
+fn write_ssh_config(app: &tauri::AppHandle) {
+    let settings_file = app.state::<SettingsFile>();
+    if let Err(e) = hosts::write_ssh_config(&app.state::<JsonStore>(), settings_file.config_dir()) {
+        log::warn!("{}", e);
+    }
+}
+
+/// Open the project named by --project in its own window in place of the main one
+fn open_project_by_name(app: &tauri::AppHandle, name: &str) {
+    let store = app.state::<JsonStore>();
+    let Ok(projects) = store.get_all_projects() else {
+        return;
+    };
+    let Some(project) = projects.iter().find(|p| p.name == name) else {
+        return;
+    };
+    let _ = app.state::<UsageFile>().record_project_opened(&project.id);
+
+    // Close default main window
+    if let Some(main_window) = app.get_webview_window("main") {
+        let _ = main_window.close();
+    }
+
+    // Create project window with proper title
+    let window_label = format!("project-{}", project.id);
+    let url = WebviewUrl::App(format!("/project/{}", project.id).into());
+    let title = format!("Devora - {}", project.name);
+
+    let _ = WebviewWindowBuilder::new(app, &window_label, url)
+        .title(&title)
+        .inner_size(1200.0, 800.0)
+        .min_inner_size(800.0, 600.0)
+        .build();
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Parse --project argument before building the app
@@ -43,41 +141,66 @@ pub fn run() {
             // Get data path from settings, or use default
             let data_dir = settings_file.get_data_path(&config_dir);
 
-            // Run migration from SQLite to JSON if needed
-            // Migration checks if metadata.json exists and if projects.db exists
-            if let Err(e) = migration::migrate_if_needed(&config_dir, &data_dir) {
-                log::error!("Migration failed: {}", e);
-                // Continue anyway - either fresh start or migration error
-            }
+            // Migration from SQLite to JSON runs in the background (can take a while
+            // for big databases); the store is reloaded once it finishes
+            let migration_pending = migration::needs_migration(&config_dir, &data_dir);
 
             // Initialize JSON store in the configured directory
-            let store = JsonStore::new(data_dir).expect("Failed to initialize JSON store");
-
-            // Handle --project argument: find project by name and open it
-            if let Some(ref project_name) = project_name_arg {
-                if let Ok(projects) = store.get_all_projects() {
-                    if let Some(project) = projects.iter().find(|p| p.name == *project_name) {
-                        // Close default main window
-                        if let Some(main_window) = app.get_webview_window("main") {
-                            let _ = main_window.close();
-                        }
-
-                        // Create project window with proper title
-                        let window_label = format!("project-{}", project.id);
-                        let url = WebviewUrl::App(format!("/project/{}", project.id).into());
-                        let title = format!("Devora - {}", project.name);
-
-                        let _ = WebviewWindowBuilder::new(app, &window_label, url)
-                            .title(&title)
-                            .inner_size(1200.0, 800.0)
-                            .min_inner_size(800.0, 600.0)
-                            .build();
-                    }
-                }
-            }
+            let store = JsonStore::new(data_dir.clone()).expect("Failed to initialize JSON store");
 
             app.manage(store);
             app.manage(settings_file);
+            app.manage(HostStatusCache::new(&config_dir));
+            app.manage(UsageFile::new(&config_dir));
+            app.manage(MigrationState::new(migration_pending));
+
+            app.manage(JanitorState(std::sync::Mutex::new(None)));
+            app.manage(MaintenanceState::new(&config_dir));
+            app.manage(ReplaceImportGuard::new());
+            app.manage(ReminderState::new(&config_dir));
+            app.manage(ScheduleState::new(&config_dir));
+            app.manage(ServiceManager::new(&config_dir));
+            app.manage(FileCardWatcher::default());
+            app.manage(LineIndexCache::default());
+            app.manage(ProcessRegistry::default());
+            app.manage(CommandRuns::default());
+            app.manage(AgentRuns::default());
+
+            // Remove orphaned temp files from interrupted writes (now and periodically)
+            janitor::spawn_janitor(app.handle().clone());
+
+            // Backups, integrity checks, index rebuilds and log rotation, deferred until idle/on AC
+            maintenance::spawn_scheduler(app.handle().clone());
+
+            // Native notifications for todos whose @due date has arrived
+            reminders::spawn_scheduler(app.handle().clone());
+
+            // Command items with a cron-like schedule (see set_item_schedule)
+            schedules::spawn_scheduler(app.handle().clone());
+
+            // Health checks of running project services (see start_service)
+            services::spawn_health_checks(app.handle().clone());
+
+            // Handle --project argument: find project by name and open it, and
+            // write the host jump chains' ssh config, once the data is there
+            if migration_pending {
+                migration::spawn_background_migration(
+                    app.handle().clone(),
+                    config_dir,
+                    data_dir,
+                    move |app| {
+                        write_ssh_config(app);
+                        if let Some(name) = project_name_arg.as_deref() {
+                            open_project_by_name(app, name);
+                        }
+                    },
+                );
+            } else {
+                write_ssh_config(app.handle());
+                if let Some(name) = project_name_arg.as_deref() {
+                    open_project_by_name(app.handle(), name);
+                }
+            }
 
             // Setup logging in debug mode
             if cfg!(debug_assertions) {
@@ -94,59 +217,240 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_updater::Builder::default().build())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             // Store reload & external change detection
             commands::reload_store,
+            commands::reload_project,
+            commands::flush_pending_saves,
             commands::check_external_changes,
+            commands::get_migration_report,
+            commands::get_cleanup_report,
+            commands::run_cleanup_now,
+            commands::get_maintenance_status,
+            commands::run_maintenance_now,
+            commands::check_metadata_conflicts,
+            commands::resolve_metadata_conflicts,
             // Projects
             commands::get_projects,
+            commands::get_recent_projects,
+            commands::touch_project_opened,
             commands::get_project,
+            commands::get_most_used_items,
             commands::create_project,
             commands::update_project,
             commands::delete_project,
+            commands::get_custom_field_schema,
+            commands::save_custom_field_schema,
+            commands::get_command_policy,
+            commands::save_command_policy,
+            commands::get_trusted_projects,
+            commands::set_project_trusted,
+            commands::get_default_shell,
+            commands::set_default_shell,
+            commands::duplicate_project,
+            commands::scan_directory_for_projects,
+            commands::import_project_candidates,
+            commands::enrich_project_metadata,
+            commands::get_project_tree,
+            commands::link_projects,
+            commands::unlink_projects,
+            commands::get_project_graph,
             // Items
             commands::create_item,
             commands::update_item,
             commands::delete_item,
+            commands::delete_items,
+            commands::update_items_common_fields,
             commands::reorder_items,
             // File Cards
             commands::get_file_cards,
             commands::create_file_card,
+            commands::create_note_card,
+            commands::create_link_card,
             commands::update_file_card,
             commands::delete_file_card,
+            commands::watch_file_card,
+            commands::unwatch_file_card,
+            commands::save_canvas_layout,
+            commands::apply_canvas_layout,
+            commands::get_canvas_layouts,
+            commands::delete_canvas_layout,
             // Settings
             commands::get_all_settings,
             commands::get_setting,
             commands::set_setting,
             commands::delete_setting,
+            // Drafts
+            commands::save_draft,
+            commands::load_draft,
+            commands::delete_draft,
+            commands::list_drafts,
+            // Git-backed data directory
+            commands::set_store_git_backed,
+            commands::get_store_history,
+            commands::restore_store_commit,
+            // Remote sync
+            commands::get_sync_settings,
+            commands::set_sync_settings,
+            commands::get_sync_status,
+            commands::sync_now,
             // Export/Import
             commands::export_data,
             commands::export_data_to_file,
+            commands::export_handoff,
+            commands::export_incremental,
+            commands::import_incremental,
+            commands::prepare_replace_import,
             commands::import_data,
+            commands::list_snapshots,
+            commands::restore_snapshot,
             // System operations
             commands::open_ide,
             commands::open_custom_ide,
             commands::open_remote_ide,
             commands::open_custom_remote_ide,
+            commands::open_remote_uri,
+            commands::open_wsl_ide,
+            commands::list_wsl_distros,
+            commands::translate_wsl_path,
             commands::open_coding_agent,
             commands::get_ssh_hosts,
+            commands::get_ssh_host_details,
             commands::list_remote_dir,
+            commands::list_local_dir,
+            commands::get_host_statuses,
+            commands::refresh_host_status,
+            commands::test_ssh_connection,
+            commands::sftp_list,
+            commands::sftp_download,
+            commands::sftp_upload,
+            commands::list_tmux_sessions,
+            commands::open_terminal,
+            commands::reveal_in_file_manager,
+            commands::attach_tmux_session,
+            commands::create_tmux_session,
+            commands::list_agent_sessions,
+            commands::resume_agent_session,
+            commands::get_agent_run_log,
+            commands::list_agent_runs,
+            commands::cancel_agent_run,
+            commands::compose_agent_prompt,
+            commands::launch_agent_with_prompt,
+            commands::get_prompt_templates,
+            commands::save_prompt_template,
+            commands::delete_prompt_template,
+            commands::launch_agent_with_template,
+            commands::get_custom_agents,
+            commands::save_custom_agents,
+            commands::get_custom_terminals,
+            commands::save_custom_terminals,
+            commands::get_host_agent_overrides,
+            commands::save_host_agent_override,
+            commands::delete_host_agent_override,
+            commands::get_host_jump_chains,
+            commands::set_host_jump_chain,
             commands::run_command,
+            commands::list_running_processes,
+            commands::kill_process,
+            commands::check_port,
+            commands::kill_process_on_port,
+            commands::check_item_ports,
+            commands::start_service,
+            commands::stop_service,
+            commands::restart_service,
+            commands::get_service_status,
+            commands::run_command_stream,
+            commands::cancel_command,
+            commands::run_script_item,
+            commands::run_item_sequence,
+            commands::run_commands_parallel,
+            commands::set_item_schedule,
+            commands::list_scheduled_runs,
+            commands::run_http_request,
+            commands::fetch_url_metadata,
+            commands::get_github_status,
+            commands::set_github_token,
+            commands::has_github_token,
+            commands::docker_compose_up,
+            commands::docker_compose_down,
+            commands::docker_compose_ps,
+            commands::docker_compose_logs,
+            commands::open_database_client,
+            commands::set_database_secret,
+            commands::has_database_secret,
+            commands::copy_snippet_to_clipboard,
+            commands::copy_to_clipboard,
+            commands::copy_ssh_command,
+            commands::copy_item_command,
+            commands::search_items,
+            commands::search_snippets,
+            commands::open_ssh_session,
             commands::read_file_content,
+            commands::read_file_preview,
+            commands::write_file_content,
+            commands::stream_file_chunks,
+            commands::get_file_limits,
             commands::get_file_info,
             commands::read_file_lines,
+            commands::search_in_file,
+            commands::diff_file_against_head,
+            commands::get_git_status,
+            commands::get_project_git_status,
+            commands::get_all_repo_statuses,
+            commands::get_git_log,
+            commands::git_fetch,
+            commands::git_pull,
+            commands::git_list_branches,
+            commands::git_checkout_branch,
+            commands::list_git_worktrees,
+            commands::create_git_worktree,
+            commands::remove_git_worktree,
             // Data path management
             commands::get_data_path,
             commands::get_default_data_path,
             commands::set_data_path,
+            commands::switch_data_path,
             commands::check_data_exists,
             commands::validate_data_path,
             // Todos (Markdown)
             commands::get_project_todos,
+            commands::get_filtered_todos,
             commands::set_project_todos,
+            commands::get_todo_progress,
+            commands::get_todo_stats,
+            commands::get_todos_by_status,
+            commands::set_todo_status,
+            commands::get_todo_status_columns,
+            commands::set_todo_status_columns,
+            commands::start_timer,
+            commands::stop_timer,
+            commands::get_running_timer,
+            commands::get_time_entries,
+            commands::get_upcoming_todos,
+            commands::get_todos_across_projects,
+            commands::render_markdown,
+            commands::get_note_checklist,
+            commands::promote_note_checklist,
+            commands::scan_code_todos,
+            commands::import_code_todos,
             // Window management
             commands::open_project_window,
+            // Actions registry
+            commands::get_actions,
+            commands::save_action,
+            commands::delete_action,
+            commands::execute_action,
+            commands::get_action_audit_log,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Project saves are written in the background; don't lose queued ones on quit
+            if let tauri::RunEvent::Exit = event {
+                if let Err(e) = app.state::<JsonStore>().flush() {
+                    log::error!("Failed to flush pending saves on exit: {}", e);
+                }
+            }
+        });
 }
@@ -0,0 +1,179 @@
+use crate::encoding;
+use crate::models::FileLinesResult;
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Indexes kept at once; the least recently used is dropped beyond this
+const MAX_CACHED_FILES: usize = 8;
+
+/// Byte offsets of the lines of one file, as of its size and mtime when indexed
+struct LineIndex {
+    len: u64,
+    modified: Option<SystemTime>,
+    encoding: &'static Encoding,
+    /// Offset of the start of every line, plus one past the last line
+    starts: Vec<u64>,
+    last_used: u64,
+}
+
+impl Default for LineIndex {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            modified: None,
+            encoding: UTF_8,
+            starts: Vec::new(),
+            last_used: 0,
+        }
+    }
+}
+
+impl LineIndex {
+    /// Same line splitting as `str::lines`: a trailing newline doesn't start a new line
+    fn build(path: &Path, len: u64, modified: Option<SystemTime>) -> Result<Self, String> {
+        let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let mut sample = Vec::with_capacity(encoding::HEAD_BYTES);
+        (&mut file)
+            .take(encoding::HEAD_BYTES as u64)
+            .read_to_end(&mut sample)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        let encoding = encoding::detect(&sample)?;
+        file.seek(SeekFrom::Start(0))
+            .map_err(|e| format!("Failed to seek file: {}", e))?;
+
+        // In UTF-16 a newline is a whole code unit, not any 0x0A byte
+        let newline: &[u8] = if encoding == UTF_16LE {
+            b"\n\0"
+        } else if encoding == UTF_16BE {
+            b"\0\n"
+        } else {
+            b"\n"
+        };
+
+        let mut reader = BufReader::with_capacity(256 * 1024, file);
+        let mut starts = vec![0];
+        let mut buffer = vec![0; 256 * 1024];
+        let mut offset = 0u64;
+        let mut previous = 0u8;
+        loop {
+            let read = reader
+                .read(&mut buffer)
+                .map_err(|e| format!("Failed to read file: {}", e))?;
+            if read == 0 {
+                break;
+            }
+            for (i, &byte) in buffer[..read].iter().enumerate() {
+                let position = offset + i as u64;
+                let is_newline = match newline {
+                    [single] => byte == *single,
+                    _ => position % 2 == 1 && [previous, byte] == newline,
+                };
+                if is_newline {
+                    starts.push(position + 1);
+                }
+                previous = byte;
+            }
+            offset += read as u64;
+        }
+        if starts.last() != Some(&offset) {
+            starts.push(offset);
+        }
+        Ok(Self {
+            len,
+            modified,
+            encoding,
+            starts,
+            last_used: 0,
+        })
+    }
+
+    fn line_count(&self) -> usize {
+        self.starts.len() - 1
+    }
+}
+
+/// Managed state: line indexes of the files being scrolled, so each request seeks
+/// straight to its lines instead of reading and splitting the whole file.
+/// Clones share the cache.
+#[derive(Default, Clone)]
+pub struct LineIndexCache {
+    indexes: Arc<Mutex<HashMap<PathBuf, Arc<Mutex<LineIndex>>>>>,
+    uses: Arc<AtomicU64>,
+}
+
+impl LineIndexCache {
+    /// Lines `start..start + count` of a file, transcoded to UTF-8. The index is
+    /// rebuilt when the file's size or mtime changed since it was built.
+    pub fn read_lines(
+        &self,
+        path: &Path,
+        start: usize,
+        count: usize,
+    ) -> Result<FileLinesResult, String> {
+        let metadata =
+            fs::metadata(path).map_err(|e| format!("Failed to read file metadata: {}", e))?;
+        let modified = metadata.modified().ok();
+        let use_id = self.uses.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let entry = {
+            let mut indexes = self.indexes.lock().unwrap();
+            if !indexes.contains_key(path) && indexes.len() >= MAX_CACHED_FILES {
+                // An index that is locked is being built or read, so it isn't evicted
+                let oldest = indexes
+                    .iter()
+                    .min_by_key(|(_, index)| index.try_lock().map_or(u64::MAX, |i| i.last_used))
+                    .map(|(path, _)| path.clone());
+                if let Some(oldest) = oldest {
+                    indexes.remove(&oldest);
+                }
+            }
+            indexes.entry(path.to_path_buf()).or_default().clone()
+        };
+
+        // Indexing a large file takes a while; only requests for the same file wait
+        let mut index = entry.lock().unwrap();
+        if index.starts.is_empty() || index.len != metadata.len() || index.modified != modified {
+            *index = LineIndex::build(path, metadata.len(), modified)?;
+        }
+        index.last_used = use_id;
+
+        let total_lines = index.line_count();
+        let encoding = index.encoding;
+        let result = |lines| FileLinesResult {
+            lines,
+            start_line: start,
+            total_lines,
+            encoding: encoding.name().to_string(),
+        };
+        if start >= total_lines {
+            return Ok(result(Vec::new()));
+        }
+        let end = start.saturating_add(count).min(total_lines);
+        let from = index.starts[start];
+        let to = index.starts[end];
+        drop(index);
+
+        let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        file.seek(SeekFrom::Start(from))
+            .map_err(|e| format!("Failed to seek file: {}", e))?;
+        let mut buffer = Vec::with_capacity((to - from) as usize);
+        file.take(to - from)
+            .read_to_end(&mut buffer)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        let content = encoding::decode_with(encoding, &buffer);
+
+        Ok(result(content.lines().map(String::from).collect()))
+    }
+
+    /// Number of lines in a file, indexing it for the reads that follow
+    pub fn line_count(&self, path: &Path) -> Result<usize, String> {
+        self.read_lines(path, usize::MAX, 0)
+            .map(|result| result.total_lines)
+    }
+}
@@ -0,0 +1,337 @@
+use crate::json_store::JsonStore;
+use crate::models::{
+    MaintenanceAggressiveness, MaintenanceReport, MaintenanceStatus, MaintenanceTask,
+    MaintenanceTaskResult,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager};
+
+/// Global setting controlling when deferred maintenance may run
+pub const MAINTENANCE_AGGRESSIVENESS_KEY: &str = "maintenanceAggressiveness";
+
+/// How often the coordinator checks whether maintenance is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// In balanced mode, work overdue by this much runs even if the machine is busy on battery
+const MAX_DEFERRAL_SECS: i64 = 24 * 60 * 60;
+
+/// Normalized 1-minute load average below which the machine counts as idle
+const IDLE_LOAD_THRESHOLD: f64 = 0.3;
+
+/// Number of maintenance snapshots kept in backups/snapshots/
+const SNAPSHOTS_KEPT: usize = 5;
+
+/// Log files older than this are removed by log rotation
+const LOG_MAX_AGE: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+impl MaintenanceTask {
+    const ALL: [MaintenanceTask; 4] = [
+        MaintenanceTask::Backup,
+        MaintenanceTask::IntegrityCheck,
+        MaintenanceTask::IndexRebuild,
+        MaintenanceTask::LogRotation,
+    ];
+
+    /// How often the task should run
+    fn interval_secs(&self) -> i64 {
+        match self {
+            MaintenanceTask::Backup
+            | MaintenanceTask::IntegrityCheck
+            | MaintenanceTask::LogRotation => 24 * 60 * 60,
+            MaintenanceTask::IndexRebuild => 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Managed state: when each task last ran (persisted to ~/.devora/maintenance.json,
+/// machine-local) and the report of the latest run
+pub struct MaintenanceState {
+    path: PathBuf,
+    last_runs: Mutex<HashMap<MaintenanceTask, String>>,
+    last_report: Mutex<Option<MaintenanceReport>>,
+}
+
+impl MaintenanceState {
+    pub fn new(config_dir: &Path) -> Self {
+        let path = config_dir.join("maintenance.json");
+        let last_runs = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            last_runs: Mutex::new(last_runs),
+            last_report: Mutex::new(None),
+        }
+    }
+
+    /// Tasks whose interval has elapsed, with how long the most overdue one has waited
+    fn due_tasks(&self) -> (Vec<MaintenanceTask>, i64) {
+        let last_runs = self.last_runs.lock().unwrap();
+        let now = chrono::Utc::now();
+        let mut due = Vec::new();
+        let mut max_overdue = 0;
+
+        for task in MaintenanceTask::ALL {
+            let elapsed = last_runs
+                .get(&task)
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| now.signed_duration_since(t).num_seconds())
+                .unwrap_or(i64::MAX);
+            if elapsed >= task.interval_secs() {
+                due.push(task);
+                max_overdue = max_overdue.max(elapsed.saturating_sub(task.interval_secs()));
+            }
+        }
+        (due, max_overdue)
+    }
+
+    fn record(&self, report: &MaintenanceReport) {
+        let mut last_runs = self.last_runs.lock().unwrap();
+        for result in &report.results {
+            last_runs.insert(result.task, report.ran_at.clone());
+        }
+        // Best-effort; a lost timestamp only means a task runs again sooner
+        if let Ok(content) = serde_json::to_string_pretty(&*last_runs) {
+            let _ = fs::write(&self.path, content);
+        }
+        *self.last_report.lock().unwrap() = Some(report.clone());
+    }
+
+    pub fn status(&self, aggressiveness: MaintenanceAggressiveness) -> MaintenanceStatus {
+        MaintenanceStatus {
+            aggressiveness,
+            on_ac_power: on_ac_power(),
+            idle: is_idle(),
+            last_runs: self.last_runs.lock().unwrap().clone(),
+            last_report: self.last_report.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// Whether the machine is running on mains power. Machines without a battery count as on AC.
+pub fn on_ac_power() -> bool {
+    #[cfg(all(not(windows), not(target_os = "macos")))]
+    {
+        let Ok(supplies) = fs::read_dir("/sys/class/power_supply") else {
+            return true;
+        };
+        let mut has_mains = false;
+        for supply in supplies.flatten() {
+            let path = supply.path();
+            let kind = fs::read_to_string(path.join("type")).unwrap_or_default();
+            if kind.trim() == "Mains" {
+                has_mains = true;
+                if fs::read_to_string(path.join("online"))
+                    .unwrap_or_default()
+                    .trim()
+                    == "1"
+                {
+                    return true;
+                }
+            }
+        }
+        !has_mains
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        command_output("pmset", &["-g", "batt"])
+            .map(|out| !out.contains("Battery Power"))
+            .unwrap_or(true)
+    }
+
+    #[cfg(windows)]
+    {
+        // BatteryStatus 2 = on AC; no battery prints nothing
+        command_output(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                "(Get-CimInstance Win32_Battery).BatteryStatus",
+            ],
+        )
+        .map(|out| out.trim().is_empty() || out.trim() == "2")
+        .unwrap_or(true)
+    }
+}
+
+/// Whether the machine is mostly idle, judged by CPU load
+pub fn is_idle() -> bool {
+    system_load().is_some_and(|load| load < IDLE_LOAD_THRESHOLD)
+}
+
+/// CPU load normalized to 0..1 (1-minute load average divided by core count)
+fn system_load() -> Option<f64> {
+    let cores = std::thread::available_parallelism()
+        .map(|n| n.get() as f64)
+        .unwrap_or(1.0);
+
+    #[cfg(all(not(windows), not(target_os = "macos")))]
+    {
+        let loadavg = fs::read_to_string("/proc/loadavg").ok()?;
+        let load: f64 = loadavg.split_whitespace().next()?.parse().ok()?;
+        Some(load / cores)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // "{ 1.23 1.10 0.98 }"
+        let out = command_output("sysctl", &["-n", "vm.loadavg"])?;
+        let load: f64 = out
+            .trim_matches(|c: char| c == '{' || c == '}' || c.is_whitespace())
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()?;
+        Some(load / cores)
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = cores;
+        let out = command_output(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                "(Get-CimInstance Win32_Processor | Measure-Object LoadPercentage -Average).Average",
+            ],
+        )?;
+        let percent: f64 = out.trim().parse().ok()?;
+        Some(percent / 100.0)
+    }
+}
+
+#[cfg(any(target_os = "macos", windows))]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(args);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd.output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Decide whether deferred maintenance may run now
+fn may_run(aggressiveness: MaintenanceAggressiveness, max_overdue: i64) -> bool {
+    match aggressiveness {
+        MaintenanceAggressiveness::Aggressive => true,
+        MaintenanceAggressiveness::Balanced => {
+            max_overdue >= MAX_DEFERRAL_SECS || is_idle() || on_ac_power()
+        }
+        MaintenanceAggressiveness::Conservative => is_idle() && on_ac_power(),
+    }
+}
+
+/// Remove log files older than LOG_MAX_AGE from the app log directory
+fn rotate_logs(app: &AppHandle) -> Result<String, String> {
+    let Ok(log_dir) = app.path().app_log_dir() else {
+        return Ok("No log directory".to_string());
+    };
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for entry in fs::read_dir(&log_dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("log") {
+            continue;
+        }
+        let age = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or_default();
+        if age > LOG_MAX_AGE {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+            removed += 1;
+        }
+    }
+    Ok(format!("Removed {} old log files", removed))
+}
+
+fn run_task(app: &AppHandle, task: MaintenanceTask) -> MaintenanceTaskResult {
+    let store = app.state::<JsonStore>();
+    let started = Instant::now();
+
+    let outcome = match task {
+        MaintenanceTask::Backup => store
+            .create_snapshot("backup", SNAPSHOTS_KEPT)
+            .map(|id| format!("Snapshot {} created", id)),
+        MaintenanceTask::IntegrityCheck => store.check_integrity().and_then(|problems| {
+            if problems.is_empty() {
+                Ok("No problems found".to_string())
+            } else {
+                Err(problems.join("; "))
+            }
+        }),
+        MaintenanceTask::IndexRebuild => Ok(format!("Indexed {} IDs", store.rebuild_item_index())),
+        MaintenanceTask::LogRotation => rotate_logs(app),
+    };
+
+    if let Err(e) = &outcome {
+        log::warn!("Maintenance task {:?} failed: {}", task, e);
+    }
+    let (success, message) = match outcome {
+        Ok(message) => (true, message),
+        Err(e) => (false, e),
+    };
+    MaintenanceTaskResult {
+        task,
+        success,
+        message,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+/// Run the given tasks now, regardless of idle/power state
+pub fn run_tasks(app: &AppHandle, tasks: &[MaintenanceTask], forced: bool) -> MaintenanceReport {
+    let report = MaintenanceReport {
+        results: tasks.iter().map(|task| run_task(app, *task)).collect(),
+        ran_at: chrono::Utc::now().to_rfc3339(),
+        forced,
+    };
+    app.state::<MaintenanceState>().record(&report);
+    report
+}
+
+/// Run every task now (used by `run_maintenance_now`)
+pub fn run_all(app: &AppHandle) -> MaintenanceReport {
+    run_tasks(app, &MaintenanceTask::ALL, true)
+}
+
+/// Periodically run due maintenance once the machine is idle or on AC power
+/// (depending on the configured aggressiveness)
+pub fn spawn_scheduler(app: AppHandle) {
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+
+            let aggressiveness = app.state::<JsonStore>().get_setting_or(
+                MAINTENANCE_AGGRESSIVENESS_KEY,
+                MaintenanceAggressiveness::default(),
+            );
+            let (due, max_overdue) = app.state::<MaintenanceState>().due_tasks();
+            if due.is_empty() || !may_run(aggressiveness, max_overdue) {
+                continue;
+            }
+            run_tasks(&app, &due, false);
+        }
+    });
+}
@@ -0,0 +1,39 @@
+use crate::models::ChecklistItem;
+use crate::todos;
+use pulldown_cmark::{Options, Parser, html};
+
+/// Render note markdown to HTML that is safe to inject into the preview.
+/// Raw HTML, scripts and event handlers are stripped; task list checkboxes are kept.
+pub fn render(content: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new_ext(content, options));
+
+    ammonia::Builder::default()
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+/// Checklist lines (`- [ ] task` / `- [x] task`) of a note, in order
+pub fn checklist(content: &str) -> Vec<ChecklistItem> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(line, text)| {
+            let completed = todos::parse_checkbox(text)?;
+            // parse_checkbox guarantees a `- [ ]`-style prefix after the indent
+            let text = text.trim_start()[5..].trim().to_string();
+            Some(ChecklistItem {
+                line,
+                text,
+                completed,
+            })
+        })
+        .filter(|item| !item.text.is_empty())
+        .collect()
+}
@@ -1,12 +1,17 @@
-use crate::json_store::{Metadata, ProjectData, ProjectInfo};
+use crate::json_store::{JsonStore, Metadata, ProjectData, ProjectInfo};
 use crate::models::*;
+use crate::schema;
+use chrono::Utc;
 use log::info;
 use rusqlite::{params, Connection};
+use serde::Serialize;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
 
 /// Result of a migration operation
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct MigrationResult {
     pub projects_migrated: usize,
     pub items_migrated: usize,
@@ -15,9 +20,63 @@ pub struct MigrationResult {
     pub settings_migrated: usize,
 }
 
-/// Check if migration is needed and perform it if so
-/// Returns Ok(Some(result)) if migration was performed, Ok(None) if not needed
-pub fn migrate_if_needed(config_dir: &Path, data_dir: &Path) -> Result<Option<MigrationResult>, String> {
+/// Progress of a running migration, emitted as `migration-progress` events
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationProgress {
+    pub current: usize,
+    pub total: usize,
+    pub project_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum MigrationStatus {
+    NotNeeded,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Outcome of the startup migration, returned by `get_migration_report`
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub status: MigrationStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<MigrationResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
+
+/// Managed state holding the latest migration report
+pub struct MigrationState(pub Mutex<MigrationReport>);
+
+impl MigrationState {
+    pub fn new(pending: bool) -> Self {
+        Self(Mutex::new(MigrationReport {
+            status: if pending {
+                MigrationStatus::Running
+            } else {
+                MigrationStatus::NotNeeded
+            },
+            result: None,
+            error: None,
+            started_at: pending.then(|| Utc::now().to_rfc3339()),
+            finished_at: None,
+        }))
+    }
+
+    /// Reset the report for a migration that is about to start
+    pub fn mark_running(&self) {
+        *self.0.lock().unwrap() = Self::new(true).0.into_inner().unwrap();
+    }
+}
+
+/// Find a SQLite database that still needs migrating, if any
+fn find_pending_database(config_dir: &Path, data_dir: &Path) -> Option<PathBuf> {
     let metadata_path = data_dir.join("metadata.json");
 
     // If metadata.json already exists with projects, no migration needed
@@ -27,7 +86,7 @@ pub fn migrate_if_needed(config_dir: &Path, data_dir: &Path) -> Result<Option<Mi
             if let Ok(metadata) = serde_json::from_str::<Metadata>(&content) {
                 if !metadata.project_ids.is_empty() || !metadata.projects.is_empty() {
                     info!("metadata.json exists with projects, skipping migration");
-                    return Ok(None);
+                    return None;
                 }
             }
         }
@@ -37,19 +96,84 @@ pub fn migrate_if_needed(config_dir: &Path, data_dir: &Path) -> Result<Option<Mi
     let sqlite_path_data = data_dir.join("projects.db");
     let sqlite_path_config = config_dir.join("projects.db");
 
-    let sqlite_path = if sqlite_path_data.exists() {
+    if sqlite_path_data.exists() {
         info!("Found SQLite database in data directory: {:?}", sqlite_path_data);
-        sqlite_path_data
+        Some(sqlite_path_data)
     } else if sqlite_path_config.exists() {
         info!("Found SQLite database in config directory: {:?}", sqlite_path_config);
-        sqlite_path_config
+        Some(sqlite_path_config)
     } else {
         info!("No existing SQLite database found, starting fresh");
+        None
+    }
+}
+
+/// Check if a migration from SQLite is needed
+pub fn needs_migration(config_dir: &Path, data_dir: &Path) -> bool {
+    find_pending_database(config_dir, data_dir).is_some()
+}
+
+/// Run the migration on a background thread, emitting `migration-progress`
+/// events per project and `migration-complete` with the final report.
+/// The store refuses saves until it is done, and is reloaded afterwards so the
+/// migrated projects show up. `on_done` runs once the store is usable again.
+pub fn spawn_background_migration(
+    app: AppHandle,
+    config_dir: PathBuf,
+    data_dir: PathBuf,
+    on_done: impl FnOnce(&AppHandle) + Send + 'static,
+) {
+    app.state::<JsonStore>().set_migrating(true);
+    std::thread::spawn(move || {
+        let progress_app = app.clone();
+        let outcome = migrate_if_needed(&config_dir, &data_dir, &|progress| {
+            let _ = progress_app.emit("migration-progress", progress);
+        });
+
+        let store = app.state::<JsonStore>();
+        if outcome.is_ok() {
+            if let Err(e) = store.reload() {
+                log::error!("Failed to reload store after migration: {}", e);
+            }
+        }
+        store.set_migrating(false);
+
+        let state = app.state::<MigrationState>();
+        let report = {
+            let mut report = state.0.lock().unwrap();
+            match outcome {
+                Ok(result) => {
+                    report.status = MigrationStatus::Completed;
+                    report.result = result;
+                }
+                Err(e) => {
+                    log::error!("Migration failed: {}", e);
+                    report.status = MigrationStatus::Failed;
+                    report.error = Some(e);
+                }
+            }
+            report.finished_at = Some(Utc::now().to_rfc3339());
+            report.clone()
+        };
+
+        let _ = app.emit("migration-complete", report);
+        on_done(&app);
+    });
+}
+
+/// Check if migration is needed and perform it if so
+/// Returns Ok(Some(result)) if migration was performed, Ok(None) if not needed
+pub fn migrate_if_needed(
+    config_dir: &Path,
+    data_dir: &Path,
+    on_progress: &dyn Fn(MigrationProgress),
+) -> Result<Option<MigrationResult>, String> {
+    let Some(sqlite_path) = find_pending_database(config_dir, data_dir) else {
         return Ok(None);
     };
 
     info!("Migrating from SQLite to JSON...");
-    let result = migrate_sqlite_to_json(&sqlite_path, data_dir)?;
+    let result = migrate_sqlite_to_json(&sqlite_path, data_dir, on_progress)?;
 
     // Rename the old database to mark it as migrated
     let migrated_path = sqlite_path.with_extension("db.migrated");
@@ -69,7 +193,11 @@ pub fn migrate_if_needed(config_dir: &Path, data_dir: &Path) -> Result<Option<Mi
 }
 
 /// Migrate data from SQLite database to JSON files
-fn migrate_sqlite_to_json(sqlite_path: &Path, data_dir: &Path) -> Result<MigrationResult, String> {
+fn migrate_sqlite_to_json(
+    sqlite_path: &Path,
+    data_dir: &Path,
+    on_progress: &dyn Fn(MigrationProgress),
+) -> Result<MigrationResult, String> {
     // Open SQLite database
     let conn = Connection::open(sqlite_path)
         .map_err(|e| format!("Failed to open SQLite database: {}", e))?;
@@ -95,13 +223,20 @@ fn migrate_sqlite_to_json(sqlite_path: &Path, data_dir: &Path) -> Result<Migrati
     // Get all projects
     let sqlite_projects = get_sqlite_projects(&conn)?;
     let mut projects = Vec::new();
+    let total = sqlite_projects.len();
 
-    for project in sqlite_projects {
+    for (index, project) in sqlite_projects.into_iter().enumerate() {
         let project_id = project.id.clone();
         let project_name = project.name.clone();
         projects.push(ProjectInfo {
             id: project_id.clone(),
-            name: project_name,
+            name: project_name.clone(),
+            todo_progress: None,
+        });
+        on_progress(MigrationProgress {
+            current: index + 1,
+            total,
+            project_name,
         });
 
         // Get items for this project
@@ -119,12 +254,16 @@ fn migrate_sqlite_to_json(sqlite_path: &Path, data_dir: &Path) -> Result<Migrati
 
         // Create ProjectData
         let project_data = ProjectData {
+            version: schema::CURRENT_VERSION,
             id: project.id,
             name: project.name,
             description: project.description,
             metadata: project.metadata,
             items,
             todos: todos_markdown,
+            todo_events: Vec::new(),
+            time_entries: Vec::new(),
+            canvas_layouts: Vec::new(),
             file_cards,
             created_at: project.created_at,
             updated_at: project.updated_at,
@@ -142,7 +281,7 @@ fn migrate_sqlite_to_json(sqlite_path: &Path, data_dir: &Path) -> Result<Migrati
 
     // Write metadata.json
     let metadata = Metadata {
-        version: 1,
+        version: schema::CURRENT_VERSION,
         project_ids: Vec::new(),
         projects,
         global_settings: settings,
@@ -233,6 +372,7 @@ fn get_sqlite_items(conn: &Connection, project_id: &str) -> Result<Vec<Item>, St
                 command_mode: command_mode_str.and_then(|s| s.parse().ok()),
                 command_cwd: row.get(11)?,
                 command_host: row.get(12)?,
+                ..Default::default()
             })
         })
         .map_err(|e| format!("Failed to query items: {}", e))?;
@@ -319,6 +459,7 @@ fn get_sqlite_file_cards(conn: &Connection, project_id: &str) -> Result<Vec<File
                 created_at: row.get(8)?,
                 updated_at: row.get(9)?,
                 is_minimized: row.get::<_, i32>(10).unwrap_or(0) == 1,
+                ..Default::default()
             })
         })
         .map_err(|e| format!("Failed to query file_cards: {}", e))?;
@@ -1,11 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use strum::{Display, EnumString};
 
 // Item types
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display, EnumString)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display, EnumString, Default)]
 #[serde(rename_all = "kebab-case")]
 #[strum(serialize_all = "kebab-case")]
 pub enum ItemType {
+    #[default]
     Note,
     Ide,
     File,
@@ -13,6 +15,22 @@ pub enum ItemType {
     RemoteIde,
     Command,
     CodingAgent,
+    Script,
+    HttpRequest,
+    Docker,
+    Database,
+    Snippet,
+    SshSession,
+}
+
+impl ItemType {
+    /// Item types that run somewhere and use `command_cwd` / `command_host`
+    pub fn runs_commands(&self) -> bool {
+        matches!(
+            self,
+            ItemType::Command | ItemType::Script | ItemType::Docker | ItemType::SshSession
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display, EnumString)]
@@ -45,6 +63,11 @@ pub enum RemoteIdeType {
     Cursor,
     Vscode,
     Zed,
+    /// JetBrains Gateway, which deploys and starts a JetBrains IDE backend on the
+    /// host when none is running there, else joins the running one
+    Gateway,
+    Windsurf,
+    Vscodium,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display, EnumString)]
@@ -55,6 +78,11 @@ pub enum CodingAgentType {
     Opencode,
     GeminiCli,
     Codex,
+    Aider,
+    Amp,
+    CursorAgent,
+    /// An agent from the customAgents registry, named by the item's custom_agent_id
+    Custom,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display, EnumString)]
@@ -77,6 +105,20 @@ pub enum TerminalType {
     Xterm,
     Kitty,
     Alacritty,
+    Ghostty,
+    Wezterm,
+    Warp,
+    Tilix,
+    Foot,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Display, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum DatabaseClient {
+    Psql,
+    Datagrip,
+    Dbeaver,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display, EnumString)]
@@ -87,6 +129,19 @@ pub enum CommandMode {
     Output,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Display, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum CommandShell {
+    Sh,
+    Bash,
+    Zsh,
+    Fish,
+    Pwsh,
+    Nushell,
+    Cmd,
+}
+
 // Working directory
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkingDir {
@@ -103,6 +158,24 @@ pub struct OtherLink {
     pub url: String,
 }
 
+// Kind of link from an umbrella project to a sub-project
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectLinkKind {
+    #[default]
+    Child,
+    Service,
+    Library,
+}
+
+// Link to a sub-project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectLink {
+    pub project_id: String,
+    #[serde(default)]
+    pub kind: ProjectLinkKind,
+}
+
 // Project metadata
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ProjectMetadata {
@@ -116,10 +189,48 @@ pub struct ProjectMetadata {
     pub working_dirs: Option<Vec<WorkingDir>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub section_order: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_projects: Option<Vec<ProjectLink>>,
+    /// Used with default_host by command-like items (and `run_command`) that set
+    /// neither a cwd nor a host of their own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_cwd: Option<String>,
+    /// Used with default_cwd, like it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_host: Option<String>,
+    /// Values of the user-defined fields in the `customFieldSchema` setting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_fields: Option<HashMap<String, String>>,
+    /// Projects this one depends on or relates to (shown in the project graph)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related_project_ids: Option<Vec<String>>,
+    /// Long-running dev servers managed with start_service/stop_service
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub services: Option<Vec<ServiceConfig>>,
 }
 
-// Item
+// Dev server of a project: a local start command with an optional health check
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceConfig {
+    pub id: String,
+    pub name: String,
+    pub command: String,
+    /// Defaults to the project's default_cwd
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Healthy only while something accepts connections on this local port
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Healthy only while this URL answers with a status below 400
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health_url: Option<String>,
+    /// Defaults to ~/.devora/service-logs/{id}.log
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<String>,
+}
+
+// Item
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Item {
     pub id: String,
     pub project_id: String,
@@ -127,6 +238,8 @@ pub struct Item {
     pub item_type: ItemType,
     pub title: String,
     pub content: String,
+    /// IDE items: the IDE. Remote IDE items opened in Gateway: the JetBrains IDE it
+    /// deploys.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ide_type: Option<String>, // Changed to String to support custom IDE IDs
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -143,11 +256,103 @@ pub struct Item {
     pub command_cwd: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub command_host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_client: Option<DatabaseClient>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tmux_session: Option<String>,
+    /// Command items: TCP ports the command is expected to listen on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ports: Option<Vec<u16>>,
+    /// Command and script items: shell that runs the command (default: the default
+    /// shell in settings.json)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_shell: Option<CommandShell>,
+    /// Command and coding agent items: WSL distro to run in instead of Windows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wsl_distro: Option<String>,
+    /// Coding agent items of the custom type: ID of the agent in the customAgents setting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_agent_id: Option<String>,
+    /// On disk only: content too large to inline lives in `blobs/{file}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_blob: Option<String>,
     pub order: i32,
     pub created_at: String,
     pub updated_at: String,
 }
 
+// Fields applied to several items at once. A missing field is left alone,
+// null clears it and a value sets it.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ItemPatch {
+    #[serde(default, deserialize_with = "double_option")]
+    pub ide_type: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub remote_ide_type: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub coding_agent_type: Option<Option<CodingAgentType>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub coding_agent_args: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub coding_agent_env: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub command_mode: Option<Option<CommandMode>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub command_cwd: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub command_host: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub database_client: Option<Option<DatabaseClient>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub language: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub tmux_session: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub ports: Option<Option<Vec<u16>>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub command_shell: Option<Option<CommandShell>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub wsl_distro: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub custom_agent_id: Option<Option<String>>,
+}
+
+impl ItemPatch {
+    pub fn apply(&self, item: &mut Item) {
+        fn set<T: Clone>(field: &mut Option<T>, value: &Option<Option<T>>) {
+            if let Some(value) = value {
+                *field = value.clone();
+            }
+        }
+        set(&mut item.ide_type, &self.ide_type);
+        set(&mut item.remote_ide_type, &self.remote_ide_type);
+        set(&mut item.coding_agent_type, &self.coding_agent_type);
+        set(&mut item.coding_agent_args, &self.coding_agent_args);
+        set(&mut item.coding_agent_env, &self.coding_agent_env);
+        set(&mut item.command_mode, &self.command_mode);
+        set(&mut item.command_cwd, &self.command_cwd);
+        set(&mut item.command_host, &self.command_host);
+        set(&mut item.database_client, &self.database_client);
+        set(&mut item.language, &self.language);
+        set(&mut item.tmux_session, &self.tmux_session);
+        set(&mut item.ports, &self.ports);
+        set(&mut item.command_shell, &self.command_shell);
+        set(&mut item.wsl_distro, &self.wsl_distro);
+        set(&mut item.custom_agent_id, &self.custom_agent_id);
+    }
+}
+
+/// Present-but-null deserializes to `Some(None)` instead of `None`
+fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
 // Project
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
@@ -157,26 +362,310 @@ pub struct Project {
     pub metadata: ProjectMetadata,
     pub created_at: String,
     pub updated_at: String,
+    /// Filled in by the project list commands from ~/.devora/usage.json (machine-local)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_opened_at: Option<String>,
+    /// Filled in project lists, from the machine-local list cache (cache/project_list.json)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub todo_progress: Option<TodoProgress>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub items: Option<Vec<Item>>,
 }
 
-// File card
+// Ordering of the project list
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectSort {
+    #[default]
+    RecentlyUpdated,
+    RecentlyOpened,
+}
+
+impl ProjectSort {
+    /// Sort projects; RecentlyOpened needs `last_opened_at` filled in (see `UsageFile`)
+    pub fn apply(self, projects: &mut [Project]) {
+        match self {
+            // Sort by updated_at descending
+            ProjectSort::RecentlyUpdated => {
+                projects.sort_by(|a, b| b.updated_at.cmp(&a.updated_at))
+            }
+            // Never-opened projects last, by updated_at
+            ProjectSort::RecentlyOpened => projects.sort_by(|a, b| {
+                b.last_opened_at
+                    .cmp(&a.last_opened_at)
+                    .then_with(|| b.updated_at.cmp(&a.updated_at))
+            }),
+        }
+    }
+}
+
+// Launches of an item on this machine (kept in ~/.devora/usage.json, not synced)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ItemLaunches {
+    pub count: u64,
+    pub last_at: String,
+}
+
+// Ordering of a project's items
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemSort {
+    /// The user's drag-and-drop order
+    #[default]
+    Manual,
+    /// Most launched first, ties broken by the most recent launch
+    MostUsed,
+}
+
+impl ItemSort {
+    pub fn apply(self, items: &mut [Item], launches: &HashMap<String, ItemLaunches>) {
+        match self {
+            ItemSort::Manual => items.sort_by_key(|i| i.order),
+            ItemSort::MostUsed => items.sort_by(|a, b| {
+                let (a_launches, b_launches) = (launches.get(&a.id), launches.get(&b.id));
+                b_launches
+                    .map(|l| l.count)
+                    .cmp(&a_launches.map(|l| l.count))
+                    .then_with(|| {
+                        b_launches
+                            .map(|l| &l.last_at)
+                            .cmp(&a_launches.map(|l| &l.last_at))
+                    })
+                    .then_with(|| a.order.cmp(&b.order))
+            }),
+        }
+    }
+}
+
+// Todo completion counts (parsed from the markdown checklist)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TodoProgress {
+    pub total: usize,
+    pub completed: usize,
+    pub percentage: f32,
+}
+
+// Checklist line of a note or of a project's todos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    /// Zero-based line number in the markdown
+    pub line: usize,
+    pub text: String,
+    pub completed: bool,
+}
+
+// TODO/FIXME/HACK comment found in a working dir
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeTodo {
+    /// Name of the working dir the file is in
+    pub working_dir: String,
+    /// Path relative to the working dir, with forward slashes
+    pub path: String,
+    /// One-based line number
+    pub line: usize,
+    pub tag: String,
+    pub text: String,
+}
+
+// Time tracked on a project, optionally against one of its todos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: String,
+    pub project_id: String,
+    /// Text of the todo being worked on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<String>,
+    pub started_at: String,
+    /// None while the timer is running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<i64>,
+}
+
+// Change to a todo, recorded when the todos markdown is saved
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TodoEventKind {
+    Added,
+    Completed,
+    Reopened,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoEvent {
+    pub kind: TodoEventKind,
+    pub text: String,
+    pub at: String,
+}
+
+// Added/completed counts for one day or week (starting Monday)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoStatsBucket {
+    /// YYYY-MM-DD
+    pub start: String,
+    pub added: usize,
+    pub completed: usize,
+}
+
+// Todo activity of a project over the last `days` days
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoStats {
+    pub days: u32,
+    pub added: usize,
+    pub completed: usize,
+    pub per_day: Vec<TodoStatsBucket>,
+    pub per_week: Vec<TodoStatsBucket>,
+    /// Mean time from a todo being added to being completed, for todos completed in range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_completion_hours: Option<f64>,
+}
+
+// Kanban column with the todos whose status it is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoColumn {
+    pub name: String,
+    /// Text is shown without the `@status(...)` marker
+    pub todos: Vec<ChecklistItem>,
+}
+
+// Ordering of a filtered todo list
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoSort {
+    /// As written in the markdown
+    #[default]
+    Document,
+    /// `!p1` first, todos without a priority last
+    Priority,
+    /// Earliest `@due(...)` first, todos without a due date last
+    DueDate,
+}
+
+// Open todo from any project, for cross-project views and reminders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectTodo {
+    pub project_id: String,
+    pub project_name: String,
+    /// Todo text without the due and status markers
+    pub text: String,
+    /// YYYY-MM-DD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+    pub overdue: bool,
+}
+
+// Which open todos a cross-project query returns
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TodoFilter {
+    /// Only todos due within this many days (0: due today or overdue)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_within_days: Option<i64>,
+    /// Only todos with a priority of at least `!p{max_priority}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_priority: Option<u8>,
+}
+
+// Node of the project tree with todo progress rolled up across sub-projects
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectTreeNode {
+    pub project: Project,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_kind: Option<ProjectLinkKind>,
+    pub todo_progress: TodoProgress,
+    pub rollup_progress: TodoProgress,
+    pub children: Vec<ProjectTreeNode>,
+}
+
+// Item found by a cross-project search, with the name of the project it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemSearchResult {
+    pub item: Item,
+    pub project_name: String,
+}
+
+// Project in the relationship graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectGraphNode {
+    pub id: String,
+    pub name: String,
+}
+
+// Link between two projects: a related-project reference (no link_kind) or a sub-project link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectGraphEdge {
+    pub source: String,
+    pub target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_kind: Option<ProjectLinkKind>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectGraph {
+    pub nodes: Vec<ProjectGraphNode>,
+    pub edges: Vec<ProjectGraphEdge>,
+}
+
+// What a canvas card shows
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CardKind {
+    #[default]
+    File,
+    /// Sticky note with markdown `content`
+    Note,
+    /// Link to `url`
+    Link,
+}
+
+// File card (or note/link card on the canvas)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct FileCard {
     pub id: String,
     pub project_id: String,
+    #[serde(default)]
+    pub kind: CardKind,
+    /// Title of note and link cards
     pub filename: String,
+    /// Empty for note and link cards
     pub file_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
     pub position_x: f64,
     pub position_y: f64,
     pub is_expanded: bool,
     pub is_minimized: bool,
     pub z_index: i32,
+    /// Per-card override of the default read size (bytes)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u64>,
     pub created_at: String,
     pub updated_at: String,
 }
 
+// Position and state of one card in a saved canvas layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardPlacement {
+    pub card_id: String,
+    pub position_x: f64,
+    pub position_y: f64,
+    pub is_expanded: bool,
+    pub is_minimized: bool,
+    pub z_index: i32,
+}
+
+// Named arrangement of a project's canvas cards ("debugging", "review", ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanvasLayout {
+    pub name: String,
+    pub cards: Vec<CardPlacement>,
+    pub saved_at: String,
+}
+
 // Export/Import data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportData {
@@ -187,6 +676,52 @@ pub struct ExportData {
     pub items: Vec<Item>,
     #[serde(skip_serializing_if = "Option::is_none", rename = "fileCards")]
     pub file_cards: Option<Vec<FileCardRow>>,
+    /// Incremental exports: time of the export this delta builds on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// Incremental exports: projects deleted since the previous export
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        rename = "deletedProjectIds"
+    )]
+    pub deleted_project_ids: Option<Vec<String>>,
+}
+
+// Last successful incremental export to a destination (machine-local)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ExportMark {
+    pub exported_at: String,
+    /// Content hash of each project at export time
+    pub project_hashes: HashMap<String, String>,
+}
+
+// Tool a project needs, detected from marker/version files in its working dirs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolRequirement {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// File the requirement was detected from
+    pub source: String,
+}
+
+// What a teammate needs installed/configured to work on a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentManifest {
+    pub tools: Vec<ToolRequirement>,
+    #[serde(rename = "sshHosts")]
+    pub ssh_hosts: Vec<String>,
+    /// Names only - values are never exported
+    #[serde(rename = "envVars")]
+    pub env_vars: Vec<String>,
+}
+
+// Single-file project handoff: the project export plus its environment manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoffPackage {
+    pub export: ExportData,
+    pub environment: EnvironmentManifest,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -195,6 +730,8 @@ pub struct ImportData {
     pub items: Vec<Item>,
     #[serde(rename = "fileCards")]
     pub file_cards: Option<Vec<FileCardRow>>,
+    #[serde(default, rename = "deletedProjectIds")]
+    pub deleted_project_ids: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -206,6 +743,21 @@ pub struct ImportResult {
     #[serde(rename = "fileCardsImported")]
     pub file_cards_imported: i32,
     pub skipped: i32,
+    /// Replace imports, and incremental imports that delete projects: snapshot of the
+    /// data before the import (see restore_snapshot)
+    #[serde(skip_serializing_if = "Option::is_none", rename = "snapshotId")]
+    pub snapshot_id: Option<String>,
+    /// Incremental imports: projects deleted because the export marks them deleted
+    #[serde(skip_serializing_if = "Option::is_none", rename = "projectsDeleted")]
+    pub projects_deleted: Option<i32>,
+}
+
+// Token required to run a replace import, with what the import will delete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceImportConfirmation {
+    pub token: String,
+    #[serde(rename = "projectsToDelete")]
+    pub projects_to_delete: usize,
 }
 
 // Raw row types (metadata as JSON string)
@@ -217,19 +769,30 @@ pub struct ProjectRow {
     pub metadata: String,
     pub created_at: String,
     pub updated_at: String,
+    /// Todos markdown, including `@status(...)` markers (absent in older exports)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub todos: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct FileCardRow {
     pub id: String,
     pub project_id: String,
+    #[serde(default)]
+    pub kind: CardKind,
     pub filename: String,
     pub file_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
     pub position_x: f64,
     pub position_y: f64,
     pub is_expanded: i32,
     pub is_minimized: i32,
     pub z_index: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u64>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -240,43 +803,967 @@ pub struct CommandResult {
     pub stdout: String,
     pub stderr: String,
     pub exit_code: i32,
+    /// Background mode: ID of the process in the running-process list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process_id: Option<String>,
+    /// Killed because it ran past its timeout
+    #[serde(default)]
+    pub timed_out: bool,
 }
 
-// Directory listing entry
+// Background process started from the app, tracked until it exits
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DirEntry {
-    pub name: String,
-    pub is_dir: bool,
+pub struct RunningProcess {
+    pub id: String,
+    /// Local PID, or the PID on `host`
+    pub pid: u32,
+    pub command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Jump host chain the process was started through, to reach it again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jump_hosts: Option<String>,
+    /// WSL distro the process runs in (the PID is of its wsl.exe)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wsl_distro: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    pub started_at: String,
 }
 
-// Directory listing result
+// Payload of the `process://exited` event
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DirListing {
-    pub current_path: String,
-    pub entries: Vec<DirEntry>,
+pub struct ProcessExit {
+    pub id: String,
+    /// None when killed by a signal or for remote processes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
 }
 
-// Read file result for drag-drop
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+// Payload of the `command://output` event: a piece of a streamed command's output
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ReadFileResult {
-    pub filename: String,
-    pub content: String,
-    pub file_size: u64,
+pub struct CommandOutputChunk {
+    pub run_id: String,
+    pub stream: OutputStream,
+    pub data: String,
 }
 
-// File info for virtual scrolling
+// Payload of the `command://finished` event
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FileInfo {
-    pub filename: String,
-    pub file_size: u64,
-    pub line_count: usize,
+pub struct CommandRunFinished {
+    pub run_id: String,
+    /// None when killed by a signal or cancelled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub cancelled: bool,
 }
 
-// File lines result for virtual scrolling
+// Process listening on a local TCP port
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FileLinesResult {
-    pub lines: Vec<String>,
-    pub start_line: usize,
+pub struct PortProcess {
+    pub pid: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+// Whether a local TCP port is taken (result of check_port)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortStatus {
+    pub port: u16,
+    pub in_use: bool,
+    /// Empty when the listener couldn't be identified (e.g. another user's process)
+    pub processes: Vec<PortProcess>,
+}
+
+// Outcome of one Command item in a run_item_sequence or run_commands_parallel run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemRunResult {
+    pub item_id: String,
+    pub title: String,
+    /// Started (background) or exited with code 0
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<CommandResult>,
+    /// The command could not be run at all (e.g. host offline)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Outcome of a run_item_sequence run, with a result per step that ran
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceRunResult {
+    pub total_steps: usize,
+    pub steps: Vec<ItemRunResult>,
+    pub success: bool,
+}
+
+// Outcome of a run_commands_parallel run, one result per item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParallelRunResult {
+    pub results: Vec<ItemRunResult>,
+    pub success: bool,
+}
+
+// Outcome of a scheduled run of a Command item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledRunOutcome {
+    pub started_at: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Cron-like schedule of a Command item (stored machine-local in ~/.devora/schedules.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemSchedule {
+    pub item_id: String,
+    pub project_id: String,
+    /// Five-field cron expression or an alias such as `@daily`
+    pub cron: String,
+    /// Command the user confirmed when scheduling it, for commands the policy asks
+    /// about. It stops counting once the item's command changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmed_command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<ScheduledRunOutcome>,
+}
+
+// Entry of list_scheduled_runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledRun {
+    pub item_id: String,
+    pub project_id: String,
+    pub title: String,
+    pub cron: String,
+    /// None when the expression never matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<ScheduledRunOutcome>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceState {
+    Stopped,
+    /// Started, health check not passing yet
+    Starting,
+    /// Up and passing its health check (if it has one)
+    Running,
+    Unhealthy,
+    /// Exited without being stopped
+    Crashed,
+}
+
+// Status of a project service, also the payload of the `service://status` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    pub service_id: String,
+    pub project_id: String,
+    pub state: ServiceState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub log_file: String,
+}
+
+// Progress of a script item run, sent over a channel as each step executes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ScriptEvent {
+    StepStarted {
+        step: usize,
+        command: String,
+    },
+    Output {
+        step: usize,
+        line: String,
+    },
+    StepFinished {
+        step: usize,
+        exit_code: i32,
+        stderr: String,
+    },
+}
+
+// Outcome of a script item run (stops at the first failing step)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptRunResult {
+    pub total_steps: usize,
+    pub steps_run: usize,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_step: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpHeader {
+    pub name: String,
+    pub value: String,
+}
+
+// Response of an HTTP request item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<HttpHeader>,
+    pub body: String,
+    pub duration_ms: u64,
+}
+
+// Live status of a project's GitHub repository (cached for a few minutes)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GithubRepoStatus {
+    pub owner: String,
+    pub repo: String,
+    pub stars: u64,
+    pub open_issues: u64,
+    pub open_prs: u64,
+    pub default_branch: String,
+    /// Latest Actions run on the default branch: its conclusion (success, failure,
+    /// ...) or, while running, its status (queued, in_progress)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ci_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ci_url: Option<String>,
+    pub fetched_at: String,
+}
+
+// Container managed by a Docker item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerStatus {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    pub state: String,
+    pub status: String,
+}
+
+// Page title, description and favicon of a URL item (cached in cache/url_metadata.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlMetadata {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+    pub fetched_at: String,
+}
+
+// Directory listing entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+    /// Bytes (of the link target for symlinks)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Last modification (RFC 3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+}
+
+// Ordering of a directory listing (directories always come first)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DirSort {
+    #[default]
+    Name,
+    Size,
+    Modified,
+}
+
+// Directory listing result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirListing {
+    pub current_path: String,
+    pub entries: Vec<DirEntry>,
+}
+
+// Remote directory listing from sftp_list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SftpListing {
+    pub current_path: String,
+    pub entries: Vec<SftpEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SftpEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub size: u64,
+    /// Mode string as shown by `ls -l`, e.g. "-rw-r--r--"
+    pub permissions: String,
+    /// Modification time as shown by `ls -l`, e.g. "Jan 2 10:00"
+    pub modified: String,
+}
+
+// Read file result for drag-drop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadFileResult {
+    pub filename: String,
+    pub content: String,
+    pub file_size: u64,
+    /// Detected encoding the content was transcoded from, e.g. "UTF-8" or "GBK"
+    pub encoding: String,
+    /// Chunk mode: byte range actually decoded, without characters cut off at the
+    /// requested offsets. The next chunk should start at `chunk_end`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_start: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_end: Option<u64>,
+    /// Modification time when read (RFC 3339); pass back to write_file_content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<String>,
+}
+
+// Result of writing a file from a file card
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteFileResult {
+    pub file_size: u64,
+    /// New modification time, for the next write
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<String>,
+    /// Copy of the previous content, if the file existed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<String>,
+}
+
+// How a file card previews a file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FilePreviewKind {
+    Text,
+    Image,
+    Pdf,
+    Binary,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePreview {
+    pub filename: String,
+    pub file_size: u64,
+    pub kind: FilePreviewKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Text files: the (possibly truncated) content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Images and PDFs up to file_preview::MEDIA_MAX_BYTES: the whole file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_base64: Option<String>,
+    /// Other binaries: hex dump of the first bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hex_dump: Option<String>,
+}
+
+// File size limits (configurable via settings)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileLimits {
+    /// Files larger than this are never read in full
+    pub absolute_max_size: u64,
+    /// Bytes read when no per-card or per-call size is given
+    pub default_read_size: u64,
+    /// Upper bound for a single streamed chunk
+    pub stream_chunk_size: u64,
+}
+
+// Chunk sent over a channel by stream_file_chunks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChunk {
+    pub offset: u64,
+    pub content: String,
+}
+
+// Where a chunked stream stopped, so the caller can request more
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileStreamProgress {
+    pub next_offset: u64,
+    pub file_size: u64,
+    pub eof: bool,
+}
+
+// File info for virtual scrolling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileInfo {
+    pub filename: String,
+    pub file_size: u64,
+    pub line_count: usize,
+}
+
+// Line of a file matching an in-card search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSearchMatch {
+    /// 0-based, as used by read_file_lines
+    pub line: usize,
+    /// Character offset of the match in the line
+    pub column: usize,
+    /// The line, shortened around the match if it is long
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSearchResult {
+    pub matches: Vec<FileSearchMatch>,
+    /// The search stopped at its match limit
+    pub truncated: bool,
+}
+
+// File lines result for virtual scrolling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileLinesResult {
+    pub lines: Vec<String>,
+    pub start_line: usize,
+    pub total_lines: usize,
+    /// Detected encoding the lines were transcoded from
+    pub encoding: String,
+}
+
+// Unified diff hunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub header: String,
+    pub lines: Vec<String>,
+}
+
+// File diff result (changes since the last commit, or since `base`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiffResult {
+    pub path: String,
+    /// Commit, branch or tag the file is compared with
+    pub base: String,
+    pub repo_root: String,
+    /// False for files git doesn't track yet (no diff)
+    pub tracked: bool,
+    /// Raw unified diff, empty if the file is unchanged
+    pub diff: String,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum SyncBackend {
+    WebDav,
+    S3,
+}
+
+// Remote sync configuration (stored in ~/.devora/settings.json, never synced)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConfig {
+    pub backend: SyncBackend,
+    /// WebDAV folder URL or S3 endpoint (e.g. https://s3.us-east-1.amazonaws.com)
+    pub endpoint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Folder inside the endpoint/bucket to sync into
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// WebDAV username or S3 access key ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// WebDAV password or S3 secret access key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+// Result of a sync run (relative file paths)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub pushed: Vec<String>,
+    pub pulled: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub synced_at: String,
+}
+
+// Sync status for the settings UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub configured: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<SyncBackend>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_sync: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Local files changed since the last sync
+    pub pending_changes: usize,
+}
+
+// A cloud-sync conflict copy of metadata.json (e.g. "metadata (1).json")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataConflict {
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<String>,
+    /// Whether the copy is newer than metadata.json (its settings win)
+    pub is_newer: bool,
+    /// Names of projects only listed in the copy
+    pub projects_added: Vec<String>,
+    /// Setting keys taken from the copy
+    pub settings_changed: Vec<String>,
+}
+
+// Conflict report for metadata.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictReport {
+    pub conflicts: Vec<MetadataConflict>,
+    /// True if the merge was applied and the copies moved to backups/
+    pub resolved: bool,
+}
+
+// Unsaved UI state persisted per window for crash recovery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Draft {
+    pub window: String,
+    pub key: String,
+    pub content: String,
+    pub updated_at: String,
+}
+
+// Commit of a working dir's repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitCommit {
+    pub hash: String,
+    pub author: String,
+    /// Author date, ISO 8601
+    pub date: String,
+    /// Subject line
+    pub message: String,
+    /// Commit page on the git host, when the `origin` remote is a known provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+// Repository health of a working dir
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GitStatus {
+    /// None when HEAD is detached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    /// Changed, staged and untracked files
+    pub dirty_files: u32,
+    /// None in a repository without commits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_commit: Option<GitCommit>,
+}
+
+// Branch of a working dir's repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitBranch {
+    /// Short name, e.g. `main` or `origin/main`
+    pub name: String,
+    pub is_current: bool,
+    pub is_remote: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    /// Date of the branch's last commit, ISO 8601
+    pub last_commit_date: String,
+}
+
+// Entry of `git worktree list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitWorktree {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub head: Option<String>,
+    /// Short branch name; None when detached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// The repository's main working tree (can't be removed)
+    pub is_main: bool,
+    pub locked: bool,
+    /// Its directory is gone; `git worktree prune` would drop it
+    pub prunable: bool,
+}
+
+// Result of a git quick action: what git printed and the repository afterwards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitActionResult {
+    pub output: String,
+    pub status: GitStatus,
+}
+
+// Git status of one of a project's working dirs; `error` is set instead of
+// `status` for dirs that aren't repos or whose host is offline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkingDirGitStatus {
+    pub name: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<GitStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Working dir repo of a project, for the cross-project repo overview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRepoStatus {
+    pub project_id: String,
+    pub project_name: String,
+    pub repo: WorkingDirGitStatus,
+}
+
+// Commit in the git-backed data directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreCommit {
+    pub hash: String,
+    pub message: String,
+    pub committed_at: String,
+}
+
+// Last-known reachability of an SSH host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostStatus {
+    pub host: String,
+    pub reachable: bool,
+    pub last_checked: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Host alias from ~/.ssh/config with the settings ssh resolves for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshHostEntry {
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<String>,
+    /// Set for hosts reached through a jump host
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_jump: Option<String>,
+}
+
+// Result of test_ssh_connection: each stage of connecting to an SSH host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshConnectionTest {
+    pub host: String,
+    /// The SSH server accepted a connection
+    pub reachable: bool,
+    /// Key-based login succeeded without prompting
+    pub authenticated: bool,
+    /// Server identification line, e.g. "SSH-2.0-OpenSSH_9.6"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<String>,
+    /// TCP connect round trip (not measured for hosts behind a proxy or jump host)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Time for a full login running a no-op command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub login_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// What to do about the error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+// A session of the tmux server on a remote host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TmuxSession {
+    pub name: String,
+    pub windows: u32,
+    /// A client is attached to the session
+    pub attached: bool,
+    /// When the session was started (RFC 3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    /// Working directory new windows start in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+// A WSL distro installed on this machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WslDistro {
+    pub name: String,
+    /// "Running", "Stopped", ...
+    pub state: String,
+    /// WSL version the distro runs under (1 or 2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u8>,
+    /// Used by `wsl` when no distro is named
+    pub is_default: bool,
+}
+
+// User-defined coding agent, from the customAgents setting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomAgent {
+    pub id: String,
+    pub name: String,
+    /// Command template, e.g. `npx my-agent`; `{args}` marks where arguments go
+    /// (default: after the command)
+    pub command: String,
+    /// Arguments used when the item doesn't give its own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<String>,
+    /// Environment variables as a JSON object, below the host's and the item's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+}
+
+// User-defined terminal, from the customTerminals setting. The defaultTerminal
+// setting selects one by ID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomTerminal {
+    pub id: String,
+    pub name: String,
+    /// Command template with `{cwd}` and `{cmd}` placeholders, e.g.
+    /// `myterm --cwd {cwd} -e sh -c {cmd}`. `{cmd}` is a shell command line (for cmd
+    /// on Windows, where it's inserted as is).
+    pub command: String,
+}
+
+// Windows Terminal options, from the windowsTerminal setting
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WindowsTerminalOptions {
+    /// Profile to open (default: Windows Terminal's default profile)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Tab title, kept even when the shell sets its own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Open a tab in the most recent window instead of a new window
+    #[serde(default)]
+    pub new_tab: bool,
+}
+
+// Reusable prompt for coding agents, from the promptTemplates setting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub id: String,
+    pub name: String,
+    /// Prompt text; `{project_name}`, `{todos}` (the open todos as a list) and
+    /// `{path}` (where the agent runs) are filled in at launch
+    pub body: String,
+    /// Agent launched when none is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<CodingAgentType>,
+}
+
+// Coding agent launched from the app, kept so it can be resumed later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentSession {
+    pub id: String,
+    pub agent: CodingAgentType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_agent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_id: Option<String>,
+    /// Working directory, on `host` or in `wsl_distro` when set
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wsl_distro: Option<String>,
+    /// Arguments the agent was started with, without the flags of a resume
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<TerminalType>,
+    pub started_at: String,
+}
+
+// Headless coding agent run that is still going
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningAgent {
+    pub id: String,
+    pub agent: String,
+    /// Working directory, on `host` or in `wsl_distro` when set
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wsl_distro: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    pub started_at: String,
+}
+
+// Preferred terminal and agent configuration when launching a coding agent for an SSH host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostAgentOverride {
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal_type: Option<TerminalType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coding_agent_args: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coding_agent_env: Option<String>,
+}
+
+// Kind of value a custom project field holds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomFieldType {
+    Text,
+    Number,
+    Boolean,
+    /// YYYY-MM-DD
+    Date,
+    /// One of the definition's options
+    Select,
+}
+
+// User-defined project field (stored as a JSON array in the customFieldSchema setting)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomFieldDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: CustomFieldType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<String>>,
+}
+
+// Command execution policy (stored as JSON in the commandPolicy setting)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandPolicy {
+    /// When non-empty, only these executables run outside trusted projects
+    pub allowed_executables: Vec<String>,
+    /// Never run, even in trusted projects
+    pub denied_executables: Vec<String>,
+    /// Regexes of commands that need confirming (None: built-in destructive patterns)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm_patterns: Option<Vec<String>>,
+}
+
+// Operation a user-defined action dispatches to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ActionOperation {
+    OpenProject { project_id: String },
+    RunItem { item_id: String },
+    StartTimer { project_id: String },
+}
+
+// User-defined action (bound to shortcuts, tray entries, deep links, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Action {
+    pub id: String,
+    pub label: String,
+    pub operation: ActionOperation,
+}
+
+// Audit log entry for an executed action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionAuditEntry {
+    pub action_id: String,
+    pub source: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub executed_at: String,
+}
+
+// Project root found by scanning a directory, ready to import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectCandidate {
+    pub name: String,
+    pub path: String,
+    /// What identified the directory as a project (".git", "package.json", "Cargo.toml")
+    pub markers: Vec<String>,
+    pub metadata: ProjectMetadata,
+    pub already_imported: bool,
+}
+
+// Result of a temp file cleanup pass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupReport {
+    pub removed: Vec<String>,
+    pub bytes_freed: u64,
+    pub errors: Vec<String>,
+    pub ran_at: String,
+}
+
+// Background maintenance work deferred until the machine is idle or on AC power
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceTask {
+    Backup,
+    IntegrityCheck,
+    IndexRebuild,
+    LogRotation,
+}
+
+// When deferred maintenance may run:
+// conservative = idle and on AC, balanced = idle or on AC (or a day overdue), aggressive = whenever due
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Display, EnumString, Default)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum MaintenanceAggressiveness {
+    Conservative,
+    #[default]
+    Balanced,
+    Aggressive,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceTaskResult {
+    pub task: MaintenanceTask,
+    pub success: bool,
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    pub results: Vec<MaintenanceTaskResult>,
+    pub ran_at: String,
+    /// True when started with run_maintenance_now rather than by the scheduler
+    pub forced: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    pub aggressiveness: MaintenanceAggressiveness,
+    pub on_ac_power: bool,
+    pub idle: bool,
+    pub last_runs: HashMap<MaintenanceTask, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_report: Option<MaintenanceReport>,
 }
 
 // Database path validation result (legacy - kept for backward compatibility)
@@ -0,0 +1,147 @@
+use crate::json_store::JsonStore;
+use crate::models::CommandPolicy;
+use crate::settings::SettingsFile;
+use regex::Regex;
+
+/// Global setting holding the command execution policy as JSON
+pub const COMMAND_POLICY_KEY: &str = "commandPolicy";
+
+/// Start of the error returned for a destructive command that wasn't confirmed, so
+/// the UI can ask and retry with `confirmed`
+pub const CONFIRMATION_REQUIRED: &str = "Confirmation required";
+
+/// Patterns that need confirmation when the policy doesn't list its own
+const DEFAULT_CONFIRM_PATTERNS: &[&str] = &[
+    r"\brm\s+(-\S+\s+)*-[a-zA-Z]*[rR]",
+    r"(?i)\bdrop\s+(table|database|schema)\b",
+    r"(?i)\btruncate\s+table\b",
+    r"\bgit\s+push\b.*\s(-f|--force)\b",
+    r"\bgit\s+reset\s+--hard\b",
+    r"\bmkfs(\.\w+)?\b",
+    r"\bdd\b.*\bof=/dev/",
+];
+
+/// Commands that run the command given as their arguments
+const WRAPPERS: &[&str] = &["sudo", "env", "nohup", "time", "exec", "nice"];
+
+pub fn load(store: &JsonStore) -> Result<CommandPolicy, String> {
+    match store.get_setting(COMMAND_POLICY_KEY)? {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse command policy: {}", e)),
+        _ => Ok(CommandPolicy::default()),
+    }
+}
+
+/// Replace the policy. Confirmation patterns must be valid regular expressions.
+pub fn save(store: &JsonStore, policy: &CommandPolicy) -> Result<(), String> {
+    for pattern in policy.confirm_patterns.iter().flatten() {
+        Regex::new(pattern)
+            .map_err(|e| format!("Invalid confirmation pattern \"{}\": {}", pattern, e))?;
+    }
+    let json = serde_json::to_string(policy)
+        .map_err(|e| format!("Failed to serialize command policy: {}", e))?;
+    store.set_setting(COMMAND_POLICY_KEY, &json)
+}
+
+/// Check a command line against the policy before running it. Denied executables
+/// are always refused; projects trusted on this machine skip the allowlist and
+/// confirmations.
+pub fn check(
+    store: &JsonStore,
+    settings: &SettingsFile,
+    project_id: Option<&str>,
+    command: &str,
+    confirmed: bool,
+) -> Result<(), String> {
+    let policy = load(store)?;
+    let executables = executables(command);
+
+    if let Some(denied) = executables
+        .iter()
+        .find(|exe| listed(&policy.denied_executables, exe))
+    {
+        return Err(format!("Command policy blocks \"{}\"", denied));
+    }
+    if project_id.is_some_and(|id| settings.is_trusted(id)) {
+        return Ok(());
+    }
+
+    if !policy.allowed_executables.is_empty() {
+        if let Some(other) = executables
+            .iter()
+            .find(|exe| !listed(&policy.allowed_executables, exe))
+        {
+            return Err(format!(
+                "Command policy only allows listed executables, not \"{}\"",
+                other
+            ));
+        }
+    }
+
+    if !confirmed {
+        let patterns = match policy.confirm_patterns {
+            Some(patterns) => patterns,
+            None => DEFAULT_CONFIRM_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        };
+        for pattern in patterns {
+            if Regex::new(&pattern).is_ok_and(|re| re.is_match(command)) {
+                return Err(format!(
+                    "{}: the command matches \"{}\"",
+                    CONFIRMATION_REQUIRED, pattern
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Project of an item, for checking commands launched from it
+pub fn item_project(store: &JsonStore, item_id: Option<&str>) -> Option<String> {
+    item_id
+        .and_then(|id| store.find_item(id))
+        .map(|item| item.project_id)
+}
+
+fn listed(list: &[String], executable: &str) -> bool {
+    list.iter().any(|entry| {
+        if cfg!(windows) {
+            entry.eq_ignore_ascii_case(executable)
+        } else {
+            entry == executable
+        }
+    })
+}
+
+/// Executables a command line runs: the first word of each pipeline or list
+/// element, past variable assignments and wrappers like `sudo`, without its
+/// directory (and `.exe`)
+fn executables(command: &str) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for segment in command.split(['|', '&', ';', '\n', '(', ')']) {
+        let program = segment
+            .split_whitespace()
+            .map(|word| word.trim_matches(['"', '\'']))
+            .find(|word| {
+                // Skip assignments, options of wrappers and redirections like `2>&1`
+                !word.contains('=')
+                    && !word.starts_with(['-', '<', '>'])
+                    && !word.starts_with(|c: char| c.is_ascii_digit())
+                    && !WRAPPERS.contains(word)
+            });
+        let Some(program) = program else {
+            continue;
+        };
+        let name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+        let name = name
+            .strip_suffix(".exe")
+            .or_else(|| name.strip_suffix(".EXE"))
+            .unwrap_or(name);
+        if !name.is_empty() && !result.iter().any(|r| r == name) {
+            result.push(name.to_string());
+        }
+    }
+    result
+}
@@ -0,0 +1,150 @@
+use crate::models::{PortProcess, PortStatus};
+use crate::processes::hidden_command;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener};
+
+/// Whether a local TCP port is taken, and by which processes
+pub fn check(port: u16) -> PortStatus {
+    let processes = listeners(port);
+    // Binding catches listeners the process lookup can't see (e.g. other users')
+    let bound = [
+        SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)),
+        SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+        SocketAddr::from((Ipv6Addr::LOCALHOST, port)),
+    ]
+    .into_iter()
+    .any(|addr| matches!(TcpListener::bind(addr), Err(e) if e.kind() == ErrorKind::AddrInUse));
+
+    PortStatus {
+        port,
+        in_use: bound || !processes.is_empty(),
+        processes,
+    }
+}
+
+/// Kill the processes listening on a local TCP port. Returns the processes killed.
+pub fn kill(port: u16) -> Result<Vec<PortProcess>, String> {
+    let processes = listeners(port);
+    if processes.is_empty() {
+        return Err(format!("No process found listening on port {}", port));
+    }
+
+    for process in &processes {
+        let pid = process.pid.to_string();
+        #[cfg(windows)]
+        let output = hidden_command("taskkill", &["/PID", &pid, "/F"]).output();
+        #[cfg(not(windows))]
+        let output = hidden_command("kill", &["-TERM", &pid]).output();
+
+        let output = output.map_err(|e| format!("Failed to stop process {}: {}", pid, e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to stop process {}: {}",
+                pid,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+    Ok(processes)
+}
+
+/// Processes listening on `port`, from netstat (Windows), lsof or ss
+fn listeners(port: u16) -> Vec<PortProcess> {
+    #[cfg(windows)]
+    let mut processes = netstat_listeners(port);
+
+    #[cfg(not(windows))]
+    let mut processes = match lsof_listeners(port) {
+        Some(processes) => processes,
+        None => ss_listeners(port),
+    };
+
+    processes.sort_by_key(|p| p.pid);
+    processes.dedup_by_key(|p| p.pid);
+    processes
+}
+
+/// Parse `lsof -F pc` output: a `p<pid>` line followed by a `c<command>` line per
+/// process. None if lsof isn't installed.
+#[cfg(not(windows))]
+fn lsof_listeners(port: u16) -> Option<Vec<PortProcess>> {
+    let output = hidden_command(
+        "lsof",
+        &[
+            "-nP",
+            &format!("-iTCP:{}", port),
+            "-sTCP:LISTEN",
+            "-F",
+            "pc",
+        ],
+    )
+    .output()
+    .ok()?;
+
+    let mut processes: Vec<PortProcess> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(pid) = line.strip_prefix('p').and_then(|p| p.parse().ok()) {
+            processes.push(PortProcess { pid, name: None });
+        } else if let (Some(name), Some(process)) = (line.strip_prefix('c'), processes.last_mut()) {
+            process.name = Some(name.to_string());
+        }
+    }
+    Some(processes)
+}
+
+/// Parse the `users:(("node",pid=1234,fd=20))` column of `ss -ltnp`
+#[cfg(not(windows))]
+fn ss_listeners(port: u16) -> Vec<PortProcess> {
+    let Ok(output) = hidden_command("ss", &["-ltnpH", &format!("sport = :{}", port)]).output()
+    else {
+        return Vec::new();
+    };
+    let user = regex::Regex::new(r#"\("([^"]*)",pid=(\d+)"#).unwrap();
+    user.captures_iter(&String::from_utf8_lossy(&output.stdout))
+        .filter_map(|caps| {
+            Some(PortProcess {
+                pid: caps[2].parse().ok()?,
+                name: Some(caps[1].to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Parse `netstat -ano` rows like `TCP 0.0.0.0:3000 0.0.0.0:0 LISTENING 1234`,
+/// naming each PID via tasklist
+#[cfg(windows)]
+fn netstat_listeners(port: u16) -> Vec<PortProcess> {
+    let Ok(output) = hidden_command("netstat", &["-ano"]).output() else {
+        return Vec::new();
+    };
+    let suffix = format!(":{}", port);
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let [protocol, local, _, state, pid] = columns[..] else {
+                return None;
+            };
+            if protocol != "TCP" || state != "LISTENING" || !local.ends_with(&suffix) {
+                return None;
+            }
+            let pid: u32 = pid.parse().ok()?;
+            Some(PortProcess {
+                pid,
+                name: tasklist_name(pid),
+            })
+        })
+        .collect()
+}
+
+/// Image name of a PID from `tasklist /FO CSV` (`"node.exe","1234",...`)
+#[cfg(windows)]
+fn tasklist_name(pid: u32) -> Option<String> {
+    let filter = format!("PID eq {}", pid);
+    let output = hidden_command("tasklist", &["/FI", &filter, "/FO", "CSV", "/NH"])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let name = stdout.lines().next()?.split(',').next()?.trim_matches('"');
+    (!name.is_empty() && !name.starts_with("INFO:")).then(|| name.to_string())
+}
@@ -0,0 +1,411 @@
+use crate::hosts;
+use crate::models::{CommandShell, ProcessExit, RunningProcess};
+use std::collections::HashMap;
+use std::fs::File;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Event emitted with a `ProcessExit` when a tracked background process ends
+pub const PROCESS_EXITED_EVENT: &str = "process://exited";
+
+/// Exit code of the remote `timeout` utility when it stopped the command
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Extra time given to an SSH session after its remote timeout, to stop it itself
+pub const REMOTE_TIMEOUT_GRACE_SECS: u64 = 10;
+
+/// Start of the stderr line in which a remote command reports its process group,
+/// see `remote_group_line`
+pub const REMOTE_PGID_MARKER: &str = "devora-pgid:";
+
+/// How long to wait for the output pipes to close after killing a timed-out command
+const PIPE_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Managed state: background processes started by `run_command`, so they can be
+/// listed and stopped from the app. Local processes are waited on by a thread
+/// that emits the exit event; remote ones are checked when the list is read.
+/// Clones share the registry.
+#[derive(Default, Clone)]
+pub struct ProcessRegistry {
+    processes: Arc<Mutex<HashMap<String, RunningProcess>>>,
+}
+
+impl ProcessRegistry {
+    /// Track a local background process until it exits
+    pub fn track_local(&self, app: &AppHandle, mut child: Child, process: RunningProcess) {
+        let id = process.id.clone();
+        self.processes.lock().unwrap().insert(id.clone(), process);
+
+        let registry = self.clone();
+        let app = app.clone();
+        std::thread::spawn(move || {
+            let exit_code = child.wait().ok().and_then(|status| status.code());
+            registry.finish(&app, &id, exit_code);
+        });
+    }
+
+    /// Track a remote background process by the PID the remote shell reported
+    pub fn track_remote(&self, process: RunningProcess) {
+        self.processes
+            .lock()
+            .unwrap()
+            .insert(process.id.clone(), process);
+    }
+
+    fn finish(&self, app: &AppHandle, id: &str, exit_code: Option<i32>) {
+        if self.processes.lock().unwrap().remove(id).is_some() {
+            let _ = app.emit(
+                PROCESS_EXITED_EVENT,
+                ProcessExit {
+                    id: id.to_string(),
+                    exit_code,
+                },
+            );
+        }
+    }
+
+    /// Running processes, oldest first. Remote processes that have ended since the
+    /// last check are dropped (with an exit event, without an exit code).
+    pub fn list(&self, app: &AppHandle) -> Vec<RunningProcess> {
+        let remote: Vec<RunningProcess> = self
+            .processes
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|p| p.host.is_some())
+            .cloned()
+            .collect();
+        let mut by_host: HashMap<String, Vec<RunningProcess>> = HashMap::new();
+        for process in remote {
+            by_host
+                .entry(process.host.clone().unwrap_or_default())
+                .or_default()
+                .push(process);
+        }
+        for (host, processes) in by_host {
+            let Some(alive) = remote_alive_pids(&host, &processes) else {
+                // Unreachable host: keep the entries until it can be checked
+                continue;
+            };
+            for process in processes.iter().filter(|p| !alive.contains(&p.pid)) {
+                self.finish(app, &process.id, None);
+            }
+        }
+
+        let mut running: Vec<RunningProcess> =
+            self.processes.lock().unwrap().values().cloned().collect();
+        running.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        running
+    }
+
+    /// Stop a tracked process and its children. Local processes report their exit
+    /// through the waiting thread; remote ones are dropped right away.
+    pub fn kill(&self, app: &AppHandle, id: &str) -> Result<(), String> {
+        let process = self
+            .processes
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or("Process not found (it may have exited)")?;
+
+        match process.host.as_deref() {
+            Some(host) => {
+                let script = remote_kill_line(process.pid);
+                let mut args = hosts::jump_args(process.jump_hosts.as_deref());
+                args.extend([host, script.as_str()]);
+                let output = hidden_command("ssh", &args)
+                    .output()
+                    .map_err(|e| format!("Failed to execute SSH command: {}", e))?;
+                // kill fails when the process is already gone, which is fine too
+                log::debug!(
+                    "Stopping remote process {}: {}",
+                    process.pid,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                self.finish(app, id, None);
+            }
+            None => kill_local(process.pid)?,
+        }
+        Ok(())
+    }
+}
+
+/// PIDs among `processes` still running on `host`, or None if it can't be reached
+fn remote_alive_pids(host: &str, processes: &[RunningProcess]) -> Option<Vec<u32>> {
+    let pids = processes
+        .iter()
+        .map(|p| p.pid.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let script = format!("ps -o pid= -p {}", pids);
+    let mut args = hosts::jump_args(processes.first().and_then(|p| p.jump_hosts.as_deref()));
+    args.extend([host, script.as_str()]);
+    // ps exits non-zero when none of the PIDs exist, so its status is ignored
+    let output = hidden_command("ssh", &args)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    // 255 is ssh's own failure (connection refused, auth, ...)
+    if output.status.code() == Some(255) {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .filter_map(|pid| pid.parse().ok())
+            .collect(),
+    )
+}
+
+/// Kill a local process with its process group (Unix) or process tree (Windows)
+pub fn kill_local(pid: u32) -> Result<(), String> {
+    #[cfg(windows)]
+    let output = hidden_command("taskkill", &["/PID", &pid.to_string(), "/T", "/F"]).output();
+
+    // Background processes lead their own process group, see `spawn_background`
+    #[cfg(not(windows))]
+    let output = Command::new("kill")
+        .args(["-TERM", "--", &format!("-{}", pid)])
+        .output();
+
+    let output = output.map_err(|e| format!("Failed to stop process: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to stop process {}: {}",
+            pid,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Start a local shell command detached from the app's output, in its own
+/// process group so it can be stopped together with its children. Its stdout and
+/// stderr go to `log` when given, else nowhere.
+pub fn spawn_background(
+    shell: CommandShell,
+    command: &str,
+    cwd: &str,
+    log: Option<File>,
+) -> Result<Child, String> {
+    let mut cmd = shell.std_command(command);
+    cmd.current_dir(cwd);
+    spawn_detached(cmd, log)
+}
+
+/// Start `cmd` the way `spawn_background` does, for commands not run by a local shell
+pub fn spawn_detached(mut cmd: Command, log: Option<File>) -> Result<Child, String> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    #[cfg(not(windows))]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    let (stdout, stderr) = match log {
+        Some(log) => {
+            let stderr = log
+                .try_clone()
+                .map_err(|e| format!("Failed to open command log: {}", e))?;
+            (Stdio::from(log), Stdio::from(stderr))
+        }
+        None => (Stdio::null(), Stdio::null()),
+    };
+    cmd.stdin(Stdio::null())
+        .stdout(stdout)
+        .stderr(stderr)
+        .spawn()
+        .map_err(|e| format!("Failed to spawn background command: {}", e))
+}
+
+/// Write `input` to the child's stdin from a separate task (so a command that
+/// writes output before reading all its input can't deadlock), then close it
+fn feed_stdin(child: &mut tokio::process::Child, input: Option<String>) {
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        tauri::async_runtime::spawn(async move {
+            // The command may exit without reading everything
+            if let Err(e) = stdin.write_all(input.as_bytes()).await {
+                log::debug!("Failed to write command input: {}", e);
+            }
+        });
+    }
+}
+
+fn stdin_for(input: &Option<String>) -> Stdio {
+    if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    }
+}
+
+/// Run `cmd` with its output captured and `input` (if any) as its stdin
+pub async fn output_with_input(
+    mut cmd: tokio::process::Command,
+    input: Option<String>,
+) -> Result<Output, String> {
+    let mut child = cmd
+        .stdin(stdin_for(&input))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+    feed_stdin(&mut child, input);
+    child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to wait for command: {}", e))
+}
+
+/// Run `cmd` with its output captured and `input` (if any) as its stdin, killing it
+/// once `timeout` passes. A `local` command gets its own process group (Unix) so
+/// its children are killed with it. Returns the output and whether it timed out.
+pub async fn output_with_timeout(
+    mut cmd: tokio::process::Command,
+    input: Option<String>,
+    timeout: Duration,
+    local: bool,
+) -> Result<(Output, bool), String> {
+    #[cfg(unix)]
+    if local {
+        cmd.process_group(0);
+    }
+    let mut child = cmd
+        .stdin(stdin_for(&input))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+    feed_stdin(&mut child, input);
+
+    // Drain both pipes concurrently so a chatty command can't block on a full pipe
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let stdout_task = tauri::async_runtime::spawn(async move {
+        let mut buffer = Vec::new();
+        if let Some(mut stdout) = stdout {
+            let _ = stdout.read_to_end(&mut buffer).await;
+        }
+        buffer
+    });
+    let stderr_task = tauri::async_runtime::spawn(async move {
+        let mut buffer = Vec::new();
+        if let Some(mut stderr) = stderr {
+            let _ = stderr.read_to_end(&mut buffer).await;
+        }
+        buffer
+    });
+
+    let timed_out = tokio::time::timeout(timeout, child.wait()).await.is_err();
+    if timed_out {
+        if local {
+            if let Some(pid) = child.id() {
+                if let Err(e) = kill_local(pid) {
+                    log::debug!("{}", e);
+                }
+            }
+        }
+        if let Err(e) = child.kill().await {
+            log::debug!("Failed to kill timed-out command: {}", e);
+        }
+    }
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| format!("Failed to wait for command: {}", e))?;
+
+    let collect = async {
+        (
+            stdout_task.await.unwrap_or_default(),
+            stderr_task.await.unwrap_or_default(),
+        )
+    };
+    let (stdout, stderr) = if timed_out {
+        // A child that left the process group may keep the pipes open after a kill
+        tokio::time::timeout(PIPE_DRAIN_TIMEOUT, collect)
+            .await
+            .unwrap_or_default()
+    } else {
+        collect.await
+    };
+
+    Ok((
+        Output {
+            status,
+            stdout,
+            stderr,
+        },
+        timed_out,
+    ))
+}
+
+/// Remote shell line that starts `script` detached from the SSH session and prints
+/// its PID. Under `setsid` (on hosts that have it) the command leads its own
+/// process group, so `remote_kill_line` stops all its descendants with it.
+pub fn remote_detached_line(script: &str) -> String {
+    let quoted = crate::git::shell_quote(script);
+    // A background job of a non-interactive shell isn't a group leader, so setsid
+    // doesn't fork and `$!` stays the command's PID
+    let line = format!(
+        "if command -v setsid >/dev/null 2>&1; then nohup setsid sh -c {quoted} >/dev/null 2>&1 & else nohup sh -c {quoted} >/dev/null 2>&1 & fi; echo $!"
+    );
+    format!("exec sh -c {}", crate::git::shell_quote(&line))
+}
+
+/// Remote shell line that runs `script` in the login shell, after reporting on
+/// stderr (as `REMOTE_PGID_MARKER` and the ID) the process group it runs in. sshd
+/// makes the session's shell a group leader and `exec` keeps its PID, so every
+/// process the command starts is in that group.
+pub fn remote_group_line(script: &str) -> String {
+    let line = format!(
+        "echo {}$$ >&2; exec \"${{SHELL:-sh}}\" -c {}",
+        REMOTE_PGID_MARKER,
+        crate::git::shell_quote(script)
+    );
+    format!("exec sh -c {}", crate::git::shell_quote(&line))
+}
+
+/// Remote shell line that stops process group `pid`, or where the process doesn't
+/// lead one (started on a host without setsid), the process and its children. A
+/// shell in the group can start its next command as the TERM lands, so whatever
+/// is left of the group gets a KILL after a moment.
+pub fn remote_kill_line(pid: u32) -> String {
+    let script = format!(
+        "if kill -TERM -{pid} 2>/dev/null; then sleep 1; kill -KILL -{pid} 2>/dev/null; true; else pkill -TERM -P {pid}; kill -TERM {pid}; fi"
+    );
+    format!("sh -c {}", crate::git::shell_quote(&script))
+}
+
+/// Wrap a remote shell command so the remote `timeout` utility stops it (with its
+/// process group) after `secs`. Hosts without `timeout` run it unbounded.
+pub fn remote_with_timeout(script: &str, secs: u64) -> String {
+    let quoted = crate::git::shell_quote(script);
+    format!(
+        "if command -v timeout >/dev/null 2>&1; then timeout -k 5 {secs} sh -c {quoted}; else sh -c {quoted}; fi"
+    )
+}
+
+pub fn hidden_command(program: &str, args: &[&str]) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    cmd
+}
@@ -0,0 +1,58 @@
+use crate::json_store::JsonStore;
+use crate::models::{ChecklistItem, Item, PromptTemplate};
+use crate::todos;
+
+/// Global setting holding the prompt template library as a JSON array
+pub const PROMPT_TEMPLATES_KEY: &str = "promptTemplates";
+
+pub fn load_templates(store: &JsonStore) -> Result<Vec<PromptTemplate>, String> {
+    match store.get_setting(PROMPT_TEMPLATES_KEY)? {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse prompt templates: {}", e)),
+        _ => Ok(Vec::new()),
+    }
+}
+
+pub fn save_templates(store: &JsonStore, templates: &[PromptTemplate]) -> Result<(), String> {
+    let json = serde_json::to_string(templates)
+        .map_err(|e| format!("Failed to serialize prompt templates: {}", e))?;
+    store.set_setting(PROMPT_TEMPLATES_KEY, &json)
+}
+
+fn todo_list(todos: &[ChecklistItem]) -> String {
+    todos
+        .iter()
+        .map(|todo| format!("- {}\n", todos::strip_markers(&todo.text)))
+        .collect()
+}
+
+/// Prompt asking a coding agent to work through `todos`, with `notes` as context
+pub fn compose(todos: &[ChecklistItem], notes: &[Item]) -> String {
+    let mut sections = Vec::new();
+    if !todos.is_empty() {
+        sections.push(format!("Work through these tasks:\n{}", todo_list(todos)));
+    }
+    if !notes.is_empty() {
+        let context = notes
+            .iter()
+            .map(|note| format!("## {}\n{}\n", note.title, note.content.trim()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        sections.push(format!("Notes for context:\n\n{}", context));
+    }
+    sections.join("\n")
+}
+
+/// A template's body with its placeholders filled in
+pub fn render(
+    template: &PromptTemplate,
+    project_name: &str,
+    todos: &[ChecklistItem],
+    path: &str,
+) -> String {
+    template
+        .body
+        .replace("{project_name}", project_name)
+        .replace("{todos}", todo_list(todos).trim_end())
+        .replace("{path}", path)
+}
@@ -0,0 +1,129 @@
+use crate::json_store::JsonStore;
+use crate::models::{ProjectTodo, TodoFilter};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// How often the scheduler looks for todos that have come due
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Above this many newly due todos a single summary notification is shown instead
+const MAX_SEPARATE_NOTIFICATIONS: usize = 3;
+
+/// Managed state: todos already notified about (persisted to ~/.devora/reminders.json,
+/// machine-local) so each due todo fires once rather than on every check or restart
+pub struct ReminderState {
+    path: PathBuf,
+    notified: Mutex<HashSet<String>>,
+}
+
+impl ReminderState {
+    pub fn new(config_dir: &Path) -> Self {
+        let path = config_dir.join("reminders.json");
+        let notified = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            notified: Mutex::new(notified),
+        }
+    }
+
+    fn save(&self, notified: &HashSet<String>) {
+        // Best-effort; a lost entry only means a reminder is shown again
+        if let Ok(content) = serde_json::to_string_pretty(notified) {
+            let _ = fs::write(&self.path, content);
+        }
+    }
+}
+
+fn reminder_key(todo: &ProjectTodo) -> String {
+    format!(
+        "{}|{}|{}",
+        todo.project_id,
+        todo.due_date.as_deref().unwrap_or_default(),
+        todo.text
+    )
+}
+
+/// Notify about todos due today or overdue that haven't been notified yet
+fn run_once(app: &AppHandle) {
+    let due = match app
+        .state::<JsonStore>()
+        .get_todos_across_projects(&TodoFilter {
+            due_within_days: Some(0),
+            ..Default::default()
+        }) {
+        Ok(due) => due,
+        Err(e) => {
+            log::warn!("Failed to check todo due dates: {}", e);
+            return;
+        }
+    };
+
+    let state = app.state::<ReminderState>();
+    let mut notified = state.notified.lock().unwrap();
+    let keys: HashSet<String> = due.iter().map(reminder_key).collect();
+    let fresh: Vec<&ProjectTodo> = due
+        .iter()
+        .filter(|todo| !notified.contains(&reminder_key(todo)))
+        .collect();
+
+    if fresh.len() > MAX_SEPARATE_NOTIFICATIONS {
+        show(
+            app,
+            &format!("{} todos are due", fresh.len()),
+            &fresh
+                .iter()
+                .map(|todo| format!("{}: {}", todo.project_name, todo.text))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    } else {
+        for todo in &fresh {
+            let title = if todo.overdue {
+                format!(
+                    "Overdue since {}",
+                    todo.due_date.as_deref().unwrap_or_default()
+                )
+            } else {
+                "Due today".to_string()
+            };
+            show(
+                app,
+                &title,
+                &format!("{}: {}", todo.project_name, todo.text),
+            );
+        }
+    }
+
+    // Forget todos that were completed, removed or re-dated so the set doesn't grow
+    let before = notified.len();
+    notified.retain(|key| keys.contains(key));
+    notified.extend(fresh.iter().map(|todo| reminder_key(todo)));
+    if !fresh.is_empty() || notified.len() != before {
+        state.save(&notified);
+    }
+}
+
+fn show(app: &AppHandle, title: &str, body: &str) {
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show todo reminder: {}", e);
+    }
+}
+
+/// Start the background thread that fires native notifications for due todos
+pub fn spawn_scheduler(app: AppHandle) {
+    std::thread::spawn(move || {
+        loop {
+            run_once(&app);
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
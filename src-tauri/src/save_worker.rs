@@ -0,0 +1,104 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// A persistence task run on the worker thread
+pub type SaveTask = Box<dyn FnOnce() -> Result<(), String> + Send>;
+
+enum Job {
+    /// Run a task; a later task with the same key supersedes it if both are pending
+    Run { key: Option<String>, task: SaveTask },
+    /// Signal once every job submitted before it has run
+    Flush(Sender<()>),
+}
+
+/// Runs file writes on a dedicated thread, in submission order, so command
+/// handlers can return as soon as the in-memory state is updated
+pub struct SaveWorker {
+    sender: Sender<Job>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl SaveWorker {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let last_error = Arc::new(Mutex::new(None));
+
+        let worker_error = last_error.clone();
+        std::thread::spawn(move || Self::run(receiver, worker_error));
+
+        Self { sender, last_error }
+    }
+
+    fn run(receiver: Receiver<Job>, last_error: Arc<Mutex<Option<String>>>) {
+        while let Ok(job) = receiver.recv() {
+            // Take everything that queued up meanwhile so repeated saves of the
+            // same file collapse into a single write
+            let mut batch = vec![job];
+            batch.extend(receiver.try_iter());
+
+            let superseded = Self::superseded(&batch);
+            for (index, job) in batch.into_iter().enumerate() {
+                match job {
+                    Job::Run { task, .. } => {
+                        if superseded[index] {
+                            continue;
+                        }
+                        if let Err(e) = task() {
+                            log::error!("Background save failed: {}", e);
+                            *last_error.lock().unwrap() = Some(e);
+                        }
+                    }
+                    Job::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Mark tasks followed by another task with the same key before the next flush
+    fn superseded(batch: &[Job]) -> Vec<bool> {
+        let mut superseded = vec![false; batch.len()];
+        for (i, job) in batch.iter().enumerate() {
+            let Job::Run { key: Some(key), .. } = job else {
+                continue;
+            };
+            for later in &batch[i + 1..] {
+                match later {
+                    Job::Flush(_) => break,
+                    Job::Run { key: Some(k), .. } if k == key => {
+                        superseded[i] = true;
+                        break;
+                    }
+                    Job::Run { .. } => {}
+                }
+            }
+        }
+        superseded
+    }
+
+    /// Queue a task. Tasks with a key (e.g. the target file path) are coalesced.
+    pub fn submit(&self, key: Option<String>, task: SaveTask) {
+        if let Err(mpsc::SendError(Job::Run { task, .. })) =
+            self.sender.send(Job::Run { key, task })
+        {
+            // The worker thread is gone; don't lose the write
+            if let Err(e) = task() {
+                log::error!("Save failed: {}", e);
+            }
+        }
+    }
+
+    /// Block until every previously submitted task has run.
+    /// Returns (and clears) the last error a background task hit, if any.
+    pub fn flush(&self) -> Result<(), String> {
+        let (done, wait) = mpsc::channel();
+        if self.sender.send(Job::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+        match self.last_error.lock().unwrap().take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
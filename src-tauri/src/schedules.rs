@@ -0,0 +1,363 @@
+use crate::commands;
+use crate::hosts::HostStatusCache;
+use crate::json_store::JsonStore;
+use crate::models::{CommandMode, ItemSchedule, ScheduledRun, ScheduledRunOutcome};
+use crate::processes::ProcessRegistry;
+use crate::usage::UsageFile;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// How often the scheduler looks for schedules that have come due
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A scheduled run still going after this long is stopped and counts as failed
+const RUN_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// A schedule with no match this far ahead never fires (e.g. `0 0 31 2 *`)
+const MAX_LOOKAHEAD_DAYS: i64 = 4 * 366;
+
+/// Parsed five-field cron expression (minute hour day-of-month month day-of-week),
+/// one bit per allowed value
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Cron matches either day field when both are restricted, both otherwise
+    days_restricted: bool,
+}
+
+impl CronSchedule {
+    /// Parse `*`, `*/n`, `a`, `a-b`, `a-b/n` and comma lists of those (numbers
+    /// only, Sunday is 0 or 7), or one of @hourly, @daily, @midnight, @weekly,
+    /// @monthly, @yearly
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Schedule \"{}\" needs 5 fields: minute hour day month weekday",
+                expr
+            ));
+        };
+
+        let mut days_of_week = parse_field(weekday, 0, 7)?;
+        // 7 is Sunday too
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days_of_month: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            days_of_week,
+            days_restricted: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = has(self.days_of_month, date.day());
+        let weekday = has(self.days_of_week, date.weekday().num_days_from_sunday());
+        if self.days_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    /// First matching minute strictly after `after`
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)?;
+        let mut time = start + chrono::Duration::minutes(1);
+        let limit = start + chrono::Duration::days(MAX_LOOKAHEAD_DAYS);
+
+        while time < limit {
+            let date = time.date();
+            if !has(self.months, date.month()) {
+                let (year, month) = if date.month() == 12 {
+                    (date.year() + 1, 1)
+                } else {
+                    (date.year(), date.month() + 1)
+                };
+                time = midnight(NaiveDate::from_ymd_opt(year, month, 1)?);
+            } else if !self.matches_day(date) {
+                time = midnight(date.succ_opt()?);
+            } else if !has(self.hours, time.hour()) {
+                time = time.with_minute(0)? + chrono::Duration::hours(1);
+            } else if !has(self.minutes, time.minute()) {
+                time += chrono::Duration::minutes(1);
+            } else if let Some(local) = Local.from_local_datetime(&time).earliest() {
+                return Some(local);
+            } else {
+                // Skipped by a daylight saving change
+                time += chrono::Duration::minutes(1);
+            }
+        }
+        None
+    }
+}
+
+fn has(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+fn midnight(date: NaiveDate) -> NaiveDateTime {
+    date.and_hms_opt(0, 0, 0).unwrap_or_default()
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let invalid = || format!("Invalid schedule field \"{}\"", field);
+    let mut mask = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                start.parse().map_err(|_| invalid())?,
+                end.parse().map_err(|_| invalid())?,
+            )
+        } else {
+            let value: u32 = range.parse().map_err(|_| invalid())?;
+            // `a/n` means every n-th value from a
+            (value, if part.contains('/') { max } else { value })
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// Managed state: cron-like schedules of Command items, keyed by item ID (persisted
+/// to ~/.devora/schedules.json, machine-local so a synced data folder doesn't run
+/// the same job on every machine)
+pub struct ScheduleState {
+    path: PathBuf,
+    schedules: Mutex<HashMap<String, ItemSchedule>>,
+}
+
+impl ScheduleState {
+    pub fn new(config_dir: &Path) -> Self {
+        let path = config_dir.join("schedules.json");
+        let schedules = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            schedules: Mutex::new(schedules),
+        }
+    }
+
+    fn save(&self, schedules: &HashMap<String, ItemSchedule>) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(schedules)
+            .map_err(|e| format!("Failed to serialize schedules: {}", e))?;
+        fs::write(&self.path, content).map_err(|e| format!("Failed to save schedules: {}", e))
+    }
+
+    /// Set (or with None, remove) the schedule of a Command item. `confirmed_command`
+    /// is the command the user confirmed for it, if they did.
+    pub fn set(
+        &self,
+        item_id: &str,
+        project_id: &str,
+        cron: Option<&str>,
+        confirmed_command: Option<String>,
+    ) -> Result<Option<ItemSchedule>, String> {
+        let mut schedules = self.schedules.lock().unwrap();
+        let schedule = match cron.map(str::trim).filter(|c| !c.is_empty()) {
+            Some(cron) => {
+                CronSchedule::parse(cron)?;
+                let schedule = ItemSchedule {
+                    item_id: item_id.to_string(),
+                    project_id: project_id.to_string(),
+                    cron: cron.to_string(),
+                    confirmed_command,
+                    last_run: schedules.get(item_id).and_then(|s| s.last_run.clone()),
+                };
+                schedules.insert(item_id.to_string(), schedule.clone());
+                Some(schedule)
+            }
+            None => {
+                schedules.remove(item_id);
+                None
+            }
+        };
+        self.save(&schedules)?;
+        Ok(schedule)
+    }
+
+    pub fn all(&self) -> Vec<ItemSchedule> {
+        self.schedules.lock().unwrap().values().cloned().collect()
+    }
+
+    fn record_run(&self, item_id: &str, outcome: ScheduledRunOutcome) {
+        let mut schedules = self.schedules.lock().unwrap();
+        // The schedule may have been removed while the command ran
+        if let Some(schedule) = schedules.get_mut(item_id) {
+            schedule.last_run = Some(outcome);
+            if let Err(e) = self.save(&schedules) {
+                log::warn!("{}", e);
+            }
+        }
+    }
+}
+
+/// Schedules with their item's title and next run time, soonest first. Schedules
+/// of deleted items are left out.
+pub fn list(app: &AppHandle) -> Vec<ScheduledRun> {
+    let store = app.state::<JsonStore>();
+    let now = Local::now();
+    let mut runs: Vec<ScheduledRun> = app
+        .state::<ScheduleState>()
+        .all()
+        .into_iter()
+        .filter_map(|schedule| {
+            let item = store.find_item(&schedule.item_id)?;
+            let next_run_at = CronSchedule::parse(&schedule.cron)
+                .ok()
+                .and_then(|cron| cron.next_after(now))
+                .map(|t| t.to_rfc3339());
+            Some(ScheduledRun {
+                item_id: schedule.item_id,
+                project_id: item.project_id,
+                title: item.title,
+                cron: schedule.cron,
+                next_run_at,
+                last_run: schedule.last_run,
+            })
+        })
+        .collect();
+    runs.sort_by(|a, b| a.next_run_at.cmp(&b.next_run_at));
+    runs
+}
+
+/// Run one scheduled item, recording the outcome and notifying on failure. It
+/// runs in output mode whatever the item's own mode, so its exit code is what
+/// gets recorded, and is stopped after `RUN_TIMEOUT`. A command the policy asks
+/// about runs only if it's the one confirmed with the schedule.
+async fn run_scheduled(app: AppHandle, schedule: &ItemSchedule) {
+    let item_id = schedule.item_id.clone();
+    let Some(item) = app.state::<JsonStore>().find_item(&item_id) else {
+        return;
+    };
+    let confirmed = schedule.confirmed_command.as_deref() == Some(item.content.as_str());
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let result = commands::run_command(
+        item.content,
+        CommandMode::Output,
+        item.command_cwd,
+        item.command_host,
+        Some(item.project_id),
+        Some(item.id),
+        Some(RUN_TIMEOUT.as_secs()),
+        item.command_shell,
+        None,
+        Some(confirmed),
+        item.wsl_distro,
+        app.clone(),
+        app.state::<HostStatusCache>(),
+        app.state::<JsonStore>(),
+        app.state::<UsageFile>(),
+        app.state::<ProcessRegistry>(),
+    )
+    .await;
+
+    let outcome = match result {
+        Ok(result) if result.timed_out => ScheduledRunOutcome {
+            started_at,
+            success: false,
+            exit_code: None,
+            error: Some(format!(
+                "Timed out after {} minutes",
+                RUN_TIMEOUT.as_secs() / 60
+            )),
+        },
+        Ok(result) => ScheduledRunOutcome {
+            started_at,
+            success: result.exit_code == 0,
+            exit_code: Some(result.exit_code),
+            error: (result.exit_code != 0).then(|| result.stderr.trim().to_string()),
+        },
+        Err(e) => ScheduledRunOutcome {
+            started_at,
+            success: false,
+            exit_code: None,
+            error: Some(e),
+        },
+    };
+    if !outcome.success {
+        let body = match (outcome.exit_code, outcome.error.as_deref()) {
+            (Some(code), _) => format!("{} exited with code {}", item.title, code),
+            (None, Some(error)) => format!("{}: {}", item.title, error),
+            (None, None) => item.title.clone(),
+        };
+        if let Err(e) = app
+            .notification()
+            .builder()
+            .title("Scheduled command failed")
+            .body(body)
+            .show()
+        {
+            log::warn!("Failed to show schedule failure: {}", e);
+        }
+    }
+    app.state::<ScheduleState>().record_run(&item_id, outcome);
+}
+
+/// Start the background task that runs scheduled Command items while the app is
+/// open. Runs missed while it was closed are skipped, and an item still running
+/// from its previous run is not started again.
+pub fn spawn_scheduler(app: AppHandle) {
+    let running: std::sync::Arc<Mutex<HashSet<String>>> = Default::default();
+    tauri::async_runtime::spawn(async move {
+        let mut last_checked = Local::now();
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let now = Local::now();
+
+            for schedule in app.state::<ScheduleState>().all() {
+                let due = CronSchedule::parse(&schedule.cron)
+                    .ok()
+                    .and_then(|cron| cron.next_after(last_checked))
+                    .is_some_and(|next| next <= now);
+                if !due || !running.lock().unwrap().insert(schedule.item_id.clone()) {
+                    continue;
+                }
+
+                let app = app.clone();
+                let running = running.clone();
+                tauri::async_runtime::spawn(async move {
+                    run_scheduled(app, &schedule).await;
+                    running.lock().unwrap().remove(&schedule.item_id);
+                });
+            }
+            last_checked = now;
+        }
+    });
+}
@@ -0,0 +1,158 @@
+use crate::models::LegacyTodoItem;
+use serde_json::Value;
+
+/// Current schema version for project files and metadata.json
+///
+/// Bump this and append a migration to `PROJECT_MIGRATIONS` whenever the
+/// on-disk project format changes in a way old data can't deserialize.
+pub const CURRENT_VERSION: u32 = 2;
+
+/// A migration upgrades a raw project JSON value by exactly one version
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Project migrations: `PROJECT_MIGRATIONS[n]` upgrades version `n + 1` to `n + 2`
+const PROJECT_MIGRATIONS: &[Migration] = &[project_v1_to_v2];
+
+/// Read the schema version of a raw project value (files without one, or with
+/// version 0 from before versions were written, are version 1)
+pub fn project_version(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .map(|v| u32::try_from(v).unwrap_or(u32::MAX).max(1))
+        .unwrap_or(1)
+}
+
+/// Upgrade a raw project value to `CURRENT_VERSION` in place.
+/// Returns the version the value had before migrating.
+pub fn migrate_project(value: &mut Value) -> Result<u32, String> {
+    let from = project_version(value);
+
+    if from > CURRENT_VERSION {
+        return Err(format!(
+            "Project file has schema version {} but this version of Devora only supports up to {}",
+            from, CURRENT_VERSION
+        ));
+    }
+
+    for version in from..CURRENT_VERSION {
+        let migration = PROJECT_MIGRATIONS[(version - 1) as usize];
+        migration(value).map_err(|e| {
+            format!(
+                "Failed to migrate project from v{} to v{}: {}",
+                version,
+                version + 1,
+                e
+            )
+        })?;
+        value["version"] = Value::from(version + 1);
+    }
+
+    Ok(from)
+}
+
+/// v1 -> v2: todos changed from a list of todo items to a markdown string
+fn project_v1_to_v2(value: &mut Value) -> Result<(), String> {
+    let obj = value
+        .as_object_mut()
+        .ok_or("Project file is not a JSON object")?;
+
+    if let Some(todos) = obj.get_mut("todos") {
+        if todos.is_array() {
+            let legacy: Vec<LegacyTodoItem> = serde_json::from_value(todos.take())
+                .map_err(|e| format!("Invalid legacy todos: {}", e))?;
+            *todos = Value::String(convert_todos_to_markdown(&legacy));
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert legacy Vec<LegacyTodoItem> to markdown string
+pub fn convert_todos_to_markdown(todos: &[LegacyTodoItem]) -> String {
+    if todos.is_empty() {
+        return String::new();
+    }
+
+    let mut sorted_todos = todos.to_vec();
+    sorted_todos.sort_by_key(|t| t.order);
+
+    sorted_todos
+        .iter()
+        .map(|todo| {
+            let indent = "  ".repeat(todo.indent_level as usize);
+            let checkbox = if todo.completed { "[x]" } else { "[ ]" };
+            format!("{}- {} {}", indent, checkbox, todo.content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn legacy_todo(content: &str, completed: bool, order: i32, indent_level: i32) -> Value {
+        json!({
+            "id": format!("todo-{}", order),
+            "project_id": "p1",
+            "content": content,
+            "completed": completed,
+            "order": order,
+            "indent_level": indent_level,
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+        })
+    }
+
+    #[test]
+    fn missing_or_zero_version_is_v1() {
+        assert_eq!(project_version(&json!({})), 1);
+        assert_eq!(project_version(&json!({ "version": 0 })), 1);
+        assert_eq!(project_version(&json!({ "version": 2 })), 2);
+    }
+
+    #[test]
+    fn migrates_v1_todo_list_to_markdown() {
+        let mut value = json!({
+            "todos": [
+                legacy_todo("sub task", true, 1, 1),
+                legacy_todo("first", false, 0, 0),
+            ],
+        });
+        assert_eq!(migrate_project(&mut value).unwrap(), 1);
+        assert_eq!(value["version"], CURRENT_VERSION);
+        assert_eq!(value["todos"], "- [ ] first\n  - [x] sub task");
+    }
+
+    #[test]
+    fn version_zero_migrates_instead_of_panicking() {
+        let mut value = json!({ "version": 0, "todos": "- [ ] keep" });
+        assert_eq!(migrate_project(&mut value).unwrap(), 1);
+        assert_eq!(value["version"], CURRENT_VERSION);
+        assert_eq!(value["todos"], "- [ ] keep");
+    }
+
+    #[test]
+    fn current_version_is_left_alone() {
+        let mut value = json!({ "version": CURRENT_VERSION, "todos": [] });
+        assert_eq!(migrate_project(&mut value).unwrap(), CURRENT_VERSION);
+        assert_eq!(value["todos"], json!([]));
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let mut value = json!({ "version": CURRENT_VERSION + 1 });
+        assert!(migrate_project(&mut value).is_err());
+        let mut value = json!({ "version": u64::MAX });
+        assert!(migrate_project(&mut value).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_legacy_todos() {
+        let mut value = json!({ "todos": [{ "content": "no other fields" }] });
+        assert!(migrate_project(&mut value).is_err());
+        assert!(migrate_project(&mut json!([])).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::models::{CommandShell, ScriptEvent, ScriptRunResult};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+
+/// Split a script item's content into steps: one command per line, `#` comments
+/// and blank lines skipped, lines ending in `\` continued on the next line
+pub fn parse_steps(content: &str) -> Vec<String> {
+    let mut steps = Vec::new();
+    let mut current = String::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if current.is_empty() && (line.is_empty() || line.starts_with('#')) {
+            continue;
+        }
+        match line.strip_suffix('\\') {
+            Some(continued) => {
+                current.push_str(continued.trim_end());
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                steps.push(std::mem::take(&mut current).trim().to_string());
+            }
+        }
+    }
+    if !current.trim().is_empty() {
+        steps.push(current.trim().to_string());
+    }
+    steps
+}
+
+/// Build the process for one step: `shell` (else the platform shell) locally, or
+/// `ssh host` running it remotely (in `shell` if set, else the login shell)
+fn step_command(
+    step: &str,
+    cwd: Option<&str>,
+    host: Option<&str>,
+    shell: Option<CommandShell>,
+) -> Command {
+    let mut cmd = match host {
+        Some(host) => {
+            let step = match shell {
+                Some(shell) => shell.remote_line(step),
+                None => step.to_string(),
+            };
+            let remote = match cwd {
+                Some(dir) => format!("cd {} && {}", dir, step),
+                None => step,
+            };
+            let mut cmd = Command::new("ssh");
+            cmd.args([host, &remote]);
+            cmd
+        }
+        None => {
+            let mut cmd = shell
+                .unwrap_or_else(CommandShell::platform_default)
+                .tokio_command(step);
+            if let Some(dir) = cwd {
+                cmd.current_dir(dir);
+            }
+            cmd
+        }
+    };
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    cmd
+}
+
+/// Run one step, reporting stdout line by line. Returns the exit code and stderr.
+async fn run_step(
+    index: usize,
+    step: &str,
+    cwd: Option<&str>,
+    host: Option<&str>,
+    shell: Option<CommandShell>,
+    on_event: &impl Fn(ScriptEvent),
+) -> Result<(i32, String), String> {
+    let mut child = step_command(step, cwd, host, shell)
+        .spawn()
+        .map_err(|e| format!("Failed to start step {}: {}", index + 1, e))?;
+
+    // Drain stderr concurrently so a chatty step can't block on a full pipe
+    let stderr = child.stderr.take();
+    let stderr_task = tauri::async_runtime::spawn(async move {
+        let mut buffer = String::new();
+        if let Some(mut stderr) = stderr {
+            let _ = stderr.read_to_string(&mut buffer).await;
+        }
+        buffer
+    });
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            on_event(ScriptEvent::Output { step: index, line });
+        }
+    }
+
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| format!("Failed to wait for step {}: {}", index + 1, e))?;
+    let stderr = stderr_task.await.unwrap_or_default();
+    Ok((status.code().unwrap_or(-1), stderr))
+}
+
+/// Run the steps one after another in `shell`, stopping at the first one that fails
+pub async fn run_script(
+    steps: &[String],
+    cwd: Option<&str>,
+    host: Option<&str>,
+    shell: Option<CommandShell>,
+    on_event: impl Fn(ScriptEvent),
+) -> ScriptRunResult {
+    let mut result = ScriptRunResult {
+        total_steps: steps.len(),
+        steps_run: 0,
+        success: true,
+        failed_step: None,
+        error: None,
+    };
+
+    for (index, step) in steps.iter().enumerate() {
+        on_event(ScriptEvent::StepStarted {
+            step: index,
+            command: step.clone(),
+        });
+        result.steps_run += 1;
+
+        let (exit_code, stderr) = match run_step(index, step, cwd, host, shell, &on_event).await {
+            Ok(outcome) => outcome,
+            Err(e) => (-1, e),
+        };
+        on_event(ScriptEvent::StepFinished {
+            step: index,
+            exit_code,
+            stderr: stderr.clone(),
+        });
+
+        if exit_code != 0 {
+            result.success = false;
+            result.failed_step = Some(index);
+            result.error = Some(format!(
+                "Step {} exited with code {}: {}",
+                index + 1,
+                exit_code,
+                stderr.trim()
+            ));
+            break;
+        }
+    }
+
+    result
+}
@@ -0,0 +1,325 @@
+use crate::json_store::JsonStore;
+use crate::models::{CommandShell, ServiceConfig, ServiceState, ServiceStatus};
+use crate::processes;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event emitted with a `ServiceStatus` whenever a service's state changes
+pub const SERVICE_STATUS_EVENT: &str = "service://status";
+
+/// How often running services are health-checked
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A service failing its health check this long after starting counts as unhealthy
+const STARTUP_GRACE_SECS: i64 = 60;
+
+/// How long a health check may take before it counts as failed
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long restart_service waits for the old process to exit
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct ServiceEntry {
+    status: ServiceStatus,
+    /// Set by stop_service so the exit isn't reported as a crash
+    stopping: bool,
+}
+
+/// Managed state: processes of the services defined in project metadata, keyed by
+/// service ID. Service logs default to ~/.devora/service-logs/. Clones share the state.
+#[derive(Clone)]
+pub struct ServiceManager {
+    log_dir: PathBuf,
+    services: Arc<Mutex<HashMap<String, ServiceEntry>>>,
+}
+
+impl ServiceManager {
+    pub fn new(config_dir: &Path) -> Self {
+        Self {
+            log_dir: config_dir.join("service-logs"),
+            services: Arc::default(),
+        }
+    }
+
+    fn log_path(&self, service: &ServiceConfig) -> PathBuf {
+        match service.log_file.as_deref().filter(|f| !f.is_empty()) {
+            Some(file) => PathBuf::from(file),
+            None => self.log_dir.join(format!("{}.log", service.id)),
+        }
+    }
+
+    /// Current status of a service (stopped if it was never started)
+    pub fn status(&self, project_id: &str, service: &ServiceConfig) -> ServiceStatus {
+        self.services
+            .lock()
+            .unwrap()
+            .get(&service.id)
+            .map(|entry| entry.status.clone())
+            .unwrap_or_else(|| ServiceStatus {
+                service_id: service.id.clone(),
+                project_id: project_id.to_string(),
+                state: ServiceState::Stopped,
+                pid: None,
+                started_at: None,
+                exit_code: None,
+                log_file: self.log_path(service).to_string_lossy().to_string(),
+            })
+    }
+
+    fn update(&self, app: &AppHandle, service_id: &str, change: impl FnOnce(&mut ServiceEntry)) {
+        let status = {
+            let mut services = self.services.lock().unwrap();
+            let Some(entry) = services.get_mut(service_id) else {
+                return;
+            };
+            let before = entry.status.state;
+            change(entry);
+            if entry.status.state == before {
+                return;
+            }
+            entry.status.clone()
+        };
+        let _ = app.emit(SERVICE_STATUS_EVENT, status);
+    }
+
+    /// Start the service's command in `shell` with its output appended to the log
+    /// file
+    pub fn start(
+        &self,
+        app: &AppHandle,
+        project_id: &str,
+        service: &ServiceConfig,
+        cwd: Option<&str>,
+        shell: CommandShell,
+    ) -> Result<ServiceStatus, String> {
+        let mut services = self.services.lock().unwrap();
+        if let Some(entry) = services.get(&service.id) {
+            if entry.status.pid.is_some() {
+                return Err(format!("Service \"{}\" is already running", service.name));
+            }
+        }
+
+        let log_path = self.log_path(service);
+        let mut child = spawn_logged(shell, &service.command, cwd.unwrap_or("."), &log_path)?;
+        let pid = child.id();
+        let has_health_check = service.port.is_some() || service.health_url.is_some();
+        let status = ServiceStatus {
+            service_id: service.id.clone(),
+            project_id: project_id.to_string(),
+            state: if has_health_check {
+                ServiceState::Starting
+            } else {
+                ServiceState::Running
+            },
+            pid: Some(pid),
+            started_at: Some(chrono::Utc::now().to_rfc3339()),
+            exit_code: None,
+            log_file: log_path.to_string_lossy().to_string(),
+        };
+        services.insert(
+            service.id.clone(),
+            ServiceEntry {
+                status: status.clone(),
+                stopping: false,
+            },
+        );
+        drop(services);
+        let _ = app.emit(SERVICE_STATUS_EVENT, status.clone());
+
+        let manager = self.clone();
+        let app = app.clone();
+        let service_id = service.id.clone();
+        std::thread::spawn(move || {
+            let exit_code = child.wait().ok().and_then(|status| status.code());
+            manager.update(&app, &service_id, |entry| {
+                // A newer run may have replaced this one already
+                if entry.status.pid != Some(pid) {
+                    return;
+                }
+                entry.status.pid = None;
+                entry.status.exit_code = exit_code;
+                entry.status.state = if entry.stopping {
+                    ServiceState::Stopped
+                } else {
+                    ServiceState::Crashed
+                };
+            });
+        });
+        Ok(status)
+    }
+
+    /// Stop the service's process (with its children). Its status switches to
+    /// stopped once the process has exited.
+    pub fn stop(&self, service_id: &str) -> Result<(), String> {
+        let pid = {
+            let mut services = self.services.lock().unwrap();
+            let entry = services
+                .get_mut(service_id)
+                .ok_or("Service is not running")?;
+            let pid = entry.status.pid.ok_or("Service is not running")?;
+            entry.stopping = true;
+            pid
+        };
+        processes::kill_local(pid)
+    }
+
+    /// Wait until the service's process has exited, up to STOP_TIMEOUT
+    pub async fn wait_stopped(&self, service_id: &str) -> Result<(), String> {
+        let started = std::time::Instant::now();
+        while started.elapsed() < STOP_TIMEOUT {
+            let running = self
+                .services
+                .lock()
+                .unwrap()
+                .get(service_id)
+                .is_some_and(|entry| entry.status.pid.is_some());
+            if !running {
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        Err("Service did not stop in time".to_string())
+    }
+
+    /// Health-check every service that is running, updating its state
+    async fn check_all(&self, app: &AppHandle) {
+        let running: Vec<(String, ServiceStatus)> = self
+            .services
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, entry)| entry.status.pid.is_some() && !entry.stopping)
+            .map(|(id, entry)| (id.clone(), entry.status.clone()))
+            .collect();
+
+        for (service_id, status) in running {
+            let Some(service) = find_service(app, &status.project_id, &service_id) else {
+                continue;
+            };
+            if service.port.is_none() && service.health_url.is_none() {
+                continue;
+            }
+
+            let healthy = is_healthy(&service).await;
+            let in_grace = status
+                .started_at
+                .as_deref()
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .is_some_and(|t| {
+                    chrono::Utc::now().signed_duration_since(t).num_seconds() < STARTUP_GRACE_SECS
+                });
+            self.update(app, &service_id, |entry| {
+                if entry.status.pid != status.pid || entry.stopping {
+                    return;
+                }
+                entry.status.state = match (healthy, entry.status.state) {
+                    (true, _) => ServiceState::Running,
+                    // Still booting: only report failure once the grace period is over
+                    (false, ServiceState::Starting) if in_grace => ServiceState::Starting,
+                    (false, _) => ServiceState::Unhealthy,
+                };
+            });
+        }
+    }
+}
+
+/// Service definition from a project's metadata
+pub fn find_service(app: &AppHandle, project_id: &str, service_id: &str) -> Option<ServiceConfig> {
+    app.state::<JsonStore>()
+        .get_project_by_id(project_id)
+        .ok()??
+        .metadata
+        .services?
+        .into_iter()
+        .find(|s| s.id == service_id)
+}
+
+/// Healthy when the port accepts connections and the health URL (http or https)
+/// answers below 400
+async fn is_healthy(service: &ServiceConfig) -> bool {
+    if let Some(port) = service.port {
+        let open = tokio::task::spawn_blocking(move || {
+            let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+            TcpStream::connect_timeout(&addr, HEALTH_CHECK_TIMEOUT).is_ok()
+        })
+        .await
+        .unwrap_or(false);
+        if !open {
+            return false;
+        }
+    }
+
+    let Some(url) = service.health_url.as_deref().filter(|u| !u.is_empty()) else {
+        return true;
+    };
+    // Anything else could reach curl as an option or another protocol
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        log::warn!(
+            "Health URL of service \"{}\" is not http(s): {}",
+            service.name,
+            url
+        );
+        return false;
+    }
+    let null_device = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    let mut cmd = tokio::process::Command::new("curl");
+    cmd.args([
+        "-s",
+        "-f",
+        "-o",
+        null_device,
+        "--max-time",
+        &HEALTH_CHECK_TIMEOUT.as_secs().to_string(),
+        url,
+    ]);
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    cmd.status().await.is_ok_and(|status| status.success())
+}
+
+/// Start a command in a local `shell` the way background commands are started,
+/// appending its stdout and stderr to `log_path`
+fn spawn_logged(
+    shell: CommandShell,
+    command: &str,
+    cwd: &str,
+    log_path: &Path,
+) -> Result<std::process::Child, String> {
+    if let Some(dir) = log_path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+    }
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .map_err(|e| format!("Failed to open service log: {}", e))?;
+    let _ = writeln!(
+        log,
+        "--- {} started: {}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        command
+    );
+    processes::spawn_background(shell, command, cwd, Some(log))
+}
+
+/// Start the background task that health-checks running services
+pub fn spawn_health_checks(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+            let manager = app.state::<ServiceManager>().inner().clone();
+            manager.check_all(&app).await;
+        }
+    });
+}
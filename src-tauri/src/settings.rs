@@ -1,3 +1,4 @@
+use crate::models::{CommandShell, SyncConfig};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -14,6 +15,21 @@ pub struct AppSettings {
     /// Legacy field for backward compatibility - will be migrated to data_path
     #[serde(skip_serializing_if = "Option::is_none")]
     pub database_path: Option<String>,
+
+    /// Remote sync (WebDAV / S3) configuration. Kept here rather than in
+    /// metadata.json so credentials never end up in the synced data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync: Option<SyncConfig>,
+
+    /// IDs of projects whose commands skip the command policy's allowlist and
+    /// confirmations. Kept here so a synced or imported project can't trust itself
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_projects: Vec<String>,
+
+    /// Shell for commands that don't pick one. Machine-local, since the shells
+    /// installed differ between machines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_shell: Option<CommandShell>,
 }
 
 /// Manages the settings.json file
@@ -88,6 +104,60 @@ impl SettingsFile {
         self.save(&settings)
     }
 
+    /// Get the config directory (~/.devora/)
+    pub fn config_dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    /// Get the remote sync configuration
+    pub fn get_sync_config(&self) -> Option<SyncConfig> {
+        self.settings.lock().unwrap().sync.clone()
+    }
+
+    /// Set (or clear) the remote sync configuration
+    pub fn set_sync_config(&self, config: Option<SyncConfig>) -> Result<(), String> {
+        let mut settings = self.settings.lock().unwrap().clone();
+        settings.sync = config;
+        self.save(&settings)
+    }
+
+    /// Whether the project is trusted on this machine
+    pub fn is_trusted(&self, project_id: &str) -> bool {
+        self.settings
+            .lock()
+            .unwrap()
+            .trusted_projects
+            .iter()
+            .any(|id| id == project_id)
+    }
+
+    /// IDs of the projects trusted on this machine
+    pub fn trusted_projects(&self) -> Vec<String> {
+        self.settings.lock().unwrap().trusted_projects.clone()
+    }
+
+    /// Trust a project on this machine, or stop trusting it
+    pub fn set_trusted(&self, project_id: &str, trusted: bool) -> Result<(), String> {
+        let mut settings = self.settings.lock().unwrap().clone();
+        settings.trusted_projects.retain(|id| id != project_id);
+        if trusted {
+            settings.trusted_projects.push(project_id.to_string());
+        }
+        self.save(&settings)
+    }
+
+    /// Get the shell for commands that don't pick one
+    pub fn default_shell(&self) -> Option<CommandShell> {
+        self.settings.lock().unwrap().default_shell
+    }
+
+    /// Set (or clear) the shell for commands that don't pick one
+    pub fn set_default_shell(&self, shell: Option<CommandShell>) -> Result<(), String> {
+        let mut settings = self.settings.lock().unwrap().clone();
+        settings.default_shell = shell;
+        self.save(&settings)
+    }
+
     // Legacy methods for backward compatibility
 
     /// Get the database path (legacy - use get_data_path instead)
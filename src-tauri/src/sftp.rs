@@ -0,0 +1,173 @@
+use crate::hosts::HostStatusCache;
+use crate::models::{SftpEntry, SftpListing};
+use crate::processes;
+use std::process::Output;
+
+/// Run sftp batch commands on `host`. sftp stops at the first failing command
+/// and exits non-zero; `-b` also keeps it from prompting for a password.
+async fn run_batch(host: &str, batch: String, cache: &HostStatusCache) -> Result<Output, String> {
+    let mut cmd = tokio::process::Command::new("sftp");
+    cmd.args(["-q", "-b", "-", host]);
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = processes::output_with_input(cmd, Some(batch)).await?;
+    cache.record_ssh_output(host, &output);
+
+    if output.status.success() {
+        Ok(output)
+    } else {
+        Err(format!(
+            "sftp failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Quote a path for an sftp batch line. A line break would start a batch line of
+/// its own (`!` ones run local commands), so paths with one are refused.
+fn quote(path: &str) -> Result<String, String> {
+    if path.contains(['\r', '\n', '\0']) {
+        return Err(format!("Invalid path {:?}", path));
+    }
+    Ok(format!(
+        "\"{}\"",
+        path.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+/// Long listing of a remote directory (the remote home by default)
+pub async fn list(
+    host: &str,
+    path: Option<&str>,
+    cache: &HostStatusCache,
+) -> Result<SftpListing, String> {
+    let mut batch = String::new();
+    if let Some(path) = path.filter(|p| !p.is_empty()) {
+        batch.push_str(&format!("cd {}\n", quote(path)?));
+    }
+    batch.push_str("pwd\nls -la\n");
+    let output = run_batch(host, batch, cache).await?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut current_path = path.unwrap_or("~").to_string();
+    let mut entries = Vec::new();
+    for line in stdout.lines() {
+        if let Some(dir) = line.strip_prefix("Remote working directory: ") {
+            current_path = dir.trim().to_string();
+        } else if let Some(entry) = parse_long_line(line) {
+            if entry.name != "." && entry.name != ".." {
+                entries.push(entry);
+            }
+        }
+    }
+    // Directories first, then by name
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(SftpListing {
+        current_path,
+        entries,
+    })
+}
+
+/// Parse an `ls -l` line such as
+/// `-rw-r--r--    1 user  group   1024 Jan  2 10:00 app.log`
+fn parse_long_line(line: &str) -> Option<SftpEntry> {
+    // The name is everything after the eighth field, spaces included
+    let mut rest = line;
+    let mut words = Vec::with_capacity(8);
+    for _ in 0..8 {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace)?;
+        words.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    let permissions = words[0];
+    if permissions.len() < 10 || !permissions.starts_with(['-', 'd', 'l', 'c', 'b', 'p', 's']) {
+        return None;
+    }
+    let name = rest.trim_start();
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(SftpEntry {
+        name: name.to_string(),
+        is_dir: permissions.starts_with('d'),
+        is_symlink: permissions.starts_with('l'),
+        size: words[4].parse().unwrap_or(0),
+        permissions: permissions.to_string(),
+        modified: words[5..8].join(" "),
+    })
+}
+
+/// Copy a remote file (or with `recursive`, a directory) to a local path
+pub async fn download(
+    host: &str,
+    remote_path: &str,
+    local_path: &str,
+    recursive: bool,
+    cache: &HostStatusCache,
+) -> Result<(), String> {
+    let flags = if recursive { "-pr" } else { "-p" };
+    let batch = format!(
+        "get {} {} {}\n",
+        flags,
+        quote(remote_path)?,
+        quote(local_path)?
+    );
+    run_batch(host, batch, cache).await.map(|_| ())
+}
+
+/// Copy a local file (or with `recursive`, a directory) to a remote path
+pub async fn upload(
+    host: &str,
+    local_path: &str,
+    remote_path: &str,
+    recursive: bool,
+    cache: &HostStatusCache,
+) -> Result<(), String> {
+    if !std::path::Path::new(local_path).exists() {
+        return Err(format!("Local path {} does not exist", local_path));
+    }
+    let flags = if recursive { "-pr" } else { "-p" };
+    let batch = format!(
+        "put {} {} {}\n",
+        flags,
+        quote(local_path)?,
+        quote(remote_path)?
+    );
+    run_batch(host, batch, cache).await.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_escapes_backslashes_and_quotes() {
+        assert_eq!(quote("/srv/app").unwrap(), "\"/srv/app\"");
+        assert_eq!(quote(r#"C:\dir\"a b""#).unwrap(), r#""C:\\dir\\\"a b\"""#);
+    }
+
+    #[test]
+    fn quote_rejects_line_breaks_and_nul() {
+        assert!(quote("a\n!rm -rf ~").is_err());
+        assert!(quote("a\rb").is_err());
+        assert!(quote("a\0b").is_err());
+    }
+
+    #[test]
+    fn parses_long_listing_lines() {
+        let entry =
+            parse_long_line("-rw-r--r--    1 user  group   1024 Jan  2 10:00 my app.log").unwrap();
+        assert_eq!(entry.name, "my app.log");
+        assert_eq!(entry.size, 1024);
+        assert!(!entry.is_dir);
+        assert_eq!(entry.modified, "Jan 2 10:00");
+        assert!(parse_long_line("Remote working directory: /home/user").is_none());
+    }
+}
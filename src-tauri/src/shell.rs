@@ -0,0 +1,226 @@
+use crate::git;
+use crate::models::CommandShell;
+use crate::settings::SettingsFile;
+
+impl CommandShell {
+    /// `cmd` on Windows, `sh` elsewhere
+    pub fn platform_default() -> Self {
+        if cfg!(windows) {
+            CommandShell::Cmd
+        } else {
+            CommandShell::Sh
+        }
+    }
+
+    pub fn program(self) -> &'static str {
+        match self {
+            CommandShell::Sh => "sh",
+            CommandShell::Bash => "bash",
+            CommandShell::Zsh => "zsh",
+            CommandShell::Fish => "fish",
+            CommandShell::Pwsh => "pwsh",
+            CommandShell::Nushell => "nu",
+            CommandShell::Cmd => "cmd",
+        }
+    }
+
+    /// Arguments that make the shell run `command` and exit. bash, zsh, fish and
+    /// nushell start as login shells so the user's profile (PATH, version managers,
+    /// exported variables) is loaded, which an app launched from the desktop lacks.
+    pub fn args(self, command: &str) -> Vec<String> {
+        let flags: &[&str] = match self {
+            CommandShell::Sh => &["-c"],
+            CommandShell::Bash | CommandShell::Zsh | CommandShell::Fish | CommandShell::Nushell => {
+                &["-l", "-c"]
+            }
+            CommandShell::Pwsh => &["-NoLogo", "-NonInteractive", "-Command"],
+            CommandShell::Cmd => &["/C"],
+        };
+        flags
+            .iter()
+            .map(|f| f.to_string())
+            .chain(std::iter::once(command.to_string()))
+            .collect()
+    }
+
+    pub fn std_command(self, command: &str) -> std::process::Command {
+        let mut cmd = std::process::Command::new(self.program());
+        // cmd parses its command line itself and doesn't honor the `\"` escapes an
+        // argument would get, so the command goes on the line as it is
+        #[cfg(windows)]
+        if self == CommandShell::Cmd {
+            use std::os::windows::process::CommandExt;
+            cmd.raw_arg(format!("/C {}", command));
+            return cmd;
+        }
+        cmd.args(self.args(command));
+        cmd
+    }
+
+    pub fn tokio_command(self, command: &str) -> tokio::process::Command {
+        tokio::process::Command::from(self.std_command(command))
+    }
+
+    /// Command line to pass to `ssh host`: the shell invoked with the command as one
+    /// argument, quoted for the remote login shell (assumed POSIX)
+    pub fn remote_line(self, command: &str) -> String {
+        std::iter::once(self.program().to_string())
+            .chain(self.args(command).iter().map(|arg| git::shell_quote(arg)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Shell for a command: its own choice, else this machine's default shell. None
+/// keeps the platform shell locally and the login shell of the remote user over SSH.
+pub fn resolve(settings: &SettingsFile, shell: Option<CommandShell>) -> Option<CommandShell> {
+    shell.or_else(|| settings.default_shell())
+}
+
+/// The local user's login shell, from $SHELL (`sh` when it's unset or unknown)
+pub fn user_shell() -> CommandShell {
+    let name = std::env::var("SHELL")
+        .ok()
+        .and_then(|path| {
+            std::path::Path::new(&path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+        })
+        .unwrap_or_default();
+    match name.as_str() {
+        "bash" => CommandShell::Bash,
+        "zsh" => CommandShell::Zsh,
+        "fish" => CommandShell::Fish,
+        "nu" => CommandShell::Nushell,
+        "pwsh" => CommandShell::Pwsh,
+        _ => CommandShell::Sh,
+    }
+}
+
+/// `value` as a single word for `shell`
+pub fn quote(value: &str, shell: CommandShell) -> String {
+    match shell {
+        // fish takes `\\` and `\'` escapes inside single quotes
+        CommandShell::Fish => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+        // nushell single quotes have no escapes at all
+        CommandShell::Nushell if !value.contains('\'') => format!("'{}'", value),
+        CommandShell::Nushell => {
+            format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+        CommandShell::Pwsh => format!("'{}'", value.replace('\'', "''")),
+        // cmd has no escape inside quotes, but a doubled quote keeps the quoting
+        // balanced and reaches the program as one `"`. A line break would end the
+        // command, so it becomes a space.
+        CommandShell::Cmd => format!(
+            "\"{}\"",
+            value.replace('"', "\"\"").replace(['\r', '\n'], " ")
+        ),
+        _ => git::shell_quote(value),
+    }
+}
+
+/// `env VAR=value ... ` prefix that sets env for the command after it, quoted for
+/// `shell`. Going through `env` works the same in every Unix shell, where the bare
+/// `VAR=value cmd` form is POSIX-only (fish before 3.1 and nushell reject it).
+pub fn env_prefix<'a>(
+    env: impl IntoIterator<Item = (&'a String, &'a String)>,
+    shell: CommandShell,
+) -> String {
+    let assignments: Vec<String> = env
+        .into_iter()
+        .map(|(k, v)| quote(&format!("{}={}", k, v), shell))
+        .collect();
+    if assignments.is_empty() {
+        return String::new();
+    }
+    // nushell would run its own `env` command
+    let program = if shell == CommandShell::Nushell {
+        "^env"
+    } else {
+        "env"
+    };
+    format!("{} {} ", program, assignments.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_SHELLS: [CommandShell; 7] = [
+        CommandShell::Sh,
+        CommandShell::Bash,
+        CommandShell::Zsh,
+        CommandShell::Fish,
+        CommandShell::Pwsh,
+        CommandShell::Nushell,
+        CommandShell::Cmd,
+    ];
+
+    #[test]
+    fn plain_values_are_single_quoted() {
+        for shell in ALL_SHELLS {
+            let expected = if shell == CommandShell::Cmd {
+                "\"a b\""
+            } else {
+                "'a b'"
+            };
+            assert_eq!(quote("a b", shell), expected, "{:?}", shell);
+        }
+    }
+
+    #[test]
+    fn posix_shells_close_and_reopen_quotes() {
+        assert_eq!(quote("it's", CommandShell::Sh), r"'it'\''s'");
+        assert_eq!(quote(r"a\b", CommandShell::Bash), r"'a\b'");
+        assert_eq!(quote("$HOME `x`", CommandShell::Zsh), "'$HOME `x`'");
+    }
+
+    #[test]
+    fn fish_escapes_backslashes_and_quotes() {
+        assert_eq!(quote(r"it's a\b", CommandShell::Fish), r"'it\'s a\\b'");
+    }
+
+    #[test]
+    fn pwsh_doubles_single_quotes() {
+        assert_eq!(quote("it's $x", CommandShell::Pwsh), "'it''s $x'");
+    }
+
+    #[test]
+    fn nushell_switches_to_double_quotes_for_single_quotes() {
+        assert_eq!(quote(r"a\b", CommandShell::Nushell), r"'a\b'");
+        assert_eq!(
+            quote(r#"it's "a\b""#, CommandShell::Nushell),
+            r#""it's \"a\\b\"""#
+        );
+    }
+
+    #[test]
+    fn cmd_doubles_quotes_and_drops_line_breaks() {
+        assert_eq!(
+            quote("say \"hi\"\r\n& exit", CommandShell::Cmd),
+            "\"say \"\"hi\"\"  & exit\""
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sh_reads_the_quoted_value_back() {
+        let value = "it's $HOME `id` \"q\" \\ ; | & * ~\nline";
+        let output = std::process::Command::new("sh")
+            .args([
+                "-c",
+                &format!("printf %s {}", quote(value, CommandShell::Sh)),
+            ])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), value);
+    }
+
+    #[test]
+    fn env_prefix_quotes_each_assignment() {
+        let env = std::collections::HashMap::from([("A".to_string(), "x y".to_string())]);
+        assert_eq!(env_prefix(&env, CommandShell::Sh), "env 'A=x y' ");
+        assert_eq!(env_prefix(&env, CommandShell::Nushell), "^env 'A=x y' ");
+        assert_eq!(env_prefix([], CommandShell::Sh), "");
+    }
+}
@@ -0,0 +1,257 @@
+use crate::models::SshHostEntry;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// ssh stops following Include directives this deep
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A `Host` block: its patterns and options in file order. Options before the
+/// first `Host` line form a block matching every host.
+struct Block {
+    patterns: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+/// Hosts named in ~/.ssh/config (and the files it includes), with the settings
+/// ssh would use for each. Wildcard patterns aren't listed themselves but their
+/// settings apply to the hosts they match.
+pub fn load_hosts() -> Result<Vec<SshHostEntry>, String> {
+    let ssh_dir = dirs::home_dir()
+        .ok_or("Could not find home directory")?
+        .join(".ssh");
+    let config_path = ssh_dir.join("config");
+    if !config_path.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut blocks = vec![Block {
+        patterns: vec!["*".to_string()],
+        options: Vec::new(),
+    }];
+    parse_file(&config_path, &ssh_dir, 0, &mut blocks)?;
+
+    let mut aliases: Vec<&str> = Vec::new();
+    for pattern in blocks.iter().skip(1).flat_map(|b| &b.patterns) {
+        let literal = !pattern.contains(['*', '?']) && !pattern.starts_with('!');
+        if literal && !aliases.contains(&pattern.as_str()) {
+            aliases.push(pattern);
+        }
+    }
+    Ok(aliases
+        .into_iter()
+        .map(|alias| resolve(&blocks, alias))
+        .collect())
+}
+
+/// Address, user and port to reach `host` (an alias or `user@host`) at, for tools
+/// that don't read ~/.ssh/config themselves. The user defaults to the local one.
+pub fn connection(host: &str) -> (String, String, u16) {
+    let (user, alias) = match host.split_once('@') {
+        Some((user, alias)) => (Some(user.to_string()), alias),
+        None => (None, host),
+    };
+    let entry = load_hosts()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|entry| entry.host == alias);
+
+    let hostname = entry
+        .as_ref()
+        .and_then(|e| e.hostname.clone())
+        .unwrap_or_else(|| alias.to_string());
+    let user = user
+        .or_else(|| entry.as_ref().and_then(|e| e.user.clone()))
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .unwrap_or_default();
+    let port = entry.and_then(|e| e.port).unwrap_or(22);
+    (hostname, user, port)
+}
+
+fn parse_file(
+    path: &Path,
+    ssh_dir: &Path,
+    depth: usize,
+    blocks: &mut Vec<Block>,
+) -> Result<(), String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read SSH config: {}", e))?;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // "Key value", "Key=value" and "Key = value" are all valid
+        let (key, value) = match line.split_once(|c: char| c.is_whitespace() || c == '=') {
+            Some((key, value)) => (key, value.trim_start_matches([' ', '\t', '='])),
+            None => (line, ""),
+        };
+        let value = value.trim();
+
+        match key.to_lowercase().as_str() {
+            "host" => blocks.push(Block {
+                patterns: split_values(value),
+                options: Vec::new(),
+            }),
+            // Match conditions aren't evaluated; their options are skipped
+            "match" => blocks.push(Block {
+                patterns: Vec::new(),
+                options: Vec::new(),
+            }),
+            "include" if depth < MAX_INCLUDE_DEPTH => {
+                for pattern in split_values(value) {
+                    for included in expand_include(&pattern, ssh_dir) {
+                        // A broken include shouldn't hide the rest of the config
+                        if let Err(e) = parse_file(&included, ssh_dir, depth + 1, blocks) {
+                            log::warn!("{}: {}", included.display(), e);
+                        }
+                    }
+                }
+            }
+            key => {
+                if let Some(block) = blocks.last_mut() {
+                    block.options.push((key.to_string(), value.to_string()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whitespace-separated values, where double quotes group words
+fn split_values(value: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    values.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        values.push(current);
+    }
+    values
+}
+
+/// Files an Include pattern names: relative paths are under ~/.ssh, and `*`/`?`
+/// are expanded in the file name
+fn expand_include(pattern: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let path = match pattern.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => ssh_dir.join(pattern),
+    };
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return Vec::new();
+    };
+    if !name.contains(['*', '?']) {
+        return if path.is_file() {
+            vec![path]
+        } else {
+            Vec::new()
+        };
+    }
+
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|n| wildcard_match(&name, &n.to_string_lossy()))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Settings for `alias`: the first value of each option among the blocks whose
+/// patterns match it, as ssh resolves them
+fn resolve(blocks: &[Block], alias: &str) -> SshHostEntry {
+    let mut entry = SshHostEntry {
+        host: alias.to_string(),
+        hostname: None,
+        user: None,
+        port: None,
+        identity_file: None,
+        proxy_jump: None,
+    };
+    for block in blocks.iter().filter(|b| block_matches(b, alias)) {
+        for (key, value) in &block.options {
+            let value = value.trim_matches('"').to_string();
+            let field = match key.as_str() {
+                // `%h` in HostName stands for the alias
+                "hostname" => {
+                    entry
+                        .hostname
+                        .get_or_insert_with(|| value.replace("%h", alias));
+                    continue;
+                }
+                "port" => {
+                    entry.port = entry.port.or(value.parse().ok());
+                    continue;
+                }
+                "user" => &mut entry.user,
+                "identityfile" => &mut entry.identity_file,
+                "proxyjump" => &mut entry.proxy_jump,
+                _ => continue,
+            };
+            field.get_or_insert(value);
+        }
+    }
+    // `ProxyJump none` turns off a jump host set by a later wildcard block
+    entry.proxy_jump = entry.proxy_jump.filter(|jump| jump != "none");
+    entry
+}
+
+/// A host matches when one pattern matches and no negated (`!`) pattern does
+fn block_matches(block: &Block, host: &str) -> bool {
+    let mut matched = false;
+    for pattern in &block.patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated, host) => return false,
+            Some(_) => {}
+            None => matched |= wildcard_match(pattern, host),
+        }
+    }
+    matched
+}
+
+/// Match `text` against a pattern where `*` is any run of characters and `?` one
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p + 1, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,429 @@
+use crate::models::{SyncBackend, SyncConfig, SyncReport, SyncStatus};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Name of the manifest stored next to the data files on the remote
+const MANIFEST_NAME: &str = "devora-sync-manifest.json";
+
+/// Remote manifest: relative file path -> SHA-256 of its content
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct Manifest {
+    files: HashMap<String, String>,
+}
+
+/// Local sync state stored in ~/.devora/sync_state.json (never synced)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct SyncState {
+    /// Hashes of each file as of the last successful sync (the merge base)
+    base: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_sync: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<String>,
+}
+
+impl SyncState {
+    fn path(config_dir: &Path) -> PathBuf {
+        config_dir.join("sync_state.json")
+    }
+
+    fn load(config_dir: &Path) -> Self {
+        fs::read_to_string(Self::path(config_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, config_dir: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize sync state: {}", e))?;
+        fs::write(Self::path(config_dir), content)
+            .map_err(|e| format!("Failed to write sync state: {}", e))
+    }
+}
+
+/// SHA-256 of a byte slice as lowercase hex
+fn hash_bytes(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Hash every syncable file in the data directory (metadata.json, projects/, blobs/)
+fn hash_local_files(data_path: &Path) -> Result<HashMap<String, String>, String> {
+    let mut hashes = HashMap::new();
+
+    let metadata_path = data_path.join("metadata.json");
+    if let Ok(bytes) = fs::read(&metadata_path) {
+        hashes.insert("metadata.json".to_string(), hash_bytes(&bytes));
+    }
+
+    let projects_dir = data_path.join("projects");
+    if let Ok(entries) = fs::read_dir(&projects_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                // Split project layout: projects/{id}/*.json + todos.md
+                let project_id = entry.file_name().to_string_lossy().to_string();
+                for part in fs::read_dir(&path).into_iter().flatten().flatten() {
+                    let name = part.file_name().to_string_lossy().to_string();
+                    if !name.ends_with(".json") && !name.ends_with(".md") {
+                        continue;
+                    }
+                    let bytes = fs::read(part.path())
+                        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+                    hashes.insert(
+                        format!("projects/{}/{}", project_id, name),
+                        hash_bytes(&bytes),
+                    );
+                }
+                continue;
+            }
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            // Conflict copies are local-only until the user resolves them
+            if name.ends_with(".conflict.json") {
+                continue;
+            }
+            let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+            hashes.insert(format!("projects/{}", name), hash_bytes(&bytes));
+        }
+    }
+
+    // Large item contents stored outside the project files
+    if let Ok(project_dirs) = fs::read_dir(data_path.join("blobs")) {
+        for project_dir in project_dirs.flatten() {
+            let project_id = project_dir.file_name().to_string_lossy().to_string();
+            for blob in fs::read_dir(project_dir.path())
+                .into_iter()
+                .flatten()
+                .flatten()
+            {
+                let name = blob.file_name().to_string_lossy().to_string();
+                if !name.ends_with(".txt") {
+                    continue;
+                }
+                let bytes =
+                    fs::read(blob.path()).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+                hashes.insert(format!("blobs/{}/{}", project_id, name), hash_bytes(&bytes));
+            }
+        }
+    }
+
+    Ok(hashes)
+}
+
+/// Remote endpoint reached through `curl`, which supports both WebDAV
+/// (basic auth) and S3-compatible storage (`--aws-sigv4`)
+struct Remote<'a> {
+    config: &'a SyncConfig,
+}
+
+impl Remote<'_> {
+    /// Full URL for a file relative to the sync root
+    fn url(&self, relative: &str) -> String {
+        let endpoint = self.config.endpoint.trim_end_matches('/');
+        let prefix = self
+            .config
+            .prefix
+            .as_deref()
+            .unwrap_or("")
+            .trim_matches('/');
+
+        let mut url = endpoint.to_string();
+        if self.config.backend == SyncBackend::S3 {
+            if let Some(bucket) = &self.config.bucket {
+                url = format!("{}/{}", url, bucket);
+            }
+        }
+        if !prefix.is_empty() {
+            url = format!("{}/{}", url, prefix);
+        }
+        format!("{}/{}", url, relative)
+    }
+
+    /// Run curl and return (http status, response body).
+    /// Credentials are passed on stdin so they never show up in the process list.
+    async fn request(
+        &self,
+        method: &str,
+        relative: &str,
+        upload: Option<&Path>,
+    ) -> Result<(u16, Vec<u8>), String> {
+        let mut cmd = Command::new("curl");
+        cmd.args(["-s", "-X", method, "-K", "-", "-w", "%{stderr}%{http_code}"]);
+
+        if self.config.backend == SyncBackend::S3 {
+            let region = self.config.region.as_deref().unwrap_or("us-east-1");
+            cmd.args(["--aws-sigv4", &format!("aws:amz:{}:s3", region)]);
+        }
+        if let Some(file) = upload {
+            cmd.arg("-T").arg(file);
+        }
+        cmd.arg(self.url(relative));
+
+        #[cfg(windows)]
+        {
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run curl: {}", e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let user = format!(
+                "{}:{}",
+                self.config.username.as_deref().unwrap_or(""),
+                self.config.password.as_deref().unwrap_or("")
+            );
+            let curl_config = format!("user = {}\n", curl_config_string(&user));
+            stdin
+                .write_all(curl_config.as_bytes())
+                .await
+                .map_err(|e| format!("Failed to pass credentials to curl: {}", e))?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|e| format!("Failed to run curl: {}", e))?;
+
+        let status: u16 = String::from_utf8_lossy(&output.stderr)
+            .trim()
+            .parse()
+            .unwrap_or(0);
+        if status == 0 {
+            return Err(format!("Could not reach {}", self.config.endpoint));
+        }
+
+        Ok((status, output.stdout))
+    }
+
+    /// Download a file, returning None if it doesn't exist remotely
+    async fn get(&self, relative: &str) -> Result<Option<Vec<u8>>, String> {
+        match self.request("GET", relative, None).await? {
+            (200..=299, body) => Ok(Some(body)),
+            (404, _) => Ok(None),
+            (status, _) => Err(format!("GET {} failed with HTTP {}", relative, status)),
+        }
+    }
+
+    /// Upload a local file
+    async fn put(&self, relative: &str, file: &Path) -> Result<(), String> {
+        match self.request("PUT", relative, Some(file)).await? {
+            (200..=299, _) => Ok(()),
+            (status, _) => Err(format!("PUT {} failed with HTTP {}", relative, status)),
+        }
+    }
+
+    /// Make sure the remote folders exist (WebDAV only; S3 has no directories)
+    async fn ensure_dirs(&self) -> Result<(), String> {
+        if self.config.backend != SyncBackend::WebDav {
+            return Ok(());
+        }
+        // MKCOL returns 405 when the collection already exists, which is fine
+        self.request("MKCOL", "", None).await?;
+        self.request("MKCOL", "projects/", None).await?;
+        self.request("MKCOL", "blobs/", None).await?;
+        Ok(())
+    }
+
+    /// Make sure the folder of a nested file (e.g. `blobs/{project_id}/`) exists
+    async fn ensure_parent(&self, relative: &str) -> Result<(), String> {
+        if self.config.backend != SyncBackend::WebDav {
+            return Ok(());
+        }
+        if let Some((parent, _)) = relative.rsplit_once('/') {
+            if parent.contains('/') {
+                self.request("MKCOL", &format!("{}/", parent), None).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Push local changes and pull remote changes, detecting conflicts with the
+/// last synced state as the merge base:
+/// - only local changed -> push
+/// - only remote changed -> pull
+/// - both changed differently -> conflict, the remote copy is saved next to
+///   the local file as `{name}.conflict.json` and neither side is overwritten
+pub async fn sync_now(
+    config: &SyncConfig,
+    data_path: &Path,
+    config_dir: &Path,
+) -> Result<SyncReport, String> {
+    let result = sync_inner(config, data_path, config_dir).await;
+
+    // Record the outcome on top of the base hashes sync_inner saved
+    let mut state = SyncState::load(config_dir);
+    match &result {
+        Ok(report) => {
+            state.last_sync = Some(report.synced_at.clone());
+            state.last_error = None;
+        }
+        Err(e) => state.last_error = Some(e.clone()),
+    }
+    state.save(config_dir)?;
+
+    result
+}
+
+async fn sync_inner(
+    config: &SyncConfig,
+    data_path: &Path,
+    config_dir: &Path,
+) -> Result<SyncReport, String> {
+    let remote = Remote { config };
+    let mut state = SyncState::load(config_dir);
+
+    remote.ensure_dirs().await?;
+
+    let remote_manifest: Manifest = match remote.get(MANIFEST_NAME).await? {
+        Some(bytes) => serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Failed to parse remote manifest: {}", e))?,
+        None => Manifest::default(),
+    };
+    let local = hash_local_files(data_path)?;
+
+    let mut report = SyncReport {
+        pushed: Vec::new(),
+        pulled: Vec::new(),
+        conflicts: Vec::new(),
+        synced_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let mut all_files: Vec<&String> = local.keys().chain(remote_manifest.files.keys()).collect();
+    all_files.sort();
+    all_files.dedup();
+
+    let mut new_manifest = remote_manifest.clone();
+
+    for file in all_files {
+        let local_hash = local.get(file);
+        let remote_hash = remote_manifest.files.get(file);
+        let base_hash = state.base.get(file);
+
+        if local_hash == remote_hash {
+            if let Some(hash) = local_hash {
+                state.base.insert(file.clone(), hash.clone());
+            }
+            continue;
+        }
+
+        let local_changed = local_hash != base_hash;
+        let remote_changed = remote_hash != base_hash;
+
+        if local_changed && !remote_changed {
+            // Deletions are not propagated; a missing local file is simply not pushed
+            let Some(hash) = local_hash else {
+                continue;
+            };
+            remote.ensure_parent(file).await?;
+            remote.put(file, &data_path.join(file)).await?;
+            new_manifest.files.insert(file.clone(), hash.clone());
+            state.base.insert(file.clone(), hash.clone());
+            report.pushed.push(file.clone());
+        } else if remote_changed && !local_changed {
+            let Some(hash) = remote_hash else {
+                continue;
+            };
+            let Some(bytes) = remote.get(file).await? else {
+                continue;
+            };
+            write_file_atomic(&data_path.join(file), &bytes)?;
+            state.base.insert(file.clone(), hash.clone());
+            report.pulled.push(file.clone());
+        } else {
+            // Both sides changed since the last sync
+            if let Some(bytes) = remote.get(file).await? {
+                let conflict_path = data_path.join(file).with_extension("conflict.json");
+                write_file_atomic(&conflict_path, &bytes)?;
+            }
+            report.conflicts.push(file.clone());
+        }
+    }
+
+    if !report.pushed.is_empty() {
+        let manifest_json = serde_json::to_vec_pretty(&new_manifest)
+            .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+        let manifest_tmp = std::env::temp_dir().join(MANIFEST_NAME);
+        fs::write(&manifest_tmp, manifest_json)
+            .map_err(|e| format!("Failed to write manifest: {}", e))?;
+        let upload = remote.put(MANIFEST_NAME, &manifest_tmp).await;
+        let _ = fs::remove_file(&manifest_tmp);
+        upload?;
+    }
+
+    state.save(config_dir)?;
+    Ok(report)
+}
+
+/// Current sync status without touching the network
+pub fn get_status(config: Option<&SyncConfig>, data_path: &Path, config_dir: &Path) -> SyncStatus {
+    let state = SyncState::load(config_dir);
+    let pending_changes = hash_local_files(data_path)
+        .map(|local| {
+            local
+                .iter()
+                .filter(|(file, hash)| state.base.get(*file) != Some(*hash))
+                .count()
+        })
+        .unwrap_or(0);
+
+    SyncStatus {
+        configured: config.is_some_and(|c| !c.endpoint.is_empty()),
+        backend: config.map(|c| c.backend.clone()),
+        last_sync: state.last_sync,
+        last_error: state.last_error,
+        pending_changes,
+    }
+}
+
+/// `value` as a double-quoted curl config string. The backslash is escaped first so
+/// the escapes added for quotes and line breaks aren't doubled.
+fn curl_config_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+/// Write bytes atomically (temp file + rename), creating parent dirs
+fn write_file_atomic(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create parent directory: {}", e))?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, bytes).map_err(|e| format!("Failed to write temp file: {}", e))?;
+    fs::rename(&temp_path, path).map_err(|e| format!("Failed to rename temp file: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curl_config_string_escapes_backslashes_first() {
+        assert_eq!(curl_config_string("user:pass"), r#""user:pass""#);
+        assert_eq!(curl_config_string(r#"u:p\"w"#), r#""u:p\\\"w""#);
+        assert_eq!(curl_config_string("u:a\nb\tc"), r#""u:a\nb\tc""#);
+    }
+}
@@ -0,0 +1,266 @@
+/// Command built from a user template such as `nvim {path}`
+#[cfg(not(windows))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateCommand {
+    /// Plain words, spawned directly: a placeholder value stays one argument
+    /// whatever it contains
+    Argv(Vec<String>),
+    /// Uses shell features (pipes, redirects, variables, ...), so it runs through
+    /// `sh -c` with each placeholder value quoted for where it appears
+    Shell(String),
+}
+
+#[cfg(not(windows))]
+impl TemplateCommand {
+    pub fn command(&self) -> std::process::Command {
+        match self {
+            TemplateCommand::Argv(words) => {
+                let mut cmd = std::process::Command::new(&words[0]);
+                cmd.args(&words[1..]);
+                cmd
+            }
+            TemplateCommand::Shell(line) => {
+                let mut cmd = std::process::Command::new("sh");
+                cmd.args(["-c", line]);
+                cmd
+            }
+        }
+    }
+}
+
+#[cfg(not(windows))]
+#[derive(Clone, Copy, PartialEq)]
+enum Quote {
+    None,
+    Single,
+    Double,
+}
+
+/// Characters that make an unquoted template need the shell
+#[cfg(not(windows))]
+const SHELL_CHARS: &str = "|&;<>()$`*?[]#~{}";
+
+/// Fill `{name}` placeholders of a POSIX shell command template. Quotes the user
+/// already wrote around a placeholder are respected, so `code "{path}"` and
+/// `code {path}` both pass the path as a single argument.
+#[cfg(not(windows))]
+pub fn render(template: &str, values: &[(&str, &str)]) -> TemplateCommand {
+    let mut words: Vec<String> = Vec::new();
+    let mut word: Option<String> = None;
+    let mut line = String::new();
+    let mut quote = Quote::None;
+    let mut uses_shell = false;
+    let mut rest = template;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((value, len)) = placeholder(rest, values) {
+            word.get_or_insert_default().push_str(value);
+            line.push_str(&match quote {
+                Quote::None => crate::git::shell_quote(value),
+                Quote::Single => value.replace('\'', "'\\''"),
+                Quote::Double => escape_double_quoted(value),
+            });
+            rest = &rest[len..];
+            continue;
+        }
+        rest = &rest[c.len_utf8()..];
+        line.push(c);
+
+        match (quote, c) {
+            (Quote::None, c) if c.is_whitespace() => words.extend(word.take()),
+            (Quote::None, '\'') => {
+                word.get_or_insert_default();
+                quote = Quote::Single;
+            }
+            (Quote::None | Quote::Double, '"') => {
+                word.get_or_insert_default();
+                quote = if quote == Quote::None {
+                    Quote::Double
+                } else {
+                    Quote::None
+                };
+            }
+            (Quote::Single, '\'') => quote = Quote::None,
+            (Quote::None | Quote::Double, '\\') => {
+                let Some(next) = rest.chars().next() else {
+                    continue;
+                };
+                // Inside double quotes a backslash only escapes these
+                if quote == Quote::Double && !"$`\"\\\n".contains(next) {
+                    word.get_or_insert_default().push('\\');
+                }
+                rest = &rest[next.len_utf8()..];
+                line.push(next);
+                word.get_or_insert_default().push(next);
+            }
+            (Quote::None, c) if SHELL_CHARS.contains(c) => {
+                uses_shell = true;
+                word.get_or_insert_default().push(c);
+            }
+            (Quote::Double, '$' | '`') => {
+                uses_shell = true;
+                word.get_or_insert_default().push(c);
+            }
+            (_, c) => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+
+    // Unbalanced quotes or `VAR=value cmd` are left for the shell to handle
+    let plain = !uses_shell
+        && quote == Quote::None
+        && words.first().is_some_and(|program| !program.contains('='));
+    if plain {
+        TemplateCommand::Argv(words)
+    } else {
+        TemplateCommand::Shell(line)
+    }
+}
+
+/// Fill `{name}` placeholders of a `cmd /c` command template, double-quoting
+/// values that the template doesn't already quote
+#[cfg(windows)]
+pub fn render_cmd(template: &str, values: &[(&str, &str)]) -> String {
+    let mut line = String::new();
+    let mut quoted = false;
+    let mut rest = template;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((value, len)) = placeholder(rest, values) {
+            // Windows paths can't contain quotes, and one would end the quoting
+            let value = value.replace('"', "");
+            if quoted {
+                line.push_str(&value);
+            } else {
+                line.push_str(&format!("\"{}\"", value));
+            }
+            rest = &rest[len..];
+            continue;
+        }
+        if c == '"' {
+            quoted = !quoted;
+        }
+        line.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    line
+}
+
+/// Fill `{name}` placeholders of a URI template, percent-encoding the values
+/// (path separators stay as they are)
+pub fn fill_uri(template: &str, values: &[(&str, &str)]) -> String {
+    let mut uri = String::new();
+    let mut rest = template;
+    while let Some(c) = rest.chars().next() {
+        if let Some((value, len)) = placeholder(rest, values) {
+            uri.push_str(&percent_encode(value, "/"));
+            rest = &rest[len..];
+        } else {
+            uri.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    uri
+}
+
+/// Encode everything but unreserved URL characters and those in `keep`
+pub fn percent_encode(value: &str, keep: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for b in value.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) || keep.as_bytes().contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+/// Value and length of the placeholder `rest` starts with, if any
+fn placeholder<'a>(rest: &str, values: &[(&str, &'a str)]) -> Option<(&'a str, usize)> {
+    let inner = rest.strip_prefix('{')?;
+    let (name, _) = inner.split_once('}')?;
+    values
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| (*value, name.len() + 2))
+}
+
+#[cfg(not(windows))]
+fn escape_double_quoted(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '$' | '`' | '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_uri_encodes_values_but_keeps_slashes() {
+        assert_eq!(
+            fill_uri(
+                "vscode://vscode-remote/ssh-remote+{host}{path}",
+                &[("host", "dev box"), ("path", "/srv/my app/ü")]
+            ),
+            "vscode://vscode-remote/ssh-remote+dev%20box/srv/my%20app/%C3%BC"
+        );
+    }
+
+    #[test]
+    fn fill_uri_encodes_uri_delimiters_in_values() {
+        assert_eq!(
+            fill_uri("x://{path}?a=1", &[("path", "/a?b=c&d#e%")]),
+            "x:///a%3Fb%3Dc%26d%23e%25?a=1"
+        );
+    }
+
+    #[test]
+    fn fill_uri_leaves_unknown_placeholders_and_braces() {
+        assert_eq!(
+            fill_uri("x://{host}/{other}/{", &[("host", "h")]),
+            "x://h/{other}/{"
+        );
+    }
+
+    #[test]
+    fn percent_encode_keeps_unreserved_characters() {
+        assert_eq!(percent_encode("aZ09-_.~", ""), "aZ09-_.~");
+        assert_eq!(percent_encode("a/b:c", "/"), "a/b%3Ac");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn render_keeps_a_placeholder_one_argument() {
+        let values = [("path", "/tmp/my dir/it's")];
+        let expected =
+            TemplateCommand::Argv(vec!["code".to_string(), "/tmp/my dir/it's".to_string()]);
+        assert_eq!(render("code {path}", &values), expected);
+        assert_eq!(render("code \"{path}\"", &values), expected);
+        assert_eq!(render("code '{path}'", &values), expected);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn render_quotes_values_for_the_shell() {
+        let value = "a'b \"c\" $HOME `id` \\";
+        let template = "printf %s {v} | cat; printf '|%s|' '{v}'; printf '[%s]' \"{v}\"";
+        let TemplateCommand::Shell(line) = render(template, &[("v", value)]) else {
+            panic!("expected a shell command");
+        };
+        let output = std::process::Command::new("sh")
+            .args(["-c", &line])
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("{}|{}|[{}]", value, value, value)
+        );
+    }
+}
@@ -0,0 +1,504 @@
+use crate::janitor;
+use crate::json_store::JsonStore;
+#[cfg(not(windows))]
+use crate::models::CommandShell;
+use crate::models::{CustomTerminal, TerminalType, WindowsTerminalOptions};
+use crate::templates;
+use std::collections::HashSet;
+use std::process::Command;
+
+/// Global setting holding the user-defined terminals as a JSON array
+pub const CUSTOM_TERMINALS_KEY: &str = "customTerminals";
+
+/// Global setting holding the Windows Terminal options as a JSON object
+pub const WINDOWS_TERMINAL_KEY: &str = "windowsTerminal";
+
+/// How long a Warp launch configuration is kept for Warp to read
+const WARP_CONFIG_LIFETIME: std::time::Duration = std::time::Duration::from_secs(60);
+
+pub fn load_custom(store: &JsonStore) -> Result<Vec<CustomTerminal>, String> {
+    match store.get_setting(CUSTOM_TERMINALS_KEY)? {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse custom terminals: {}", e)),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Replace the custom terminals. IDs must be unique and can't name a built-in
+/// terminal, and every terminal needs a command.
+pub fn save_custom(store: &JsonStore, terminals: &[CustomTerminal]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for terminal in terminals {
+        let id = terminal.id.trim();
+        if id.is_empty() {
+            return Err("Custom terminal IDs can't be empty".to_string());
+        }
+        if id.parse::<TerminalType>().is_ok() {
+            return Err(format!("\"{}\" is a built-in terminal", id));
+        }
+        if !ids.insert(id) {
+            return Err(format!("Duplicate custom terminal \"{}\"", id));
+        }
+        if terminal.command.trim().is_empty() {
+            return Err(format!(
+                "Custom terminal \"{}\" has no command",
+                terminal.name
+            ));
+        }
+    }
+
+    let json = serde_json::to_string(terminals)
+        .map_err(|e| format!("Failed to serialize custom terminals: {}", e))?;
+    store.set_setting(CUSTOM_TERMINALS_KEY, &json)
+}
+
+/// The custom terminal the defaultTerminal setting names, if it names one
+pub fn default_custom(store: &JsonStore) -> Result<Option<CustomTerminal>, String> {
+    let Some(id) = store.get_setting("defaultTerminal")? else {
+        return Ok(None);
+    };
+    if id.parse::<TerminalType>().is_ok() {
+        return Ok(None);
+    }
+    Ok(load_custom(store)?.into_iter().find(|t| t.id == id))
+}
+
+pub fn load_wt_options(store: &JsonStore) -> Result<WindowsTerminalOptions, String> {
+    match store.get_setting(WINDOWS_TERMINAL_KEY)? {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse Windows Terminal options: {}", e)),
+        _ => Ok(WindowsTerminalOptions::default()),
+    }
+}
+
+/// `wt` arguments for a tab with the options' profile and title, starting in `cwd`.
+/// The command line that follows them must go through `wt_escape`.
+#[cfg(windows)]
+pub fn wt_args(options: &WindowsTerminalOptions, cwd: Option<&str>) -> String {
+    // Window 0 is the most recent one. Without `-w`, wt's own windowing setting
+    // would decide.
+    let mut args = if options.new_tab {
+        String::from("-w 0 new-tab")
+    } else {
+        String::from("-w new new-tab")
+    };
+    if let Some(profile) = options.profile.as_deref().filter(|p| !p.is_empty()) {
+        args.push_str(&format!(" -p \"{}\"", profile));
+    }
+    if let Some(title) = options.title.as_deref().filter(|t| !t.is_empty()) {
+        args.push_str(&format!(
+            " --title \"{}\" --suppressApplicationTitle",
+            title
+        ));
+    }
+    if let Some(dir) = cwd.filter(|d| !d.is_empty()) {
+        args.push_str(&format!(" -d \"{}\"", dir));
+    }
+    args
+}
+
+/// wt splits its command line into subcommands at `;`
+#[cfg(windows)]
+pub fn wt_escape(command: &str) -> String {
+    command.replace(';', "\\;")
+}
+
+/// Open `command` in `terminal`, or without one, in the terminal the defaultTerminal
+/// setting names, which may be a custom one
+pub fn open(
+    store: &JsonStore,
+    terminal: Option<TerminalType>,
+    cwd: Option<&str>,
+    command: &str,
+    env: &[(String, String)],
+) -> Result<(), String> {
+    if terminal.is_some() {
+        let wt = wt_options_for(store, terminal.as_ref())?;
+        return open_in_terminal(terminal, cwd, command, env, &wt);
+    }
+    if let Some(custom) = default_custom(store)? {
+        return open_custom(&custom, cwd, command, env);
+    }
+    let terminal = store
+        .get_setting("defaultTerminal")?
+        .and_then(|t| t.parse::<TerminalType>().ok());
+    let wt = wt_options_for(store, terminal.as_ref())?;
+    open_in_terminal(terminal, cwd, command, env, &wt)
+}
+
+/// The Windows Terminal options, read only when they're used (Windows Terminal on
+/// Windows), so a malformed setting can't break the other terminals
+fn wt_options_for(
+    store: &JsonStore,
+    terminal: Option<&TerminalType>,
+) -> Result<WindowsTerminalOptions, String> {
+    if cfg!(windows) && terminal == Some(&TerminalType::WindowsTerminal) {
+        load_wt_options(store)
+    } else {
+        Ok(WindowsTerminalOptions::default())
+    }
+}
+
+/// Open `command` in a custom terminal, starting in `cwd` (the home directory by
+/// default) and leaving an interactive shell behind once it exits
+pub fn open_custom(
+    terminal: &CustomTerminal,
+    cwd: Option<&str>,
+    command: &str,
+    env: &[(String, String)],
+) -> Result<(), String> {
+    let home = dirs::home_dir()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let cwd = cwd.filter(|c| !c.is_empty()).unwrap_or(&home);
+    let spawn_err = |e: std::io::Error| format!("Failed to open {}: {}", terminal.name, e);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+        let line =
+            templates::render_cmd(&terminal.command, &[("cwd", cwd)]).replace("{cmd}", command);
+        Command::new("cmd")
+            .raw_arg(format!("/c {}", line))
+            .envs(env.iter().cloned())
+            .creation_flags(CREATE_NO_WINDOW)
+            .spawn()
+            .map_err(spawn_err)?;
+    }
+
+    #[cfg(not(windows))]
+    {
+        let shell_cmd = with_shell(command);
+        templates::render(&terminal.command, &[("cwd", cwd), ("cmd", &shell_cmd)])
+            .command()
+            .envs(env.iter().cloned())
+            .spawn()
+            .map_err(spawn_err)?;
+    }
+
+    Ok(())
+}
+
+/// Warp can't be handed a command line, so `command` goes into a launch
+/// configuration that Warp opens through its URL scheme. The configuration is
+/// private to the user and removed once Warp has had time to read it. `env` stays
+/// out of it: with env the command runs from a private script (sh on Unix, cmd on
+/// Windows) that sets it and removes itself.
+pub fn open_warp(cwd: Option<&str>, command: &str, env: &[(String, String)]) -> Result<(), String> {
+    let home = dirs::home_dir()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let cwd = cwd.filter(|c| !c.is_empty()).unwrap_or(&home);
+    let command = if env.is_empty() || command.is_empty() {
+        command.to_string()
+    } else {
+        warp_env_script(command, env)?
+    };
+    // JSON strings are valid YAML scalars
+    let quote = |value: &str| serde_json::to_string(value).unwrap_or_default();
+    let mut config = format!(
+        "---\nname: Devora\nwindows:\n  - tabs:\n      - layout:\n          cwd: {}\n",
+        quote(cwd)
+    );
+    if !command.is_empty() {
+        config.push_str(&format!(
+            "          commands:\n            - exec: {}\n",
+            quote(&command)
+        ));
+    }
+    let path = janitor::write_private_temp(&format!("warp-{}.yaml", uuid::Uuid::new_v4()), &config)
+        .map_err(|e| format!("Failed to write Warp launch configuration: {}", e))?;
+    janitor::remove_later(path.clone(), WARP_CONFIG_LIFETIME);
+    let url = format!(
+        "warp://launch/{}",
+        templates::percent_encode(&path.to_string_lossy(), "/\\:")
+    );
+
+    #[cfg(windows)]
+    let result = {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        Command::new("cmd")
+            .raw_arg(format!("/c start \"\" \"{}\"", url))
+            .creation_flags(CREATE_NO_WINDOW)
+            .spawn()
+    };
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open").arg(&url).spawn();
+    #[cfg(all(not(windows), not(target_os = "macos")))]
+    let result = Command::new("xdg-open").arg(&url).spawn();
+
+    result
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open Warp: {}", e))
+}
+
+/// Command that runs `command` with `env` from a private script, which removes
+/// itself as it starts (Unix) or ends (Windows)
+fn warp_env_script(command: &str, env: &[(String, String)]) -> Result<String, String> {
+    let id = uuid::Uuid::new_v4();
+
+    #[cfg(windows)]
+    {
+        // `%` expands in batch files and a line break would end the `set`
+        let sets: String = env
+            .iter()
+            .map(|(k, v)| {
+                let value = v.replace('%', "%%").replace(['\r', '\n'], " ");
+                format!("set \"{}={}\"\r\n", k, value)
+            })
+            .collect();
+        // `(goto)` leaves the batch file before `del` removes it
+        let script = format!(
+            "@echo off\r\n{}{}\r\n(goto) 2>nul & del \"%~f0\"\r\n",
+            sets, command
+        );
+        let path = janitor::write_private_temp(&format!("warp-{}.cmd", id), &script)?;
+        Ok(format!("cmd /c \"{}\"", path.display()))
+    }
+
+    #[cfg(not(windows))]
+    {
+        let script = format!(
+            "rm -f -- \"$0\"\n{}{}\n",
+            inline_env(env, CommandShell::Sh),
+            command
+        );
+        let path = janitor::write_private_temp(&format!("warp-{}.sh", id), &script)?;
+        Ok(format!(
+            "sh {}",
+            crate::git::shell_quote(&path.to_string_lossy())
+        ))
+    }
+}
+
+/// `command` preceded by a cd into `cwd`, for a POSIX shell
+#[cfg(not(windows))]
+fn with_cwd(cwd: Option<&str>, command: &str) -> String {
+    match cwd.filter(|dir| !dir.is_empty()) {
+        None => command.to_string(),
+        Some(dir) if command.is_empty() => format!("cd {}", crate::git::shell_quote(dir)),
+        Some(dir) => format!("cd {} && {}", crate::git::shell_quote(dir), command),
+    }
+}
+
+/// `command`, then the user's shell in its place once it exits
+#[cfg(not(windows))]
+fn with_shell(command: &str) -> String {
+    if command.is_empty() {
+        "exec $SHELL".to_string()
+    } else {
+        format!("{} ; exec $SHELL", command)
+    }
+}
+
+/// `env VAR=value ` prefix that sets env inline for the line's `shell`
+#[cfg(not(windows))]
+fn inline_env(env: &[(String, String)], shell: CommandShell) -> String {
+    crate::shell::env_prefix(env.iter().map(|(k, v)| (k, v)), shell)
+}
+
+/// `value` for the inside of an AppleScript string literal
+#[cfg(target_os = "macos")]
+pub fn applescript_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Open `command` in a new window of the given terminal (or the platform default),
+/// starting in `cwd` when given and leaving an interactive shell behind once the
+/// command exits. `env` is set for the command only; `wt` applies to Windows Terminal.
+fn open_in_terminal(
+    terminal: Option<TerminalType>,
+    cwd: Option<&str>,
+    command: &str,
+    env: &[(String, String)],
+    wt: &WindowsTerminalOptions,
+) -> Result<(), String> {
+    let spawn_err = |e: std::io::Error| format!("Failed to open terminal: {}", e);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+        let cwd = cwd.filter(|dir| !dir.is_empty());
+        // Shells that take the command as one argument get it quoted for cmd
+        let arg = |command: &str| crate::shell::quote(command, crate::models::CommandShell::Cmd);
+        // `start` hands cmd's environment and working dir on to the new window
+        let raw = match terminal.unwrap_or(TerminalType::Cmd) {
+            TerminalType::PowerShell => {
+                format!("/c start \"\" powershell -NoExit -Command {}", arg(command))
+            }
+            TerminalType::PwshCore => {
+                format!("/c start \"\" pwsh -NoExit -Command {}", arg(command))
+            }
+            // wt starts in the profile's directory unless told otherwise
+            TerminalType::WindowsTerminal => {
+                format!("/c wt {} cmd /k {}", wt_args(wt, cwd), wt_escape(command))
+            }
+            TerminalType::GitBash if command.is_empty() => {
+                "/c start \"\" \"C:\\Program Files\\Git\\bin\\bash.exe\"".to_string()
+            }
+            TerminalType::GitBash => format!(
+                "/c start \"\" \"C:\\Program Files\\Git\\bin\\bash.exe\" -c {}",
+                arg(&format!("{} ; exec bash", command))
+            ),
+            TerminalType::Nushell => format!("/c start \"\" nu -e {}", arg(command)),
+            TerminalType::Wezterm => format!("/c start \"\" wezterm start -- cmd /k {}", command),
+            TerminalType::Warp => return open_warp(cwd, command, env),
+            _ => format!("/c start \"\" cmd /k {}", command),
+        };
+        let mut cmd = Command::new("cmd");
+        cmd.raw_arg(raw)
+            .envs(env.iter().cloned())
+            .creation_flags(CREATE_NO_WINDOW);
+        if let Some(dir) = cwd {
+            cmd.current_dir(dir);
+        }
+        cmd.spawn().map_err(spawn_err)?;
+    }
+
+    #[cfg(not(windows))]
+    let _ = wt;
+
+    #[cfg(target_os = "macos")]
+    {
+        let cwd_cmd = with_cwd(cwd, command);
+        let shell_cmd = with_shell(&cwd_cmd);
+        match terminal.unwrap_or(TerminalType::MacTerminal) {
+            TerminalType::Kitty => {
+                Command::new("kitty")
+                    .args(["-e", "sh", "-c", &shell_cmd])
+                    .envs(env.iter().cloned())
+                    .spawn()
+                    .map_err(spawn_err)?;
+            }
+            TerminalType::Alacritty => {
+                Command::new("alacritty")
+                    .args(["-e", "sh", "-c", &shell_cmd])
+                    .envs(env.iter().cloned())
+                    .spawn()
+                    .map_err(spawn_err)?;
+            }
+            TerminalType::Wezterm => {
+                Command::new("wezterm")
+                    .args(["start", "--", "sh", "-c", &shell_cmd])
+                    .envs(env.iter().cloned())
+                    .spawn()
+                    .map_err(spawn_err)?;
+            }
+            // Ghostty's CLI can't open windows on macOS, so it goes through `open`,
+            // which doesn't pass env on
+            TerminalType::Ghostty => {
+                let shell_cmd = format!("{}{}", inline_env(env, CommandShell::Sh), shell_cmd);
+                Command::new("open")
+                    .args(["-na", "Ghostty", "--args", "-e", "sh", "-c", &shell_cmd])
+                    .spawn()
+                    .map_err(spawn_err)?;
+            }
+            TerminalType::Warp => return open_warp(cwd, command, env),
+            terminal => {
+                // Terminal.app and iTerm start a fresh login shell, so env goes inline
+                let script_cmd = applescript_escape(&format!(
+                    "{}{}",
+                    inline_env(env, crate::shell::user_shell()),
+                    cwd_cmd
+                ));
+                let script = if terminal == TerminalType::ITerm2 {
+                    format!(
+                        "tell application \"iTerm\" to create window with default profile command \"{}\"",
+                        script_cmd
+                    )
+                } else {
+                    format!(
+                        "tell application \"Terminal\" to do script \"{}\"",
+                        script_cmd
+                    )
+                };
+                Command::new("osascript")
+                    .args(["-e", &script])
+                    .spawn()
+                    .map_err(spawn_err)?;
+            }
+        }
+    }
+
+    #[cfg(all(not(windows), not(target_os = "macos")))]
+    {
+        if terminal == Some(TerminalType::Warp) {
+            return open_warp(cwd, command, env);
+        }
+        let shell_cmd = with_shell(&with_cwd(cwd, command));
+        let sh = |before: &[&str]| -> Vec<String> {
+            before
+                .iter()
+                .chain(&["sh", "-c", shell_cmd.as_str()])
+                .map(|arg| arg.to_string())
+                .collect()
+        };
+        let candidates: Vec<(&str, Vec<String>)> = match terminal {
+            Some(TerminalType::Konsole) => vec![("konsole", sh(&["-e"]))],
+            Some(TerminalType::Xterm) => vec![("xterm", sh(&["-e"]))],
+            Some(TerminalType::Kitty) => vec![("kitty", sh(&["-e"]))],
+            Some(TerminalType::Alacritty) => vec![("alacritty", sh(&["-e"]))],
+            Some(TerminalType::Ghostty) => vec![("ghostty", sh(&["-e"]))],
+            Some(TerminalType::Wezterm) => vec![("wezterm", sh(&["start", "--"]))],
+            Some(TerminalType::Foot) => vec![("foot", sh(&[]))],
+            // Tilix takes the whole command as one argument
+            Some(TerminalType::Tilix) => vec![(
+                "tilix",
+                vec![
+                    "-e".to_string(),
+                    format!("sh -c {}", crate::git::shell_quote(&shell_cmd)),
+                ],
+            )],
+            Some(TerminalType::GnomeTerminal) | None => vec![("gnome-terminal", sh(&["--"]))],
+            // Not a Linux terminal: try the common ones
+            Some(_) => vec![
+                ("gnome-terminal", sh(&["--"])),
+                ("konsole", sh(&["-e"])),
+                ("xterm", sh(&["-e"])),
+            ],
+        };
+
+        let term = candidates
+            .iter()
+            .find(|(term, _)| {
+                candidates.len() == 1
+                    || Command::new("which")
+                        .arg(term)
+                        .output()
+                        .map(|o| o.status.success())
+                        .unwrap_or(false)
+            })
+            .ok_or("No supported terminal emulator found")?;
+        Command::new(term.0)
+            .args(&term.1)
+            .envs(env.iter().cloned())
+            .spawn()
+            .map_err(spawn_err)?;
+    }
+
+    Ok(())
+}
+
+/// Command line for an interactive `ssh -t` to `host` (through the `jump` chain),
+/// running `remote` there when given. The result is meant for `open`.
+pub fn ssh_command(host: &str, jump: Option<&str>, remote: Option<&str>) -> String {
+    let mut line = String::from("ssh -t");
+    if let Some(chain) = jump {
+        line.push_str(&format!(" -J {}", chain));
+    }
+    line.push_str(&format!(" {}", host));
+    if let Some(remote) = remote.filter(|r| !r.is_empty()) {
+        let quoted = if cfg!(windows) {
+            crate::shell::quote(remote, crate::models::CommandShell::Cmd)
+        } else {
+            crate::git::shell_quote(remote)
+        };
+        line.push_str(&format!(" {}", quoted));
+    }
+    line
+}
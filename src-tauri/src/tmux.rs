@@ -0,0 +1,142 @@
+use crate::git::shell_quote;
+use crate::hosts::{self, HostStatusCache};
+use crate::models::TmuxSession;
+use chrono::{TimeZone, Utc};
+use std::process::Output;
+
+/// One tab-separated line per session; the name goes last as it may contain tabs
+const LIST_FORMAT: &str =
+    "#{session_windows}\t#{session_attached}\t#{session_created}\t#{session_path}\t#{session_name}";
+
+/// Run `script` on `host` without a terminal
+async fn run(
+    host: &str,
+    jump: Option<&str>,
+    script: &str,
+    cache: &HostStatusCache,
+) -> Result<Output, String> {
+    let mut cmd = tokio::process::Command::new("ssh");
+    cmd.args(hosts::jump_args(jump)).args([host, script]);
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute SSH command: {}", e))?;
+    cache.record_ssh_output(host, &output);
+    Ok(output)
+}
+
+fn failure(output: &Output) -> String {
+    if output.status.code() == Some(127) {
+        return "tmux is not installed on the host".to_string();
+    }
+    format!(
+        "tmux failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    )
+}
+
+/// tmux turns `.` and `:` in session names into `_`, so such a name couldn't
+/// be attached to again by the name it was created with
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Session name is empty".to_string());
+    }
+    if name.contains(['.', ':']) {
+        return Err(format!(
+            "Session name \"{}\" can't contain '.' or ':'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Sessions of the tmux server on `host`, oldest first. A host with no server
+/// running has no sessions.
+pub async fn list(
+    host: &str,
+    jump: Option<&str>,
+    cache: &HostStatusCache,
+) -> Result<Vec<TmuxSession>, String> {
+    let script = format!("tmux list-sessions -F {}", shell_quote(LIST_FORMAT));
+    let output = run(host, jump, &script, cache).await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // "no server running on ..." or "error connecting to ..." with no socket
+        if stderr.contains("no server running") || stderr.contains("error connecting to") {
+            return Ok(Vec::new());
+        }
+        return Err(failure(&output));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut sessions: Vec<TmuxSession> = stdout.lines().filter_map(parse_line).collect();
+    sessions.sort_by(|a, b| a.created.cmp(&b.created));
+    Ok(sessions)
+}
+
+fn parse_line(line: &str) -> Option<TmuxSession> {
+    let fields: Vec<&str> = line.splitn(5, '\t').collect();
+    let [windows, attached, created, path, name] = fields[..] else {
+        return None;
+    };
+    if name.is_empty() {
+        return None;
+    }
+    Some(TmuxSession {
+        name: name.to_string(),
+        windows: windows.parse().unwrap_or(0),
+        attached: attached.parse::<u32>().is_ok_and(|clients| clients > 0),
+        created: created
+            .parse()
+            .ok()
+            .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+            .map(|t| t.to_rfc3339()),
+        // Older tmux versions don't know session_path and print nothing
+        path: Some(path.to_string()).filter(|p| !p.is_empty()),
+    })
+}
+
+/// Start a detached session on `host`, in `cwd` and running `command` when given
+pub async fn create(
+    host: &str,
+    jump: Option<&str>,
+    name: &str,
+    cwd: Option<&str>,
+    command: Option<&str>,
+    cache: &HostStatusCache,
+) -> Result<(), String> {
+    validate_name(name)?;
+    // Path is left unquoted so that `~` expands on the remote side
+    let mut script = match cwd.filter(|d| !d.is_empty()) {
+        Some(dir) => format!("cd {} && ", dir),
+        None => String::new(),
+    };
+    script.push_str(&format!("tmux new-session -d -s {}", shell_quote(name)));
+    if let Some(command) = command.filter(|c| !c.trim().is_empty()) {
+        script.push_str(&format!(" {}", shell_quote(command)));
+    }
+
+    let output = run(host, jump, &script, cache).await?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(failure(&output))
+    }
+}
+
+/// Remote command attaching to session `name`. The `=` prefix makes tmux match
+/// the name exactly rather than as a prefix.
+pub fn attach_line(name: &str) -> String {
+    format!(
+        "tmux attach-session -t {}",
+        shell_quote(&format!("={}", name))
+    )
+}
@@ -0,0 +1,391 @@
+use crate::json_store::JsonStore;
+use crate::markdown;
+use crate::models::{
+    ChecklistItem, TodoColumn, TodoEvent, TodoEventKind, TodoProgress, TodoSort, TodoStats,
+    TodoStatsBucket,
+};
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use std::collections::{HashMap, HashSet};
+
+/// Parse a markdown checklist line (`- [ ] task` / `- [x] task`, any indent).
+/// Returns whether the item is completed, or None if the line isn't a todo.
+pub fn parse_checkbox(line: &str) -> Option<bool> {
+    let rest = line
+        .trim_start()
+        .strip_prefix(['-', '*', '+'])?
+        .strip_prefix(' ')?;
+    match rest.get(..3)? {
+        "[ ]" => Some(false),
+        "[x]" | "[X]" => Some(true),
+        _ => None,
+    }
+}
+
+impl TodoProgress {
+    pub fn new(total: usize, completed: usize) -> Self {
+        let percentage = if total > 0 {
+            (completed as f32 / total as f32) * 100.0
+        } else {
+            0.0
+        };
+        Self {
+            total,
+            completed,
+            percentage,
+        }
+    }
+
+    /// Combine two progress counts (used for sub-project rollups)
+    pub fn merge(&self, other: &TodoProgress) -> Self {
+        Self::new(self.total + other.total, self.completed + other.completed)
+    }
+}
+
+/// Count total and completed todos in a markdown checklist
+pub fn todo_progress(markdown: &str) -> TodoProgress {
+    let (total, completed) = markdown
+        .lines()
+        .filter_map(parse_checkbox)
+        .fold((0, 0), |(total, completed), done| {
+            (total + 1, completed + usize::from(done))
+        });
+    TodoProgress::new(total, completed)
+}
+
+/// Value of an `@name(value)` marker in a todo's text
+fn marker_value<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("@{}(", name);
+    let start = text.find(&open)? + open.len();
+    let end = start + text[start..].find(')')?;
+    Some(text[start..end].trim())
+}
+
+/// Todo text with an `@name(...)` marker removed
+fn strip_marker(text: &str, name: &str) -> String {
+    let Some(start) = text.find(&format!("@{}(", name)) else {
+        return text.to_string();
+    };
+    let end = text[start..]
+        .find(')')
+        .map_or(text.len(), |i| start + i + 1);
+    format!("{}{}", &text[..start], &text[end..])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Todo text for display, without its `@due(...)` and `@status(...)` markers
+pub fn strip_markers(text: &str) -> String {
+    strip_marker(&strip_marker(text, "due"), "status")
+}
+
+/// Due date of a todo, written as `@due(YYYY-MM-DD)` anywhere in its text
+pub fn parse_due(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(marker_value(text, "due")?, "%Y-%m-%d").ok()
+}
+
+/// Priority of a todo, written as a `!p1` (highest) to `!p9` word in its text
+pub fn parse_priority(text: &str) -> Option<u8> {
+    text.split_whitespace().find_map(|word| {
+        let digit = word.strip_prefix("!p")?;
+        match digit.parse::<u8>() {
+            Ok(p @ 1..=9) => Some(p),
+            _ => None,
+        }
+    })
+}
+
+/// Todos of `content` matching the filter, in the requested order. Each keeps its line
+/// number, so edits are made to the full markdown rather than to this view.
+pub fn filter_checklist(
+    content: &str,
+    max_priority: Option<u8>,
+    include_completed: bool,
+    sort: TodoSort,
+) -> Vec<ChecklistItem> {
+    let mut todos: Vec<ChecklistItem> = markdown::checklist(content)
+        .into_iter()
+        .filter(|todo| include_completed || !todo.completed)
+        .filter(|todo| {
+            max_priority.is_none_or(|max| parse_priority(&todo.text).is_some_and(|p| p <= max))
+        })
+        .collect();
+
+    // Stable sorts keep document order among equals; todos without a value go last
+    match sort {
+        TodoSort::Document => {}
+        TodoSort::Priority => {
+            todos.sort_by_key(|todo| parse_priority(&todo.text).unwrap_or(u8::MAX))
+        }
+        TodoSort::DueDate => {
+            todos.sort_by_key(|todo| parse_due(&todo.text).unwrap_or(NaiveDate::MAX))
+        }
+    }
+    todos
+}
+
+/// Events turning the checklist `old` into `new`, matched by todo text
+pub fn diff_events(old: &str, new: &str, at: &str) -> Vec<TodoEvent> {
+    let before: HashMap<String, bool> = markdown::checklist(old)
+        .into_iter()
+        .map(|todo| (todo.text, todo.completed))
+        .collect();
+
+    let mut events = Vec::new();
+    let mut event = |kind, text: &str| {
+        events.push(TodoEvent {
+            kind,
+            text: text.to_string(),
+            at: at.to_string(),
+        })
+    };
+    for todo in markdown::checklist(new) {
+        match before.get(&todo.text) {
+            None => {
+                event(TodoEventKind::Added, &todo.text);
+                if todo.completed {
+                    event(TodoEventKind::Completed, &todo.text);
+                }
+            }
+            Some(false) if todo.completed => event(TodoEventKind::Completed, &todo.text),
+            Some(true) if !todo.completed => event(TodoEventKind::Reopened, &todo.text),
+            Some(_) => {}
+        }
+    }
+    events
+}
+
+/// Longest range `stats` covers (ten years); longer ones would overflow the date math
+const MAX_STATS_DAYS: u32 = 3650;
+
+/// Activity over the `days` days up to and including `today`
+pub fn stats(events: &[TodoEvent], days: u32, today: NaiveDate) -> TodoStats {
+    let days = days.clamp(1, MAX_STATS_DAYS);
+    let first = today - chrono::Duration::days(i64::from(days) - 1);
+    let dated: Vec<(&TodoEvent, DateTime<Local>)> = events
+        .iter()
+        .filter_map(|event| {
+            let at = DateTime::parse_from_rfc3339(&event.at).ok()?;
+            Some((event, at.with_timezone(&Local)))
+        })
+        .collect();
+
+    let mut per_day: Vec<TodoStatsBucket> = first
+        .iter_days()
+        .take(days as usize)
+        .map(|day| TodoStatsBucket {
+            start: day.format("%Y-%m-%d").to_string(),
+            added: 0,
+            completed: 0,
+        })
+        .collect();
+    let mut completion_hours = Vec::new();
+
+    for (index, (event, at)) in dated.iter().enumerate() {
+        let day = at.date_naive();
+        if day < first || day > today {
+            continue;
+        }
+        let bucket = &mut per_day[(day - first).num_days() as usize];
+        match event.kind {
+            TodoEventKind::Added => bucket.added += 1,
+            TodoEventKind::Completed => {
+                bucket.completed += 1;
+                // Time since the todo was most recently added before this
+                let added = dated[..index]
+                    .iter()
+                    .rev()
+                    .find(|(e, _)| e.kind == TodoEventKind::Added && e.text == event.text);
+                if let Some((_, added_at)) = added {
+                    completion_hours.push((*at - *added_at).num_minutes() as f64 / 60.0);
+                }
+            }
+            TodoEventKind::Reopened => {}
+        }
+    }
+
+    let mut per_week: Vec<TodoStatsBucket> = Vec::new();
+    for bucket in &per_day {
+        let day = NaiveDate::parse_from_str(&bucket.start, "%Y-%m-%d").unwrap_or(first);
+        let monday = day - chrono::Duration::days(i64::from(day.weekday().num_days_from_monday()));
+        let start = monday.format("%Y-%m-%d").to_string();
+        match per_week.last_mut() {
+            Some(week) if week.start == start => {
+                week.added += bucket.added;
+                week.completed += bucket.completed;
+            }
+            _ => per_week.push(TodoStatsBucket {
+                start,
+                added: bucket.added,
+                completed: bucket.completed,
+            }),
+        }
+    }
+
+    TodoStats {
+        days,
+        added: per_day.iter().map(|b| b.added).sum(),
+        completed: per_day.iter().map(|b| b.completed).sum(),
+        per_day,
+        per_week,
+        average_completion_hours: (!completion_hours.is_empty())
+            .then(|| completion_hours.iter().sum::<f64>() / completion_hours.len() as f64),
+    }
+}
+
+/// Global setting holding the kanban column names as a JSON array
+pub const TODO_STATUS_COLUMNS_KEY: &str = "todoStatusColumns";
+
+/// Columns used until the user configures their own
+const DEFAULT_STATUS_COLUMNS: &[&str] = &["Backlog", "Doing", "Done"];
+
+pub fn load_status_columns(store: &JsonStore) -> Result<Vec<String>, String> {
+    let columns: Vec<String> = match store.get_setting(TODO_STATUS_COLUMNS_KEY)? {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse todo status columns: {}", e))?,
+        _ => Vec::new(),
+    };
+    // Hand-edited settings with fewer than two columns fall back to the defaults
+    if columns.len() < 2 {
+        return Ok(DEFAULT_STATUS_COLUMNS
+            .iter()
+            .map(|c| c.to_string())
+            .collect());
+    }
+    Ok(columns)
+}
+
+/// Replace the column names. There must be at least two (open and done), all unique.
+pub fn save_status_columns(store: &JsonStore, columns: &[String]) -> Result<(), String> {
+    if columns.len() < 2 {
+        return Err("At least two status columns are needed".to_string());
+    }
+    let mut names = HashSet::new();
+    for column in columns {
+        let name = column.trim();
+        if name.is_empty() || name.contains(['(', ')']) {
+            return Err(format!("Invalid status column name \"{}\"", column));
+        }
+        if !names.insert(name.to_lowercase()) {
+            return Err(format!("Duplicate status column \"{}\"", name));
+        }
+    }
+
+    let json = serde_json::to_string(columns)
+        .map_err(|e| format!("Failed to serialize todo status columns: {}", e))?;
+    store.set_setting(TODO_STATUS_COLUMNS_KEY, &json)
+}
+
+/// Column of a todo: completed ones are in the last column, open ones in the column
+/// named by their `@status(...)` marker, or the first column without a (known) marker
+fn column_index(text: &str, completed: bool, columns: &[String]) -> usize {
+    if completed {
+        return columns.len() - 1;
+    }
+    marker_value(text, "status")
+        .and_then(|status| {
+            columns[..columns.len() - 1]
+                .iter()
+                .position(|c| c.eq_ignore_ascii_case(status))
+        })
+        .unwrap_or(0)
+}
+
+/// The project's todos grouped into the status columns, in document order
+pub fn by_status(markdown: &str, columns: &[String]) -> Vec<TodoColumn> {
+    let mut grouped: Vec<TodoColumn> = columns
+        .iter()
+        .map(|name| TodoColumn {
+            name: name.clone(),
+            todos: Vec::new(),
+        })
+        .collect();
+    for todo in markdown::checklist(markdown) {
+        let index = column_index(&todo.text, todo.completed, columns);
+        grouped[index].todos.push(ChecklistItem {
+            text: strip_marker(&todo.text, "status"),
+            ..todo
+        });
+    }
+    grouped
+}
+
+/// Move the todo on `line` to the `status` column: the last column checks it off,
+/// the first clears its marker and any other sets `@status(...)`
+pub fn set_status(
+    markdown: &str,
+    line: usize,
+    status: &str,
+    columns: &[String],
+) -> Result<String, String> {
+    let index = columns
+        .iter()
+        .position(|c| c.eq_ignore_ascii_case(status))
+        .ok_or_else(|| format!("Unknown status \"{}\"", status))?;
+    let mut lines: Vec<String> = markdown.lines().map(String::from).collect();
+    let Some(current) = lines.get(line).filter(|l| parse_checkbox(l).is_some()) else {
+        return Err(format!("Line {} is not a todo", line + 1));
+    };
+
+    // parse_checkbox guarantees `<indent><bullet> [?] ` at the start
+    let indent = &current[..current.len() - current.trim_start().len()];
+    let bullet = &current[indent.len()..indent.len() + 1];
+    let text = strip_marker(current.trim_start()[5..].trim(), "status");
+    let done = index == columns.len() - 1;
+    let mut rewritten = format!(
+        "{}{} [{}] {}",
+        indent,
+        bullet,
+        if done { 'x' } else { ' ' },
+        text
+    );
+    if index > 0 && !done {
+        rewritten.push_str(&format!(" @status({})", columns[index]));
+    }
+    lines[line] = rewritten;
+
+    let mut result = lines.join("\n");
+    if markdown.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: TodoEventKind, text: &str, at: &str) -> TodoEvent {
+        TodoEvent {
+            kind,
+            text: text.to_string(),
+            at: at.to_string(),
+        }
+    }
+
+    #[test]
+    fn stats_clamps_the_range() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let stats = stats(&[], u32::MAX, today);
+        assert_eq!(stats.days, MAX_STATS_DAYS);
+        assert_eq!(stats.per_day.len(), MAX_STATS_DAYS as usize);
+        assert_eq!(stats.per_day.last().unwrap().start, "2024-06-15");
+
+        assert_eq!(super::stats(&[], 0, today).per_day.len(), 1);
+    }
+
+    #[test]
+    fn stats_counts_events_in_range() {
+        let today = Local::now().date_naive();
+        let now = Local::now().to_rfc3339();
+        let long_ago = (Local::now() - chrono::Duration::days(400)).to_rfc3339();
+        let events = [
+            event(TodoEventKind::Added, "old", &long_ago),
+            event(TodoEventKind::Added, "a", &now),
+            event(TodoEventKind::Completed, "a", &now),
+        ];
+        let stats = stats(&events, 7, today);
+        assert_eq!((stats.added, stats.completed), (1, 1));
+        assert_eq!(stats.average_completion_hours, Some(0.0));
+    }
+}
@@ -0,0 +1,223 @@
+use crate::models::UrlMetadata;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Cached metadata older than this is fetched again
+const CACHE_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Pages larger than this are not downloaded (the head is all that's needed)
+const MAX_PAGE_BYTES: u32 = 2 * 1024 * 1024;
+
+const FETCH_TIMEOUT_SECS: u32 = 10;
+
+fn cache_path(data_path: &Path) -> PathBuf {
+    data_path.join("cache").join("url_metadata.json")
+}
+
+fn load_cache(data_path: &Path) -> HashMap<String, UrlMetadata> {
+    fs::read_to_string(cache_path(data_path))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Cached metadata for `url`, unless it has expired
+pub fn cached(data_path: &Path, url: &str) -> Option<UrlMetadata> {
+    let metadata = load_cache(data_path).remove(url)?;
+    let fetched_at = chrono::DateTime::parse_from_rfc3339(&metadata.fetched_at).ok()?;
+    let age = chrono::Utc::now().signed_duration_since(fetched_at);
+    (age.num_seconds() < CACHE_TTL_SECS).then_some(metadata)
+}
+
+pub fn store_cached(data_path: &Path, metadata: &UrlMetadata) -> Result<(), String> {
+    let mut cache = load_cache(data_path);
+    cache.insert(metadata.url.clone(), metadata.clone());
+
+    let path = cache_path(data_path);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(&cache)
+        .map_err(|e| format!("Failed to serialize URL metadata cache: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write URL metadata cache: {}", e))
+}
+
+/// Download the page with curl and pull title, description and favicon out of its HTML
+pub async fn fetch(url: &str) -> Result<UrlMetadata, String> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("Not an http(s) URL: {}", url));
+    }
+
+    let mut cmd = Command::new("curl");
+    cmd.args([
+        "-s",
+        "-S",
+        "-L",
+        "--max-time",
+        &FETCH_TIMEOUT_SECS.to_string(),
+        "--max-filesize",
+        &MAX_PAGE_BYTES.to_string(),
+        "-A",
+        "Mozilla/5.0 (compatible; Devora)",
+        // The final URL after redirects is needed to resolve relative favicon links
+        "-w",
+        "\n%{url_effective}",
+        url,
+    ]);
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to fetch {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (html, final_url) = stdout.rsplit_once('\n').unwrap_or((&stdout, url));
+    let tags = head_tags(html);
+
+    let meta = |keys: &[&str]| {
+        tags.iter()
+            .filter(|(name, _)| name == "meta")
+            .find_map(|(_, attrs)| {
+                let key = attr(attrs, "name").or_else(|| attr(attrs, "property"))?;
+                keys.contains(&key.to_lowercase().as_str())
+                    .then(|| attr(attrs, "content"))
+                    .flatten()
+            })
+            .map(|v| decode_entities(&v))
+            .filter(|v| !v.is_empty())
+    };
+
+    let title = meta(&["og:title"]).or_else(|| page_title(html));
+    let description = meta(&["description", "og:description"]);
+    let favicon = tags
+        .iter()
+        .filter(|(name, _)| name == "link")
+        .find_map(|(_, attrs)| {
+            let rel = attr(attrs, "rel")?.to_lowercase();
+            rel.split_whitespace()
+                .any(|r| r == "icon")
+                .then(|| attr(attrs, "href"))
+                .flatten()
+        })
+        .map(|href| resolve(final_url, &decode_entities(&href)))
+        .unwrap_or_else(|| resolve(final_url, "/favicon.ico"));
+
+    Ok(UrlMetadata {
+        url: url.to_string(),
+        title,
+        description,
+        favicon: Some(favicon),
+        fetched_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// `<meta>` and `<link>` tags before `</head>`, as (tag name, raw attribute text)
+fn head_tags(html: &str) -> Vec<(String, String)> {
+    let lower = html.to_ascii_lowercase();
+    let end = lower.find("</head>").unwrap_or(lower.len());
+    let mut tags = Vec::new();
+    let mut pos = 0;
+
+    while let Some(start) = lower[pos..end].find('<').map(|i| pos + i) {
+        let Some(close) = lower[start..end].find('>').map(|i| start + i) else {
+            break;
+        };
+        let tag = &html[start + 1..close];
+        let name_end = tag
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+        let name = tag[..name_end].to_lowercase();
+        if name == "meta" || name == "link" {
+            tags.push((name, tag[name_end..].to_string()));
+        }
+        pos = close + 1;
+    }
+    tags
+}
+
+/// Value of an attribute in raw tag text: `key="value"`, `key='value'` or `key=value`
+fn attr(attrs: &str, key: &str) -> Option<String> {
+    let lower = attrs.to_ascii_lowercase();
+    let mut search = 0;
+    while let Some(found) = lower[search..].find(key).map(|i| search + i) {
+        search = found + key.len();
+        // Must be a whole attribute name, e.g. not `data-name` when looking for `name`
+        let before = lower[..found].chars().next_back();
+        if before.is_some_and(|c| !c.is_whitespace()) {
+            continue;
+        }
+        let rest = attrs[search..].trim_start();
+        let Some(value) = rest.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        return match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().map(String::from),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                .next()
+                .map(String::from),
+        };
+    }
+    None
+}
+
+fn page_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title>")?;
+    let title = decode_entities(html[start..end].trim());
+    (!title.is_empty()).then_some(title)
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Resolve a link found on the page at `base` to an absolute URL
+fn resolve(base: &str, href: &str) -> String {
+    if href.starts_with("http://") || href.starts_with("https://") || href.starts_with("data:") {
+        return href.to_string();
+    }
+    let (scheme, rest) = base.split_once("://").unwrap_or(("https", base));
+    if let Some(host_relative) = href.strip_prefix("//") {
+        return format!("{}://{}", scheme, host_relative);
+    }
+
+    let path_start = rest.find('/').unwrap_or(rest.len());
+    let origin = format!("{}://{}", scheme, &rest[..path_start]);
+    if href.starts_with('/') {
+        return format!("{}{}", origin, href);
+    }
+    let path = rest[path_start..]
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default();
+    let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    format!("{}{}/{}", origin, dir, href)
+}
@@ -0,0 +1,75 @@
+use crate::models::{ItemLaunches, Project};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct Usage {
+    /// Project ID -> when a window for it was last opened
+    #[serde(default)]
+    projects_opened: HashMap<String, String>,
+    /// Item ID -> its launches
+    #[serde(default)]
+    item_launches: HashMap<String, ItemLaunches>,
+}
+
+/// Project opens and item launches on this machine, persisted to ~/.devora/usage.json.
+/// Kept out of the synced project files, where every launch would be a sync conflict.
+pub struct UsageFile {
+    path: PathBuf,
+    usage: Mutex<Usage>,
+}
+
+impl UsageFile {
+    pub fn new(config_dir: &Path) -> Self {
+        let path = config_dir.join("usage.json");
+        let usage = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            usage: Mutex::new(usage),
+        }
+    }
+
+    fn save(&self, usage: &Usage) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(usage)
+            .map_err(|e| format!("Failed to serialize usage: {}", e))?;
+        fs::write(&self.path, content).map_err(|e| format!("Failed to write usage: {}", e))
+    }
+
+    /// Record that a window for the project was opened
+    pub fn record_project_opened(&self, project_id: &str) -> Result<(), String> {
+        let mut usage = self.usage.lock().unwrap();
+        usage
+            .projects_opened
+            .insert(project_id.to_string(), chrono::Utc::now().to_rfc3339());
+        self.save(&usage)
+    }
+
+    /// Count a launch of the item
+    pub fn record_item_launch(&self, item_id: &str) -> Result<(), String> {
+        let mut usage = self.usage.lock().unwrap();
+        let launches = usage.item_launches.entry(item_id.to_string()).or_default();
+        launches.count += 1;
+        launches.last_at = chrono::Utc::now().to_rfc3339();
+        self.save(&usage)
+    }
+
+    /// Fill in `last_opened_at` of each project
+    pub fn fill_opened(&self, projects: &mut [Project]) {
+        let usage = self.usage.lock().unwrap();
+        for project in projects {
+            project.last_opened_at = usage.projects_opened.get(&project.id).cloned();
+        }
+    }
+
+    /// Launches of every item launched on this machine, by item ID
+    pub fn item_launches(&self) -> HashMap<String, ItemLaunches> {
+        self.usage.lock().unwrap().item_launches.clone()
+    }
+}
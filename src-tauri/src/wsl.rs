@@ -0,0 +1,160 @@
+use crate::models::{CommandShell, WslDistro};
+use crate::shell;
+use encoding_rs::UTF_16LE;
+use std::process::Output;
+
+pub fn check_available() -> Result<(), String> {
+    if cfg!(windows) {
+        Ok(())
+    } else {
+        Err("WSL is only available on Windows".to_string())
+    }
+}
+
+/// `wsl.exe` invocation
+fn wsl() -> Result<std::process::Command, String> {
+    check_available()?;
+    let mut cmd = std::process::Command::new("wsl");
+    // Newer WSL versions print UTF-8 instead of UTF-16 when asked
+    cmd.env("WSL_UTF8", "1");
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    Ok(cmd)
+}
+
+fn run(args: &[&str]) -> Result<Output, String> {
+    let output = wsl()?
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run wsl: {}", e))?;
+    if output.status.success() {
+        Ok(output)
+    } else {
+        Err(format!("wsl failed: {}", decode(&output.stderr).trim()))
+    }
+}
+
+/// wsl.exe's own messages are UTF-16 unless WSL_UTF8 was honoured
+fn decode(bytes: &[u8]) -> String {
+    if bytes.len() >= 2 && bytes.iter().skip(1).step_by(2).all(|&b| b == 0) {
+        UTF_16LE.decode(bytes).0.into_owned()
+    } else {
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+}
+
+/// Installed distros from `wsl -l -v`, e.g.
+/// ```text
+///   NAME            STATE           VERSION
+/// * Ubuntu          Running         2
+///   docker-desktop  Stopped         2
+/// ```
+pub fn list_distros() -> Result<Vec<WslDistro>, String> {
+    let output = run(&["--list", "--verbose"])?;
+    let stdout = decode(&output.stdout);
+    // The header is localized, so it's skipped by position
+    Ok(stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .skip(1)
+        .filter_map(|line| {
+            let line = line.trim();
+            let (is_default, line) = match line.strip_prefix('*') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            // Distro names can't contain spaces
+            let mut fields = line.split_whitespace();
+            Some(WslDistro {
+                name: fields.next()?.to_string(),
+                state: fields.next().unwrap_or_default().to_string(),
+                version: fields.next().and_then(|v| v.parse().ok()),
+                is_default,
+            })
+        })
+        .collect())
+}
+
+/// Translate a path between Windows and `distro` with `wslpath`: a Windows path
+/// to its Linux form, or with `to_windows`, a Linux path to its Windows form
+pub fn translate_path(distro: &str, path: &str, to_windows: bool) -> Result<String, String> {
+    let flag = if to_windows { "-w" } else { "-u" };
+    let output = run(&["-d", distro, "--exec", "wslpath", "-a", flag, path])?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// A Linux path in `distro` for `path`, which may be given in either form
+pub fn linux_path(distro: &str, path: &str) -> Result<String, String> {
+    let is_windows_path = path.contains('\\') || path.as_bytes().get(1) == Some(&b':');
+    if is_windows_path {
+        translate_path(distro, path, false)
+    } else {
+        Ok(path.to_string())
+    }
+}
+
+/// Arguments for `wsl.exe` that run `command` in `distro`, starting in `cwd`
+/// (a Windows or Linux path, `~` included). `shell` is the item's own choice
+/// only, never the default shell, which names one installed on Windows. Without
+/// it the command runs in a login `sh` so the distro's profile sets up PATH.
+pub fn command_args(
+    distro: &str,
+    cwd: Option<&str>,
+    shell: Option<CommandShell>,
+    command: &str,
+) -> Vec<String> {
+    let mut args = vec!["-d".to_string(), distro.to_string()];
+    if let Some(dir) = cwd.filter(|d| !d.is_empty()) {
+        args.extend(["--cd".to_string(), dir.to_string()]);
+    }
+    args.push("--exec".to_string());
+    // cmd can't run inside the distro
+    match shell.filter(|s| *s != CommandShell::Cmd) {
+        Some(shell) => {
+            args.push(shell.program().to_string());
+            args.extend(shell.args(command));
+        }
+        None => args.extend(["sh".to_string(), "-lc".to_string(), command.to_string()]),
+    }
+    args
+}
+
+pub fn std_command(
+    distro: &str,
+    cwd: Option<&str>,
+    shell: Option<CommandShell>,
+    command: &str,
+) -> Result<std::process::Command, String> {
+    let mut cmd = wsl()?;
+    cmd.args(command_args(distro, cwd, shell, command));
+    Ok(cmd)
+}
+
+pub fn tokio_command(
+    distro: &str,
+    cwd: Option<&str>,
+    shell: Option<CommandShell>,
+    command: &str,
+) -> Result<tokio::process::Command, String> {
+    std_command(distro, cwd, shell, command).map(tokio::process::Command::from)
+}
+
+/// Command line for a Windows terminal that runs `command` in `distro`, then
+/// stays in the distro's shell. The command is quoted for cmd, which runs the line.
+pub fn terminal_line(distro: &str, cwd: Option<&str>, command: &str) -> String {
+    let mut line = format!("wsl -d {}", distro);
+    if let Some(dir) = cwd.filter(|d| !d.is_empty()) {
+        line.push_str(&format!(" --cd {}", shell::quote(dir, CommandShell::Cmd)));
+    }
+    let script = format!("{} ; exec \"${{SHELL:-sh}}\" -l", command);
+    line.push_str(&format!(
+        " --exec sh -lc {}",
+        shell::quote(&script, CommandShell::Cmd)
+    ));
+    line
+}
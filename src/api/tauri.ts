@@ -104,6 +104,8 @@ export interface ImportResult {
   itemsImported: number
   fileCardsImported: number
   skipped: number
+  snapshotId?: string
+  projectsDeleted?: number
 }
 
 // ============ Store Reload API ============